@@ -0,0 +1,63 @@
+//! Benchmarks of frame parsing and the decoder, which gateways reading many
+//! sensors at once spend most of their time in
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use sen0177::{
+    decoder::FrameDecoder,
+    protocol::{checksum, encode_frame, parse_frame, Plantower, FRAME_LEN},
+    serial::DEFAULT_RESYNC_BUDGET,
+    Concentrations, Reading,
+};
+
+fn reading() -> Reading {
+    let concentrations = Concentrations::new(12, 25, 40);
+    Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0])
+}
+
+/// A hundred frames back to back
+fn clean_stream() -> Vec<u8> {
+    encode_frame(&reading()).repeat(100)
+}
+
+/// A full OS buffer of garbage without any header bytes, then a frame
+fn garbage_stream() -> Vec<u8> {
+    let mut stream = (0..DEFAULT_RESYNC_BUDGET as usize - FRAME_LEN)
+        .map(|i| (i % 0x40) as u8)
+        .collect::<Vec<_>>();
+    stream.extend_from_slice(&encode_frame(&reading()));
+    stream
+}
+
+fn parsing(c: &mut Criterion) {
+    let frame = encode_frame(&reading());
+    c.bench_function("checksum", |b| b.iter(|| checksum(black_box(&frame))));
+    c.bench_function("parse_frame", |b| b.iter(|| parse_frame(black_box(&frame))));
+}
+
+fn decoding(c: &mut Criterion) {
+    for (name, stream) in [("clean", clean_stream()), ("resync", garbage_stream())] {
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(stream.len() as u64));
+        group.bench_function("push", |b| {
+            b.iter(|| {
+                let mut decoder = FrameDecoder::<Plantower>::new();
+                black_box(&stream)
+                    .iter()
+                    .filter_map(|&byte| decoder.push(byte))
+                    .count()
+            })
+        });
+        group.bench_function("push_slice", |b| {
+            b.iter(|| {
+                let mut decoder = FrameDecoder::<Plantower>::new();
+                decoder.push_slice(black_box(&stream)).count()
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, parsing, decoding);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Protocol analyzer
+//!
+//! Decodes a capture of UART traffic from the sensor, printing every frame
+//! found along with its parsed fields and checksum status, followed by
+//! statistics about the garbage between frames.
+//!
+//! Usage: `analyze [FILE]`
+//!
+//! The capture is read from `FILE` (or standard input, if omitted or `-`),
+//! and may be raw binary, a textual hex dump (e.g. from `xxd -p`), or a
+//! pcap file written by `capture::Recorder`.
+//! A capture can be taken with something like:
+//!
+//! ```sh
+//! stty -F /dev/serial0 9600 raw && timeout 10 cat /dev/serial0 > capture.bin
+//! ```
+
+use sen0177::{
+    analyze::{analyze, parse_hex},
+    capture,
+};
+use std::{
+    env, fs,
+    io::{self, Read},
+};
+
+pub fn main() -> anyhow::Result<()> {
+    let capture = match env::args().nth(1).filter(|path| path != "-") {
+        Some(path) => fs::read(path)?,
+        None => {
+            let mut capture = Vec::new();
+            io::stdin().read_to_end(&mut capture)?;
+            capture
+        }
+    };
+
+    let bytes = if capture::is_capture(&capture) {
+        capture::read_received(&capture)?
+    } else {
+        match std::str::from_utf8(&capture) {
+            Ok(text) if !text.trim().is_empty() => parse_hex(text)?,
+            _ => capture,
+        }
+    };
+
+    println!("{}", analyze(&bytes));
+    Ok(())
+}
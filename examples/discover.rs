@@ -0,0 +1,33 @@
+//! Serial port discovery
+//!
+//! Lists the serial ports on which a sensor is sending data.
+//!
+//! Usage: `discover [LISTEN_SECS]`
+
+use sen0177::discover::discover_ports;
+use std::{env, time::Duration};
+
+const DEFAULT_LISTEN_SECS: u64 = 3;
+
+pub fn main() -> anyhow::Result<()> {
+    let listen = Duration::from_secs(
+        env::args()
+            .nth(1)
+            .map(|arg| arg.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_LISTEN_SECS),
+    );
+
+    let ports = discover_ports(listen)?;
+    if ports.is_empty() {
+        println!("No sensors found");
+    }
+    for port in ports {
+        print!("{}: {} frames", port.path, port.frames);
+        match port.usb_adapter {
+            Some(adapter) => println!(" (via {})", adapter),
+            None => println!(),
+        }
+    }
+    Ok(())
+}
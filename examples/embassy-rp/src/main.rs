@@ -0,0 +1,108 @@
+//! Async reading of a SEN0177 on a Raspberry Pi Pico, under Embassy
+//!
+//! The sensor's TX goes to GP1 (UART0 RX) and its RX to GP0 (UART0 TX).
+//! One task duty-cycles the sensor (waking it, waiting for the fan to spin
+//! up, reading, and putting it back to sleep) and sends each reading over a
+//! channel; another receives the readings and reports them.  Anything else
+//! that needs the readings (a display, a network uplink) can receive from
+//! the channel the same way.
+//!
+//! Build and flash from this directory with `cargo run --release` (using
+//! `probe-rs`).
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+use embassy_executor::Spawner;
+use embassy_rp::{
+    bind_interrupts,
+    peripherals::UART0,
+    uart::{self, BufferedInterruptHandler, BufferedUart, BufferedUartRx, BufferedUartTx},
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+use embassy_time::{with_timeout, Duration, Timer};
+use embedded_io_async::Write;
+use sen0177::{
+    protocol::{encode_command, Command},
+    serial::{AsyncSen0177, Sen0177Builder},
+    Reading,
+};
+use static_cell::StaticCell;
+
+bind_interrupts!(struct Irqs {
+    UART0_IRQ => BufferedInterruptHandler<UART0>;
+});
+
+/// The datasheet recommends waiting 30 seconds after waking for the fan to
+/// stabilize the airflow
+const WARM_UP: Duration = Duration::from_secs(30);
+
+/// The time between readings; the sensor sleeps for most of it
+const INTERVAL: Duration = Duration::from_secs(300);
+
+/// The sensor sends a frame about once a second, so a read taking much
+/// longer than that means it isn't sending
+const READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+static READINGS: Channel<CriticalSectionRawMutex, Reading, 4> = Channel::new();
+
+#[embassy_executor::task]
+async fn sensor_task(rx: BufferedUartRx<'static, UART0>, mut tx: BufferedUartTx<'static, UART0>) {
+    let mut sensor: AsyncSen0177<_> = Sen0177Builder::new().build_async(rx);
+    loop {
+        Timer::after(WARM_UP).await;
+
+        // The receive buffer overflowed while the fan was spinning up, so
+        // the first frame or two may be stale or corrupted; keep the last
+        // of a few good ones
+        let mut latest = None;
+        for _ in 0..3 {
+            match with_timeout(READ_TIMEOUT, sensor.read()).await {
+                Ok(Ok(reading)) => latest = Some(reading),
+                Ok(Err(error)) => defmt::debug!("Read failed: {}", defmt::Debug2Format(&error)),
+                Err(_) => defmt::warn!("Timed out waiting for the sensor"),
+            }
+        }
+        if let Some(reading) = latest {
+            READINGS.send(reading).await;
+        }
+
+        tx.write_all(&encode_command(Command::Sleep)).await.ok();
+        Timer::after(INTERVAL - WARM_UP).await;
+        tx.write_all(&encode_command(Command::Wakeup)).await.ok();
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_rp::init(Default::default());
+
+    static TX_BUF: StaticCell<[u8; 16]> = StaticCell::new();
+    static RX_BUF: StaticCell<[u8; 64]> = StaticCell::new();
+    let mut config = uart::Config::default();
+    config.baudrate = 9600;
+    let uart = BufferedUart::new(
+        p.UART0,
+        Irqs,
+        p.PIN_0,
+        p.PIN_1,
+        TX_BUF.init([0; 16]),
+        RX_BUF.init([0; 64]),
+        config,
+    );
+    let (tx, rx) = uart.split();
+    spawner.must_spawn(sensor_task(rx, tx));
+
+    loop {
+        let reading = READINGS.receive().await;
+        defmt::info!(
+            "PM1: {}µg/m³, PM2.5: {}µg/m³, PM10: {}µg/m³",
+            reading.pm1(),
+            reading.pm2_5(),
+            reading.pm10()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Sensor emulator
+//!
+//! Creates a pseudo-terminal and writes Plantower-protocol data frames to
+//! it once per interval, as the SEN0177 does in active mode, so that
+//! applications can be developed and tested without hardware.  Point the
+//! driver at the printed device path (e.g. with the `linux-serial` or
+//! `soak` examples).
+//!
+//! Usage: `emulator [PM2.5] [NOISE_PERCENT] [FAULT_PERCENT] [INTERVAL_MS]`
+//!
+//! Each frame's values are derived from the PM2.5 concentration, jittered by
+//! up to `NOISE_PERCENT` percent.  With probability `FAULT_PERCENT` percent,
+//! a frame is sent with a corrupted checksum, truncated, or preceded by a
+//! burst of garbage bytes.
+
+use anyhow::bail;
+use sen0177::{
+    protocol::{encode_frame, FRAME_LEN},
+    Concentrations, Reading,
+};
+use std::{
+    env,
+    ffi::CStr,
+    fs::File,
+    io::Write,
+    os::fd::{FromRawFd, RawFd},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DEFAULT_PM2_5: u16 = 12;
+const DEFAULT_NOISE_PERCENT: u32 = 10;
+const DEFAULT_FAULT_PERCENT: u32 = 0;
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+
+/// A small xorshift PRNG, so the example needs no extra dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn seeded() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self(nanos | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u32) -> u32 {
+        (self.next() % bound as u64) as u32
+    }
+
+    fn percent(&mut self, percent: u32) -> bool {
+        self.below(100) < percent
+    }
+
+    /// Scales `value` by a random factor within ±`percent` percent
+    fn jitter(&mut self, value: u16, percent: u32) -> u16 {
+        let spread = percent.min(100) * 2 + 1;
+        let factor = 100 - percent.min(100) + self.below(spread);
+        ((value as u32 * factor + 50) / 100).min(u16::MAX as u32) as u16
+    }
+}
+
+fn synthesize(pm2_5: u16, noise_percent: u32, rng: &mut Rng) -> Reading {
+    let pm2_5 = rng.jitter(pm2_5, noise_percent);
+    let pm1 = pm2_5 * 2 / 3;
+    let pm10 = pm2_5.saturating_add(pm2_5 / 3);
+    let concentrations = Concentrations::new(pm1, pm2_5, pm10);
+
+    // Roughly typical ambient size distribution, per 0.1L
+    let base = pm2_5.saturating_mul(60).max(1);
+    let counts = [
+        base,
+        base / 3,
+        base / 15,
+        base / 120,
+        base / 600,
+        base / 2000,
+    ]
+    .map(|count| rng.jitter(count, noise_percent));
+
+    Reading::new(concentrations, concentrations, counts)
+}
+
+fn open_pty() -> anyhow::Result<(File, File, String)> {
+    // SAFETY: plain libc calls on file descriptors we own; every return
+    // value is checked before the descriptor is used.
+    unsafe {
+        let master: RawFd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if master < 0 {
+            bail!("posix_openpt: {}", std::io::Error::last_os_error());
+        }
+        let master_file = File::from_raw_fd(master);
+        if libc::grantpt(master) != 0 || libc::unlockpt(master) != 0 {
+            bail!("grantpt/unlockpt: {}", std::io::Error::last_os_error());
+        }
+
+        let mut name = [0 as libc::c_char; 128];
+        if libc::ptsname_r(master, name.as_mut_ptr(), name.len()) != 0 {
+            bail!("ptsname_r: {}", std::io::Error::last_os_error());
+        }
+        let path = CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned();
+
+        // Hold the slave side open in raw mode, so that nothing is echoed
+        // back or translated before a client opens the device
+        let slave: RawFd = libc::open(name.as_ptr(), libc::O_RDWR | libc::O_NOCTTY);
+        if slave < 0 {
+            bail!("open {}: {}", path, std::io::Error::last_os_error());
+        }
+        let slave_file = File::from_raw_fd(slave);
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(slave, &mut termios) != 0 {
+            bail!("tcgetattr: {}", std::io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        libc::cfsetspeed(&mut termios, libc::B9600);
+        if libc::tcsetattr(slave, libc::TCSANOW, &termios) != 0 {
+            bail!("tcsetattr: {}", std::io::Error::last_os_error());
+        }
+
+        Ok((master_file, slave_file, path))
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let pm2_5 = args
+        .next()
+        .map(|arg| arg.parse())
+        .transpose()?
+        .unwrap_or(DEFAULT_PM2_5);
+    let noise_percent = args
+        .next()
+        .map(|arg| arg.parse())
+        .transpose()?
+        .unwrap_or(DEFAULT_NOISE_PERCENT);
+    let fault_percent = args
+        .next()
+        .map(|arg| arg.parse())
+        .transpose()?
+        .unwrap_or(DEFAULT_FAULT_PERCENT);
+    let interval = Duration::from_millis(
+        args.next()
+            .map(|arg| arg.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_INTERVAL_MS),
+    );
+
+    let (mut master, _slave, path) = open_pty()?;
+    println!("Emulating a SEN0177 on {}", path);
+
+    let mut rng = Rng::seeded();
+    loop {
+        let reading = synthesize(pm2_5, noise_percent, &mut rng);
+        let mut frame = encode_frame(&reading);
+        let mut len = FRAME_LEN;
+
+        if rng.percent(fault_percent) {
+            match rng.below(3) {
+                0 => {
+                    let bit = rng.below(8 * (FRAME_LEN as u32 - 4)) + 16;
+                    frame[bit as usize / 8] ^= 1 << (bit % 8);
+                    eprintln!("Fault: corrupted checksum");
+                }
+                1 => {
+                    len = 2 + rng.below(FRAME_LEN as u32 - 2) as usize;
+                    eprintln!("Fault: truncated frame to {} bytes", len);
+                }
+                _ => {
+                    let garbage: Vec<u8> =
+                        (0..1 + rng.below(16)).map(|_| rng.next() as u8).collect();
+                    master.write_all(&garbage)?;
+                    eprintln!("Fault: {} garbage bytes", garbage.len());
+                }
+            }
+        }
+
+        master.write_all(&frame[..len])?;
+        thread::sleep(interval);
+    }
+}
@@ -0,0 +1,96 @@
+//! Async reading of a SEN0177 on an ESP32-C3, under Embassy
+//!
+//! The sensor's TX goes to GPIO20 (UART1 RX) and its RX to GPIO21 (UART1
+//! TX).  One task duty-cycles the sensor (waking it, waiting for the fan to
+//! spin up, reading, and putting it back to sleep) and sends each reading
+//! over a channel; another receives the readings and reports them.
+//!
+//! For other ESP32 chips, change the chip features in `Cargo.toml`, the
+//! target in `.cargo/config.toml`, and the pins.  Build and flash from this
+//! directory with `cargo run --release` (using `espflash`).
+
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+
+use embassy_executor::Spawner;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+use embassy_time::{with_timeout, Duration, Timer};
+use embedded_io_async::Write;
+use esp_hal::{
+    timer::timg::TimerGroup,
+    uart::{Config, Uart, UartRx, UartTx},
+    Async,
+};
+use esp_println::println;
+use sen0177::{
+    protocol::{encode_command, Command},
+    serial::{AsyncSen0177, Sen0177Builder},
+    Reading,
+};
+
+/// The datasheet recommends waiting 30 seconds after waking for the fan to
+/// stabilize the airflow
+const WARM_UP: Duration = Duration::from_secs(30);
+
+/// The time between readings; the sensor sleeps for most of it
+const INTERVAL: Duration = Duration::from_secs(300);
+
+/// The sensor sends a frame about once a second, so a read taking much
+/// longer than that means it isn't sending
+const READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+static READINGS: Channel<CriticalSectionRawMutex, Reading, 4> = Channel::new();
+
+#[embassy_executor::task]
+async fn sensor_task(rx: UartRx<'static, Async>, mut tx: UartTx<'static, Async>) {
+    let mut sensor: AsyncSen0177<_> = Sen0177Builder::new().build_async(rx);
+    loop {
+        Timer::after(WARM_UP).await;
+
+        // The UART's FIFO overflowed while the fan was spinning up, so the
+        // first frame or two may be stale or corrupted; keep the last of a
+        // few good ones
+        let mut latest = None;
+        for _ in 0..3 {
+            match with_timeout(READ_TIMEOUT, sensor.read()).await {
+                Ok(Ok(reading)) => latest = Some(reading),
+                Ok(Err(error)) => println!("Read failed: {}", error),
+                Err(_) => println!("Timed out waiting for the sensor"),
+            }
+        }
+        if let Some(reading) = latest {
+            READINGS.send(reading).await;
+        }
+
+        tx.write_all(&encode_command(Command::Sleep)).await.ok();
+        Timer::after(INTERVAL - WARM_UP).await;
+        tx.write_all(&encode_command(Command::Wakeup)).await.ok();
+    }
+}
+
+#[esp_hal_embassy::main]
+async fn main(spawner: Spawner) {
+    let peripherals = esp_hal::init(esp_hal::Config::default());
+    let timg0 = TimerGroup::new(peripherals.TIMG0);
+    esp_hal_embassy::init(timg0.timer0);
+
+    let uart = Uart::new(peripherals.UART1, Config::default().baudrate(9600))
+        .unwrap()
+        .with_rx(peripherals.GPIO20)
+        .with_tx(peripherals.GPIO21)
+        .into_async();
+    let (rx, tx) = uart.split();
+    spawner.must_spawn(sensor_task(rx, tx));
+
+    loop {
+        let reading = READINGS.receive().await;
+        println!(
+            "PM1: {}µg/m³, PM2.5: {}µg/m³, PM10: {}µg/m³",
+            reading.pm1(),
+            reading.pm2_5(),
+            reading.pm10()
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use linux_embedded_hal::{
     serialport::{self, DataBits, FlowControl, Parity, StopBits},
-    Serial,
+    Delay, Serial,
 };
-use sen0177::{serial::Sen0177, AirQualitySensor};
+use sen0177::{backoff::Backoff, prelude::*};
 use std::time::Duration;
 
 const SERIAL_PORT: &str = "/dev/ttyS0";
@@ -20,10 +20,12 @@ pub fn main() -> anyhow::Result<()> {
         .stop_bits(STOP_BITS)
         .timeout(Duration::from_millis(1500));
     let serial = Serial::open_from_builder(builder)?;
-    let mut sensor = Sen0177::new(serial);
+    let mut sensor = Sen0177Uart::new(serial);
 
-    loop {
-        match sensor.read() {
+    // If the sensor is unplugged, retry less and less often rather than
+    // printing an error every 1.5 seconds
+    for result in sensor.readings().with_backoff(Delay, Backoff::default()) {
+        match result {
             Ok(reading) => {
                 println!(
                     "PM1: {}µg/m³, PM2.5: {}µg/m³, PM10: {}µg/m³",
@@ -35,4 +37,6 @@ pub fn main() -> anyhow::Result<()> {
             Err(err) => eprintln!("Error: {:?}", err),
         }
     }
+
+    Ok(())
 }
@@ -0,0 +1,70 @@
+//! Reading a SEN0177 through the nRF52840's UARTE, whose EasyDMA delivers
+//! whole buffers rather than single bytes
+//!
+//! The sensor's TX goes to P0.08 (RXD) and its RX to P0.06 (TXD).  Each
+//! DMA transfer fills a fixed-size chunk, whose size has nothing to do with
+//! the 32-byte frame length, so frames routinely start in one chunk and
+//! finish in the next.  `FrameDecoder::push_slice` keeps a partial frame
+//! across chunks, so each chunk can simply be pushed into the decoder as it
+//! arrives.
+//!
+//! Build and flash from this directory with `cargo run --release` (using
+//! `probe-rs`).
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+use embassy_executor::Spawner;
+use embassy_nrf::{bind_interrupts, peripherals, uarte};
+use sen0177::decoder::FrameDecoder;
+
+bind_interrupts!(struct Irqs {
+    UARTE0 => uarte::InterruptHandler<peripherals::UARTE0>;
+});
+
+/// The size of each DMA transfer
+///
+/// A transfer completes only once its buffer is full, so smaller chunks
+/// deliver each frame sooner after it arrives, at the cost of more
+/// interrupts.  The UARTE holds a few bytes in its FIFO while the next
+/// transfer is being started, so none are lost between chunks at 9600
+/// baud.
+const CHUNK_LEN: usize = 20;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_nrf::init(Default::default());
+
+    let mut config = uarte::Config::default();
+    config.parity = uarte::Parity::EXCLUDED;
+    config.baudrate = uarte::Baudrate::BAUD9600;
+    let uart = uarte::Uarte::new(p.UARTE0, Irqs, p.P0_08, p.P0_06, config);
+    let (_tx, mut rx) = uart.split();
+
+    let mut decoder: FrameDecoder = FrameDecoder::new();
+    // EasyDMA can only read from and write to RAM, which a local buffer is
+    let mut chunk = [0u8; CHUNK_LEN];
+    loop {
+        if let Err(error) = rx.read(&mut chunk).await {
+            defmt::warn!("UART error: {}", error);
+            // The chunk is incomplete, so any frame in progress is too
+            decoder.reset();
+            continue;
+        }
+
+        for result in decoder.push_slice(&chunk) {
+            match result {
+                Ok((_frame, reading)) => defmt::info!(
+                    "PM1: {}µg/m³, PM2.5: {}µg/m³, PM10: {}µg/m³",
+                    reading.pm1(),
+                    reading.pm2_5(),
+                    reading.pm10()
+                ),
+                Err(error) => defmt::debug!("Dropped frame: {}", defmt::Debug2Format(&error)),
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! A link-time check that frame parsing and decoding cannot panic
+//!
+//! `panic-never` provides a panic handler that fails to link, so this only
+//! builds if the optimizer has removed every path to a panic from the
+//! parsers and the decoder when fed arbitrary input.  It is never meant to
+//! be run; build it from this directory with `cargo build --release`.
+
+#![no_std]
+#![no_main]
+
+use panic_never as _;
+
+use core::hint::black_box;
+
+use cortex_m_rt::entry;
+use sen0177::{
+    decoder::FrameDecoder,
+    protocol::{
+        checksum, parse_frame, validate, FrameProtocol, Plantower, PlantowerStandard, Pms3003,
+        Pms5003T, FRAME_LEN,
+    },
+};
+
+fn parse<P: FrameProtocol>() {
+    let frame = black_box(P::new_frame());
+    black_box(P::is_data_frame(black_box(frame.as_ref())));
+    black_box(P::parse(&frame).ok());
+    black_box(P::parse_unchecked(&frame));
+}
+
+fn decode<P: FrameProtocol>() {
+    let mut decoder = black_box(FrameDecoder::<P>::new());
+    black_box(decoder.push(black_box(0)).map(|result| result.ok()));
+    let chunk = black_box([0u8; 20]);
+    for result in decoder.push_slice(black_box(&chunk[..])) {
+        black_box(result.ok());
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let frame = black_box([0u8; FRAME_LEN]);
+    black_box(checksum(black_box(&frame[..])));
+    if let Ok(reading) = parse_frame(&frame) {
+        black_box(validate(&reading).is_ok());
+    }
+
+    parse::<Plantower>();
+    parse::<PlantowerStandard>();
+    parse::<Pms3003>();
+    parse::<Pms5003T>();
+    decode::<Plantower>();
+    decode::<PlantowerStandard>();
+    decode::<Pms3003>();
+    decode::<Pms5003T>();
+
+    loop {}
+}
@@ -0,0 +1,181 @@
+//! Interrupt-driven reading of a SEN0177 on a Raspberry Pi Pico, under
+//! RTIC 2
+//!
+//! The sensor's TX goes to GP1 (UART0 RX) and its RX to GP0 (UART0 TX).
+//! The UART's receive interrupt pushes bytes into a lock-free queue, and a
+//! software task drains the queue through a `FrameDecoder`, so nothing ever
+//! blocks waiting for a frame.  A second task duty-cycles the sensor: it
+//! wakes it, lets the fan spin up, reports the latest reading, then puts it
+//! back to sleep to extend the laser's life.
+//!
+//! Build and flash from this directory with `cargo run --release` (using
+//! `probe-rs`).
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+use rp_pico::hal::{
+    gpio::{
+        bank0::{Gpio0, Gpio1},
+        FunctionUart, Pin, PullDown,
+    },
+    pac::UART0,
+    uart::{Reader, Writer},
+};
+use sen0177::protocol::{encode_command, Command};
+
+type UartPins = (
+    Pin<Gpio0, FunctionUart, PullDown>,
+    Pin<Gpio1, FunctionUart, PullDown>,
+);
+
+/// Sends a command to the sensor
+///
+/// Commands are only 7 bytes long, so writing them blocks only briefly.
+fn send(writer: &mut Writer<UART0, UartPins>, command: Command) {
+    writer.write_full_blocking(&encode_command(command));
+}
+
+#[rtic::app(device = rp_pico::hal::pac, dispatchers = [TIMER_IRQ_1])]
+mod app {
+    use super::*;
+
+    use embedded_hal_nb::serial::Read;
+    use heapless::spsc::{Consumer, Producer, Queue};
+    use rp_pico::hal::{
+        clocks::init_clocks_and_plls,
+        fugit::RateExtU32,
+        uart::{DataBits, StopBits, UartConfig, UartPeripheral},
+        Clock, Sio, Watchdog,
+    };
+    use rtic_monotonics::rp2040::prelude::*;
+    use sen0177::{decoder::FrameDecoder, isr::ByteQueue, Reading};
+
+    rp2040_timer_monotonic!(Mono);
+
+    /// Holds two frames' worth of bytes, so the decoder task can fall
+    /// behind by a frame without losing data
+    const QUEUE_LEN: usize = 65;
+
+    #[shared]
+    struct Shared {
+        latest: Option<Reading>,
+    }
+
+    #[local]
+    struct Local {
+        reader: Reader<UART0, UartPins>,
+        writer: Writer<UART0, UartPins>,
+        producer: Producer<'static, u8, QUEUE_LEN>,
+        consumer: Consumer<'static, u8, QUEUE_LEN>,
+        decoder: FrameDecoder,
+    }
+
+    #[init(local = [queue: ByteQueue<QUEUE_LEN> = Queue::new()])]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        let mut device = cx.device;
+        let mut watchdog = Watchdog::new(device.WATCHDOG);
+        let clocks = init_clocks_and_plls(
+            rp_pico::XOSC_CRYSTAL_FREQ,
+            device.XOSC,
+            device.CLOCKS,
+            device.PLL_SYS,
+            device.PLL_USB,
+            &mut device.RESETS,
+            &mut watchdog,
+        )
+        .ok()
+        .unwrap();
+        Mono::start(device.TIMER, &device.RESETS);
+
+        let sio = Sio::new(device.SIO);
+        let pins = rp_pico::Pins::new(
+            device.IO_BANK0,
+            device.PADS_BANK0,
+            sio.gpio_bank0,
+            &mut device.RESETS,
+        );
+        let uart_pins = (
+            pins.gpio0.into_function::<FunctionUart>(),
+            pins.gpio1.into_function::<FunctionUart>(),
+        );
+        let uart = UartPeripheral::new(device.UART0, uart_pins, &mut device.RESETS)
+            .enable(
+                UartConfig::new(9600.Hz(), DataBits::Eight, None, StopBits::One),
+                clocks.peripheral_clock.freq(),
+            )
+            .unwrap();
+        let (mut reader, writer) = uart.split();
+        reader.enable_rx_interrupt();
+
+        let (producer, consumer) = cx.local.queue.split();
+        duty_cycle::spawn().ok();
+
+        (
+            Shared { latest: None },
+            Local {
+                reader,
+                writer,
+                producer,
+                consumer,
+                decoder: FrameDecoder::new(),
+            },
+        )
+    }
+
+    /// Moves received bytes from the UART's FIFO into the queue
+    #[task(binds = UART0_IRQ, priority = 2, local = [reader, producer, overruns: u32 = 0])]
+    fn uart_rx(cx: uart_rx::Context) {
+        while let Ok(byte) = cx.local.reader.read() {
+            if cx.local.producer.enqueue(byte).is_err() {
+                *cx.local.overruns += 1;
+                defmt::warn!("Byte queue full ({} bytes lost)", *cx.local.overruns);
+            }
+        }
+        // If the decoder task is already pending, it will see these bytes
+        // too; if it's just finishing, the next interrupt will spawn it
+        // again.
+        decode::spawn().ok();
+    }
+
+    /// Decodes whatever bytes have been received so far
+    #[task(priority = 1, local = [consumer, decoder], shared = [latest])]
+    async fn decode(mut cx: decode::Context) {
+        for result in cx.local.decoder.drain(cx.local.consumer) {
+            match result {
+                Ok((_frame, reading)) => cx.shared.latest.lock(|latest| *latest = Some(reading)),
+                Err(error) => defmt::debug!("Dropped frame: {}", defmt::Debug2Format(&error)),
+            }
+        }
+    }
+
+    /// Wakes the sensor, reports a reading, and puts it back to sleep, once
+    /// every five minutes
+    #[task(priority = 1, local = [writer], shared = [latest])]
+    async fn duty_cycle(mut cx: duty_cycle::Context) {
+        loop {
+            // The datasheet recommends waiting 30 seconds after waking
+            // for the fan to stabilize the airflow
+            Mono::delay(30.secs()).await;
+            cx.shared.latest.lock(|latest| *latest = None);
+            Mono::delay(5.secs()).await;
+
+            match cx.shared.latest.lock(|latest| latest.take()) {
+                Some(reading) => defmt::info!(
+                    "PM1: {}µg/m³, PM2.5: {}µg/m³, PM10: {}µg/m³",
+                    reading.pm1(),
+                    reading.pm2_5(),
+                    reading.pm10()
+                ),
+                None => defmt::warn!("No reading received; is the sensor connected?"),
+            }
+
+            send(cx.local.writer, Command::Sleep);
+            Mono::delay(265.secs()).await;
+            send(cx.local.writer, Command::Wakeup);
+        }
+    }
+}
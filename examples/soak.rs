@@ -0,0 +1,243 @@
+//! Long-running soak test
+//!
+//! Reads from a sensor for a configurable amount of time, periodically
+//! printing a report of error rates, read timing distribution, and memory
+//! usage.
+//!
+//! Usage: `cargo run --example soak -- [SERIAL_PORT] [DURATION_SECS] [REPORT_INTERVAL_SECS]`
+
+use linux_embedded_hal::{
+    serialport::{self, DataBits, FlowControl, Parity, StopBits},
+    Serial,
+};
+use sen0177::{serial::Sen0177, AirQualitySensor, SensorError};
+use std::{
+    env, fs,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_SERIAL_PORT: &str = "/dev/ttyS0";
+const DEFAULT_DURATION_SECS: u64 = 4 * 60 * 60;
+const DEFAULT_REPORT_INTERVAL_SECS: u64 = 5 * 60;
+const BAUD_RATE: u32 = 9600;
+
+/// The width of each bucket of the read time histogram, in milliseconds
+const BUCKET_MS: u64 = 10;
+/// The number of buckets of the read time histogram; the last one also
+/// counts all longer reads
+const BUCKETS: usize = 500;
+
+/// A fixed-size histogram of read times, so that memory use stays flat no
+/// matter how long the soak runs
+///
+/// Percentiles are only as precise as the bucket width, but the minimum,
+/// mean, and maximum are exact.
+struct Timings {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            total: Duration::ZERO,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl Timings {
+    fn record(&mut self, time: Duration) {
+        let bucket = (time.as_millis() / u128::from(BUCKET_MS)).min(BUCKETS as u128 - 1);
+        self.buckets[bucket as usize] += 1;
+        self.count += 1;
+        self.total += time;
+        self.min = self.min.min(time);
+        self.max = self.max.max(time);
+    }
+
+    fn min(&self) -> Duration {
+        // Zero, like the maximum, until a read is recorded
+        self.min.min(self.max)
+    }
+
+    fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total.div_f64(self.count as f64)
+        }
+    }
+
+    /// Returns the upper edge of the bucket holding the `pct`th percentile,
+    /// but no more than the maximum
+    fn percentile(&self, pct: u64) -> Duration {
+        let rank = self.count.saturating_sub(1) * pct / 100;
+        let mut seen = 0;
+        for (bucket, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen > rank {
+                let edge = Duration::from_millis((bucket as u64 + 1) * BUCKET_MS);
+                return edge.min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    ok: u64,
+    bad_magic: u64,
+    baud_mismatch: u64,
+    checksum_mismatch: u64,
+    implausible: u64,
+    timeout: u64,
+    stale: u64,
+    read_error: u64,
+    timings: Timings,
+}
+
+impl Stats {
+    fn total(&self) -> u64 {
+        self.ok
+            + self.bad_magic
+            + self.baud_mismatch
+            + self.checksum_mismatch
+            + self.implausible
+            + self.timeout
+            + self.stale
+            + self.read_error
+    }
+
+    fn report(&self, elapsed: Duration) {
+        let total = self.total().max(1) as f64;
+        let rate = |count: u64| count as f64 * 100.0 / total;
+
+        println!("=== Soak report after {}s ===", elapsed.as_secs());
+        println!("Reads:             {}", self.total());
+        println!("OK:                {} ({:.2}%)", self.ok, rate(self.ok));
+        println!(
+            "BadMagic:          {} ({:.2}%)",
+            self.bad_magic,
+            rate(self.bad_magic)
+        );
+        println!(
+            "BaudMismatch:      {} ({:.2}%)",
+            self.baud_mismatch,
+            rate(self.baud_mismatch)
+        );
+        println!(
+            "ChecksumMismatch:  {} ({:.2}%)",
+            self.checksum_mismatch,
+            rate(self.checksum_mismatch)
+        );
+        println!(
+            "ImplausibleData:   {} ({:.2}%)",
+            self.implausible,
+            rate(self.implausible)
+        );
+        println!(
+            "Timeout:           {} ({:.2}%)",
+            self.timeout,
+            rate(self.timeout)
+        );
+        println!(
+            "StaleData:         {} ({:.2}%)",
+            self.stale,
+            rate(self.stale)
+        );
+        println!(
+            "ReadError:         {} ({:.2}%)",
+            self.read_error,
+            rate(self.read_error)
+        );
+        println!(
+            "Read time (ms):    min {} / mean {} / p50 {} / p95 {} / p99 {} / max {}",
+            self.timings.min().as_millis(),
+            self.timings.mean().as_millis(),
+            self.timings.percentile(50).as_millis(),
+            self.timings.percentile(95).as_millis(),
+            self.timings.percentile(99).as_millis(),
+            self.timings.max.as_millis(),
+        );
+        match resident_memory_kb() {
+            Some(kb) => println!("Resident memory:   {} kB", kb),
+            None => println!("Resident memory:   unknown"),
+        }
+    }
+}
+
+fn resident_memory_kb() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let serial_port = args
+        .next()
+        .unwrap_or_else(|| DEFAULT_SERIAL_PORT.to_string());
+    let duration = Duration::from_secs(
+        args.next()
+            .map(|arg| arg.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_DURATION_SECS),
+    );
+    let report_interval = Duration::from_secs(
+        args.next()
+            .map(|arg| arg.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_REPORT_INTERVAL_SECS),
+    );
+
+    let builder = serialport::new(&serial_port, BAUD_RATE)
+        .data_bits(DataBits::Eight)
+        .flow_control(FlowControl::None)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .timeout(Duration::from_millis(1500));
+    let serial = Serial::open_from_builder(builder)?;
+    let mut sensor = Sen0177::new(serial);
+
+    let mut stats = Stats::default();
+    let start = Instant::now();
+    let mut last_report = start;
+
+    while start.elapsed() < duration {
+        let read_start = Instant::now();
+        let result = sensor.read();
+        stats.timings.record(read_start.elapsed());
+
+        match result {
+            Ok(_) => stats.ok += 1,
+            Err(SensorError::BadMagic) => stats.bad_magic += 1,
+            Err(SensorError::LikelyBaudMismatch) => stats.baud_mismatch += 1,
+            Err(SensorError::ChecksumMismatch) => stats.checksum_mismatch += 1,
+            Err(SensorError::ImplausibleData(_)) => stats.implausible += 1,
+            Err(SensorError::Timeout) | Err(SensorError::NoData { .. }) => stats.timeout += 1,
+            Err(SensorError::StaleData) => stats.stale += 1,
+            Err(SensorError::ReadError(err)) => {
+                eprintln!("Read error: {:?}", err);
+                stats.read_error += 1;
+            }
+        }
+
+        if last_report.elapsed() >= report_interval {
+            stats.report(start.elapsed());
+            last_report = Instant::now();
+        }
+    }
+
+    stats.report(start.elapsed());
+    Ok(())
+}
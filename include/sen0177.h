@@ -0,0 +1,113 @@
+#ifndef SEN0177_H
+#define SEN0177_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// The operation succeeded
+#define SEN0177_OK 0
+
+// A required pointer argument was null, or a string was not valid UTF-8
+#define SEN0177_ERR_INVALID_ARGUMENT -1
+
+// The start of a data frame couldn't be found
+#define SEN0177_ERR_BAD_MAGIC -2
+
+// The start of a data frame couldn't be found, and the data suggests the
+// wrong baud rate
+#define SEN0177_ERR_BAUD_MISMATCH -3
+
+// A data frame's checksum did not match
+#define SEN0177_ERR_CHECKSUM -4
+
+// A reading failed plausibility validation
+#define SEN0177_ERR_IMPLAUSIBLE -5
+
+// No data arrived before the timeout
+#define SEN0177_ERR_TIMEOUT -6
+
+// The sensor's data has not been updated
+#define SEN0177_ERR_STALE -7
+
+// No valid frame arrived before a watchdog's timeout
+#define SEN0177_ERR_NO_DATA -8
+
+// Reading from or writing to the serial port failed
+#define SEN0177_ERR_IO -9
+
+// A data frame had the wrong length
+#define SEN0177_ERR_FRAME_LENGTH -10
+
+// An open serial sensor, opaque to C
+typedef struct sen0177_handle sen0177_handle;
+
+// A reading, as returned to C
+typedef struct sen0177_reading {
+  // The standard (CF=1) PM1 concentration, in µg/m³
+  uint16_t pm1;
+  // The standard (CF=1) PM2.5 concentration, in µg/m³
+  uint16_t pm2_5;
+  // The standard (CF=1) PM10 concentration, in µg/m³
+  uint16_t pm10;
+  // The environmental PM1 concentration, in µg/m³; zero if `has_env` is
+  // zero
+  uint16_t env_pm1;
+  // The environmental PM2.5 concentration, in µg/m³
+  uint16_t env_pm2_5;
+  // The environmental PM10 concentration, in µg/m³
+  uint16_t env_pm10;
+  // The counts of particles beyond 0.3µm, 0.5µm, 1µm, 2.5µm, 5µm, and
+  // 10µm, in 0.1L of air
+  uint16_t particle_counts[6];
+  // The firmware version reported by the sensor
+  uint8_t firmware_version;
+  // The error code reported by the sensor
+  uint8_t device_error_code;
+  // Nonzero if the environmental concentrations are valid; zero if the
+  // sensor doesn't support them
+  uint8_t has_env;
+} sen0177_reading;
+
+// Opens the sensor on the serial port at `path`, at 9600 baud
+//
+// Reads fail with `SEN0177_ERR_TIMEOUT` after waiting about `timeout_ms`
+// milliseconds for data; a timeout of zero waits forever.  Returns null
+// if the port can't be opened.
+//
+// # Safety
+//
+// `path` must be null or point to a NUL-terminated string.
+sen0177_handle *sen0177_open(const char *path, uint32_t timeout_ms);
+
+// Closes a sensor opened with [`sen0177_open`]
+//
+// # Safety
+//
+// `handle` must be null or a handle returned by [`sen0177_open`] that
+// has not already been closed.
+void sen0177_close(sen0177_handle *handle);
+
+// Reads a single measurement into `reading`, returning a status code
+//
+// # Safety
+//
+// `handle` must be null or a handle returned by [`sen0177_open`] that
+// has not been closed, and `reading` must be null or point to writable
+// memory for a `sen0177_reading`.
+int sen0177_read(sen0177_handle *handle, sen0177_reading *reading);
+
+// Parses the `len`-byte data frame at `frame` into `reading`, returning a
+// status code
+//
+// # Safety
+//
+// `frame` must be null or point to `len` readable bytes, and `reading`
+// must be null or point to writable memory for a `sen0177_reading`.
+int sen0177_parse(const uint8_t *frame, size_t len, sen0177_reading *reading);
+
+// Returns a static, NUL-terminated description of a status code
+const char *sen0177_strerror(int status);
+
+#endif  /* SEN0177_H */
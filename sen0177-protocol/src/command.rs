@@ -0,0 +1,50 @@
+use crate::frame::{checksum, MAGIC_BYTE_0, MAGIC_BYTE_1};
+
+/// The length, in bytes, of an encoded command
+pub const COMMAND_LEN: usize = 7;
+
+/// Commands that can be sent to a sensor over its UART
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Requests a single data frame while in passive mode
+    PassiveRead,
+    /// Switches the sensor to passive mode, where it only sends data when asked
+    PassiveMode,
+    /// Switches the sensor to active mode, where it continuously sends data
+    ActiveMode,
+    /// Puts the sensor to sleep, stopping its fan and laser
+    Sleep,
+    /// Wakes the sensor up from sleep
+    Wakeup,
+}
+
+impl Command {
+    const fn code_and_data(self) -> (u8, u16) {
+        use Command::*;
+        match self {
+            PassiveRead => (0xe2, 0x0000),
+            PassiveMode => (0xe1, 0x0000),
+            ActiveMode => (0xe1, 0x0001),
+            Sleep => (0xe4, 0x0000),
+            Wakeup => (0xe4, 0x0001),
+        }
+    }
+}
+
+/// Encodes a command into the bytes that should be written to the sensor
+pub const fn encode_command(command: Command) -> [u8; COMMAND_LEN] {
+    let (code, data) = command.code_and_data();
+    let mut buf = [
+        MAGIC_BYTE_0,
+        MAGIC_BYTE_1,
+        code,
+        (data >> 8) as u8,
+        data as u8,
+        0,
+        0,
+    ];
+    let sum = checksum(buf.as_slice().split_at(COMMAND_LEN - 2).0);
+    buf[COMMAND_LEN - 2] = (sum >> 8) as u8;
+    buf[COMMAND_LEN - 1] = sum as u8;
+    buf
+}
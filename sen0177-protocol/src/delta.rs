@@ -0,0 +1,70 @@
+use crate::Reading;
+
+/// The change between two readings, field by field
+///
+/// Each value is the later reading's minus the earlier reading's, so a
+/// positive value is an increase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadingDelta {
+    /// The change in the standard (CF=1) PM1 concentration, in µg/m³
+    pub pm1: i32,
+    /// The change in the standard (CF=1) PM2.5 concentration, in µg/m³
+    pub pm2_5: i32,
+    /// The change in the standard (CF=1) PM10 concentration, in µg/m³
+    pub pm10: i32,
+    /// The change in the environmental PM1 concentration, in µg/m³
+    pub env_pm1: i32,
+    /// The change in the environmental PM2.5 concentration, in µg/m³
+    pub env_pm2_5: i32,
+    /// The change in the environmental PM10 concentration, in µg/m³
+    pub env_pm10: i32,
+    /// The changes in the counts of particles beyond 0.3µm, 0.5µm, 1µm,
+    /// 2.5µm, 5µm, and 10µm, in 0.1L of air
+    pub particle_counts: [i32; 6],
+}
+
+impl ReadingDelta {
+    /// Returns `true` if no field changed
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Reading {
+    /// Returns the change from this reading to `other`
+    ///
+    /// The firmware version and device error code are not compared.  The
+    /// environmental concentrations are only compared if both readings have
+    /// them (see [`Reading::atmospheric`]); otherwise their changes are
+    /// zero.
+    pub const fn delta(&self, other: &Reading) -> ReadingDelta {
+        const fn diff(from: u16, to: u16) -> i32 {
+            to as i32 - from as i32
+        }
+        let env = self.atmospheric().is_some() && other.atmospheric().is_some();
+        const fn env_diff(env: bool, from: u16, to: u16) -> i32 {
+            if env {
+                diff(from, to)
+            } else {
+                0
+            }
+        }
+        ReadingDelta {
+            pm1: diff(self.pm1, other.pm1),
+            pm2_5: diff(self.pm2_5, other.pm2_5),
+            pm10: diff(self.pm10, other.pm10),
+            env_pm1: env_diff(env, self.env_pm1, other.env_pm1),
+            env_pm2_5: env_diff(env, self.env_pm2_5, other.env_pm2_5),
+            env_pm10: env_diff(env, self.env_pm10, other.env_pm10),
+            particle_counts: [
+                diff(self.particles_0_3, other.particles_0_3),
+                diff(self.particles_0_5, other.particles_0_5),
+                diff(self.particles_1, other.particles_1),
+                diff(self.particles_2_5, other.particles_2_5),
+                diff(self.particles_5, other.particles_5),
+                diff(self.particles_10, other.particles_10),
+            ],
+        }
+    }
+}
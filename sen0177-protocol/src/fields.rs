@@ -0,0 +1,185 @@
+use core::iter::FusedIterator;
+
+use crate::Reading;
+
+/// The unit of mass concentrations
+pub const CONCENTRATION_UNIT: &str = "µg/m³";
+
+/// The unit of particle counts
+pub const COUNT_UNIT: &str = "particles/0.1L";
+
+/// Identifies one of a reading's measured values
+///
+/// Fields are listed (and ordered) as in the data frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldId {
+    /// The standard (CF=1) PM1 concentration
+    Pm1,
+    /// The standard (CF=1) PM2.5 concentration
+    Pm2_5,
+    /// The standard (CF=1) PM10 concentration
+    Pm10,
+    /// The environmental PM1 concentration
+    EnvPm1,
+    /// The environmental PM2.5 concentration
+    EnvPm2_5,
+    /// The environmental PM10 concentration
+    EnvPm10,
+    /// The count of particles beyond 0.3µm
+    Particles0_3,
+    /// The count of particles beyond 0.5µm
+    Particles0_5,
+    /// The count of particles beyond 1µm
+    Particles1,
+    /// The count of particles beyond 2.5µm
+    Particles2_5,
+    /// The count of particles beyond 5µm
+    Particles5,
+    /// The count of particles beyond 10µm
+    Particles10,
+}
+
+impl FieldId {
+    /// Every field, in frame order
+    pub const ALL: [FieldId; 12] = [
+        FieldId::Pm1,
+        FieldId::Pm2_5,
+        FieldId::Pm10,
+        FieldId::EnvPm1,
+        FieldId::EnvPm2_5,
+        FieldId::EnvPm10,
+        FieldId::Particles0_3,
+        FieldId::Particles0_5,
+        FieldId::Particles1,
+        FieldId::Particles2_5,
+        FieldId::Particles5,
+        FieldId::Particles10,
+    ];
+
+    /// Returns the field's name, matching its [`Reading`] accessor (and its
+    /// name when serialized, or in a CSV header)
+    pub const fn name(self) -> &'static str {
+        match self {
+            FieldId::Pm1 => "pm1",
+            FieldId::Pm2_5 => "pm2_5",
+            FieldId::Pm10 => "pm10",
+            FieldId::EnvPm1 => "env_pm1",
+            FieldId::EnvPm2_5 => "env_pm2_5",
+            FieldId::EnvPm10 => "env_pm10",
+            FieldId::Particles0_3 => "particles_0_3",
+            FieldId::Particles0_5 => "particles_0_5",
+            FieldId::Particles1 => "particles_1",
+            FieldId::Particles2_5 => "particles_2_5",
+            FieldId::Particles5 => "particles_5",
+            FieldId::Particles10 => "particles_10",
+        }
+    }
+
+    /// Returns the unit of the field's values: [`CONCENTRATION_UNIT`] or
+    /// [`COUNT_UNIT`]
+    pub const fn unit(self) -> &'static str {
+        if self.is_count() {
+            COUNT_UNIT
+        } else {
+            CONCENTRATION_UNIT
+        }
+    }
+
+    /// Returns `true` if the field is a particle count, rather than a mass
+    /// concentration
+    pub const fn is_count(self) -> bool {
+        matches!(
+            self,
+            FieldId::Particles0_3
+                | FieldId::Particles0_5
+                | FieldId::Particles1
+                | FieldId::Particles2_5
+                | FieldId::Particles5
+                | FieldId::Particles10
+        )
+    }
+
+    /// Returns the field with the given [`name`](FieldId::name)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+}
+
+impl Reading {
+    /// Returns the value of `field`, or `None` for the environmental
+    /// concentrations of a reading [without](Reading::without_atmospheric)
+    /// them
+    ///
+    /// Particle counts are per 0.1L of air, as returned by
+    /// [`ParticleCount::per_deciliter`](crate::ParticleCount::per_deciliter).
+    pub const fn get(&self, field: FieldId) -> Option<u16> {
+        Some(match field {
+            FieldId::Pm1 => self.pm1,
+            FieldId::Pm2_5 => self.pm2_5,
+            FieldId::Pm10 => self.pm10,
+            FieldId::EnvPm1 => return self.env_pm1(),
+            FieldId::EnvPm2_5 => return self.env_pm2_5(),
+            FieldId::EnvPm10 => return self.env_pm10(),
+            FieldId::Particles0_3 => self.particles_0_3,
+            FieldId::Particles0_5 => self.particles_0_5,
+            FieldId::Particles1 => self.particles_1,
+            FieldId::Particles2_5 => self.particles_2_5,
+            FieldId::Particles5 => self.particles_5,
+            FieldId::Particles10 => self.particles_10,
+        })
+    }
+
+    /// Returns an iterator over every measured value, with its field, name,
+    /// and unit, so that telemetry layers can publish them all without
+    /// naming each accessor
+    ///
+    /// Fields without a value (see [`get`](Reading::get)) are skipped.
+    ///
+    /// ```
+    /// # use sen0177_protocol::Reading;
+    /// # let reading = Reading::default();
+    /// for (_, name, value, unit) in reading.fields() {
+    ///     println!("sensors/pm/{} = {} {}", name, value, unit);
+    /// }
+    /// ```
+    pub fn fields(&self) -> Fields {
+        Fields {
+            reading: *self,
+            next: 0,
+        }
+    }
+}
+
+/// An iterator over a reading's values, returned by [`Reading::fields`]
+#[derive(Debug, Clone)]
+pub struct Fields {
+    reading: Reading,
+    next: usize,
+}
+
+impl Iterator for Fields {
+    type Item = (FieldId, &'static str, u16, &'static str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let field = *FieldId::ALL.get(self.next)?;
+            self.next += 1;
+            if let Some(value) = self.reading.get(field) {
+                return Some((field, field.name(), value, field.unit()));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = FieldId::ALL[self.next..]
+            .iter()
+            .filter(|&&field| self.reading.get(field).is_some())
+            .count();
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Fields {}
+
+impl FusedIterator for Fields {}
@@ -0,0 +1,121 @@
+use crate::{ProtocolError, Reading};
+
+/// The first "magic" byte that starts every data frame
+pub const MAGIC_BYTE_0: u8 = 0x42;
+/// The second "magic" byte that starts every data frame
+pub const MAGIC_BYTE_1: u8 = 0x4d;
+/// The length, in bytes, of a complete data frame
+pub const FRAME_LEN: usize = 32;
+
+/// Computes the checksum used by data frames and commands
+///
+/// The checksum is the sum of all bytes in `data`, truncated to 16 bits.  A
+/// full data frame can never overflow 16 bits, but the sum wraps rather than
+/// panicking for arbitrarily long input.
+pub const fn checksum(data: &[u8]) -> u16 {
+    let mut sum = 0u16;
+    let mut i = 0;
+    while i < data.len() {
+        sum = sum.wrapping_add(data[i] as u16);
+        i += 1;
+    }
+    sum
+}
+
+/// Parses a complete data frame, verifying its magic bytes and checksum
+///
+/// Like the other parsing and encoding functions, this is a `const fn`, so
+/// known frames can be parsed at compile time:
+///
+/// ```
+/// use sen0177_protocol::{parse_frame, Reading, FRAME_LEN};
+///
+/// const FRAME: [u8; FRAME_LEN] = [
+///     0x42, 0x4d, 0x00, 0x1c, 0x00, 0x05, 0x00, 0x08, 0x00, 0x0a, 0x00, 0x05,
+///     0x00, 0x08, 0x00, 0x0a, 0x03, 0xde, 0x01, 0x21, 0x00, 0x3c, 0x00, 0x08,
+///     0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x02, 0x22,
+/// ];
+/// const READING: Reading = match parse_frame(&FRAME) {
+///     Ok(reading) => reading,
+///     Err(_) => panic!("corrupt frame"),
+/// };
+/// const _: () = assert!(READING.pm2_5() == 8);
+/// ```
+pub const fn parse_frame(buf: &[u8; FRAME_LEN]) -> Result<Reading, ProtocolError> {
+    if buf[0] != MAGIC_BYTE_0 || buf[1] != MAGIC_BYTE_1 {
+        return Err(ProtocolError::BadMagic);
+    }
+
+    let computed = checksum(buf.as_slice().split_at(FRAME_LEN - 2).0);
+    let expected = as_u16(buf[FRAME_LEN - 2], buf[FRAME_LEN - 1]);
+    if expected == computed {
+        Ok(parse_frame_unchecked(buf))
+    } else {
+        Err(ProtocolError::ChecksumMismatch { expected, computed })
+    }
+}
+
+/// Parses a complete data frame without verifying its magic bytes or checksum
+///
+/// The resulting reading may contain garbage if the frame was corrupted.
+pub const fn parse_frame_unchecked(buf: &[u8; FRAME_LEN]) -> Reading {
+    Reading {
+        pm1: as_u16(buf[4], buf[5]),
+        pm2_5: as_u16(buf[6], buf[7]),
+        pm10: as_u16(buf[8], buf[9]),
+        env_pm1: as_u16(buf[10], buf[11]),
+        env_pm2_5: as_u16(buf[12], buf[13]),
+        env_pm10: as_u16(buf[14], buf[15]),
+        particles_0_3: as_u16(buf[16], buf[17]),
+        particles_0_5: as_u16(buf[18], buf[19]),
+        particles_1: as_u16(buf[20], buf[21]),
+        particles_2_5: as_u16(buf[22], buf[23]),
+        particles_5: as_u16(buf[24], buf[25]),
+        particles_10: as_u16(buf[26], buf[27]),
+        firmware_version: buf[28],
+        device_error_code: buf[29],
+    }
+}
+
+/// Encodes a reading into a complete data frame, including magic bytes,
+/// frame length, and checksum
+///
+/// A reading [without environmental
+/// concentrations](Reading::without_atmospheric) is encoded with all of
+/// them `0xffff`, which parses back to a reading without them.
+pub const fn encode_frame(reading: &Reading) -> [u8; FRAME_LEN] {
+    let words = [
+        (FRAME_LEN - 4) as u16,
+        reading.pm1,
+        reading.pm2_5,
+        reading.pm10,
+        reading.env_pm1,
+        reading.env_pm2_5,
+        reading.env_pm10,
+        reading.particles_0_3,
+        reading.particles_0_5,
+        reading.particles_1,
+        reading.particles_2_5,
+        reading.particles_5,
+        reading.particles_10,
+    ];
+    let mut buf = [0u8; FRAME_LEN];
+    buf[0] = MAGIC_BYTE_0;
+    buf[1] = MAGIC_BYTE_1;
+    let mut i = 0;
+    while i < words.len() {
+        buf[2 + 2 * i] = (words[i] >> 8) as u8;
+        buf[3 + 2 * i] = words[i] as u8;
+        i += 1;
+    }
+    buf[28] = reading.firmware_version;
+    buf[29] = reading.device_error_code;
+    let sum = checksum(buf.as_slice().split_at(FRAME_LEN - 2).0);
+    buf[FRAME_LEN - 2] = (sum >> 8) as u8;
+    buf[FRAME_LEN - 1] = sum as u8;
+    buf
+}
+
+pub(crate) const fn as_u16(hi: u8, lo: u8) -> u16 {
+    ((hi as u16) << 8) | (lo as u16)
+}
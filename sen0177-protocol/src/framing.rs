@@ -0,0 +1,234 @@
+use crate::{
+    frame::{
+        as_u16, checksum, parse_frame, parse_frame_unchecked, FRAME_LEN, MAGIC_BYTE_0, MAGIC_BYTE_1,
+    },
+    Concentrations, ProtocolError, Reading,
+};
+
+/// Describes how a sensor family frames its data on a byte stream
+///
+/// Implementing this trait is enough to reuse the crate's serial transport
+/// (synchronization, resync, timeouts, and checksum handling) for a new
+/// sensor family.
+pub trait FrameProtocol {
+    /// The value produced by parsing a frame
+    type Output;
+    /// A buffer that holds exactly one complete frame
+    type Frame: AsRef<[u8]> + AsMut<[u8]> + Copy;
+
+    /// The bytes that start every frame; must not be empty
+    const HEADER: &'static [u8];
+    /// The number of bytes (including the header) needed to decide whether
+    /// a frame is a data frame, as opposed to e.g. a command response
+    const PREFIX_LEN: usize;
+
+    /// Returns a zeroed frame buffer
+    fn new_frame() -> Self::Frame;
+
+    /// Returns `true` if `prefix` (the first [`PREFIX_LEN`](Self::PREFIX_LEN)
+    /// bytes of a frame) starts a data frame that should be parsed
+    fn is_data_frame(prefix: &[u8]) -> bool;
+
+    /// Parses a complete frame, verifying its header and checksum
+    fn parse(frame: &Self::Frame) -> Result<Self::Output, ProtocolError>;
+
+    /// Parses a complete frame without verifying its header or checksum
+    fn parse_unchecked(frame: &Self::Frame) -> Self::Output;
+}
+
+/// The frame protocol spoken by Plantower-style sensors, including the
+/// SEN0177 and PMSA003I
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Plantower;
+
+impl FrameProtocol for Plantower {
+    type Output = Reading;
+    type Frame = [u8; FRAME_LEN];
+
+    const HEADER: &'static [u8] = &[MAGIC_BYTE_0, MAGIC_BYTE_1];
+    const PREFIX_LEN: usize = 4;
+
+    fn new_frame() -> Self::Frame {
+        [0; FRAME_LEN]
+    }
+
+    fn is_data_frame(prefix: &[u8]) -> bool {
+        // Command responses share the same magic bytes, but declare a
+        // shorter length than a data frame
+        declared_len(prefix) == FRAME_LEN
+    }
+
+    fn parse(frame: &Self::Frame) -> Result<Self::Output, ProtocolError> {
+        parse_frame(frame)
+    }
+
+    fn parse_unchecked(frame: &Self::Frame) -> Self::Output {
+        parse_frame_unchecked(frame)
+    }
+}
+
+/// The Plantower frame protocol, parsing only the standard (CF=1)
+/// concentrations
+///
+/// Frames are verified exactly as with [`Plantower`], but the atmospheric
+/// concentrations, particle counts, and other fields are never extracted,
+/// so each parsed value is a 6-byte [`Concentrations`] rather than a
+/// [`Reading`].  This suits targets with very little RAM, such as 8-bit
+/// AVRs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlantowerStandard;
+
+impl FrameProtocol for PlantowerStandard {
+    type Output = Concentrations;
+    type Frame = [u8; FRAME_LEN];
+
+    const HEADER: &'static [u8] = &[MAGIC_BYTE_0, MAGIC_BYTE_1];
+    const PREFIX_LEN: usize = 4;
+
+    fn new_frame() -> Self::Frame {
+        [0; FRAME_LEN]
+    }
+
+    fn is_data_frame(prefix: &[u8]) -> bool {
+        Plantower::is_data_frame(prefix)
+    }
+
+    fn parse(frame: &Self::Frame) -> Result<Self::Output, ProtocolError> {
+        verify(frame)?;
+        Ok(Self::parse_unchecked(frame))
+    }
+
+    fn parse_unchecked(frame: &Self::Frame) -> Self::Output {
+        Concentrations::new(
+            as_u16(frame[4], frame[5]),
+            as_u16(frame[6], frame[7]),
+            as_u16(frame[8], frame[9]),
+        )
+    }
+}
+
+/// The length, in bytes, of a PMS1003/PMS3003 data frame
+pub const PMS3003_FRAME_LEN: usize = 24;
+
+/// The frame protocol spoken by the PMS1003 and PMS3003
+///
+/// These older sensors send shorter data frames with no particle counts, so
+/// readings parsed from them report zero for every count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pms3003;
+
+impl FrameProtocol for Pms3003 {
+    type Output = Reading;
+    type Frame = [u8; PMS3003_FRAME_LEN];
+
+    const HEADER: &'static [u8] = &[MAGIC_BYTE_0, MAGIC_BYTE_1];
+    const PREFIX_LEN: usize = 4;
+
+    fn new_frame() -> Self::Frame {
+        [0; PMS3003_FRAME_LEN]
+    }
+
+    fn is_data_frame(prefix: &[u8]) -> bool {
+        declared_len(prefix) == PMS3003_FRAME_LEN
+    }
+
+    fn parse(frame: &Self::Frame) -> Result<Self::Output, ProtocolError> {
+        verify(frame)?;
+        Ok(Self::parse_unchecked(frame))
+    }
+
+    fn parse_unchecked(frame: &Self::Frame) -> Self::Output {
+        Reading::new(
+            Concentrations::new(
+                as_u16(frame[4], frame[5]),
+                as_u16(frame[6], frame[7]),
+                as_u16(frame[8], frame[9]),
+            ),
+            Concentrations::new(
+                as_u16(frame[10], frame[11]),
+                as_u16(frame[12], frame[13]),
+                as_u16(frame[14], frame[15]),
+            ),
+            [0; 6],
+        )
+    }
+}
+
+/// A reading from a PMS5003T
+///
+/// The PMS5003T reports temperature and relative humidity in place of the
+/// counts of particles beyond 5µm and 10µm, so those counts are zero in
+/// `reading`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Pms5003TReading {
+    /// The particulate reading
+    pub reading: Reading,
+    /// The temperature, in tenths of a °C
+    pub temperature: i16,
+    /// The relative humidity, in tenths of a percent
+    pub relative_humidity: u16,
+}
+
+/// The frame protocol spoken by the PMS5003T
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pms5003T;
+
+impl FrameProtocol for Pms5003T {
+    type Output = Pms5003TReading;
+    type Frame = [u8; FRAME_LEN];
+
+    const HEADER: &'static [u8] = &[MAGIC_BYTE_0, MAGIC_BYTE_1];
+    const PREFIX_LEN: usize = 4;
+
+    fn new_frame() -> Self::Frame {
+        [0; FRAME_LEN]
+    }
+
+    fn is_data_frame(prefix: &[u8]) -> bool {
+        Plantower::is_data_frame(prefix)
+    }
+
+    fn parse(frame: &Self::Frame) -> Result<Self::Output, ProtocolError> {
+        verify(frame)?;
+        Ok(Self::parse_unchecked(frame))
+    }
+
+    fn parse_unchecked(frame: &Self::Frame) -> Self::Output {
+        let reading = parse_frame_unchecked(frame);
+        Pms5003TReading {
+            reading: Reading {
+                particles_5: 0,
+                particles_10: 0,
+                ..reading
+            },
+            temperature: reading.particles_5 as i16,
+            relative_humidity: reading.particles_10,
+        }
+    }
+}
+
+/// Returns the total frame length declared by the length field in `prefix`,
+/// or zero if `prefix` is too short to hold it
+fn declared_len(prefix: &[u8]) -> usize {
+    match prefix {
+        [_, _, hi, lo, ..] => 4 + usize::from(as_u16(*hi, *lo)),
+        _ => 0,
+    }
+}
+
+/// Verifies the magic bytes and trailing checksum of a complete frame
+fn verify(frame: &[u8]) -> Result<(), ProtocolError> {
+    if !frame.starts_with(&[MAGIC_BYTE_0, MAGIC_BYTE_1]) {
+        return Err(ProtocolError::BadMagic);
+    }
+    let Some((data, &[hi, lo])) = frame.split_last_chunk::<2>() else {
+        return Err(ProtocolError::BadMagic);
+    };
+    let computed = checksum(data);
+    let expected = as_u16(hi, lo);
+    if expected == computed {
+        Ok(())
+    } else {
+        Err(ProtocolError::ChecksumMismatch { expected, computed })
+    }
+}
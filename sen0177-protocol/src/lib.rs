@@ -0,0 +1,418 @@
+// Copyright 2020 Brian J. Tarricone <brian@tarricone.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod command;
+mod delta;
+mod fields;
+mod frame;
+mod framing;
+#[cfg(feature = "ufmt")]
+mod ufmt_impls;
+#[cfg(feature = "uom")]
+mod units;
+mod validate;
+
+pub use command::*;
+pub use delta::*;
+pub use fields::*;
+pub use frame::*;
+pub use framing::*;
+pub use validate::*;
+
+use core::fmt;
+
+/// A set of PM1, PM2.5, and PM10 mass concentrations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Concentrations {
+    pm1: u16,
+    pm2_5: u16,
+    pm10: u16,
+}
+
+impl Concentrations {
+    /// Creates a new set of concentrations, each in µg/m³
+    pub const fn new(pm1: u16, pm2_5: u16, pm10: u16) -> Self {
+        Self { pm1, pm2_5, pm10 }
+    }
+
+    /// Returns the PM1 concentration in µg/m³
+    pub const fn pm1(&self) -> u16 {
+        self.pm1
+    }
+
+    /// Returns the PM2.5 concentration in µg/m³
+    pub const fn pm2_5(&self) -> u16 {
+        self.pm2_5
+    }
+
+    /// Returns the PM10 concentration in µg/m³
+    pub const fn pm10(&self) -> u16 {
+        self.pm10
+    }
+}
+
+/// A number of particles counted in 0.1L (one deciliter) of air
+///
+/// The sensor reports counts per 0.1L; use the conversion methods rather than
+/// the raw value to avoid confusing units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParticleCount(pub u16);
+
+impl ParticleCount {
+    /// Returns the number of particles per 0.1L of air, as reported by the
+    /// sensor
+    pub const fn per_deciliter(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the number of particles per liter of air
+    pub const fn per_liter(&self) -> u32 {
+        self.0 as u32 * 10
+    }
+
+    /// Returns the number of particles per cubic centimeter of air, rounded
+    /// to the nearest integer
+    ///
+    /// Since 0.1L is 100cm³, this loses precision for low counts; see
+    /// [`per_cubic_centimeter_hundredths`](ParticleCount::per_cubic_centimeter_hundredths).
+    pub const fn per_cubic_centimeter(&self) -> u16 {
+        ((self.0 as u32 + 50) / 100) as u16
+    }
+
+    /// Returns the number of particles per cubic centimeter of air, in
+    /// hundredths (i.e. fixed-point with two decimal places)
+    ///
+    /// This is numerically equal to the count per 0.1L.
+    pub const fn per_cubic_centimeter_hundredths(&self) -> u16 {
+        self.0
+    }
+}
+
+/// A single air quality sensor reading
+///
+/// The sensor reports two sets of mass concentrations: "standard" values,
+/// calibrated against a standard particle (documented by Plantower as
+/// CF=1), and "environmental" values, which are intended for use under
+/// typical atmospheric conditions.  The [`cf1`](Reading::cf1) and
+/// [`atmospheric`](Reading::atmospheric) accessors group these sets
+/// together; the individual `pm*()` and `env_pm*()` accessors return the
+/// same values.
+///
+/// Some clones fill the environmental block with zeros or mirror the
+/// standard block into it.  Readings parsed from a frame carry whatever the
+/// sensor sent, but once the `sen0177` drivers detect such a sensor they
+/// mark its readings (see
+/// [`without_atmospheric`](Reading::without_atmospheric)), and the
+/// environmental accessors then return `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reading {
+    pm1: u16,
+    pm2_5: u16,
+    pm10: u16,
+    env_pm1: u16,
+    env_pm2_5: u16,
+    env_pm10: u16,
+    particles_0_3: u16,
+    particles_0_5: u16,
+    particles_1: u16,
+    particles_2_5: u16,
+    particles_5: u16,
+    particles_10: u16,
+    firmware_version: u8,
+    device_error_code: u8,
+}
+
+/// The value of every environmental concentration of a reading without
+/// them
+const NO_ENV: u16 = u16::MAX;
+
+impl Reading {
+    /// Creates a new reading
+    ///
+    /// `particle_counts` holds the counts for particles beyond 0.3µm, 0.5µm,
+    /// 1µm, 2.5µm, 5µm, and 10µm, in that order.  The firmware version and
+    /// device error code are set to zero; see
+    /// [`with_device_status`](Reading::with_device_status).
+    ///
+    /// This is mainly useful for tests and simulations; readings from a real
+    /// sensor are produced by [`parse_frame`].
+    pub const fn new(
+        cf1: Concentrations,
+        atmospheric: Concentrations,
+        particle_counts: [u16; 6],
+    ) -> Self {
+        Self {
+            pm1: cf1.pm1,
+            pm2_5: cf1.pm2_5,
+            pm10: cf1.pm10,
+            env_pm1: atmospheric.pm1,
+            env_pm2_5: atmospheric.pm2_5,
+            env_pm10: atmospheric.pm10,
+            particles_0_3: particle_counts[0],
+            particles_0_5: particle_counts[1],
+            particles_1: particle_counts[2],
+            particles_2_5: particle_counts[3],
+            particles_5: particle_counts[4],
+            particles_10: particle_counts[5],
+            firmware_version: 0,
+            device_error_code: 0,
+        }
+    }
+
+    /// Returns a copy of this reading with the given firmware version and
+    /// device error code
+    pub const fn with_device_status(self, firmware_version: u8, device_error_code: u8) -> Self {
+        Self {
+            firmware_version,
+            device_error_code,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this reading with its standard (CF=1)
+    /// concentrations replaced, e.g. by corrected values
+    pub const fn with_cf1(self, cf1: Concentrations) -> Self {
+        Self {
+            pm1: cf1.pm1,
+            pm2_5: cf1.pm2_5,
+            pm10: cf1.pm10,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this reading with its environmental (atmospheric)
+    /// concentrations replaced
+    ///
+    /// The new concentrations are taken to be genuine, even if this reading
+    /// was marked as [without](Reading::without_atmospheric) them (unless
+    /// they are all `u16::MAX`).
+    pub const fn with_atmospheric(self, atmospheric: Concentrations) -> Self {
+        Self {
+            env_pm1: atmospheric.pm1,
+            env_pm2_5: atmospheric.pm2_5,
+            env_pm10: atmospheric.pm10,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this reading marked as coming from a sensor that
+    /// doesn't report genuine environmental concentrations
+    ///
+    /// [`atmospheric`](Reading::atmospheric) and the `env_pm*()` accessors
+    /// of the copy return `None`.  The values the sensor sent are replaced
+    /// by `u16::MAX`, which is how the mark is stored (and serialized), so
+    /// that it takes no space.
+    pub const fn without_atmospheric(self) -> Self {
+        Self {
+            env_pm1: NO_ENV,
+            env_pm2_5: NO_ENV,
+            env_pm10: NO_ENV,
+            ..self
+        }
+    }
+
+    const fn has_atmospheric(&self) -> bool {
+        !(self.env_pm1 == NO_ENV && self.env_pm2_5 == NO_ENV && self.env_pm10 == NO_ENV)
+    }
+
+    /// Returns the standard (CF=1) concentrations
+    pub const fn cf1(&self) -> Concentrations {
+        Concentrations {
+            pm1: self.pm1,
+            pm2_5: self.pm2_5,
+            pm10: self.pm10,
+        }
+    }
+
+    /// Returns the environmental (atmospheric) concentrations, or `None`
+    /// if the reading was marked as coming from a sensor that doesn't
+    /// support them
+    pub const fn atmospheric(&self) -> Option<Concentrations> {
+        if !self.has_atmospheric() {
+            None
+        } else {
+            Some(Concentrations {
+                pm1: self.env_pm1,
+                pm2_5: self.env_pm2_5,
+                pm10: self.env_pm10,
+            })
+        }
+    }
+
+    /// Returns the standard (CF=1) PM1 concentration in µg/m³
+    pub const fn pm1(&self) -> u16 {
+        self.pm1
+    }
+
+    /// Returns the standard (CF=1) PM2.5 concentration in µg/m³
+    pub const fn pm2_5(&self) -> u16 {
+        self.pm2_5
+    }
+
+    /// Returns the standard (CF=1) PM10 concentration in µg/m³
+    pub const fn pm10(&self) -> u16 {
+        self.pm10
+    }
+
+    /// Returns the environmental (atmospheric) PM1 concentration in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub const fn env_pm1(&self) -> Option<u16> {
+        if !self.has_atmospheric() {
+            None
+        } else {
+            Some(self.env_pm1)
+        }
+    }
+
+    /// Returns the environmental (atmospheric) PM2.5 concentration in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub const fn env_pm2_5(&self) -> Option<u16> {
+        if !self.has_atmospheric() {
+            None
+        } else {
+            Some(self.env_pm2_5)
+        }
+    }
+
+    /// Returns the environmental (atmospheric) PM10 concentration in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub const fn env_pm10(&self) -> Option<u16> {
+        if !self.has_atmospheric() {
+            None
+        } else {
+            Some(self.env_pm10)
+        }
+    }
+
+    /// Returns the count of particles beyond 0.3µm in 0.1L of air
+    pub const fn particles_0_3(&self) -> ParticleCount {
+        ParticleCount(self.particles_0_3)
+    }
+
+    /// Returns the count of particles beyond 0.5µm in 0.1L of air
+    pub const fn particles_0_5(&self) -> ParticleCount {
+        ParticleCount(self.particles_0_5)
+    }
+
+    /// Returns the count of particles beyond 1µm in 0.1L of air
+    pub const fn particles_1(&self) -> ParticleCount {
+        ParticleCount(self.particles_1)
+    }
+
+    /// Returns the count of particles beyond 2.5µm in 0.1L of air
+    pub const fn particles_2_5(&self) -> ParticleCount {
+        ParticleCount(self.particles_2_5)
+    }
+
+    /// Returns the count of particles beyond 5µm in 0.1L of air
+    pub const fn particles_5(&self) -> ParticleCount {
+        ParticleCount(self.particles_5)
+    }
+
+    /// Returns the count of particles beyond 10µm in 0.1L of air
+    pub const fn particles_10(&self) -> ParticleCount {
+        ParticleCount(self.particles_10)
+    }
+
+    /// Returns the firmware version reported by the sensor
+    pub const fn firmware_version(&self) -> u8 {
+        self.firmware_version
+    }
+
+    /// Returns the error code reported by the sensor
+    ///
+    /// A value of zero indicates that the sensor did not report a fault.
+    pub const fn device_error_code(&self) -> u8 {
+        self.device_error_code
+    }
+
+    /// Returns the standard coarse particulate (PM10 − PM2.5) concentration
+    /// in µg/m³
+    pub const fn pm_coarse(&self) -> u16 {
+        self.pm10.saturating_sub(self.pm2_5)
+    }
+
+    /// Returns the ratio of the standard PM2.5 concentration to the standard
+    /// PM10 concentration, in thousandths
+    ///
+    /// Returns `None` if the PM10 concentration is zero.  A genuine sensor
+    /// never reports more PM2.5 than PM10, but if one does, the ratio
+    /// saturates at `u16::MAX`.
+    pub const fn fine_ratio_permille(&self) -> Option<u16> {
+        if self.pm10 > 0 {
+            let ratio = self.pm2_5 as u32 * 1000 / self.pm10 as u32;
+            Some(if ratio > u16::MAX as u32 {
+                u16::MAX
+            } else {
+                ratio as u16
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Converts the cumulative particle counts into differential counts
+    /// for each size bin
+    ///
+    /// The returned bins are, in order: 0.3–0.5µm, 0.5–1µm, 1–2.5µm,
+    /// 2.5–5µm, 5–10µm, and beyond 10µm.  If the sensor reports a larger
+    /// count for a bigger size (which is physically implausible), the
+    /// affected bin is zero.
+    pub const fn particle_bins(&self) -> [ParticleCount; 6] {
+        [
+            ParticleCount(self.particles_0_3.saturating_sub(self.particles_0_5)),
+            ParticleCount(self.particles_0_5.saturating_sub(self.particles_1)),
+            ParticleCount(self.particles_1.saturating_sub(self.particles_2_5)),
+            ParticleCount(self.particles_2_5.saturating_sub(self.particles_5)),
+            ParticleCount(self.particles_5.saturating_sub(self.particles_10)),
+            ParticleCount(self.particles_10),
+        ]
+    }
+}
+
+/// Describes errors encountered while parsing a data frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The frame did not start with the expected "magic" bytes
+    BadMagic,
+    /// The checksum provided in the frame did not match the checksum of the data itself
+    ChecksumMismatch {
+        /// The checksum stored in the frame
+        expected: u16,
+        /// The checksum computed from the frame's contents
+        computed: u16,
+    },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ProtocolError::*;
+        match self {
+            BadMagic => f.write_str("Unable to find magic bytes at start of payload"),
+            ChecksumMismatch { expected, computed } => write!(
+                f,
+                "Data read was corrupt (expected checksum {:#06x}, computed {:#06x})",
+                expected, computed
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ProtocolError {}
@@ -0,0 +1,112 @@
+//! [`ufmt`] implementations, for printing readings on targets where
+//! `core::fmt` is too heavy.
+
+use crate::{
+    Concentrations, Implausibility, ParticleCount, ProtocolError, Reading, MAX_CONCENTRATION,
+};
+use ufmt::{uDebug, uDisplay, uWrite, uwrite, Formatter};
+
+impl uDebug for Concentrations {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_struct("Concentrations")?
+            .field("pm1", &self.pm1)?
+            .field("pm2_5", &self.pm2_5)?
+            .field("pm10", &self.pm10)?
+            .finish()
+    }
+}
+
+impl uDebug for ParticleCount {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_tuple("ParticleCount")?.field(&self.0)?.finish()
+    }
+}
+
+impl uDebug for Reading {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_struct("Reading")?
+            .field("pm1", &self.pm1)?
+            .field("pm2_5", &self.pm2_5)?
+            .field("pm10", &self.pm10)?
+            .field("env_pm1", &self.env_pm1)?
+            .field("env_pm2_5", &self.env_pm2_5)?
+            .field("env_pm10", &self.env_pm10)?
+            .field("particles_0_3", &self.particles_0_3)?
+            .field("particles_0_5", &self.particles_0_5)?
+            .field("particles_1", &self.particles_1)?
+            .field("particles_2_5", &self.particles_2_5)?
+            .field("particles_5", &self.particles_5)?
+            .field("particles_10", &self.particles_10)?
+            .field("firmware_version", &self.firmware_version)?
+            .field("device_error_code", &self.device_error_code)?
+            .finish()
+    }
+}
+
+/// Prints the standard concentrations, e.g. `PM1 5, PM2.5 12, PM10 20 µg/m³`
+impl uDisplay for Reading {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(
+            f,
+            "PM1 {}, PM2.5 {}, PM10 {} µg/m³",
+            self.pm1,
+            self.pm2_5,
+            self.pm10
+        )
+    }
+}
+
+impl uDebug for ProtocolError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            ProtocolError::BadMagic => f.write_str("BadMagic"),
+            ProtocolError::ChecksumMismatch { expected, computed } => f
+                .debug_struct("ChecksumMismatch")?
+                .field("expected", expected)?
+                .field("computed", computed)?
+                .finish(),
+        }
+    }
+}
+
+impl uDisplay for ProtocolError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            ProtocolError::BadMagic => {
+                f.write_str("Unable to find magic bytes at start of payload")
+            }
+            ProtocolError::ChecksumMismatch { expected, computed } => uwrite!(
+                f,
+                "Data read was corrupt (expected checksum {}, computed {})",
+                expected,
+                computed
+            ),
+        }
+    }
+}
+
+impl uDebug for Implausibility {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            Implausibility::ConcentrationOrder => "ConcentrationOrder",
+            Implausibility::ParticleCountOrder => "ParticleCountOrder",
+            Implausibility::OutOfRange => "OutOfRange",
+        })
+    }
+}
+
+impl uDisplay for Implausibility {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            Implausibility::ConcentrationOrder => {
+                f.write_str("PM concentrations are not ordered PM1 ≤ PM2.5 ≤ PM10")
+            }
+            Implausibility::ParticleCountOrder => {
+                f.write_str("Particle counts increase with particle size")
+            }
+            Implausibility::OutOfRange => {
+                uwrite!(f, "Concentration exceeds {}µg/m³", MAX_CONCENTRATION)
+            }
+        }
+    }
+}
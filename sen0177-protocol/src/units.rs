@@ -0,0 +1,77 @@
+//! Accessors returning [`uom`] quantities, so that concentrations and
+//! particle counts carry their units in the type system.
+
+use crate::{Concentrations, ParticleCount, Reading};
+use uom::si::{
+    f32::{MassConcentration, VolumetricNumberDensity},
+    mass_concentration::microgram_per_cubic_meter,
+    volumetric_number_density::per_cubic_meter,
+};
+
+/// The number of 0.1L volumes (in which the sensor counts particles) in a
+/// cubic meter
+const DECILITERS_PER_CUBIC_METER: f32 = 10_000.0;
+
+fn mass(micrograms_per_cubic_meter: u16) -> MassConcentration {
+    MassConcentration::new::<microgram_per_cubic_meter>(micrograms_per_cubic_meter as f32)
+}
+
+impl Concentrations {
+    /// Returns the PM1 concentration as a [`MassConcentration`]
+    pub fn pm1_mass(&self) -> MassConcentration {
+        mass(self.pm1)
+    }
+
+    /// Returns the PM2.5 concentration as a [`MassConcentration`]
+    pub fn pm2_5_mass(&self) -> MassConcentration {
+        mass(self.pm2_5)
+    }
+
+    /// Returns the PM10 concentration as a [`MassConcentration`]
+    pub fn pm10_mass(&self) -> MassConcentration {
+        mass(self.pm10)
+    }
+}
+
+impl ParticleCount {
+    /// Returns the number of particles per unit volume as a
+    /// [`VolumetricNumberDensity`]
+    pub fn density(&self) -> VolumetricNumberDensity {
+        VolumetricNumberDensity::new::<per_cubic_meter>(self.0 as f32 * DECILITERS_PER_CUBIC_METER)
+    }
+}
+
+impl Reading {
+    /// Returns the standard PM1 concentration as a [`MassConcentration`]
+    pub fn pm1_mass(&self) -> MassConcentration {
+        mass(self.pm1)
+    }
+
+    /// Returns the standard PM2.5 concentration as a [`MassConcentration`]
+    pub fn pm2_5_mass(&self) -> MassConcentration {
+        mass(self.pm2_5)
+    }
+
+    /// Returns the standard PM10 concentration as a [`MassConcentration`]
+    pub fn pm10_mass(&self) -> MassConcentration {
+        mass(self.pm10)
+    }
+
+    /// Returns the environmental PM1 concentration as a
+    /// [`MassConcentration`], or `None` if the sensor doesn't support it
+    pub fn env_pm1_mass(&self) -> Option<MassConcentration> {
+        self.env_pm1().map(mass)
+    }
+
+    /// Returns the environmental PM2.5 concentration as a
+    /// [`MassConcentration`], or `None` if the sensor doesn't support it
+    pub fn env_pm2_5_mass(&self) -> Option<MassConcentration> {
+        self.env_pm2_5().map(mass)
+    }
+
+    /// Returns the environmental PM10 concentration as a
+    /// [`MassConcentration`], or `None` if the sensor doesn't support it
+    pub fn env_pm10_mass(&self) -> Option<MassConcentration> {
+        self.env_pm10().map(mass)
+    }
+}
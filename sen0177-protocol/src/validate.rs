@@ -0,0 +1,59 @@
+use crate::Reading;
+use core::fmt;
+
+/// The maximum mass concentration, in µg/m³, that the sensor is documented
+/// to be able to report
+pub const MAX_CONCENTRATION: u16 = 1000;
+
+/// Describes why a reading was deemed physically implausible
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Implausibility {
+    /// The standard concentrations were not ordered PM1 ≤ PM2.5 ≤ PM10
+    ConcentrationOrder,
+    /// The cumulative particle counts increased with particle size
+    ParticleCountOrder,
+    /// A standard concentration exceeded [`MAX_CONCENTRATION`]
+    OutOfRange,
+}
+
+impl fmt::Display for Implausibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Implausibility::*;
+        match self {
+            ConcentrationOrder => {
+                f.write_str("PM concentrations are not ordered PM1 ≤ PM2.5 ≤ PM10")
+            }
+            ParticleCountOrder => f.write_str("Particle counts increase with particle size"),
+            OutOfRange => write!(f, "Concentration exceeds {}µg/m³", MAX_CONCENTRATION),
+        }
+    }
+}
+
+/// Checks a reading for physical consistency
+///
+/// This verifies that the standard concentrations are ordered
+/// PM1 ≤ PM2.5 ≤ PM10, that the cumulative particle counts do not increase
+/// with particle size, and that no standard concentration exceeds
+/// [`MAX_CONCENTRATION`].  The environmental concentrations are not checked,
+/// as some devices do not populate them.
+pub fn validate(reading: &Reading) -> Result<(), Implausibility> {
+    if reading.pm1().max(reading.pm2_5()).max(reading.pm10()) > MAX_CONCENTRATION {
+        Err(Implausibility::OutOfRange)
+    } else if reading.pm1() > reading.pm2_5() || reading.pm2_5() > reading.pm10() {
+        Err(Implausibility::ConcentrationOrder)
+    } else if [
+        reading.particles_0_3(),
+        reading.particles_0_5(),
+        reading.particles_1(),
+        reading.particles_2_5(),
+        reading.particles_5(),
+        reading.particles_10(),
+    ]
+    .windows(2)
+    .any(|pair| matches!(pair, [smaller, larger] if smaller < larger))
+    {
+        Err(Implausibility::ParticleCountOrder)
+    } else {
+        Ok(())
+    }
+}
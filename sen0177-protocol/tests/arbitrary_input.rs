@@ -0,0 +1,53 @@
+//! Feeds arbitrary bytes to the parsers, which must reject or accept them
+//! without panicking
+
+use sen0177_protocol::*;
+
+/// Fills `buf` with pseudo-random bytes, starting it with a frame header
+/// some of the time so that parsing gets past the magic bytes
+fn fill(buf: &mut [u8], state: &mut u32) {
+    for byte in buf.iter_mut() {
+        *state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        *byte = (*state >> 16) as u8;
+    }
+    if *state & 0x3 != 0 {
+        buf[0] = MAGIC_BYTE_0;
+        buf[1] = MAGIC_BYTE_1;
+    }
+}
+
+fn exercise<P: FrameProtocol>(state: &mut u32) {
+    let mut frame = P::new_frame();
+    for _ in 0..10_000 {
+        fill(frame.as_mut(), state);
+        let _ = P::is_data_frame(frame.as_ref());
+        for len in 0..=4 {
+            let _ = P::is_data_frame(&frame.as_ref()[..len]);
+        }
+        let _ = P::parse(&frame);
+        let _ = P::parse_unchecked(&frame);
+    }
+}
+
+#[test]
+fn protocols_survive_arbitrary_frames() {
+    let mut state = 1;
+    exercise::<Plantower>(&mut state);
+    exercise::<PlantowerStandard>(&mut state);
+    exercise::<Pms3003>(&mut state);
+    exercise::<Pms5003T>(&mut state);
+}
+
+#[test]
+fn parse_frame_survives_arbitrary_frames() {
+    let mut state = 2;
+    let mut frame = [0u8; FRAME_LEN];
+    for _ in 0..10_000 {
+        fill(&mut frame, &mut state);
+        if let Ok(reading) = parse_frame(&frame) {
+            let _ = validate(&reading);
+        }
+        let _ = validate(&parse_frame_unchecked(&frame));
+        let _ = checksum(&frame);
+    }
+}
@@ -0,0 +1,40 @@
+use sen0177_protocol::*;
+
+#[test]
+fn checksum_of_worst_case_noise_does_not_overflow() {
+    let noise = [0xffu8; FRAME_LEN - 2];
+    assert_eq!(checksum(&noise), 0xff * (FRAME_LEN as u16 - 2));
+}
+
+#[test]
+fn checksum_wraps_on_long_input() {
+    let noise = [0xffu8; 300];
+    assert_eq!(checksum(&noise), (0xffu32 * 300 % 0x10000) as u16);
+}
+
+#[test]
+fn noise_frame_is_rejected() {
+    let mut frame = [0xffu8; FRAME_LEN];
+    frame[0] = MAGIC_BYTE_0;
+    frame[1] = MAGIC_BYTE_1;
+    assert!(matches!(
+        parse_frame(&frame),
+        Err(ProtocolError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn valid_frame_round_trips() {
+    let mut frame = [0u8; FRAME_LEN];
+    frame[0] = MAGIC_BYTE_0;
+    frame[1] = MAGIC_BYTE_1;
+    frame[3] = (FRAME_LEN - 4) as u8;
+    frame[7] = 12;
+    let sum = checksum(&frame[0..FRAME_LEN - 2]);
+    frame[FRAME_LEN - 2] = (sum >> 8) as u8;
+    frame[FRAME_LEN - 1] = sum as u8;
+
+    let reading = parse_frame(&frame).unwrap();
+    assert_eq!(reading.pm2_5(), 12);
+    assert_eq!(encode_frame(&reading), frame);
+}
@@ -0,0 +1,44 @@
+//! Golden frames parsed and validated at compile time
+
+use sen0177_protocol::*;
+
+const READING: Reading = Reading::new(
+    Concentrations::new(5, 8, 10),
+    Concentrations::new(4, 7, 9),
+    [990, 289, 60, 8, 2, 0],
+)
+.with_device_status(0x91, 0);
+
+const FRAME: [u8; FRAME_LEN] = encode_frame(&READING);
+
+const PARSED: Reading = match parse_frame(&FRAME) {
+    Ok(reading) => reading,
+    Err(_) => panic!("golden frame failed to parse"),
+};
+
+const CORRUPTED: [u8; FRAME_LEN] = {
+    let mut frame = FRAME;
+    frame[6] ^= 0x01;
+    frame
+};
+
+const _: () = {
+    assert!(PARSED.pm1() == 5);
+    assert!(PARSED.pm2_5() == 8);
+    assert!(matches!(PARSED.env_pm10(), Some(9)));
+    assert!(PARSED.particles_0_3().per_deciliter() == 990);
+    assert!(PARSED.firmware_version() == 0x91);
+    assert!(checksum(&[0x42, 0x4d, 0x01]) == 0x90);
+    assert!(matches!(
+        parse_frame(&CORRUPTED),
+        Err(ProtocolError::ChecksumMismatch { .. })
+    ));
+    assert!(encode_command(Command::Sleep)[2] == 0xe4);
+};
+
+#[test]
+fn const_parse_matches_runtime_parse() {
+    assert_eq!(PARSED, READING);
+    assert_eq!(parse_frame(&FRAME).unwrap(), PARSED);
+    assert_eq!(FRAME, encode_frame(&READING));
+}
@@ -0,0 +1,32 @@
+use sen0177_protocol::*;
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(0, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0])
+}
+
+#[test]
+fn fine_ratio_in_thousandths() {
+    assert_eq!(reading(12, 0).fine_ratio_permille(), None);
+    assert_eq!(reading(12, 24).fine_ratio_permille(), Some(500));
+    assert_eq!(reading(20, 30).fine_ratio_permille(), Some(666));
+    assert_eq!(reading(30, 30).fine_ratio_permille(), Some(1000));
+}
+
+#[test]
+fn fine_ratio_saturates() {
+    assert_eq!(reading(100, 1).fine_ratio_permille(), Some(u16::MAX));
+    assert_eq!(reading(65, 1).fine_ratio_permille(), Some(65_000));
+}
+
+#[test]
+fn coarse_concentration_and_bins() {
+    assert_eq!(reading(12, 24).pm_coarse(), 12);
+    assert_eq!(reading(30, 20).pm_coarse(), 0);
+    assert_eq!(
+        reading(12, 24)
+            .particle_bins()
+            .map(|count| count.per_deciliter()),
+        [400, 160, 35, 4, 1, 0]
+    );
+}
@@ -0,0 +1,68 @@
+use sen0177_protocol::*;
+
+fn reading() -> Reading {
+    Reading::new(
+        Concentrations::new(6, 12, 24),
+        Concentrations::new(5, 11, 22),
+        [600, 200, 40, 5, 1, 0],
+    )
+    .with_device_status(0x91, 0)
+}
+
+#[test]
+fn fields_match_accessors() {
+    let reading = reading();
+    let fields: Vec<_> = reading.fields().collect();
+    assert_eq!(fields.len(), 12);
+    assert_eq!(fields[1], (FieldId::Pm2_5, "pm2_5", 12, "µg/m³"));
+    assert_eq!(fields[5], (FieldId::EnvPm10, "env_pm10", 22, "µg/m³"));
+    assert_eq!(
+        fields[6],
+        (
+            FieldId::Particles0_3,
+            "particles_0_3",
+            600,
+            "particles/0.1L"
+        )
+    );
+    let values: Vec<_> = fields.iter().map(|&(_, _, value, _)| value).collect();
+    assert_eq!(values, [6, 12, 24, 5, 11, 22, 600, 200, 40, 5, 1, 0]);
+    assert_eq!(
+        reading.get(FieldId::Particles2_5),
+        Some(reading.particles_2_5().per_deciliter())
+    );
+}
+
+#[test]
+fn fields_skip_unsupported_environmental_values() {
+    let reading = reading().without_atmospheric();
+    assert_eq!(reading.get(FieldId::EnvPm2_5), None);
+    assert_eq!(reading.get(FieldId::Pm2_5), Some(12));
+    let fields = reading.fields();
+    assert_eq!(fields.len(), 9);
+    let values: Vec<_> = fields.map(|(_, _, value, _)| value).collect();
+    assert_eq!(values, [6, 12, 24, 600, 200, 40, 5, 1, 0]);
+}
+
+#[test]
+fn fields_iterator_is_exact_size() {
+    let mut fields = reading().fields();
+    assert_eq!(fields.len(), 12);
+    fields.nth(10);
+    assert_eq!(fields.len(), 1);
+    assert!(fields.next().is_some());
+    assert!(fields.next().is_none());
+    assert!(fields.next().is_none());
+}
+
+#[test]
+fn names_round_trip() {
+    for field in FieldId::ALL {
+        assert_eq!(FieldId::from_name(field.name()), Some(field));
+    }
+    assert_eq!(FieldId::from_name("firmware_version"), None);
+    assert_eq!(
+        FieldId::ALL.iter().filter(|field| field.is_count()).count(),
+        6
+    );
+}
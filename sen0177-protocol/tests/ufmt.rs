@@ -0,0 +1,81 @@
+use sen0177_protocol::*;
+use ufmt::{uWrite, uwrite};
+
+/// A fixed-size buffer, as used on targets without an allocator
+struct Buffer {
+    bytes: [u8; 128],
+    len: usize,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Self {
+            bytes: [0; 128],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+impl uWrite for Buffer {
+    type Error = ();
+
+    fn write_str(&mut self, s: &str) -> Result<(), ()> {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(())?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn reading() -> Reading {
+    Reading::new(
+        Concentrations::new(5, 12, 20),
+        Concentrations::new(5, 11, 19),
+        [600, 200, 40, 5, 1, 0],
+    )
+}
+
+#[test]
+fn displays_reading() {
+    let mut buffer = Buffer::new();
+    uwrite!(&mut buffer, "{}", reading()).unwrap();
+    assert_eq!(buffer.as_str(), "PM1 5, PM2.5 12, PM10 20 µg/m³");
+}
+
+#[test]
+fn debugs_concentrations() {
+    let mut buffer = Buffer::new();
+    uwrite!(&mut buffer, "{:?}", Concentrations::new(5, 12, 20)).unwrap();
+    assert_eq!(
+        buffer.as_str(),
+        "Concentrations { pm1: 5, pm2_5: 12, pm10: 20 }"
+    );
+}
+
+#[test]
+fn displays_errors() {
+    let mut buffer = Buffer::new();
+    let error = ProtocolError::ChecksumMismatch {
+        expected: 0x1234,
+        computed: 0x1235,
+    };
+    uwrite!(&mut buffer, "{}", error).unwrap();
+    assert_eq!(
+        buffer.as_str(),
+        "Data read was corrupt (expected checksum 4660, computed 4661)"
+    );
+}
+
+#[test]
+fn full_buffer_reports_an_error() {
+    let mut buffer = Buffer::new();
+    buffer.len = buffer.bytes.len() - 4;
+    assert_eq!(uwrite!(&mut buffer, "{}", reading()), Err(()));
+}
@@ -0,0 +1,53 @@
+use sen0177_protocol::*;
+use uom::si::{
+    mass_concentration::{microgram_per_cubic_meter, milligram_per_cubic_meter},
+    volumetric_number_density::{per_cubic_centimeter, per_cubic_meter},
+};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * 1e-4,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+fn reading() -> Reading {
+    Reading::new(
+        Concentrations::new(6, 12, 24),
+        Concentrations::new(5, 11, 22),
+        [600, 200, 40, 5, 1, 0],
+    )
+}
+
+#[test]
+fn mass_concentrations_convert_between_units() {
+    let pm2_5 = reading().pm2_5_mass();
+    assert_close(pm2_5.get::<microgram_per_cubic_meter>(), 12.0);
+    assert_close(pm2_5.get::<milligram_per_cubic_meter>(), 0.012);
+    assert_close(
+        reading()
+            .env_pm10_mass()
+            .unwrap()
+            .get::<microgram_per_cubic_meter>(),
+        22.0,
+    );
+    assert_close(
+        reading()
+            .atmospheric()
+            .unwrap()
+            .pm2_5_mass()
+            .get::<microgram_per_cubic_meter>(),
+        11.0,
+    );
+}
+
+#[test]
+fn particle_counts_are_per_tenth_of_a_liter() {
+    // 200 particles per 0.1L is 2000 per liter, or 2 per cm³
+    let density = reading().particles_0_5().density();
+    assert_close(density.get::<per_cubic_centimeter>(), 2.0);
+    assert_close(density.get::<per_cubic_meter>(), 2_000_000.0);
+    assert_close(ParticleCount(0).density().get::<per_cubic_meter>(), 0.0);
+}
@@ -0,0 +1,292 @@
+//! Python bindings for the `sen0177` crate.
+//!
+//! This builds a Python extension module named `sen0177` with
+//! [maturin](https://www.maturin.rs/):
+//!
+//! ```text
+//! cd sen0177-python && maturin develop --release
+//! ```
+//!
+//! It exposes the frame parser, the serial driver on a Linux serial port,
+//! and the AQI and humidity-correction math, so that data from the sensor
+//! can be analyzed in notebooks with the same code that runs on the
+//! device:
+//!
+//! ```python
+//! import sen0177
+//!
+//! sensor = sen0177.Sensor("/dev/ttyUSB0", timeout_ms=3000)
+//! reading = sensor.read()
+//! aqi, category = sen0177.aqi_pm2_5(reading.pm2_5 * 10)
+//! print(reading.pm2_5, aqi, category)
+//! ```
+
+use pyo3::{create_exception, exceptions::PyException, exceptions::PyValueError, prelude::*};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+use std::time::Duration;
+
+use sen0177::{
+    aqi::{self, Aqi, AqiCalculator, AqiCategory},
+    humidity::{self, Calibration},
+    io::{IoError, IoSerial},
+    protocol::{self, FRAME_LEN},
+    serial::{Sen0177, Sen0177Builder},
+};
+
+create_exception!(
+    sen0177,
+    SensorError,
+    PyException,
+    "Raised when reading from the sensor fails"
+);
+
+/// How long each read from the serial port waits for data
+const READ_TIMEOUT_MS: u32 = 100;
+
+/// A single measurement from the sensor
+#[pyclass(frozen, name = "Reading", module = "sen0177")]
+#[derive(Clone, Copy)]
+struct PyReading(sen0177::Reading);
+
+#[pymethods]
+impl PyReading {
+    /// The standard (CF=1) PM1 concentration, in µg/m³
+    #[getter]
+    fn pm1(&self) -> u16 {
+        self.0.pm1()
+    }
+
+    /// The standard (CF=1) PM2.5 concentration, in µg/m³
+    #[getter]
+    fn pm2_5(&self) -> u16 {
+        self.0.pm2_5()
+    }
+
+    /// The standard (CF=1) PM10 concentration, in µg/m³
+    #[getter]
+    fn pm10(&self) -> u16 {
+        self.0.pm10()
+    }
+
+    /// The environmental PM1 concentration, in µg/m³, or `None` if the
+    /// sensor doesn't support it
+    #[getter]
+    fn env_pm1(&self) -> Option<u16> {
+        self.0.env_pm1()
+    }
+
+    /// The environmental PM2.5 concentration, in µg/m³, or `None` if the
+    /// sensor doesn't support it
+    #[getter]
+    fn env_pm2_5(&self) -> Option<u16> {
+        self.0.env_pm2_5()
+    }
+
+    /// The environmental PM10 concentration, in µg/m³, or `None` if the
+    /// sensor doesn't support it
+    #[getter]
+    fn env_pm10(&self) -> Option<u16> {
+        self.0.env_pm10()
+    }
+
+    /// The counts of particles beyond 0.3µm, 0.5µm, 1µm, 2.5µm, 5µm, and
+    /// 10µm, in 0.1L of air
+    #[getter]
+    fn particle_counts(&self) -> [u16; 6] {
+        self.0.particle_bins().map(|count| count.per_deciliter())
+    }
+
+    /// The firmware version reported by the sensor
+    #[getter]
+    fn firmware_version(&self) -> u8 {
+        self.0.firmware_version()
+    }
+
+    /// The error code reported by the sensor
+    #[getter]
+    fn device_error_code(&self) -> u8 {
+        self.0.device_error_code()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Reading(pm1={}, pm2_5={}, pm10={})",
+            self.0.pm1(),
+            self.0.pm2_5(),
+            self.0.pm10()
+        )
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// A sensor on a Linux serial port, in active mode
+#[pyclass(name = "Sensor", module = "sen0177")]
+struct PySensor {
+    sensor: Sen0177<IoSerial<Box<dyn serialport::SerialPort>>, IoError>,
+}
+
+#[pymethods]
+impl PySensor {
+    /// Opens the sensor on the serial port at `path`, at 9600 baud
+    ///
+    /// Reads raise `SensorError` after waiting about `timeout_ms`
+    /// milliseconds for data; a timeout of zero waits forever.
+    #[new]
+    #[pyo3(signature = (path, timeout_ms = 3000))]
+    fn new(path: &str, timeout_ms: u32) -> PyResult<Self> {
+        let port = serialport::new(path, 9600)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .flow_control(FlowControl::None)
+            .timeout(Duration::from_millis(READ_TIMEOUT_MS.into()))
+            .open()
+            .map_err(|err| SensorError::new_err(format!("Failed to open {}: {}", path, err)))?;
+        let mut builder = Sen0177Builder::new();
+        if timeout_ms > 0 {
+            builder = builder.timeout_polls(timeout_ms.div_ceil(READ_TIMEOUT_MS));
+        }
+        Ok(Self {
+            sensor: builder.build(IoSerial::new(port)),
+        })
+    }
+
+    /// Reads a single measurement, releasing the GIL while waiting for it
+    fn read(&mut self, py: Python<'_>) -> PyResult<PyReading> {
+        let sensor = &mut self.sensor;
+        py.allow_threads(move || sensor.read_raw())
+            .map(|(_, reading)| PyReading(reading))
+            .map_err(|err| SensorError::new_err(err.to_string()))
+    }
+}
+
+/// Parses a complete 32-byte data frame, verifying its magic bytes and
+/// checksum
+///
+/// Raises `ValueError` if the frame is the wrong length or corrupt.
+#[pyfunction]
+fn parse_frame(frame: &[u8]) -> PyResult<PyReading> {
+    let frame: &[u8; FRAME_LEN] = frame.try_into().map_err(|_| {
+        PyValueError::new_err(format!(
+            "Expected a {}-byte frame, got {} bytes",
+            FRAME_LEN,
+            frame.len()
+        ))
+    })?;
+    protocol::parse_frame(frame)
+        .map(PyReading)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn category_name(category: AqiCategory) -> &'static str {
+    match category {
+        AqiCategory::Good => "Good",
+        AqiCategory::Moderate => "Moderate",
+        AqiCategory::UnhealthyForSensitiveGroups => "Unhealthy for Sensitive Groups",
+        AqiCategory::Unhealthy => "Unhealthy",
+        AqiCategory::VeryUnhealthy => "Very Unhealthy",
+        AqiCategory::Hazardous => "Hazardous",
+    }
+}
+
+fn aqi_tuple(aqi: Aqi) -> (u16, &'static str) {
+    (aqi.value(), category_name(aqi.category()))
+}
+
+/// Computes the US EPA AQI from readings, averaged over hours as the EPA
+/// requires
+#[pyclass(name = "AqiCalculator", module = "sen0177")]
+struct PyAqiCalculator(AqiCalculator);
+
+#[pymethods]
+impl PyAqiCalculator {
+    /// Creates a calculator over timestamps in seconds, expecting a reading
+    /// every `interval_secs` seconds
+    #[new]
+    fn new(interval_secs: u32) -> Self {
+        Self(AqiCalculator::new(interval_secs))
+    }
+
+    /// Adds a reading taken at `timestamp`
+    fn push(&mut self, timestamp: u64, reading: PyReading) {
+        self.0.push(timestamp, reading.0);
+    }
+
+    /// Removes all readings
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The NowCast AQI and its category, or `None` unless two of the last
+    /// three hours hold readings
+    fn nowcast(&self) -> Option<(u16, &'static str)> {
+        self.0.nowcast().map(aqi_tuple)
+    }
+
+    /// The 24-hour AQI and its category, or `None` unless at least 75% of
+    /// the readings expected over the day are present
+    fn daily(&self) -> Option<(u16, &'static str)> {
+        self.0.daily().map(aqi_tuple)
+    }
+}
+
+/// Computes the US EPA AQI, and its category, for a PM2.5 concentration
+/// given in tenths of a µg/m³
+#[pyfunction]
+fn aqi_pm2_5(concentration_tenths: u32) -> (u16, &'static str) {
+    aqi_tuple(aqi::pm2_5(concentration_tenths))
+}
+
+/// Computes the US EPA AQI, and its category, for a PM10 concentration
+/// given in tenths of a µg/m³
+#[pyfunction]
+fn aqi_pm10(concentration_tenths: u32) -> (u16, &'static str) {
+    aqi_tuple(aqi::pm10(concentration_tenths))
+}
+
+/// Applies the US EPA's nationwide correction for PurpleAir sensors to a
+/// standard (CF=1) PM2.5 concentration in µg/m³, with relative humidity in
+/// tenths of a percent, returning tenths of a µg/m³
+#[pyfunction]
+fn epa_pm2_5(pm2_5: u16, relative_humidity: u16) -> u32 {
+    humidity::epa_pm2_5(pm2_5, relative_humidity)
+}
+
+/// Applies a linear correction with the given coefficients (in
+/// ten-thousandths, as fitted by `sen0177::calibrate`) to a standard
+/// (CF=1) PM2.5 concentration in µg/m³, with relative humidity in tenths
+/// of a percent, returning tenths of a µg/m³
+#[pyfunction]
+#[pyo3(signature = (pm2_5, relative_humidity, slope, humidity = 0, offset = 0))]
+fn correct_pm2_5(
+    pm2_5: u16,
+    relative_humidity: u16,
+    slope: i32,
+    humidity: i32,
+    offset: i32,
+) -> u32 {
+    Calibration {
+        slope,
+        humidity,
+        offset,
+    }
+    .apply(pm2_5, relative_humidity)
+}
+
+#[pymodule]
+fn sen0177(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("SensorError", m.py().get_type_bound::<SensorError>())?;
+    m.add("FRAME_LEN", FRAME_LEN)?;
+    m.add_class::<PyReading>()?;
+    m.add_class::<PySensor>()?;
+    m.add_class::<PyAqiCalculator>()?;
+    m.add_function(wrap_pyfunction!(parse_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(aqi_pm2_5, m)?)?;
+    m.add_function(wrap_pyfunction!(aqi_pm10, m)?)?;
+    m.add_function(wrap_pyfunction!(epa_pm2_5, m)?)?;
+    m.add_function(wrap_pyfunction!(correct_pm2_5, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+import pytest
+
+import sen0177
+
+
+def frame(pm2_5):
+    data = bytearray(32)
+    data[0:4] = b"\x42\x4d\x00\x1c"
+    for offset in (4, 10):
+        data[offset:offset + 6] = bytes([0, pm2_5 // 2, 0, pm2_5, 0, pm2_5 * 2])
+    checksum = sum(data[:30])
+    data[30:32] = checksum.to_bytes(2, "big")
+    return bytes(data)
+
+
+def test_parses_frame():
+    reading = sen0177.parse_frame(frame(12))
+    assert (reading.pm1, reading.pm2_5, reading.pm10) == (6, 12, 24)
+    assert reading.env_pm2_5 == 12
+
+
+def test_rejects_wrong_length():
+    with pytest.raises(ValueError):
+        sen0177.parse_frame(frame(12)[:31])
+
+
+def test_rejects_bad_checksum():
+    data = bytearray(frame(12))
+    data[31] ^= 1
+    with pytest.raises(ValueError):
+        sen0177.parse_frame(bytes(data))
+
+
+def test_computes_aqi():
+    assert sen0177.aqi_pm2_5(90) == (50, "Good")
+    assert sen0177.aqi_pm10(0) == (0, "Good")
+
+
+def test_averages_aqi_over_hours():
+    calculator = sen0177.AqiCalculator(60)
+    reading = sen0177.parse_frame(frame(12))
+    calculator.push(0, reading)
+    assert calculator.nowcast() is None
+    for minute in range(1, 2 * 60):
+        calculator.push(minute * 60, reading)
+    assert calculator.nowcast() == sen0177.aqi_pm2_5(120)
+    assert calculator.daily() is None
+
+
+def test_applies_epa_correction():
+    assert sen0177.epa_pm2_5(20, 500) == sen0177.correct_pm2_5(20, 500, 5240, -862, 57500)
+
+
+def test_open_failure_raises_sensor_error():
+    with pytest.raises(sen0177.SensorError):
+        sen0177.Sensor("/dev/does-not-exist")
@@ -0,0 +1,80 @@
+use crate::Reading;
+
+/// A pair of thresholds implementing hysteresis
+///
+/// An alert is entered when a value rises to `rising` or above, and is not
+/// exited until the value drops below `falling`.  `falling` should be less
+/// than or equal to `rising`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    /// The value at or above which the alert is entered
+    pub rising: u16,
+    /// The value below which the alert is exited
+    pub falling: u16,
+}
+
+/// An event emitted when a watched value crosses a [`Threshold`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertEvent {
+    /// The value rose to or above the rising threshold
+    Entered,
+    /// The value fell below the falling threshold
+    Exited,
+}
+
+/// Watches a single value extracted from each reading and reports when it
+/// crosses a [`Threshold`]
+///
+/// The value is extracted by `field`, which can select any reading field
+/// (e.g. `Reading::pm2_5`) or compute a derived value such as an AQI.
+#[derive(Debug, Clone, Copy)]
+pub struct ThresholdWatcher<F> {
+    threshold: Threshold,
+    field: F,
+    active: bool,
+}
+
+impl<F> ThresholdWatcher<F>
+where
+    F: Fn(&Reading) -> u16,
+{
+    /// Creates a new watcher over the value selected by `field`
+    pub fn new(threshold: Threshold, field: F) -> Self {
+        Self {
+            threshold,
+            field,
+            active: false,
+        }
+    }
+
+    /// Updates the watcher with a new reading, returning an event if the
+    /// watched value crossed the threshold
+    pub fn update(&mut self, reading: &Reading) -> Option<AlertEvent> {
+        let value = (self.field)(reading);
+        self.update_value(value)
+    }
+
+    /// Updates the watcher with a value that has already been extracted,
+    /// returning an event if it crossed the threshold
+    pub fn update_value(&mut self, value: u16) -> Option<AlertEvent> {
+        if !self.active && value >= self.threshold.rising {
+            self.active = true;
+            Some(AlertEvent::Entered)
+        } else if self.active && value < self.threshold.falling {
+            self.active = false;
+            Some(AlertEvent::Exited)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the alert is currently entered
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns the watcher's threshold
+    pub fn threshold(&self) -> Threshold {
+        self.threshold
+    }
+}
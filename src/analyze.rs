@@ -0,0 +1,294 @@
+//! Decodes raw captures of UART traffic, reporting every frame found along
+//! with its parsed contents, checksum status, and the garbage seen between
+//! frames.  This is mainly useful for diagnosing reports of persistent
+//! [`SensorError::BadMagic`](crate::SensorError::BadMagic) or
+//! [`SensorError::ChecksumMismatch`](crate::SensorError::ChecksumMismatch)
+//! errors from a capture of the offending byte stream.
+
+use crate::Reading;
+use core::fmt;
+use sen0177_protocol::{
+    checksum, parse_frame, parse_frame_unchecked, ProtocolError, FRAME_LEN, MAGIC_BYTE_0,
+    MAGIC_BYTE_1,
+};
+
+/// The longest frame, in bytes, that the analyzer will recognize
+///
+/// Plantower-protocol frames carry a 16-bit length field, so a corrupted
+/// length would otherwise swallow the rest of the capture.
+pub const MAX_FRAME_LEN: usize = 64;
+
+/// The contents of a frame found in a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// A data frame, with its parsed reading
+    ///
+    /// If the checksum did not match, the reading was parsed regardless and
+    /// may contain garbage.
+    Data(Reading),
+    /// A frame of some other length, such as a command acknowledgement,
+    /// with the length of its payload (excluding the checksum)
+    Other {
+        /// The value of the frame's length field, less the two checksum
+        /// bytes
+        payload_len: usize,
+    },
+}
+
+/// A frame found in a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundFrame {
+    /// The offset, in bytes, of the start of the frame within the capture
+    pub offset: usize,
+    /// The total length of the frame, in bytes
+    pub len: usize,
+    /// The number of garbage bytes between the previous frame (or the start
+    /// of the capture) and this one
+    pub garbage_before: usize,
+    /// The frame's contents
+    pub kind: FrameKind,
+    /// The result of checking the frame's checksum
+    pub checksum: Result<(), ProtocolError>,
+}
+
+/// The result of analyzing a capture
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Every frame found, in order
+    pub frames: Vec<FoundFrame>,
+    /// The total number of bytes that were not part of any frame
+    pub garbage_bytes: usize,
+    /// The number of separate runs of garbage bytes
+    pub garbage_runs: usize,
+    /// The length of the longest run of garbage bytes
+    pub longest_garbage_run: usize,
+    /// The number of bytes at the end of the capture that start what looks
+    /// like a frame, but were cut off
+    pub truncated_bytes: usize,
+}
+
+impl Analysis {
+    /// Returns the number of data frames found
+    pub fn data_frames(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|frame| matches!(frame.kind, FrameKind::Data(_)))
+            .count()
+    }
+
+    /// Returns the number of frames whose checksum did not match
+    pub fn checksum_failures(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|frame| frame.checksum.is_err())
+            .count()
+    }
+
+    fn add_garbage(&mut self, run: usize) {
+        if run > 0 {
+            self.garbage_bytes += run;
+            self.garbage_runs += 1;
+            self.longest_garbage_run = self.longest_garbage_run.max(run);
+        }
+    }
+}
+
+impl fmt::Display for FoundFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}: ", self.offset)?;
+        match self.kind {
+            FrameKind::Data(reading) => {
+                // Found frames are parsed as is, so this is always present
+                let env = reading.atmospheric().unwrap_or_default();
+                write!(
+                    f,
+                    "data frame: PM1 {}, PM2.5 {}, PM10 {} µg/m³ (atmospheric {}/{}/{}); \
+                 counts/0.1L >0.3µm {}, >0.5µm {}, >1µm {}, >2.5µm {}, >5µm {}, >10µm {}; \
+                 firmware {:#04x}, error code {:#04x}",
+                    reading.pm1(),
+                    reading.pm2_5(),
+                    reading.pm10(),
+                    env.pm1(),
+                    env.pm2_5(),
+                    env.pm10(),
+                    reading.particles_0_3().per_deciliter(),
+                    reading.particles_0_5().per_deciliter(),
+                    reading.particles_1().per_deciliter(),
+                    reading.particles_2_5().per_deciliter(),
+                    reading.particles_5().per_deciliter(),
+                    reading.particles_10().per_deciliter(),
+                    reading.firmware_version(),
+                    reading.device_error_code(),
+                )?
+            }
+            FrameKind::Other { payload_len } => {
+                write!(f, "other frame with {} byte payload", payload_len)?
+            }
+        }
+        match self.checksum {
+            Ok(()) => f.write_str(" [checksum ok]")?,
+            Err(error) => write!(f, " [{}]", error)?,
+        }
+        if self.garbage_before > 0 {
+            write!(f, " (after {} garbage bytes)", self.garbage_before)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for frame in &self.frames {
+            writeln!(f, "{}", frame)?;
+        }
+        writeln!(
+            f,
+            "{} frames ({} data), {} checksum failures",
+            self.frames.len(),
+            self.data_frames(),
+            self.checksum_failures(),
+        )?;
+        write!(
+            f,
+            "{} garbage bytes in {} runs (longest {}), {} truncated bytes at end",
+            self.garbage_bytes, self.garbage_runs, self.longest_garbage_run, self.truncated_bytes,
+        )
+    }
+}
+
+/// Finds and decodes every frame in `capture`, a raw byte stream received
+/// from the sensor
+///
+/// A frame is recognized by its magic bytes and a length field that fits
+/// within [`MAX_FRAME_LEN`]; everything else is counted as garbage.
+pub fn analyze(capture: &[u8]) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut offset = 0;
+    let mut garbage_run = 0;
+
+    while offset < capture.len() {
+        let rest = &capture[offset..];
+        if rest.len() < 4 || rest[0] != MAGIC_BYTE_0 || rest[1] != MAGIC_BYTE_1 {
+            if rest.len() < 4 && rest.first() == Some(&MAGIC_BYTE_0) {
+                analysis.truncated_bytes = rest.len();
+                break;
+            }
+            garbage_run += 1;
+            offset += 1;
+            continue;
+        }
+
+        let len = 4 + u16::from_be_bytes([rest[2], rest[3]]) as usize;
+        if !(6..=MAX_FRAME_LEN).contains(&len) {
+            garbage_run += 1;
+            offset += 1;
+            continue;
+        }
+        if rest.len() < len {
+            analysis.truncated_bytes = rest.len();
+            break;
+        }
+
+        let frame = &rest[..len];
+        let (kind, checksum) = if len == FRAME_LEN {
+            let mut buf = [0u8; FRAME_LEN];
+            buf.copy_from_slice(frame);
+            match parse_frame(&buf) {
+                Ok(reading) => (FrameKind::Data(reading), Ok(())),
+                Err(error) => (FrameKind::Data(parse_frame_unchecked(&buf)), Err(error)),
+            }
+        } else {
+            let computed = checksum(&frame[..len - 2]);
+            let expected = u16::from_be_bytes([frame[len - 2], frame[len - 1]]);
+            let result = if computed == expected {
+                Ok(())
+            } else {
+                Err(ProtocolError::ChecksumMismatch { expected, computed })
+            };
+            (
+                FrameKind::Other {
+                    payload_len: len - 6,
+                },
+                result,
+            )
+        };
+
+        analysis.add_garbage(garbage_run);
+        analysis.frames.push(FoundFrame {
+            offset,
+            len,
+            garbage_before: garbage_run,
+            kind,
+            checksum,
+        });
+        garbage_run = 0;
+        offset += len;
+    }
+
+    analysis.add_garbage(garbage_run);
+    analysis
+}
+
+/// Describes errors encountered while parsing a hex dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    /// A token was not a valid hex byte; holds the token's byte offset
+    /// within the input
+    InvalidToken(usize),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidToken(offset) => {
+                write!(f, "Invalid hex at offset {} of input", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for HexError {}
+
+/// Parses a textual hex dump into raw bytes
+///
+/// Bytes may be separated by whitespace or commas, and may carry a `0x`
+/// prefix; runs of hex digits without separators (such as the output of
+/// `xxd -p`) are split into bytes.  Tokens ending in `:` (such as line
+/// offsets) are skipped, as is anything following a `#` on a line.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, HexError> {
+    let mut bytes = Vec::new();
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.split('#').next().unwrap_or("");
+        let mut token_start = line_start;
+        for token in content.split_inclusive(SEPARATORS) {
+            let offset = token_start;
+            token_start += token.len();
+            let token = token.trim_end_matches(SEPARATORS);
+            if token.is_empty() || token.ends_with(':') {
+                continue;
+            }
+            let digits = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .unwrap_or(token);
+            if digits.is_empty() || digits.len() % 2 != 0 {
+                return Err(HexError::InvalidToken(offset));
+            }
+            for pair in digits.as_bytes().chunks(2) {
+                match (hex_digit(pair[0]), hex_digit(pair[1])) {
+                    (Some(high), Some(low)) => bytes.push(high << 4 | low),
+                    _ => return Err(HexError::InvalidToken(offset)),
+                }
+            }
+        }
+        line_start += line.len();
+    }
+    Ok(bytes)
+}
+
+const SEPARATORS: [char; 5] = [' ', '\t', ',', '\r', '\n'];
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|digit| digit as u8)
+}
@@ -0,0 +1,330 @@
+//! Concentrations are passed in as fixed-point values in tenths of a µg/m³
+//! (so 12.3µg/m³ is `123`), which allows averaged values to be used without
+//! floating point math.  All calculations use integer arithmetic only.
+//!
+//! The AQI is defined over averaged concentrations, not instantaneous ones:
+//! an AQI computed from a single reading jumps around with every puff of
+//! smoke, and overstates short spikes.  An [`AqiCalculator`] accumulates
+//! readings into hourly averages and reports the US EPA's NowCast AQI (or
+//! the 24-hour AQI), refusing to report anything until it has enough data.
+//! To compute an AQI from averages kept elsewhere, build an [`AqiInput`]
+//! from a [`History`] or [`DailyAverage`]; there is no way to build one
+//! from a single reading.  To put an instantaneous concentration on the
+//! AQI scale anyway, e.g. to color a live indicator, use [`pm2_5`] or
+//! [`pm10`] directly.
+//!
+//! ```
+//! use sen0177::{aqi::AqiCalculator, Reading};
+//!
+//! # let readings = [(0, Reading::default())];
+//! // Timestamps in seconds, with a reading expected every minute
+//! let mut calculator = AqiCalculator::new(60);
+//! for (timestamp, reading) in readings {
+//!     calculator.push(timestamp, reading);
+//! }
+//! match calculator.nowcast() {
+//!     Some(aqi) => println!("AQI: {} ({:?})", aqi.value(), aqi.category()),
+//!     None => println!("AQI: not enough data yet"),
+//! }
+//! ```
+
+use crate::{
+    guidelines::Averages,
+    history::History,
+    rolling::{DailyAverage, SECONDS_PER_HOUR},
+    Reading,
+};
+
+/// Category of an AQI value, as defined by the US EPA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AqiCategory {
+    /// AQI 0 to 50
+    Good,
+    /// AQI 51 to 100
+    Moderate,
+    /// AQI 101 to 150
+    UnhealthyForSensitiveGroups,
+    /// AQI 151 to 200
+    Unhealthy,
+    /// AQI 201 to 300
+    VeryUnhealthy,
+    /// AQI 301 and above
+    Hazardous,
+}
+
+/// A US EPA Air Quality Index value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Aqi(u16);
+
+impl Aqi {
+    /// The highest AQI value; concentrations beyond the top breakpoint are
+    /// clamped to this value
+    pub const MAX: Aqi = Aqi(500);
+
+    /// Returns the numeric AQI value
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the category the AQI value falls into
+    pub fn category(&self) -> AqiCategory {
+        match self.0 {
+            0..=50 => AqiCategory::Good,
+            51..=100 => AqiCategory::Moderate,
+            101..=150 => AqiCategory::UnhealthyForSensitiveGroups,
+            151..=200 => AqiCategory::Unhealthy,
+            201..=300 => AqiCategory::VeryUnhealthy,
+            _ => AqiCategory::Hazardous,
+        }
+    }
+}
+
+// (concentration low, concentration high, AQI low, AQI high), with
+// concentrations in tenths of a µg/m³
+type Breakpoint = (u32, u32, u16, u16);
+
+// US EPA breakpoints for 24-hour PM2.5, as revised in 2024
+const PM2_5_BREAKPOINTS: [Breakpoint; 6] = [
+    (0, 90, 0, 50),
+    (91, 354, 51, 100),
+    (355, 554, 101, 150),
+    (555, 1254, 151, 200),
+    (1255, 2254, 201, 300),
+    (2255, 3254, 301, 500),
+];
+
+// US EPA breakpoints for 24-hour PM10
+const PM10_BREAKPOINTS: [Breakpoint; 6] = [
+    (0, 540, 0, 50),
+    (550, 1540, 51, 100),
+    (1550, 2540, 101, 150),
+    (2550, 3540, 151, 200),
+    (3550, 4240, 201, 300),
+    (4250, 6040, 301, 500),
+];
+
+/// Computes the AQI for a PM2.5 concentration given in tenths of a µg/m³
+pub fn pm2_5(concentration_tenths: u32) -> Aqi {
+    interpolate(&PM2_5_BREAKPOINTS, concentration_tenths)
+}
+
+/// Computes the AQI for a PM10 concentration given in tenths of a µg/m³
+///
+/// Per the EPA's guidance, the concentration is truncated to a whole µg/m³
+/// first.
+pub fn pm10(concentration_tenths: u32) -> Aqi {
+    interpolate(&PM10_BREAKPOINTS, concentration_tenths / 10 * 10)
+}
+
+fn interpolate(breakpoints: &[Breakpoint], concentration: u32) -> Aqi {
+    for &(c_lo, c_hi, i_lo, i_hi) in breakpoints {
+        // Values falling in the gap between two breakpoint ranges belong to
+        // the upper range
+        if concentration <= c_hi {
+            let c_lo = c_lo.min(concentration);
+            let numerator = (i_hi - i_lo) as u32 * (concentration - c_lo);
+            let denominator = c_hi - c_lo;
+            // Round to the nearest integer
+            let offset = (numerator + denominator / 2) / denominator;
+            return Aqi(i_lo + offset as u16);
+        }
+    }
+    Aqi::MAX
+}
+
+/// The period the concentrations of an [`AqiInput`] were averaged over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AqiWindow {
+    /// A trailing one-hour mean
+    Hour,
+    /// The US EPA's NowCast, a weighted mean of up to 12 hourly means
+    /// that favors recent hours when concentrations are changing
+    NowCast,
+    /// A 24-hour mean of hourly means
+    Day,
+}
+
+/// Mean PM2.5 and PM10 concentrations over a window suitable for
+/// computing an AQI
+///
+/// These can only be built from averages, never from a single reading.
+/// Convert one into an [`Aqi`] with [`Aqi::from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AqiInput {
+    averages: Averages,
+    window: AqiWindow,
+}
+
+/// The fraction of a window (in percent) that its readings must cover
+const MIN_COVERAGE: u64 = 75;
+
+/// The fixed-point scale of NowCast weights
+const WEIGHT_SCALE: u64 = 10_000;
+
+impl AqiInput {
+    /// Computes the mean standard (CF=1) concentrations over the hour up to
+    /// `now`, from a history with timestamps in seconds
+    ///
+    /// Only readings taken within the hour up to and including `now` count.
+    /// Returns `None` unless they span at least 45 minutes.
+    pub fn hourly<const N: usize>(history: &History<N>, now: u64) -> Option<Self> {
+        let since = now.saturating_sub(SECONDS_PER_HOUR);
+        let mut readings = history
+            .window(since)
+            .take_while(|&(timestamp, _)| timestamp <= now);
+        let (first, reading) = readings.next()?;
+        let mut last = first;
+        let mut sums = (u64::from(reading.pm2_5()), u64::from(reading.pm10()));
+        let mut count = 1;
+        for (timestamp, reading) in readings {
+            last = timestamp;
+            sums.0 += u64::from(reading.pm2_5());
+            sums.1 += u64::from(reading.pm10());
+            count += 1;
+        }
+        // Timestamps that went backwards span nothing
+        if last.saturating_sub(first) < SECONDS_PER_HOUR * MIN_COVERAGE / 100 {
+            return None;
+        }
+        let mean = |sum: u64| ((sum * 10 + count / 2) / count) as u32;
+        Some(Self {
+            averages: Averages {
+                pm2_5: mean(sums.0),
+                pm10: mean(sums.1),
+            },
+            window: AqiWindow::Hour,
+        })
+    }
+
+    /// Computes the NowCast of the hourly means of a rolling average, the
+    /// hour currently being filled counting as the most recent hour
+    ///
+    /// Returns `None` unless at least two of the three most recent hours
+    /// hold readings, as the EPA requires.
+    pub fn nowcast(hours: &DailyAverage) -> Option<Self> {
+        let mut hourly = [None; 12];
+        for (slot, averages) in hourly.iter_mut().zip(hours.bin_averages()) {
+            *slot = averages;
+        }
+        if hourly[..3].iter().filter(|hour| hour.is_some()).count() < 2 {
+            return None;
+        }
+        Some(Self {
+            averages: Averages {
+                pm2_5: nowcast(hourly.map(|hour| hour.map(|averages| averages.pm2_5))),
+                pm10: nowcast(hourly.map(|hour| hour.map(|averages| averages.pm10))),
+            },
+            window: AqiWindow::NowCast,
+        })
+    }
+
+    /// Takes the 24-hour mean of a rolling average
+    ///
+    /// Returns `None` unless the average is at least 75% complete, as the
+    /// EPA requires.
+    pub fn daily(hours: &DailyAverage) -> Option<Self> {
+        if u64::from(hours.completeness()) < MIN_COVERAGE {
+            return None;
+        }
+        Some(Self {
+            averages: hours.averages()?,
+            window: AqiWindow::Day,
+        })
+    }
+
+    /// Returns the mean concentrations, in tenths of a µg/m³
+    pub fn averages(&self) -> Averages {
+        self.averages
+    }
+
+    /// Returns the period the concentrations were averaged over
+    pub fn window(&self) -> AqiWindow {
+        self.window
+    }
+}
+
+impl From<AqiInput> for Aqi {
+    /// Computes the overall AQI of averaged concentrations: the higher of
+    /// the AQIs of their PM2.5 and PM10 means
+    fn from(input: AqiInput) -> Self {
+        pm2_5(input.averages.pm2_5).max(pm10(input.averages.pm10))
+    }
+}
+
+/// Computes the NowCast of hourly means (newest first) in tenths of a unit
+///
+/// Each hour is weighted by the ratio of the lowest to the highest mean,
+/// but no less than a half, raised to the hour's age.
+fn nowcast(hourly: [Option<u32>; 12]) -> u32 {
+    let values = hourly.iter().flatten().map(|&value| u64::from(value));
+    let (min, max) = values.clone().fold((u64::MAX, 0), |(min, max), value| {
+        (min.min(value), max.max(value))
+    });
+    let ratio = (min * WEIGHT_SCALE)
+        .checked_div(max)
+        .map_or(WEIGHT_SCALE, |ratio| ratio.max(WEIGHT_SCALE / 2));
+
+    let mut weight = WEIGHT_SCALE;
+    let (mut sum, mut weights) = (0, 0);
+    for hour in hourly {
+        if let Some(value) = hour {
+            sum += weight * u64::from(value);
+            weights += weight;
+        }
+        weight = weight * ratio / WEIGHT_SCALE;
+    }
+    ((sum + weights / 2) / weights) as u32
+}
+
+/// Computes the AQI from readings, averaged as the US EPA requires
+///
+/// See the [module documentation](self).  Readings are accumulated into
+/// hourly means over the last day, so memory use is fixed.  Until enough
+/// hours hold readings, the AQI methods return `None` rather than an
+/// instantaneous value.
+#[derive(Debug, Clone)]
+pub struct AqiCalculator {
+    hours: DailyAverage,
+}
+
+impl AqiCalculator {
+    /// Creates a calculator over timestamps in seconds, expecting a reading
+    /// every `interval_secs` seconds
+    pub fn new(interval_secs: u32) -> Self {
+        Self::from_average(DailyAverage::daily(interval_secs))
+    }
+
+    /// Creates a calculator that accumulates into `hours`, which may
+    /// already hold readings
+    pub fn from_average(hours: DailyAverage) -> Self {
+        Self { hours }
+    }
+
+    /// Adds a reading taken at `timestamp`
+    pub fn push(&mut self, timestamp: u64, reading: Reading) {
+        self.hours.push(timestamp, reading);
+    }
+
+    /// Removes all readings
+    pub fn clear(&mut self) {
+        self.hours.clear();
+    }
+
+    /// Returns the hourly means the AQI is computed from
+    pub fn hours(&self) -> &DailyAverage {
+        &self.hours
+    }
+
+    /// Returns the NowCast AQI, as shown by AirNow and suited to current
+    /// conditions, or `None` unless two of the last three hours hold
+    /// readings
+    pub fn nowcast(&self) -> Option<Aqi> {
+        AqiInput::nowcast(&self.hours).map(Aqi::from)
+    }
+
+    /// Returns the 24-hour AQI, or `None` unless at least 75% of the
+    /// readings expected over the day are present
+    pub fn daily(&self) -> Option<Aqi> {
+        AqiInput::daily(&self.hours).map(Aqi::from)
+    }
+}
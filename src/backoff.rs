@@ -0,0 +1,139 @@
+use crate::{AirQualitySensor, Reading, SensorError};
+use embedded_hal::delay::DelayNs;
+
+/// The default delay after the first failure: about one frame interval
+pub const DEFAULT_INITIAL_MS: u32 = 1000;
+
+/// The default upper limit on the delay between retries
+pub const DEFAULT_MAX_MS: u32 = 60_000;
+
+/// Exponential backoff with jitter, for retrying after persistent errors
+///
+/// When the sensor is unplugged or unpowered, every read fails straight
+/// away (or after a timeout), and retrying in a tight loop keeps the CPU
+/// and bus busy and floods any log of the errors.  Each consecutive failure
+/// doubles the delay before the next attempt, up to a maximum, and a
+/// success resets it.
+///
+/// Each delay is randomized between half and all of its nominal value, so
+/// that several devices that lost power together don't retry in lockstep.
+/// Give each device its own [seed](Backoff::with_seed) (e.g. from a serial
+/// number) for that to be effective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    initial_ms: u32,
+    max_ms: u32,
+    failures: u32,
+    rng: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(DEFAULT_INITIAL_MS, DEFAULT_MAX_MS)
+    }
+}
+
+impl Backoff {
+    /// Creates a backoff waiting about `initial_ms` milliseconds after the
+    /// first failure, doubling with each further failure up to `max_ms`
+    pub const fn new(initial_ms: u32, max_ms: u32) -> Self {
+        Self {
+            initial_ms,
+            max_ms,
+            failures: 0,
+            rng: 0x9e37_79b9,
+        }
+    }
+
+    /// Seeds the jitter, replacing the default seed
+    pub const fn with_seed(mut self, seed: u32) -> Self {
+        // xorshift gets stuck at zero
+        self.rng = if seed == 0 { 0x9e37_79b9 } else { seed };
+        self
+    }
+
+    /// Returns the number of consecutive failures since the last success
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Records a failure, returning the number of milliseconds to wait
+    /// before the next attempt
+    pub fn next_delay_ms(&mut self) -> u32 {
+        let factor = 1u32.checked_shl(self.failures).unwrap_or(u32::MAX);
+        let nominal = self.initial_ms.saturating_mul(factor).min(self.max_ms);
+        self.failures = self.failures.saturating_add(1);
+        let half = nominal / 2;
+        half + self.next_random() % (nominal - half + 1)
+    }
+
+    /// Records a success, so that the next failure waits only the initial
+    /// delay
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    fn next_random(&mut self) -> u32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+}
+
+/// An infinite iterator over readings from a sensor, backing off after
+/// errors
+///
+/// Created by [`Readings::with_backoff`](crate::iter::Readings::with_backoff).
+/// Errors are returned as soon as they occur; the delay is taken before the
+/// following read.
+pub struct BackoffReadings<'a, S: ?Sized, E, D> {
+    readings: crate::iter::Readings<'a, S, E>,
+    delay: D,
+    backoff: Backoff,
+    pending_ms: u32,
+}
+
+impl<'a, S: ?Sized, E, D> BackoffReadings<'a, S, E, D> {
+    pub(crate) fn new(
+        readings: crate::iter::Readings<'a, S, E>,
+        delay: D,
+        backoff: Backoff,
+    ) -> Self {
+        Self {
+            readings,
+            delay,
+            backoff,
+            pending_ms: 0,
+        }
+    }
+
+    /// Returns the backoff state, e.g. to only log the first of a run of
+    /// failures
+    pub fn backoff(&self) -> &Backoff {
+        &self.backoff
+    }
+}
+
+impl<S, E, D> Iterator for BackoffReadings<'_, S, E, D>
+where
+    S: AirQualitySensor<E> + ?Sized,
+    D: DelayNs,
+{
+    type Item = Result<Reading, SensorError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending_ms > 0 {
+            self.delay.delay_ms(self.pending_ms);
+        }
+        let result = self.readings.next()?;
+        self.pending_ms = match result {
+            Ok(_) => {
+                self.backoff.reset();
+                0
+            }
+            Err(_) => self.backoff.next_delay_ms(),
+        };
+        Some(result)
+    }
+}
@@ -0,0 +1,62 @@
+//! Encodes readings as the Bluetooth SIG particulate matter characteristic
+//! values, for firmware exposing them through the Environmental Sensing
+//! Service (e.g. with `nrf-softdevice` or TrouBLE).
+//!
+//! The PM1, PM2.5, and PM10 Concentration characteristics each hold a
+//! single IEEE 11073 16-bit SFLOAT (`medfloat16`) in kg/m³, sent
+//! little-endian.  The format's smallest exponent is -8, so the finest
+//! resolution it can express is 10µg/m³; concentrations are rounded to the
+//! nearest 10µg/m³, or to the nearest 100µg/m³ beyond 20450µg/m³, which no
+//! real sensor will report.  Standard (CF=1) concentrations are used.
+
+use crate::Reading;
+
+/// The UUID of the Environmental Sensing Service
+pub const ENVIRONMENTAL_SENSING_SERVICE_UUID: u16 = 0x181a;
+
+/// The UUID of the PM1 Concentration characteristic
+pub const PM1_CONCENTRATION_UUID: u16 = 0x2bd5;
+
+/// The UUID of the PM2.5 Concentration characteristic
+pub const PM2_5_CONCENTRATION_UUID: u16 = 0x2bd6;
+
+/// The UUID of the PM10 Concentration characteristic
+pub const PM10_CONCENTRATION_UUID: u16 = 0x2bd7;
+
+/// The value to send when no concentration is available (SFLOAT NaN)
+pub const NOT_AVAILABLE: [u8; 2] = [0xff, 0x07];
+
+/// The largest positive SFLOAT mantissa; larger values are reserved for
+/// special values
+const MAX_MANTISSA: u32 = 0x07fd;
+
+/// Encodes a concentration in µg/m³ as a characteristic value
+pub fn encode_concentration(concentration: u16) -> [u8; 2] {
+    // Starting from 10⁻⁸ kg/m³ (10µg/m³) per unit of mantissa, give up one
+    // digit of precision at a time until the mantissa fits
+    let mut exponent: i8 = -8;
+    let mut divisor = 10;
+    let mut mantissa = (u32::from(concentration) + divisor / 2) / divisor;
+    while mantissa > MAX_MANTISSA {
+        exponent += 1;
+        divisor *= 10;
+        mantissa = (u32::from(concentration) + divisor / 2) / divisor;
+    }
+    let raw = ((exponent as u16 & 0x000f) << 12) | mantissa as u16;
+    raw.to_le_bytes()
+}
+
+/// Returns the PM1 Concentration characteristic value for `reading`
+pub fn pm1(reading: &Reading) -> [u8; 2] {
+    encode_concentration(reading.pm1())
+}
+
+/// Returns the PM2.5 Concentration characteristic value for `reading`
+pub fn pm2_5(reading: &Reading) -> [u8; 2] {
+    encode_concentration(reading.pm2_5())
+}
+
+/// Returns the PM10 Concentration characteristic value for `reading`
+pub fn pm10(reading: &Reading) -> [u8; 2] {
+    encode_concentration(reading.pm10())
+}
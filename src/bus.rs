@@ -0,0 +1,234 @@
+//! The drivers return the HAL's own error type in
+//! [`SensorError::ReadError`], which generic application code can't
+//! inspect.  Every HAL error can report an `embedded-hal` (or
+//! `embedded-io`) error kind, though, and [`BusErrorKind`] folds those
+//! kinds (for serial ports, I2C buses, and byte streams alike) into one
+//! set of categories, so that retry logic can branch on the class of an
+//! error without knowing which HAL, or which bus, produced it.
+//!
+//! Wrapping a sensor in [`Classified`] replaces its bus errors with their
+//! [`BusErrorKind`]:
+//!
+//! ```
+//! # #[cfg(feature = "mock")] {
+//! use sen0177::{bus::Classified, serial::Sen0177Builder, AirQualitySensor, SensorError};
+//!
+//! # let mut serial = sen0177::mock::MockSerial::new();
+//! # serial.feed_reading(&Default::default());
+//! let sensor = Sen0177Builder::new().timeout_polls(10).build(&mut serial);
+//! let mut sensor = Classified::serial(sensor);
+//! match sensor.read() {
+//!     Ok(reading) => println!("PM2.5: {}µg/m³", reading.pm2_5()),
+//!     Err(SensorError::ReadError(kind)) if kind.is_transient() => println!("Retrying"),
+//!     Err(error) => println!("Giving up: {}", error),
+//! }
+//! # }
+//! ```
+
+use core::fmt;
+use embedded_hal::i2c;
+use embedded_hal_nb::serial;
+
+use crate::{AirQualitySensor, Reading, SensorError, SensorInfo};
+
+/// The category of a bus error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BusErrorKind {
+    /// The device is not there: it didn't acknowledge its I2C address, or
+    /// the port or connection is gone
+    Disconnected,
+    /// The device didn't acknowledge a byte of data, or the source of the
+    /// NACK is unknown
+    NoAcknowledge,
+    /// Another I2C controller won arbitration of the bus
+    ArbitrationLoss,
+    /// A bus protocol error, such as a misplaced I2C start or stop
+    /// condition
+    Bus,
+    /// Data was received faster than it was read, and some was lost
+    Overrun,
+    /// A received byte had a framing error, as is typical of the wrong
+    /// baud rate
+    Framing,
+    /// A received byte failed its parity check
+    Parity,
+    /// Noise was detected on the line
+    Noise,
+    /// The operation timed out
+    Timeout,
+    /// The operation was interrupted, and can be retried
+    Interrupted,
+    /// Any other error
+    Other,
+}
+
+impl BusErrorKind {
+    /// Returns `true` if the error is likely to clear up if the operation
+    /// is retried, rather than needing the device (or its connection) to
+    /// be fixed
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, BusErrorKind::Disconnected | BusErrorKind::Other)
+    }
+
+    /// Classifies a serial port error
+    pub fn of_serial<E: serial::Error>(error: &E) -> Self {
+        error.kind().into()
+    }
+
+    /// Classifies an I2C bus error
+    pub fn of_i2c<E: i2c::Error>(error: &E) -> Self {
+        error.kind().into()
+    }
+
+    /// Classifies an `embedded-io` stream error
+    #[cfg(feature = "async")]
+    pub fn of_io<E: embedded_io_async::Error>(error: &E) -> Self {
+        error.kind().into()
+    }
+}
+
+impl fmt::Display for BusErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BusErrorKind::Disconnected => "Device disconnected",
+            BusErrorKind::NoAcknowledge => "No acknowledgement",
+            BusErrorKind::ArbitrationLoss => "Bus arbitration lost",
+            BusErrorKind::Bus => "Bus protocol error",
+            BusErrorKind::Overrun => "Receive overrun",
+            BusErrorKind::Framing => "Framing error",
+            BusErrorKind::Parity => "Parity error",
+            BusErrorKind::Noise => "Line noise",
+            BusErrorKind::Timeout => "Bus timeout",
+            BusErrorKind::Interrupted => "Interrupted",
+            BusErrorKind::Other => "Bus error",
+        })
+    }
+}
+
+impl core::error::Error for BusErrorKind {}
+
+impl From<serial::ErrorKind> for BusErrorKind {
+    fn from(kind: serial::ErrorKind) -> Self {
+        match kind {
+            serial::ErrorKind::Overrun => BusErrorKind::Overrun,
+            serial::ErrorKind::FrameFormat => BusErrorKind::Framing,
+            serial::ErrorKind::Parity => BusErrorKind::Parity,
+            serial::ErrorKind::Noise => BusErrorKind::Noise,
+            _ => BusErrorKind::Other,
+        }
+    }
+}
+
+impl From<i2c::ErrorKind> for BusErrorKind {
+    fn from(kind: i2c::ErrorKind) -> Self {
+        match kind {
+            i2c::ErrorKind::Bus => BusErrorKind::Bus,
+            i2c::ErrorKind::ArbitrationLoss => BusErrorKind::ArbitrationLoss,
+            i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address) => {
+                BusErrorKind::Disconnected
+            }
+            i2c::ErrorKind::NoAcknowledge(_) => BusErrorKind::NoAcknowledge,
+            i2c::ErrorKind::Overrun => BusErrorKind::Overrun,
+            _ => BusErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<embedded_io_async::ErrorKind> for BusErrorKind {
+    fn from(kind: embedded_io_async::ErrorKind) -> Self {
+        use embedded_io_async::ErrorKind::*;
+        match kind {
+            NotFound | ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected
+            | BrokenPipe => BusErrorKind::Disconnected,
+            TimedOut => BusErrorKind::Timeout,
+            Interrupted => BusErrorKind::Interrupted,
+            _ => BusErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::ErrorKind> for BusErrorKind {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        use std::io::ErrorKind::*;
+        match kind {
+            NotFound | ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected
+            | BrokenPipe | UnexpectedEof => BusErrorKind::Disconnected,
+            TimedOut | WouldBlock => BusErrorKind::Timeout,
+            Interrupted => BusErrorKind::Interrupted,
+            _ => BusErrorKind::Other,
+        }
+    }
+}
+
+impl<E> SensorError<E> {
+    /// Returns the category of the bus error, if this is a
+    /// [`ReadError`](SensorError::ReadError), using `classify` (such as
+    /// [`BusErrorKind::of_serial`])
+    pub fn bus_error_kind(&self, classify: fn(&E) -> BusErrorKind) -> Option<BusErrorKind> {
+        match self {
+            SensorError::ReadError(error) => Some(classify(error)),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps a sensor to replace its bus errors with their [`BusErrorKind`]
+///
+/// See the [module documentation](self).
+pub struct Classified<S, E> {
+    sensor: S,
+    classify: fn(&E) -> BusErrorKind,
+}
+
+impl<S, E> Classified<S, E> {
+    /// Wraps a sensor on a serial port
+    pub fn serial(sensor: S) -> Self
+    where
+        E: serial::Error,
+    {
+        Self::new(sensor, BusErrorKind::of_serial)
+    }
+
+    /// Wraps a sensor on an I2C bus
+    pub fn i2c(sensor: S) -> Self
+    where
+        E: i2c::Error,
+    {
+        Self::new(sensor, BusErrorKind::of_i2c)
+    }
+
+    /// Wraps a sensor whose errors are classified by `classify`, e.g. one
+    /// reached through a HAL-specific transport
+    pub fn new(sensor: S, classify: fn(&E) -> BusErrorKind) -> Self {
+        Self { sensor, classify }
+    }
+
+    /// Consumes the wrapper, returning the underlying sensor
+    pub fn release(self) -> S {
+        self.sensor
+    }
+}
+
+impl<S, E> AirQualitySensor<BusErrorKind> for Classified<S, E>
+where
+    S: AirQualitySensor<E>,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<BusErrorKind>> {
+        self.sensor.read().map_err(|error| match error {
+            SensorError::BadMagic => SensorError::BadMagic,
+            SensorError::LikelyBaudMismatch => SensorError::LikelyBaudMismatch,
+            SensorError::ChecksumMismatch => SensorError::ChecksumMismatch,
+            SensorError::ImplausibleData(reason) => SensorError::ImplausibleData(reason),
+            SensorError::Timeout => SensorError::Timeout,
+            SensorError::StaleData => SensorError::StaleData,
+            SensorError::NoData { since } => SensorError::NoData { since },
+            SensorError::ReadError(error) => SensorError::ReadError((self.classify)(&error)),
+        })
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensor.info()
+    }
+}
@@ -0,0 +1,187 @@
+//! Low-cost sensors are best calibrated by running them next to a
+//! reference instrument (such as a beta attenuation monitor) for a few
+//! weeks, and fitting a correction from the paired readings.  [`fit`] does
+//! this by least squares, producing a [`Calibration`] for the
+//! [`humidity`](crate::humidity) module to apply, without any external
+//! tooling.  Samples are usually hourly means of both series.
+//!
+//! Fitting needs floating point math, but no allocator: the samples are
+//! taken as a slice.
+//!
+//! ```
+//! use sen0177::calibrate::{fit, Model, Sample};
+//!
+//! let samples = [
+//!     Sample::new(10, 550, 62),
+//!     Sample::new(22, 600, 118),
+//!     Sample::new(35, 420, 201),
+//!     Sample::new(51, 700, 265),
+//! ];
+//! let fitted = fit(&samples, Model::WithHumidity)?;
+//! let corrected = fitted.calibration.apply(30, 500);
+//! # Ok::<(), sen0177::calibrate::FitError>(())
+//! ```
+
+use core::fmt;
+
+pub use crate::humidity::Calibration;
+
+/// A pair of co-located readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sample {
+    /// The sensor's standard (CF=1) PM2.5 concentration, in µg/m³
+    pub sensor: u16,
+    /// The relative humidity, in tenths of a percent
+    pub relative_humidity: u16,
+    /// The reference monitor's PM2.5 concentration, in tenths of a µg/m³
+    pub reference: u32,
+}
+
+impl Sample {
+    /// Creates a new sample
+    pub const fn new(sensor: u16, relative_humidity: u16, reference: u32) -> Self {
+        Self {
+            sensor,
+            relative_humidity,
+            reference,
+        }
+    }
+}
+
+/// The terms fitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Model {
+    /// A slope and an offset; the humidity term is zero
+    Linear,
+    /// A slope, an offset, and a relative humidity term, as in the US EPA
+    /// correction
+    WithHumidity,
+}
+
+/// Describes why a fit failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FitError {
+    /// There were fewer samples than terms to fit
+    TooFewSamples,
+    /// The samples don't determine the terms, e.g. because the sensor's
+    /// concentration (or the relative humidity) never varied, or the two
+    /// always varied together
+    Degenerate,
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FitError::TooFewSamples => f.write_str("Too few samples to fit"),
+            FitError::Degenerate => f.write_str("Samples do not determine a fit"),
+        }
+    }
+}
+
+impl core::error::Error for FitError {}
+
+/// A fitted correction, and how well it fits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    /// The correction
+    pub calibration: Calibration,
+    /// The coefficient of determination (R²) of the corrected
+    /// concentrations against the reference
+    pub r_squared: f32,
+    /// The mean absolute difference between the corrected concentrations
+    /// and the reference, in µg/m³
+    pub mean_absolute_error: f32,
+}
+
+/// Fits a correction of the sensor's concentrations to the reference's by
+/// ordinary least squares
+pub fn fit(samples: &[Sample], model: Model) -> Result<Fit, FitError> {
+    let terms = match model {
+        Model::Linear => 2,
+        Model::WithHumidity => 3,
+    };
+    if samples.len() < terms {
+        return Err(FitError::TooFewSamples);
+    }
+
+    // Centering on the means keeps the sums well conditioned
+    let n = samples.len() as f64;
+    let mean = |value: fn(&Sample) -> f64| samples.iter().map(value).sum::<f64>() / n;
+    let (mean_x, mean_h, mean_y) = (mean(sensor), mean(humidity), mean(reference));
+    let (mut sxx, mut shh, mut sxh, mut sxy, mut shy, mut syy) = (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    for sample in samples {
+        let x = sensor(sample) - mean_x;
+        let h = humidity(sample) - mean_h;
+        let y = reference(sample) - mean_y;
+        sxx += x * x;
+        shh += h * h;
+        sxh += x * h;
+        sxy += x * y;
+        shy += h * y;
+        syy += y * y;
+    }
+
+    let (slope, humidity_coefficient) = match model {
+        Model::Linear => {
+            if sxx <= f64::EPSILON * n {
+                return Err(FitError::Degenerate);
+            }
+            (sxy / sxx, 0.0)
+        }
+        Model::WithHumidity => {
+            let determinant = sxx * shh - sxh * sxh;
+            if determinant <= f64::EPSILON * sxx * shh {
+                return Err(FitError::Degenerate);
+            }
+            (
+                (sxy * shh - shy * sxh) / determinant,
+                (shy * sxx - sxy * sxh) / determinant,
+            )
+        }
+    };
+    let offset = mean_y - slope * mean_x - humidity_coefficient * mean_h;
+    let calibration = Calibration {
+        slope: fixed_point(slope),
+        humidity: fixed_point(humidity_coefficient),
+        offset: fixed_point(offset),
+    };
+
+    let (mut residual, mut absolute) = (0.0, 0.0);
+    for sample in samples {
+        let predicted = slope * sensor(sample) + humidity_coefficient * humidity(sample) + offset;
+        let error = predicted.max(0.0) - reference(sample);
+        residual += error * error;
+        absolute += if error < 0.0 { -error } else { error };
+    }
+    let r_squared = if syy > 0.0 { 1.0 - residual / syy } else { 1.0 };
+    Ok(Fit {
+        calibration,
+        r_squared: r_squared as f32,
+        mean_absolute_error: (absolute / n) as f32,
+    })
+}
+
+fn sensor(sample: &Sample) -> f64 {
+    f64::from(sample.sensor)
+}
+
+/// The relative humidity in percent
+fn humidity(sample: &Sample) -> f64 {
+    f64::from(sample.relative_humidity) / 10.0
+}
+
+/// The reference concentration in µg/m³
+fn reference(sample: &Sample) -> f64 {
+    f64::from(sample.reference) / 10.0
+}
+
+/// Converts a coefficient to ten-thousandths, rounding to the nearest
+fn fixed_point(value: f64) -> i32 {
+    let scaled = value * 10_000.0;
+    // Casts saturate, so extreme coefficients are clamped
+    (if scaled < 0.0 {
+        scaled - 0.5
+    } else {
+        scaled + 0.5
+    }) as i32
+}
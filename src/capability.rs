@@ -0,0 +1,198 @@
+use crate::{AirQualitySensor, Concentrations, Reading, SensorError, SensorInfo};
+
+/// A sensor that measures particulate matter
+///
+/// Every [`AirQualitySensor`] is a particulate sensor producing a
+/// Plantower-style [`Reading`]; devices with a differently shaped output
+/// (e.g. additional size fractions or number concentrations) can implement
+/// this trait directly with their own reading type.
+pub trait ParticulateSensor<E> {
+    /// The particulate reading produced by the device
+    type Reading;
+
+    /// Reads a single particulate measurement
+    fn read_particulates(&mut self) -> Result<Self::Reading, SensorError<E>>;
+}
+
+impl<S, E> ParticulateSensor<E> for S
+where
+    S: AirQualitySensor<E> + ?Sized,
+{
+    type Reading = Reading;
+
+    fn read_particulates(&mut self) -> Result<Reading, SensorError<E>> {
+        self.read()
+    }
+}
+
+/// A temperature and relative humidity measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TempHumidity {
+    temperature: i16,
+    relative_humidity: u16,
+}
+
+impl TempHumidity {
+    /// Creates a new measurement from a temperature in tenths of a °C and
+    /// a relative humidity in tenths of a percent
+    pub fn new(temperature: i16, relative_humidity: u16) -> Self {
+        Self {
+            temperature,
+            relative_humidity,
+        }
+    }
+
+    /// Returns the temperature in tenths of a °C
+    pub fn temperature(&self) -> i16 {
+        self.temperature
+    }
+
+    /// Returns the relative humidity in tenths of a percent
+    pub fn relative_humidity(&self) -> u16 {
+        self.relative_humidity
+    }
+}
+
+/// A sensor that measures temperature and relative humidity
+pub trait TempHumiditySensor<E> {
+    /// Reads a single temperature and humidity measurement
+    fn read_temp_humidity(&mut self) -> Result<TempHumidity, SensorError<E>>;
+}
+
+/// A gas concentration measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct GasReading {
+    formaldehyde: u16,
+}
+
+impl GasReading {
+    /// Creates a new measurement from a formaldehyde (HCHO) concentration
+    /// in µg/m³
+    pub fn new(formaldehyde: u16) -> Self {
+        Self { formaldehyde }
+    }
+
+    /// Returns the formaldehyde (HCHO) concentration in µg/m³
+    pub fn formaldehyde(&self) -> u16 {
+        self.formaldehyde
+    }
+}
+
+/// A sensor that measures gas concentrations
+pub trait GasSensor<E> {
+    /// Reads a single gas measurement
+    fn read_gas(&mut self) -> Result<GasReading, SensorError<E>>;
+}
+
+/// The range of adjustment coefficients accepted by [`Calibratable`]
+/// sensors, in percent
+///
+/// This matches the range of the Honeywell HPMA115S0's customer adjustment
+/// coefficient.
+pub const ADJUSTMENT_RANGE: core::ops::RangeInclusive<u8> = 30..=200;
+
+/// Where a [`Calibratable`] sensor's adjustment is applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CalibrationSite {
+    /// The device applies the adjustment itself (and may remember it across
+    /// power cycles)
+    Device,
+    /// The driver applies the adjustment to each reading on the host
+    Driver,
+}
+
+/// A sensor whose mass concentrations can be scaled by an adjustment
+/// coefficient
+///
+/// Some devices (such as the HPMA115S0) accept the coefficient as a
+/// command and apply it themselves, while others need each reading to be
+/// corrected by the host; see [`Adjusted`].  This trait lets application
+/// code calibrate either kind the same way.
+pub trait Calibratable<E> {
+    /// Sets the adjustment coefficient, in percent, clamped to
+    /// [`ADJUSTMENT_RANGE`]; 100 leaves concentrations unchanged
+    fn set_adjustment(&mut self, percent: u8) -> Result<(), SensorError<E>>;
+
+    /// Returns the adjustment coefficient, in percent
+    fn adjustment(&self) -> u8;
+
+    /// Returns where the adjustment is applied
+    fn calibration_site(&self) -> CalibrationSite;
+}
+
+/// Wraps a sensor to apply an adjustment coefficient to its readings on the
+/// host
+///
+/// All six mass concentrations are scaled by the coefficient, rounded to
+/// the nearest µg/m³ (the environmental ones only if the reading has them);
+/// particle counts are left unchanged.
+pub struct Adjusted<S> {
+    sensor: S,
+    percent: u8,
+}
+
+impl<S> Adjusted<S> {
+    /// Wraps `sensor`, with an adjustment coefficient of 100%
+    pub fn new(sensor: S) -> Self {
+        Self {
+            sensor,
+            percent: 100,
+        }
+    }
+
+    /// Consumes the wrapper, returning the sensor
+    pub fn release(self) -> S {
+        self.sensor
+    }
+
+    /// Applies the adjustment coefficient to `reading`
+    pub fn adjust(&self, reading: &Reading) -> Reading {
+        let scale = |value: u16| {
+            let scaled = (u32::from(value) * u32::from(self.percent) + 50) / 100;
+            u16::try_from(scaled).unwrap_or(u16::MAX)
+        };
+        let scale_all = |concentrations: Concentrations| {
+            Concentrations::new(
+                scale(concentrations.pm1()),
+                scale(concentrations.pm2_5()),
+                scale(concentrations.pm10()),
+            )
+        };
+        let adjusted = reading.with_cf1(scale_all(reading.cf1()));
+        match reading.atmospheric() {
+            Some(atmospheric) => adjusted.with_atmospheric(scale_all(atmospheric)),
+            None => adjusted,
+        }
+    }
+}
+
+impl<S, E> Calibratable<E> for Adjusted<S>
+where
+    S: AirQualitySensor<E>,
+{
+    fn set_adjustment(&mut self, percent: u8) -> Result<(), SensorError<E>> {
+        self.percent = percent.clamp(*ADJUSTMENT_RANGE.start(), *ADJUSTMENT_RANGE.end());
+        Ok(())
+    }
+
+    fn adjustment(&self) -> u8 {
+        self.percent
+    }
+
+    fn calibration_site(&self) -> CalibrationSite {
+        CalibrationSite::Driver
+    }
+}
+
+impl<S, E> AirQualitySensor<E> for Adjusted<S>
+where
+    S: AirQualitySensor<E>,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        self.sensor.read().map(|reading| self.adjust(&reading))
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensor.info()
+    }
+}
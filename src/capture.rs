@@ -0,0 +1,224 @@
+//! Records raw serial traffic to a pcap file, so that parsing failures seen
+//! in the field can be reproduced exactly.
+//!
+//! Wrap the UART in a [`Recorder`] before handing it to the driver; every
+//! byte read from or written to the sensor is then passed through unchanged
+//! and also written to the capture.  The capture can be opened in
+//! Wireshark, decoded with the `analyze` example, or turned back into the
+//! received byte stream with [`read_received`] (e.g. to replay it through
+//! `mock::MockSerial`).
+//!
+//! # Format
+//!
+//! Captures are standard little-endian pcap files (microsecond timestamps)
+//! with link type `DLT_USER0` (147).  Each packet holds a run of bytes
+//! transferred in one direction without the UART running dry, prefixed by
+//! a single direction byte: [`RECEIVED`] for bytes read from the sensor, or
+//! [`SENT`] for bytes written to it.  Runs are capped at
+//! [`MAX_RUN_LEN`] bytes.
+
+use core::fmt;
+use embedded_hal_nb::{
+    nb,
+    serial::{ErrorType, Read, Write},
+};
+use std::{
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The pcap link type used for captures (`DLT_USER0`)
+pub const LINK_TYPE: u32 = 147;
+/// Direction byte for bytes read from the sensor
+pub const RECEIVED: u8 = 0;
+/// Direction byte for bytes written to the sensor
+pub const SENT: u8 = 1;
+/// The maximum number of bytes recorded in a single packet
+pub const MAX_RUN_LEN: usize = 256;
+
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const PCAP_HEADER_LEN: usize = 24;
+const RECORD_HEADER_LEN: usize = 16;
+
+/// Wraps a UART, recording all traffic through it to `W` in pcap format
+///
+/// Bytes are buffered until the transfer pauses, so call
+/// [`finish`](Recorder::finish) when done to make sure the last of them are
+/// written.  Errors writing the capture do not interrupt communication with
+/// the sensor; recording stops at the first such error, which is returned by
+/// `finish`.
+pub struct Recorder<R, W: io::Write> {
+    serial_port: R,
+    output: W,
+    direction: u8,
+    run: Vec<u8>,
+    run_started: SystemTime,
+    error: Option<io::Error>,
+}
+
+impl<R, W: io::Write> Recorder<R, W> {
+    /// Creates a new recorder wrapping `serial_port`, writing the capture to
+    /// `output`
+    ///
+    /// The pcap file header is written immediately.
+    pub fn new(serial_port: R, mut output: W) -> io::Result<Self> {
+        let mut header = [0u8; PCAP_HEADER_LEN];
+        header[0..4].copy_from_slice(&PCAP_MAGIC.to_le_bytes());
+        header[4..6].copy_from_slice(&2u16.to_le_bytes());
+        header[6..8].copy_from_slice(&4u16.to_le_bytes());
+        header[16..20].copy_from_slice(&(MAX_RUN_LEN as u32 + 1).to_le_bytes());
+        header[20..24].copy_from_slice(&LINK_TYPE.to_le_bytes());
+        output.write_all(&header)?;
+
+        Ok(Self {
+            serial_port,
+            output,
+            direction: RECEIVED,
+            run: Vec::with_capacity(MAX_RUN_LEN),
+            run_started: UNIX_EPOCH,
+            error: None,
+        })
+    }
+
+    /// Writes any pending bytes to the capture and returns the underlying
+    /// UART and output, or the first error encountered writing the capture
+    pub fn finish(mut self) -> io::Result<(R, W)> {
+        self.end_run();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.output.flush()?;
+        Ok((self.serial_port, self.output))
+    }
+
+    fn record(&mut self, direction: u8, byte: u8) {
+        if direction != self.direction || self.run.len() >= MAX_RUN_LEN {
+            self.end_run();
+        }
+        if self.run.is_empty() {
+            self.direction = direction;
+            self.run_started = SystemTime::now();
+        }
+        self.run.push(byte);
+    }
+
+    fn end_run(&mut self) {
+        if self.run.is_empty() {
+            return;
+        }
+        if self.error.is_none() {
+            if let Err(error) = self.write_run() {
+                self.error = Some(error);
+            }
+        }
+        self.run.clear();
+    }
+
+    fn write_run(&mut self) -> io::Result<()> {
+        let timestamp = self
+            .run_started
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let len = (self.run.len() + 1) as u32;
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        header[0..4].copy_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        header[8..12].copy_from_slice(&len.to_le_bytes());
+        header[12..16].copy_from_slice(&len.to_le_bytes());
+        self.output.write_all(&header)?;
+        self.output.write_all(&[self.direction])?;
+        self.output.write_all(&self.run)
+    }
+}
+
+impl<R: ErrorType, W: io::Write> ErrorType for Recorder<R, W> {
+    type Error = R::Error;
+}
+
+impl<R: Read<u8>, W: io::Write> Read<u8> for Recorder<R, W> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.serial_port.read() {
+            Ok(byte) => {
+                self.record(RECEIVED, byte);
+                Ok(byte)
+            }
+            Err(error) => {
+                // A gap in the data (or an error) ends the current run
+                self.end_run();
+                Err(error)
+            }
+        }
+    }
+}
+
+impl<R: Write<u8>, W: io::Write> Write<u8> for Recorder<R, W> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.serial_port.write(word)?;
+        self.record(SENT, word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.end_run();
+        self.serial_port.flush()
+    }
+}
+
+/// Describes errors encountered while reading a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureError {
+    /// The data is not a little-endian, microsecond-resolution pcap file
+    NotPcap,
+    /// The capture's link type is not [`LINK_TYPE`]
+    WrongLinkType(u32),
+    /// The capture ends in the middle of a packet
+    Truncated,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CaptureError::*;
+        match self {
+            NotPcap => f.write_str("Not a pcap capture"),
+            WrongLinkType(link_type) => write!(f, "Unexpected pcap link type {}", link_type),
+            Truncated => f.write_str("Capture is truncated"),
+        }
+    }
+}
+
+impl core::error::Error for CaptureError {}
+
+/// Returns `true` if `data` starts with the pcap file header written by
+/// [`Recorder`]
+pub fn is_capture(data: &[u8]) -> bool {
+    data.len() >= 4 && data[0..4] == PCAP_MAGIC.to_le_bytes()
+}
+
+/// Extracts the bytes received from the sensor, in order, from a capture
+/// written by [`Recorder`]
+pub fn read_received(capture: &[u8]) -> Result<Vec<u8>, CaptureError> {
+    if !is_capture(capture) || capture.len() < PCAP_HEADER_LEN {
+        return Err(CaptureError::NotPcap);
+    }
+    let link_type = u32::from_le_bytes([capture[20], capture[21], capture[22], capture[23]]);
+    if link_type != LINK_TYPE {
+        return Err(CaptureError::WrongLinkType(link_type));
+    }
+
+    let mut received = Vec::new();
+    let mut rest = &capture[PCAP_HEADER_LEN..];
+    while !rest.is_empty() {
+        if rest.len() < RECORD_HEADER_LEN {
+            return Err(CaptureError::Truncated);
+        }
+        let len = u32::from_le_bytes([rest[8], rest[9], rest[10], rest[11]]) as usize;
+        let packet = rest[RECORD_HEADER_LEN..]
+            .get(..len)
+            .ok_or(CaptureError::Truncated)?;
+        if let Some((&RECEIVED, data)) = packet.split_first() {
+            received.extend_from_slice(data);
+        }
+        rest = &rest[RECORD_HEADER_LEN + len..];
+    }
+    Ok(received)
+}
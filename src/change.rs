@@ -0,0 +1,149 @@
+//! Over a bandwidth-limited link (LoRaWAN, Sigfox, a metered cellular
+//! modem), sending every reading wastes airtime on values that barely
+//! moved.  A [`ChangeDetector`] compares each new reading with the last one
+//! sent, using [`Reading::delta`], and only reports it as worth sending if
+//! some field changed by at least its [threshold](ChangeThresholds).
+//! Comparing against the last reading *sent*, rather than the previous
+//! reading, means that a slow drift is still reported once it adds up.
+//!
+//! ```
+//! use sen0177::{
+//!     change::{ChangeDetector, ChangeThresholds},
+//!     Reading,
+//! };
+//!
+//! # let readings = [Reading::default()];
+//! # fn transmit(_: &Reading) {}
+//! let mut detector = ChangeDetector::new(ChangeThresholds::default());
+//! for reading in readings {
+//!     if detector.check(&reading) {
+//!         transmit(&reading);
+//!     }
+//! }
+//! ```
+
+use crate::{Reading, ReadingDelta};
+
+/// The smallest change in each field that is significant
+///
+/// A field whose threshold is `None` is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeThresholds {
+    /// The threshold for the standard (CF=1) PM1 concentration, in µg/m³
+    pub pm1: Option<u16>,
+    /// The threshold for the standard (CF=1) PM2.5 concentration, in µg/m³
+    pub pm2_5: Option<u16>,
+    /// The threshold for the standard (CF=1) PM10 concentration, in µg/m³
+    pub pm10: Option<u16>,
+    /// The threshold for the environmental PM1 concentration, in µg/m³
+    pub env_pm1: Option<u16>,
+    /// The threshold for the environmental PM2.5 concentration, in µg/m³
+    pub env_pm2_5: Option<u16>,
+    /// The threshold for the environmental PM10 concentration, in µg/m³
+    pub env_pm10: Option<u16>,
+    /// The threshold for each of the particle counts, in 0.1L of air
+    pub particle_counts: Option<u16>,
+}
+
+impl Default for ChangeThresholds {
+    /// 2µg/m³ for the standard concentrations, ignoring the environmental
+    /// concentrations and particle counts
+    fn default() -> Self {
+        Self {
+            pm1: Some(2),
+            pm2_5: Some(2),
+            pm10: Some(2),
+            env_pm1: None,
+            env_pm2_5: None,
+            env_pm10: None,
+            particle_counts: None,
+        }
+    }
+}
+
+impl ChangeThresholds {
+    /// Thresholds ignoring every field
+    pub const fn none() -> Self {
+        Self {
+            pm1: None,
+            pm2_5: None,
+            pm10: None,
+            env_pm1: None,
+            env_pm2_5: None,
+            env_pm10: None,
+            particle_counts: None,
+        }
+    }
+
+    /// Returns `true` if any field of `delta` changed by at least its
+    /// threshold
+    pub fn is_significant(&self, delta: &ReadingDelta) -> bool {
+        let exceeds = |threshold: Option<u16>, change: i32| {
+            threshold.is_some_and(|threshold| change.unsigned_abs() >= u32::from(threshold))
+        };
+        exceeds(self.pm1, delta.pm1)
+            || exceeds(self.pm2_5, delta.pm2_5)
+            || exceeds(self.pm10, delta.pm10)
+            || exceeds(self.env_pm1, delta.env_pm1)
+            || exceeds(self.env_pm2_5, delta.env_pm2_5)
+            || exceeds(self.env_pm10, delta.env_pm10)
+            || delta
+                .particle_counts
+                .iter()
+                .any(|&change| exceeds(self.particle_counts, change))
+    }
+}
+
+/// Decides whether each new reading differs enough from the last one sent
+/// to be worth sending
+///
+/// See the [module documentation](self).  The first reading is always
+/// worth sending.
+#[derive(Debug, Clone)]
+pub struct ChangeDetector {
+    thresholds: ChangeThresholds,
+    last_sent: Option<Reading>,
+}
+
+impl ChangeDetector {
+    /// Creates a new detector using `thresholds`
+    pub const fn new(thresholds: ChangeThresholds) -> Self {
+        Self {
+            thresholds,
+            last_sent: None,
+        }
+    }
+
+    /// Returns the detector's thresholds
+    pub fn thresholds(&self) -> ChangeThresholds {
+        self.thresholds
+    }
+
+    /// Returns the last reading reported as worth sending
+    pub fn last_sent(&self) -> Option<&Reading> {
+        self.last_sent.as_ref()
+    }
+
+    /// Returns `true` if `reading` is worth sending, without recording it
+    pub fn is_significant(&self, reading: &Reading) -> bool {
+        self.last_sent.as_ref().map_or(true, |last| {
+            self.thresholds.is_significant(&last.delta(reading))
+        })
+    }
+
+    /// Returns `true` if `reading` is worth sending, in which case it
+    /// becomes the reading later ones are compared with
+    pub fn check(&mut self, reading: &Reading) -> bool {
+        let significant = self.is_significant(reading);
+        if significant {
+            self.last_sent = Some(*reading);
+        }
+        significant
+    }
+
+    /// Forgets the last reading sent, so that the next one is always worth
+    /// sending (e.g. after the link was down)
+    pub fn reset(&mut self) {
+        self.last_sent = None;
+    }
+}
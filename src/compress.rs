@@ -0,0 +1,276 @@
+//! Compact encoding of reading sequences, for transmission over links
+//! like LoRaWAN or NB-IoT where every byte counts.
+//!
+//! Air quality changes slowly, so consecutive readings are mostly alike.
+//! An [`Encoder`] stores each field of each reading as its difference from
+//! the same field of the previous reading (the first reading is stored as
+//! its difference from zero), zigzag-encoded so that small negative
+//! changes are small numbers too, and written as a varint: seven bits per
+//! byte, with the top bit set on all but the last byte.  A typical reading
+//! after the first takes 14 to 20 bytes, rather than the 26 bytes of its
+//! raw values (or the 32 bytes of its frame).  A [`Decoder`] reverses this.
+//!
+//! # Layout
+//!
+//! | Length   | Contents                                             |
+//! |----------|------------------------------------------------------|
+//! | 1        | Layout version ([`VERSION`])                         |
+//! | variable | Each reading's 14 fields, as zigzag varint deltas    |
+//!
+//! The fields of each reading are, in order: the standard PM1, PM2.5, and
+//! PM10 concentrations; the environmental PM1, PM2.5, and PM10
+//! concentrations; the six particle counts, from 0.3µm to 10µm; the
+//! firmware version, with bit 8 set if the reading is marked as [without
+//! environmental concentrations](Reading::without_atmospheric); and the
+//! device error code.  The number of readings is not stored; the decoder
+//! reads until the end of the data.
+//!
+//! ```
+//! use sen0177::{
+//!     compress::{Decoder, Encoder},
+//!     Reading,
+//! };
+//!
+//! # let readings = [Reading::default(), Reading::default()];
+//! let mut buf = [0u8; 200];
+//! let mut encoder = Encoder::new(&mut buf)?;
+//! for reading in &readings {
+//!     encoder.push(reading)?;
+//! }
+//! let payload = encoder.finish();
+//!
+//! let decoded = Decoder::new(payload)?.collect::<Result<Vec<Reading>, _>>()?;
+//! assert_eq!(decoded, readings);
+//! # Ok::<(), sen0177::compress::CompressError>(())
+//! ```
+
+use core::fmt;
+
+use crate::{Concentrations, Reading};
+
+/// The version of the layout produced by [`Encoder`]
+pub const VERSION: u8 = 1;
+
+/// The most bytes a single reading can take up
+pub const MAX_READING_LEN: usize = 12 * 3 + 2 * 2;
+
+const FIELDS: usize = 14;
+
+/// Set in the firmware version field of readings without environmental
+/// concentrations
+const ENV_UNSUPPORTED: u16 = 0x100;
+
+/// Describes errors encountered while encoding or decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressError {
+    /// The buffer is too small to hold the encoded data
+    BufferFull,
+    /// The data's layout version is not one this decoder understands
+    UnknownVersion(u8),
+    /// The data ended partway through a reading
+    Truncated,
+    /// The data holds a value out of range for its field
+    Malformed,
+}
+
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressError::BufferFull => f.write_str("Buffer too small for encoded readings"),
+            CompressError::UnknownVersion(version) => {
+                write!(f, "Unknown encoding version {}", version)
+            }
+            CompressError::Truncated => f.write_str("Encoded readings are truncated"),
+            CompressError::Malformed => f.write_str("Encoded readings are malformed"),
+        }
+    }
+}
+
+impl core::error::Error for CompressError {}
+
+fn fields(reading: &Reading) -> [u16; FIELDS] {
+    let env = reading.atmospheric();
+    let env_flag = if env.is_some() { 0 } else { ENV_UNSUPPORTED };
+    let env = env.unwrap_or_default();
+    [
+        reading.pm1(),
+        reading.pm2_5(),
+        reading.pm10(),
+        env.pm1(),
+        env.pm2_5(),
+        env.pm10(),
+        reading.particles_0_3().per_deciliter(),
+        reading.particles_0_5().per_deciliter(),
+        reading.particles_1().per_deciliter(),
+        reading.particles_2_5().per_deciliter(),
+        reading.particles_5().per_deciliter(),
+        reading.particles_10().per_deciliter(),
+        u16::from(reading.firmware_version()) | env_flag,
+        reading.device_error_code().into(),
+    ]
+}
+
+fn from_fields(fields: [u16; FIELDS]) -> Option<Reading> {
+    let [pm1, pm2_5, pm10, env_pm1, env_pm2_5, env_pm10, counts @ .., firmware_version, device_error_code] =
+        fields;
+    let reading = Reading::new(
+        Concentrations::new(pm1, pm2_5, pm10),
+        Concentrations::new(env_pm1, env_pm2_5, env_pm10),
+        counts,
+    )
+    .with_device_status(
+        (firmware_version & !ENV_UNSUPPORTED).try_into().ok()?,
+        device_error_code.try_into().ok()?,
+    );
+    if firmware_version & ENV_UNSUPPORTED != 0 {
+        Some(reading.without_atmospheric())
+    } else {
+        Some(reading)
+    }
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn unzigzag(value: u32) -> i32 {
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+/// Writes readings into a buffer in the compact encoding
+///
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct Encoder<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    previous: [u16; FIELDS],
+}
+
+impl<'a> Encoder<'a> {
+    /// Creates an encoder writing into `buf`, starting with the layout
+    /// version
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, CompressError> {
+        let first = buf.first_mut().ok_or(CompressError::BufferFull)?;
+        *first = VERSION;
+        Ok(Self {
+            buf,
+            len: 1,
+            previous: [0; FIELDS],
+        })
+    }
+
+    /// Appends `reading`
+    ///
+    /// If the reading doesn't fit, nothing is written, and the encoded data
+    /// so far remains valid.
+    pub fn push(&mut self, reading: &Reading) -> Result<(), CompressError> {
+        let current = fields(reading);
+        let mut len = self.len;
+        for (&value, &previous) in current.iter().zip(&self.previous) {
+            let mut varint = zigzag(i32::from(value) - i32::from(previous));
+            loop {
+                let byte = self.buf.get_mut(len).ok_or(CompressError::BufferFull)?;
+                len += 1;
+                if varint < 0x80 {
+                    *byte = varint as u8;
+                    break;
+                }
+                *byte = (varint & 0x7f) as u8 | 0x80;
+                varint >>= 7;
+            }
+        }
+        self.len = len;
+        self.previous = current;
+        Ok(())
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no readings have been written
+    pub fn is_empty(&self) -> bool {
+        self.len <= 1
+    }
+
+    /// Finishes encoding, returning the encoded data
+    pub fn finish(self) -> &'a [u8] {
+        &self.buf[..self.len]
+    }
+}
+
+/// Reads readings back from the compact encoding
+///
+/// This is an iterator over the readings.  After an error, it returns
+/// `None`.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    previous: [u16; FIELDS],
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder for data produced by an [`Encoder`]
+    pub fn new(buf: &'a [u8]) -> Result<Self, CompressError> {
+        match buf.first() {
+            None => Err(CompressError::Truncated),
+            Some(&VERSION) => Ok(Self {
+                buf,
+                pos: 1,
+                previous: [0; FIELDS],
+            }),
+            Some(&version) => Err(CompressError::UnknownVersion(version)),
+        }
+    }
+
+    fn read_varint(&mut self) -> Result<u32, CompressError> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or(CompressError::Truncated)?;
+            self.pos += 1;
+            let bits = u32::from(byte & 0x7f);
+            // The fifth byte only has room for four bits
+            if (bits << shift) >> shift != bits {
+                return Err(CompressError::Malformed);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CompressError::Malformed)
+    }
+
+    fn read_reading(&mut self) -> Result<Reading, CompressError> {
+        let mut current = self.previous;
+        for value in &mut current {
+            let delta = unzigzag(self.read_varint()?);
+            *value = i32::from(*value)
+                .checked_add(delta)
+                .ok_or(CompressError::Malformed)?
+                .try_into()
+                .map_err(|_| CompressError::Malformed)?;
+        }
+        let reading = from_fields(current).ok_or(CompressError::Malformed)?;
+        self.previous = current;
+        Ok(reading)
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = Result<Reading, CompressError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.buf.len() {
+            return None;
+        }
+        let result = self.read_reading();
+        if result.is_err() {
+            self.pos = self.buf.len();
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,228 @@
+//! Each reading is written as one row, preceded by its timestamp, with the
+//! columns in the order given by [`HEADER`].  This order is stable; new
+//! columns will only ever be added at the end.  Values are written as
+//! plain decimal integers, in the units returned by the corresponding
+//! [`Reading`] accessors (µg/m³ for concentrations, particles per 0.1L for
+//! counts).  The environmental concentrations are left empty for readings
+//! marked as [without them](Reading::without_atmospheric).
+//!
+//! Rows can be parsed back with
+//! [`Timestamped::from_csv_row`](Timestamped::from_csv_row), so that logs
+//! can be replayed through the analysis utilities (such as
+//! [`History`](crate::history::History)).  With the `csv` feature,
+//! [`write_record`] and [`from_string_record`] do the same through the
+//! [`csv`](https://crates.io/crates/csv) crate, which handles quoting and
+//! varied line endings.
+
+use core::fmt::{self, Write};
+
+use crate::{time::Timestamped, Concentrations, Reading};
+
+/// The header row, naming each column
+pub const HEADER: &str = "timestamp,pm1,pm2_5,pm10,env_pm1,env_pm2_5,env_pm10,\
+    particles_0_3,particles_0_5,particles_1,particles_2_5,particles_5,particles_10,\
+    firmware_version,device_error_code\n";
+
+/// The maximum length, in bytes, of a row (including its newline)
+pub const MAX_ROW_LEN: usize = 104;
+
+/// The number of columns in a row
+pub const COLUMNS: usize = 15;
+
+/// The columns of the environmental concentrations
+const ENV_COLUMNS: core::ops::Range<usize> = 4..7;
+
+/// Describes errors encountered while parsing a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvError {
+    /// The row did not have [`COLUMNS`] columns; holds the number it had
+    WrongColumnCount(usize),
+    /// The value in a column (numbered from zero) was not a valid integer
+    /// of the expected size
+    InvalidValue(usize),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::WrongColumnCount(count) => {
+                write!(f, "Expected {} columns, found {}", COLUMNS, count)
+            }
+            CsvError::InvalidValue(column) => write!(f, "Invalid value in column {}", column),
+        }
+    }
+}
+
+impl core::error::Error for CsvError {}
+
+impl Timestamped<Reading> {
+    /// Formats the record as a row, including the trailing newline
+    #[cfg(feature = "std")]
+    pub fn to_csv_row(&self) -> String {
+        Row::new(self).as_str().to_string()
+    }
+
+    /// Parses a row written by [`to_csv_row`](Self::to_csv_row), [`Row`], or
+    /// [`write_row`]
+    ///
+    /// A trailing newline (`\n` or `\r\n`) is ignored.
+    pub fn from_csv_row(row: &str) -> Result<Self, CsvError> {
+        let mut fields = [""; COLUMNS];
+        let mut count = 0;
+        for field in row.trim_end_matches(['\r', '\n']).split(',') {
+            if let Some(slot) = fields.get_mut(count) {
+                *slot = field;
+            }
+            count += 1;
+        }
+        if count != COLUMNS {
+            return Err(CsvError::WrongColumnCount(count));
+        }
+        from_fields(&fields)
+    }
+}
+
+fn from_fields(fields: &[&str; COLUMNS]) -> Result<Timestamped<Reading>, CsvError> {
+    fn parse<T: core::str::FromStr>(
+        fields: &[&str; COLUMNS],
+        column: usize,
+    ) -> Result<T, CsvError> {
+        fields[column]
+            .trim()
+            .parse()
+            .map_err(|_| CsvError::InvalidValue(column))
+    }
+    let timestamp = parse(fields, 0)?;
+    let has_env = fields[ENV_COLUMNS]
+        .iter()
+        .any(|field| !field.trim().is_empty());
+    let mut values = [0u16; 12];
+    for (index, value) in values.iter_mut().enumerate() {
+        let column = index + 1;
+        if has_env || !ENV_COLUMNS.contains(&column) {
+            *value = parse(fields, column)?;
+        }
+    }
+    let reading = Reading::new(
+        Concentrations::new(values[0], values[1], values[2]),
+        Concentrations::new(values[3], values[4], values[5]),
+        [
+            values[6], values[7], values[8], values[9], values[10], values[11],
+        ],
+    )
+    .with_device_status(parse(fields, 13)?, parse(fields, 14)?);
+    let reading = if has_env {
+        reading
+    } else {
+        reading.without_atmospheric()
+    };
+    Ok(Timestamped::new(timestamp, reading))
+}
+
+/// Writes `record` through a [`csv::Writer`](::csv::Writer)
+///
+/// Write [`HEADER`]'s column names first, if desired.
+#[cfg(feature = "csv")]
+pub fn write_record<W: std::io::Write>(
+    writer: &mut ::csv::Writer<W>,
+    record: &Timestamped<Reading>,
+) -> ::csv::Result<()> {
+    let row = Row::new(record);
+    writer.write_record(row.as_str().trim_end().split(','))
+}
+
+/// Parses a record read through a [`csv::Reader`](::csv::Reader)
+#[cfg(feature = "csv")]
+pub fn from_string_record(record: &::csv::StringRecord) -> Result<Timestamped<Reading>, CsvError> {
+    if record.len() != COLUMNS {
+        return Err(CsvError::WrongColumnCount(record.len()));
+    }
+    let mut fields = [""; COLUMNS];
+    for (slot, field) in fields.iter_mut().zip(record.iter()) {
+        *slot = field;
+    }
+    from_fields(&fields)
+}
+
+/// Writes `record` as a row, including the trailing newline
+pub fn write_row<W: Write>(w: &mut W, record: &Timestamped<Reading>) -> fmt::Result {
+    let reading = &record.value;
+    write!(
+        w,
+        "{},{},{},{},",
+        record.timestamp,
+        reading.pm1(),
+        reading.pm2_5(),
+        reading.pm10(),
+    )?;
+    match reading.atmospheric() {
+        Some(env) => write!(w, "{},{},{},", env.pm1(), env.pm2_5(), env.pm10())?,
+        None => w.write_str(",,,")?,
+    }
+    writeln!(
+        w,
+        "{},{},{},{},{},{},{},{}",
+        reading.particles_0_3().per_deciliter(),
+        reading.particles_0_5().per_deciliter(),
+        reading.particles_1().per_deciliter(),
+        reading.particles_2_5().per_deciliter(),
+        reading.particles_5().per_deciliter(),
+        reading.particles_10().per_deciliter(),
+        reading.firmware_version(),
+        reading.device_error_code(),
+    )
+}
+
+/// A row formatted into a fixed-size buffer, for use without an allocator
+#[derive(Clone)]
+pub struct Row {
+    buf: StrBuf<MAX_ROW_LEN>,
+}
+
+impl Row {
+    /// Formats `record` as a row
+    pub fn new(record: &Timestamped<Reading>) -> Self {
+        let mut buf = StrBuf::new();
+        // The buffer is sized for the longest possible row
+        let _ = write_row(&mut buf, record);
+        Self { buf }
+    }
+
+    /// Returns the row's text, including the trailing newline
+    pub fn as_str(&self) -> &str {
+        self.buf.as_str()
+    }
+}
+
+/// A string of up to `N` bytes, stored inline
+#[derive(Clone)]
+pub(crate) struct StrBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StrBuf<N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // Only ever filled from `&str`s, and never split mid-character
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Write for StrBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
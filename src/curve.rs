@@ -0,0 +1,225 @@
+//! A single slope and offset (as in [`humidity::Calibration`]) fits well
+//! at everyday concentrations, but optical sensors respond less than
+//! linearly in heavy smoke, so calibrations spanning a wide range need
+//! more points.  A [`Curve`] maps a raw concentration to a corrected one by
+//! interpolating between up to `N` calibration points, and
+//! [`CalibrationCurves`] holds one curve per standard concentration.
+//!
+//! With the `serde` feature, curves serialize as a sequence of their
+//! points, so calibrations can be stored in flash (e.g. with `postcard`)
+//! and loaded at boot.  Deserialization rejects sequences that don't make a
+//! valid curve.
+//!
+//! [`humidity::Calibration`]: crate::humidity::Calibration
+//!
+//! ```
+//! use sen0177::curve::{Curve, CurvePoint};
+//!
+//! let curve = Curve::<8>::from_points(&[
+//!     CurvePoint::new(0, 0),
+//!     CurvePoint::new(100, 700),
+//!     CurvePoint::new(300, 3500),
+//! ])?;
+//! // Halfway between the last two points
+//! assert_eq!(curve.apply(200), 2100);
+//! # Ok::<(), sen0177::curve::CurveError>(())
+//! ```
+
+use core::fmt;
+
+use crate::{Concentrations, Reading};
+
+/// A calibration point: a raw concentration and its corrected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurvePoint {
+    /// The concentration reported by the sensor, in µg/m³
+    pub raw: u16,
+    /// The corrected concentration, in tenths of a µg/m³
+    pub corrected: u32,
+}
+
+impl CurvePoint {
+    /// Creates a new calibration point
+    pub const fn new(raw: u16, corrected: u32) -> Self {
+        Self { raw, corrected }
+    }
+}
+
+/// Describes why a set of points doesn't make a valid [`Curve`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CurveError {
+    /// There were more points than the curve can hold
+    TooManyPoints,
+    /// The points' raw concentrations were not strictly increasing
+    NotIncreasing,
+}
+
+impl fmt::Display for CurveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurveError::TooManyPoints => f.write_str("Too many calibration points"),
+            CurveError::NotIncreasing => {
+                f.write_str("Calibration points are not in increasing order")
+            }
+        }
+    }
+}
+
+impl core::error::Error for CurveError {}
+
+/// A piecewise-linear calibration curve of up to `N` points
+///
+/// Between two points, the corrected value is interpolated linearly.
+/// Beyond the first or last point, the first or last segment is extended,
+/// and corrected values are clamped at zero.  A curve with a single point
+/// shifts every concentration by that point's offset, and a curve with no
+/// points leaves concentrations unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Curve<const N: usize> {
+    points: [CurvePoint; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for Curve<N> {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<const N: usize> Curve<N> {
+    /// Creates a curve with no points, which leaves concentrations unchanged
+    pub const fn identity() -> Self {
+        Self {
+            points: [CurvePoint::new(0, 0); N],
+            len: 0,
+        }
+    }
+
+    /// Creates a curve through `points`, whose raw concentrations must be
+    /// strictly increasing
+    pub fn from_points(points: &[CurvePoint]) -> Result<Self, CurveError> {
+        if points.len() > N {
+            return Err(CurveError::TooManyPoints);
+        }
+        if points.windows(2).any(|pair| pair[0].raw >= pair[1].raw) {
+            return Err(CurveError::NotIncreasing);
+        }
+        let mut curve = Self::identity();
+        curve.points[..points.len()].copy_from_slice(points);
+        curve.len = points.len();
+        Ok(curve)
+    }
+
+    /// Returns the curve's points
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points[..self.len]
+    }
+
+    /// Corrects a concentration in µg/m³, returning the corrected
+    /// concentration in tenths of a µg/m³
+    pub fn apply(&self, raw: u16) -> u32 {
+        let (first, second) = match self.points() {
+            [] => return u32::from(raw) * 10,
+            [point] => {
+                let offset = i64::from(point.corrected) - i64::from(point.raw) * 10;
+                return (i64::from(raw) * 10 + offset).max(0) as u32;
+            }
+            points => {
+                // The segment containing `raw`, or the nearest one
+                let end = points
+                    .iter()
+                    .position(|point| point.raw >= raw)
+                    .unwrap_or(points.len() - 1)
+                    .clamp(1, points.len() - 1);
+                (points[end - 1], points[end])
+            }
+        };
+        let (x0, y0) = (i64::from(first.raw), i64::from(first.corrected));
+        let (x1, y1) = (i64::from(second.raw), i64::from(second.corrected));
+        let numerator = (y1 - y0) * (i64::from(raw) - x0);
+        let span = x1 - x0;
+        // Round to the nearest, in either direction
+        let delta = if numerator < 0 {
+            (numerator - span / 2) / span
+        } else {
+            (numerator + span / 2) / span
+        };
+        (y0 + delta).clamp(0, u32::MAX.into()) as u32
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for Curve<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(self.points(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Curve<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, SeqAccess, Visitor};
+
+        struct PointsVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for PointsVisitor<N> {
+            type Value = Curve<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a sequence of at most {} calibration points", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut points = [CurvePoint::default(); N];
+                let mut len = 0;
+                while let Some(point) = seq.next_element::<CurvePoint>()? {
+                    let slot = points
+                        .get_mut(len)
+                        .ok_or_else(|| A::Error::custom(CurveError::TooManyPoints))?;
+                    *slot = point;
+                    len += 1;
+                }
+                Curve::from_points(&points[..len]).map_err(A::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_seq(PointsVisitor)
+    }
+}
+
+/// Calibration curves for each of a reading's standard (CF=1)
+/// concentrations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalibrationCurves<const N: usize> {
+    /// The curve for the PM1 concentration
+    pub pm1: Curve<N>,
+    /// The curve for the PM2.5 concentration
+    pub pm2_5: Curve<N>,
+    /// The curve for the PM10 concentration
+    pub pm10: Curve<N>,
+}
+
+impl<const N: usize> CalibrationCurves<N> {
+    /// Returns the corrected standard concentrations of `reading`, in
+    /// tenths of a µg/m³, as PM1, PM2.5, and PM10
+    pub fn apply_tenths(&self, reading: &Reading) -> [u32; 3] {
+        [
+            self.pm1.apply(reading.pm1()),
+            self.pm2_5.apply(reading.pm2_5()),
+            self.pm10.apply(reading.pm10()),
+        ]
+    }
+
+    /// Returns a copy of `reading` with its standard concentrations
+    /// corrected, rounded to the nearest µg/m³
+    ///
+    /// The environmental concentrations and particle counts are unchanged.
+    pub fn apply(&self, reading: &Reading) -> Reading {
+        let [pm1, pm2_5, pm10] = self
+            .apply_tenths(reading)
+            .map(|tenths| u16::try_from(tenths.saturating_add(5) / 10).unwrap_or(u16::MAX));
+        reading.with_cf1(Concentrations::new(pm1, pm2_5, pm10))
+    }
+}
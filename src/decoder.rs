@@ -0,0 +1,337 @@
+//! A [`FrameDecoder`] assembles frames from bytes pushed into it one at a
+//! time, handling synchronization to the start of a frame, skipping
+//! non-data frames, and checksum verification, without doing any I/O
+//! itself.  The serial drivers are thin adapters that read bytes from a
+//! UART and push them into a decoder, so the blocking and async drivers
+//! behave identically.  A decoder can also be used directly wherever bytes
+//! arrive by some other means.
+
+use core::convert::Infallible;
+
+use crate::{
+    logging::{debug, trace},
+    read::{parse_with, FRAME_LEN},
+    serial::Sen0177Builder,
+    SensorError,
+};
+use sen0177_protocol::{FrameProtocol, Plantower};
+
+/// The minimum number of bytes that must be discarded while failing to
+/// synchronize before a baud rate mismatch is suspected
+const BAUD_MISMATCH_MIN_BYTES: u32 = FRAME_LEN as u32;
+
+/// Tracks the bytes discarded while synchronizing to the start of a frame
+///
+/// A receiver running at the wrong baud rate tends to see mostly framing
+/// errors, which many UARTs deliver as 0x00 or 0xff; real sensor data
+/// (even corrupted) rarely consists of nothing else.
+struct Discarded {
+    count: u32,
+    all_idle: bool,
+}
+
+impl Default for Discarded {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            all_idle: true,
+        }
+    }
+}
+
+impl Discarded {
+    fn record(&mut self, byte: u8) {
+        self.count = self.count.saturating_add(1);
+        self.all_idle &= byte == 0x00 || byte == 0xff;
+    }
+
+    fn record_all(&mut self, bytes: &[u8]) {
+        self.count = self.count.saturating_add(bytes.len() as u32);
+        self.all_idle = self.all_idle && bytes.iter().all(|&byte| byte == 0x00 || byte == 0xff);
+    }
+
+    fn suggests_baud_mismatch(&self) -> bool {
+        self.all_idle && self.count >= BAUD_MISMATCH_MIN_BYTES
+    }
+}
+
+/// The result of decoding a frame of protocol `P`: the raw frame along with
+/// its parsed contents
+///
+/// Decoding involves no I/O, so the error is never
+/// [`SensorError::ReadError`].
+pub type DecodeResult<P> =
+    Result<(<P as FrameProtocol>::Frame, <P as FrameProtocol>::Output), SensorError<Infallible>>;
+
+pub(crate) type FrameResult<P, E> =
+    Result<(<P as FrameProtocol>::Frame, <P as FrameProtocol>::Output), SensorError<E>>;
+
+/// Assembles frames of protocol `P` from bytes pushed one at a time
+///
+/// All progress through a frame (including synchronization) is held in the
+/// decoder, so bytes can be pushed as they become available, and a read
+/// that is abandoned part way through (such as a cancelled future) can be
+/// resumed later.
+///
+/// The decoder gives up on synchronizing, returning
+/// [`SensorError::BadMagic`] or [`SensorError::LikelyBaudMismatch`], after
+/// examining the [resync budget](Sen0177Builder::resync_budget) of bytes
+/// without finding the start of a frame header, or after using up its
+/// [sync attempts](Sen0177Builder::sync_attempts).  It then starts afresh
+/// with the next byte.
+pub struct FrameDecoder<P: FrameProtocol = Plantower> {
+    frame: P::Frame,
+    /// The number of bytes of `frame` received so far; zero while searching
+    /// for the first header byte
+    len: usize,
+    /// The number of bytes examined in the current search for the first
+    /// header byte
+    searched: u32,
+    attempts_left: u32,
+    discarded: Discarded,
+    resync_budget: u32,
+    sync_attempts: u32,
+    strict_checksum: bool,
+}
+
+impl<P: FrameProtocol> Default for FrameDecoder<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: FrameProtocol> FrameDecoder<P> {
+    /// Creates a new decoder with the default parameters
+    ///
+    /// Use [`Sen0177Builder::build_decoder`] to change them.
+    pub fn new() -> Self {
+        Sen0177Builder::new().build_decoder()
+    }
+
+    pub(crate) fn with_limits(
+        resync_budget: u32,
+        sync_attempts: u32,
+        strict_checksum: bool,
+    ) -> Self {
+        Self {
+            frame: P::new_frame(),
+            len: 0,
+            searched: 0,
+            attempts_left: sync_attempts,
+            discarded: Discarded::default(),
+            resync_budget,
+            sync_attempts,
+            strict_checksum,
+        }
+    }
+
+    /// Processes a single byte, returning the result once a frame has been
+    /// completed or synchronization has failed
+    pub fn push(&mut self, byte: u8) -> Option<DecodeResult<P>> {
+        let header = P::HEADER;
+        if self.len == 0 {
+            if self.attempts_left == 0 {
+                return Some(Err(self.fail()));
+            }
+            self.searched = self.searched.saturating_add(1);
+            if header.first() == Some(&byte) {
+                self.attempts_left -= 1;
+                self.searched = 0;
+                self.accept(byte);
+            } else {
+                self.discarded.record(byte);
+                if self.searched >= self.resync_budget {
+                    trace!("Discarded {} bytes looking for a header", self.searched);
+                    return Some(Err(self.fail()));
+                }
+            }
+            return None;
+        }
+
+        if let Some(&expected) = header.get(self.len) {
+            if byte != expected {
+                self.discarded.record(byte);
+                return self.restart();
+            }
+            self.accept(byte);
+            if self.len == header.len() {
+                trace!("Synchronized to start of frame");
+            }
+            return None;
+        }
+
+        self.accept(byte);
+        if self.len == P::PREFIX_LEN {
+            let prefix = self.frame.as_ref().get(..P::PREFIX_LEN).unwrap_or_default();
+            if !P::is_data_frame(prefix) {
+                debug!("Skipping non-data frame: {:02x?}", prefix);
+                return self.restart();
+            }
+        }
+        if self.len >= self.frame.as_ref().len() {
+            return Some(self.complete());
+        }
+        None
+    }
+
+    /// Pushes a chunk of bytes (such as a DMA buffer) into the decoder,
+    /// yielding the result of each frame completed along the way
+    ///
+    /// Frames may span chunks: the bytes of a frame left incomplete at the
+    /// end of `bytes` are kept, and the frame is completed by the next
+    /// chunk.  Bytes are only pushed as the iterator is advanced, so run it
+    /// to completion before pushing more.
+    pub fn push_slice<'d, 'b>(&'d mut self, bytes: &'b [u8]) -> PushSlice<'d, 'b, P> {
+        PushSlice {
+            decoder: self,
+            bytes,
+        }
+    }
+
+    /// Discards any partially received frame and synchronization progress
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.searched = 0;
+        self.attempts_left = self.sync_attempts;
+        self.discarded = Discarded::default();
+    }
+
+    /// Processes a single byte as [`push`](FrameDecoder::push) does, with the
+    /// error typed for a driver whose bus errors are `E`
+    pub(crate) fn push_for<E>(&mut self, byte: u8) -> Option<FrameResult<P, E>> {
+        self.push(byte).map(|result| result.map_err(widen))
+    }
+
+    /// Processes the start of `bytes` as [`push`](FrameDecoder::push) would,
+    /// returning the number of bytes consumed along with any result
+    ///
+    /// While searching for a header, and once a frame's prefix has been
+    /// accepted, runs of bytes are handled at once rather than one at a time.
+    fn push_run(&mut self, bytes: &[u8]) -> (usize, Option<DecodeResult<P>>) {
+        let frame = self.frame.as_mut();
+        let frame_len = frame.len();
+        if self.len >= P::PREFIX_LEN {
+            let run = frame_len.saturating_sub(self.len).min(bytes.len());
+            let body = frame.get_mut(self.len..).unwrap_or_default();
+            for (slot, &byte) in body.iter_mut().zip(bytes).take(run) {
+                *slot = byte;
+            }
+            self.len += run;
+            let result = (self.len >= frame_len).then(|| self.complete());
+            return (run, result);
+        }
+        let Some(&first) = bytes.first() else {
+            return (0, None);
+        };
+        if self.len > 0 || self.attempts_left == 0 {
+            return (1, self.push(first));
+        }
+
+        // Searching for the first header byte; the budget runs out on the
+        // last byte of the window
+        let budget_left = self.resync_budget.saturating_sub(self.searched).max(1);
+        let window = bytes.get(..budget_left as usize).unwrap_or(bytes);
+        let header = P::HEADER.first();
+        match window.iter().position(|byte| Some(byte) == header) {
+            Some(skipped) => {
+                self.discarded
+                    .record_all(window.get(..skipped).unwrap_or_default());
+                self.attempts_left -= 1;
+                self.searched = 0;
+                self.accept(P::HEADER.first().copied().unwrap_or_default());
+                (skipped + 1, None)
+            }
+            None => {
+                self.discarded.record_all(window);
+                self.searched = self.searched.saturating_add(window.len() as u32);
+                if self.searched >= self.resync_budget {
+                    trace!("Discarded {} bytes looking for a header", self.searched);
+                    (window.len(), Some(Err(self.fail())))
+                } else {
+                    (window.len(), None)
+                }
+            }
+        }
+    }
+
+    /// Parses the completed frame and starts looking for the next one
+    fn complete(&mut self) -> DecodeResult<P> {
+        let frame = self.frame;
+        self.reset();
+        parse_with::<P, _>(&frame, self.strict_checksum).map(|output| (frame, output))
+    }
+
+    fn accept(&mut self, byte: u8) {
+        if let Some(slot) = self.frame.as_mut().get_mut(self.len) {
+            *slot = byte;
+            self.len += 1;
+        }
+    }
+
+    /// Goes back to searching for a header, if any attempts remain
+    fn restart(&mut self) -> Option<DecodeResult<P>> {
+        self.len = 0;
+        if self.attempts_left == 0 {
+            Some(Err(self.fail()))
+        } else {
+            None
+        }
+    }
+
+    fn fail(&mut self) -> SensorError<Infallible> {
+        debug!("Unable to synchronize to start of frame");
+        let error = if self.discarded.suggests_baud_mismatch() {
+            debug!("Discarded {} bytes of only 0x00/0xff", self.discarded.count);
+            SensorError::LikelyBaudMismatch
+        } else {
+            SensorError::BadMagic
+        };
+        self.reset();
+        error
+    }
+}
+
+/// An iterator over the frames decoded from a chunk of bytes
+///
+/// Returned by [`FrameDecoder::push_slice`].
+pub struct PushSlice<'d, 'b, P: FrameProtocol> {
+    decoder: &'d mut FrameDecoder<P>,
+    bytes: &'b [u8],
+}
+
+impl<P: FrameProtocol> PushSlice<'_, '_, P> {
+    /// Returns the bytes not yet pushed into the decoder
+    pub fn remaining(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl<P: FrameProtocol> Iterator for PushSlice<'_, '_, P> {
+    type Item = DecodeResult<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.bytes.is_empty() {
+            let (consumed, result) = self.decoder.push_run(self.bytes);
+            self.bytes = self.bytes.get(consumed..).unwrap_or_default();
+            if result.is_some() {
+                return result;
+            }
+        }
+        None
+    }
+}
+
+/// Converts a decoding error, which never holds a bus error, into a
+/// driver's error type
+pub(crate) fn widen<E>(error: SensorError<Infallible>) -> SensorError<E> {
+    match error {
+        SensorError::BadMagic => SensorError::BadMagic,
+        SensorError::LikelyBaudMismatch => SensorError::LikelyBaudMismatch,
+        SensorError::ChecksumMismatch => SensorError::ChecksumMismatch,
+        SensorError::ImplausibleData(reason) => SensorError::ImplausibleData(reason),
+        SensorError::Timeout => SensorError::Timeout,
+        SensorError::StaleData => SensorError::StaleData,
+        SensorError::NoData { since } => SensorError::NoData { since },
+        SensorError::ReadError(never) => match never {},
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{AirQualitySensor, Reading, SensorError, SensorInfo};
+
+/// A reading annotated with whether it repeats the previous reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduplicated {
+    /// The reading differs from the previous reading
+    New(Reading),
+    /// The reading is identical to the previous reading
+    Repeated(Reading),
+}
+
+impl Deduplicated {
+    /// Returns the reading, regardless of whether it was repeated
+    pub fn reading(&self) -> Reading {
+        match self {
+            Deduplicated::New(reading) | Deduplicated::Repeated(reading) => *reading,
+        }
+    }
+}
+
+/// Wraps a sensor to detect or skip repeated identical frames
+///
+/// In active mode, the sensor sends the same measurement several times
+/// before updating it.  When used through [`AirQualitySensor::read`], this
+/// wrapper skips those repeats.  Since identical consecutive readings can also
+/// be legitimate (in very stable air, for example), at most
+/// [`max_skipped`](Dedup::max_skipped) repeats in a row are skipped before a
+/// repeated reading is returned anyway.
+pub struct Dedup<S> {
+    sensor: S,
+    last: Option<Reading>,
+    max_skipped: u32,
+}
+
+impl<S> Dedup<S> {
+    /// Wraps `sensor`, skipping at most 5 repeated readings in a row
+    pub fn new(sensor: S) -> Self {
+        Self {
+            sensor,
+            last: None,
+            max_skipped: 5,
+        }
+    }
+
+    /// Sets the maximum number of repeated readings that will be skipped in
+    /// a row
+    pub fn max_skipped(mut self, max_skipped: u32) -> Self {
+        self.max_skipped = max_skipped;
+        self
+    }
+
+    /// Consumes the wrapper, returning the underlying sensor
+    pub fn release(self) -> S {
+        self.sensor
+    }
+
+    /// Reads a single sensor measurement, reporting whether or not it was
+    /// identical to the previous measurement
+    pub fn read_deduplicated<E>(&mut self) -> Result<Deduplicated, SensorError<E>>
+    where
+        S: AirQualitySensor<E>,
+    {
+        let reading = self.sensor.read()?;
+        if self.last.replace(reading) == Some(reading) {
+            Ok(Deduplicated::Repeated(reading))
+        } else {
+            Ok(Deduplicated::New(reading))
+        }
+    }
+}
+
+impl<S, E> AirQualitySensor<E> for Dedup<S>
+where
+    S: AirQualitySensor<E>,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        let mut skipped = 0;
+        loop {
+            match self.read_deduplicated()? {
+                Deduplicated::Repeated(_) if skipped < self.max_skipped => skipped += 1,
+                deduplicated => break Ok(deduplicated.reading()),
+            }
+        }
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensor.info()
+    }
+}
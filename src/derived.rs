@@ -0,0 +1,234 @@
+//! These are estimates built on published empirical relationships, not
+//! measurements; the assumptions behind each are documented on the
+//! individual functions.  Since they require floating point math, they are
+//! only available with the `float` feature.
+
+use crate::Reading;
+
+/// Dry mass extinction efficiency of fine particles, in m²/g
+const FINE_MASS_EXTINCTION_EFFICIENCY: f32 = 3.0;
+
+/// Rayleigh scattering by clean air, in Mm⁻¹
+const RAYLEIGH_SCATTERING: f32 = 10.0;
+
+/// Hygroscopic growth factor f(RH) = (1 − RH)^−0.7 (Kasten), tabulated at
+/// 10% RH steps up to 90%, plus 95%
+const GROWTH_FACTORS: [(f32, f32); 11] = [
+    (0.0, 1.0),
+    (10.0, 1.077),
+    (20.0, 1.169),
+    (30.0, 1.284),
+    (40.0, 1.430),
+    (50.0, 1.625),
+    (60.0, 1.899),
+    (70.0, 2.323),
+    (80.0, 3.085),
+    (90.0, 5.012),
+    (95.0, 8.142),
+];
+
+fn growth_factor(relative_humidity: f32) -> f32 {
+    let rh = relative_humidity.clamp(0.0, 95.0);
+    GROWTH_FACTORS
+        .windows(2)
+        .find(|pair| rh <= pair[1].0)
+        .map(|pair| {
+            let ((rh_lo, f_lo), (rh_hi, f_hi)) = (pair[0], pair[1]);
+            f_lo + (f_hi - f_lo) * (rh - rh_lo) / (rh_hi - rh_lo)
+        })
+        .unwrap_or(GROWTH_FACTORS[GROWTH_FACTORS.len() - 1].1)
+}
+
+/// Estimates the visual range, in kilometers, from the standard PM2.5
+/// concentration and the relative humidity (in percent)
+///
+/// The light extinction coefficient is estimated as
+/// `b_ext = 3 m²/g × f(RH) × PM2.5 + 10 Mm⁻¹`, where f(RH) is Kasten's
+/// hygroscopic growth factor `(1 − RH)^−0.7` (with RH clamped to 95%), and
+/// the final term accounts for Rayleigh scattering.  The visual range then
+/// follows from the Koschmieder relation `V = 3.912 / b_ext`.
+///
+/// This ignores coarse particles, absorbing aerosols, and gases, so it is
+/// only a rough approximation.
+pub fn visibility_km(reading: &Reading, relative_humidity: f32) -> f32 {
+    let extinction =
+        FINE_MASS_EXTINCTION_EFFICIENCY * growth_factor(relative_humidity) * reading.pm2_5() as f32
+            + RAYLEIGH_SCATTERING;
+    3912.0 / extinction
+}
+
+/// Representative diameters, in µm, of the differential particle bins
+/// returned by [`Reading::particle_bins`], taken as the geometric mean of
+/// each bin's edges
+///
+/// The open-ended bin beyond 10µm has no meaningful representative size and
+/// is excluded.
+pub const BIN_DIAMETERS_UM: [f32; 5] = [0.387, 0.707, 1.581, 3.536, 7.071];
+
+/// Assumptions used to reconstruct mass concentrations from particle counts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassModel {
+    /// Particle density, in g/cm³
+    pub density: f32,
+    /// Factor applied to each bin's diameter, to correct for the difference
+    /// between the optical size the sensor measures (which depends on the
+    /// particles' refractive index) and their geometric size
+    pub diameter_scale: f32,
+}
+
+impl Default for MassModel {
+    /// Spherical particles with a density of 1.65 g/cm³ (a typical value for
+    /// ambient PM2.5) and no diameter correction
+    fn default() -> Self {
+        Self {
+            density: 1.65,
+            diameter_scale: 1.0,
+        }
+    }
+}
+
+/// Mass concentrations, in µg/m³, reconstructed from particle counts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassEstimate {
+    /// Estimated PM1 concentration
+    pub pm1: f32,
+    /// Estimated PM2.5 concentration
+    pub pm2_5: f32,
+    /// Estimated PM10 concentration
+    pub pm10: f32,
+}
+
+/// Reconstructs PM1, PM2.5, and PM10 mass concentrations from the reading's
+/// particle counts
+///
+/// Each differential bin is treated as spheres of the bin's representative
+/// diameter (see [`BIN_DIAMETERS_UM`]), scaled by `model.diameter_scale`,
+/// with density `model.density`.  Particles smaller than 0.3µm are not
+/// counted by the sensor, so the estimates will tend to be low.  Comparing
+/// the result against the sensor's own concentrations over time can reveal
+/// drift between its count and mass channels.
+pub fn mass_from_counts(reading: &Reading, model: &MassModel) -> MassEstimate {
+    let bins = reading.particle_bins();
+    let mut masses = [0.0f32; 5];
+    for (mass, (count, diameter)) in masses
+        .iter_mut()
+        .zip(bins.iter().zip(BIN_DIAMETERS_UM.iter()))
+    {
+        let d = diameter * model.diameter_scale;
+        // count/0.1L × 10⁴ → count/m³; πd³/6 µm³ × ρ g/cm³ × 10⁻⁶ → µg
+        *mass = count.per_deciliter() as f32
+            * model.density
+            * (core::f32::consts::PI / 6.0)
+            * d
+            * d
+            * d
+            * 0.01;
+    }
+    let pm1 = masses[0] + masses[1];
+    let pm2_5 = pm1 + masses[2];
+    MassEstimate {
+        pm1,
+        pm2_5,
+        pm10: pm2_5 + masses[3] + masses[4],
+    }
+}
+
+/// Fractions of inhaled particles deposited in the alveolar region of the
+/// lung, for each of [`BIN_DIAMETERS_UM`]
+///
+/// These follow the ICRP 66 lung deposition model for a reference adult
+/// breathing through the nose, as fit by Hinds (1999), including the
+/// fraction of particles that are inhalable at all.
+pub const ALVEOLAR_DEPOSITION: [f32; 5] = [0.0673, 0.1042, 0.1263, 0.0823, 0.0326];
+
+/// Number, surface area, volume, and mass concentrations of one
+/// differential size bin
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BinConcentrations {
+    /// The bin's representative diameter, in µm, after scaling
+    pub diameter_um: f32,
+    /// Number concentration, in particles per cm³
+    pub number: f32,
+    /// Surface area concentration, in µm²/cm³
+    pub surface_area: f32,
+    /// Surface area concentration of the particles deposited in the
+    /// alveolar region of the lung, in µm²/cm³
+    pub lung_deposited_surface_area: f32,
+    /// Volume concentration, in µm³/cm³ (numerically equal to the mass
+    /// concentration in µg/m³ of particles with a density of 1 g/cm³)
+    pub volume: f32,
+    /// Mass concentration, in µg/m³
+    pub mass: f32,
+}
+
+/// Concentrations of each differential size bin of a reading, as returned
+/// by [`size_distribution`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SizeDistribution {
+    /// The bins, from 0.3–0.5µm to 5–10µm
+    pub bins: [BinConcentrations; 5],
+}
+
+impl SizeDistribution {
+    /// Returns the total surface area concentration, in µm²/cm³
+    pub fn surface_area(&self) -> f32 {
+        self.bins.iter().map(|bin| bin.surface_area).sum()
+    }
+
+    /// Returns the total lung-deposited surface area (LDSA) concentration,
+    /// in µm²/cm³
+    pub fn lung_deposited_surface_area(&self) -> f32 {
+        self.bins
+            .iter()
+            .map(|bin| bin.lung_deposited_surface_area)
+            .sum()
+    }
+
+    /// Returns the total volume concentration, in µm³/cm³
+    pub fn volume(&self) -> f32 {
+        self.bins.iter().map(|bin| bin.volume).sum()
+    }
+
+    /// Returns the total mass concentration, in µg/m³
+    pub fn mass(&self) -> f32 {
+        self.bins.iter().map(|bin| bin.mass).sum()
+    }
+}
+
+/// Estimates the number, surface area, volume, and mass concentrations of
+/// each of the reading's differential particle bins
+///
+/// As in [`mass_from_counts`], each bin is treated as spheres of the bin's
+/// representative diameter (see [`BIN_DIAMETERS_UM`]), scaled by
+/// `model.diameter_scale`, with density `model.density` (which only
+/// affects the mass).  A sphere of diameter `d` has a surface area of
+/// `πd²` and a volume of `πd³/6`.  The lung-deposited surface area weights
+/// each bin's surface area by its [`ALVEOLAR_DEPOSITION`] fraction, which
+/// is computed for the unscaled diameters.
+///
+/// Since every particle in a bin is taken to be the same size, these are
+/// coarse estimates: LDSA in particular is dominated by ultrafine particles,
+/// which the sensor can't see at all, so it is greatly underestimated
+/// compared with a diffusion charger.  They are best used to compare
+/// readings from the same sensor.
+pub fn size_distribution(reading: &Reading, model: &MassModel) -> SizeDistribution {
+    let counts = reading.particle_bins();
+    let mut distribution = SizeDistribution::default();
+    for (i, bin) in distribution.bins.iter_mut().enumerate() {
+        let d = BIN_DIAMETERS_UM[i] * model.diameter_scale;
+        // count/0.1L → count/cm³
+        let number = counts[i].per_deciliter() as f32 / 100.0;
+        let surface_area = number * core::f32::consts::PI * d * d;
+        let volume = surface_area * d / 6.0;
+        *bin = BinConcentrations {
+            diameter_um: d,
+            number,
+            surface_area,
+            lung_deposited_surface_area: surface_area * ALVEOLAR_DEPOSITION[i],
+            volume,
+            // µm³/cm³ × g/cm³ → µg/m³
+            mass: volume * model.density,
+        };
+    }
+    distribution
+}
@@ -0,0 +1,232 @@
+//! Plantower sensors share the same magic bytes and checksum, but differ in
+//! their frame layout.  [`probe`] passively inspects a few frames from the
+//! data stream (without sending any commands) to tell the families apart:
+//!
+//! * The PMS1003 and PMS3003 send 24-byte frames with no particle counts.
+//! * The PMS5003, PMS7003, SEN0177, and similar send 32-byte frames.
+//! * The PMS5003T also sends 32-byte frames, but reports temperature and
+//!   humidity in place of the two largest particle counts.  Since the
+//!   length is the same, this is detected heuristically: real particle
+//!   counts are cumulative, so each must be no larger than the one before
+//!   it, while temperature and humidity values usually break that rule.
+//!
+//! Probing deliberately doesn't look at how the sensor responds to
+//! commands.  It only needs the sensor's TX line, so it also works where
+//! the host can't transmit, and it leaves the sensor's mode alone.  The
+//! families that accept commands answer them identically anyway, and the
+//! PMS1003 and PMS3003, which ignore them, are already told apart by their
+//! frame length.
+
+use embedded_hal_nb::{
+    nb::block,
+    serial::{Error as SerialError, Read},
+};
+use sen0177_protocol::{
+    checksum, Pms3003, Pms5003T, FRAME_LEN, MAGIC_BYTE_0, MAGIC_BYTE_1, PMS3003_FRAME_LEN,
+};
+
+use crate::{
+    capability::{TempHumidity, TempHumiditySensor},
+    logging::debug,
+    serial::{Sen0177, SerialDriver},
+    AirQualitySensor, Reading, SensorError, SensorInfo,
+};
+
+/// The number of valid frames inspected before deciding on a model
+pub const PROBE_FRAMES: usize = 4;
+
+/// The maximum number of bytes read while probing before giving up with
+/// [`SensorError::BadMagic`]
+pub const PROBE_BUDGET: u32 = 512;
+
+/// A family of sensors identified by [`probe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectedModel {
+    /// A PMS1003 or PMS3003, which sends 24-byte frames without particle
+    /// counts
+    Pms1003Or3003,
+    /// A PMS5003, PMS7003, SEN0177, or compatible sensor, which sends
+    /// 32-byte frames with particle counts
+    Pms5003Or7003,
+    /// A PMS5003T, which sends 32-byte frames with temperature and humidity
+    Pms5003T,
+}
+
+const PMS3003_INFO: SensorInfo = SensorInfo {
+    name: "PMS1003/PMS3003",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: false,
+    supports_temperature_humidity: false,
+    frame_len: PMS3003_FRAME_LEN,
+    default_i2c_address: None,
+};
+
+const PMS5003T_INFO: SensorInfo = SensorInfo {
+    name: "PMS5003T",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: true,
+    supports_temperature_humidity: true,
+    frame_len: FRAME_LEN,
+    default_i2c_address: None,
+};
+
+impl DetectedModel {
+    /// Returns the length, in bytes, of the model's data frames
+    pub fn frame_len(&self) -> usize {
+        match self {
+            DetectedModel::Pms1003Or3003 => PMS3003_FRAME_LEN,
+            DetectedModel::Pms5003Or7003 | DetectedModel::Pms5003T => FRAME_LEN,
+        }
+    }
+
+    /// Creates the appropriate driver for the model, connected to UART
+    /// `serial_port`
+    pub fn connect<R, E>(self, serial_port: R) -> DetectedDriver<R, E>
+    where
+        R: Read<u8, Error = E>,
+        E: SerialError,
+    {
+        match self {
+            DetectedModel::Pms1003Or3003 => DetectedDriver::Pms3003(SerialDriver::new(serial_port)),
+            DetectedModel::Pms5003Or7003 => DetectedDriver::Pms5003(Sen0177::new(serial_port)),
+            DetectedModel::Pms5003T => DetectedDriver::Pms5003T(SerialDriver::new(serial_port)),
+        }
+    }
+}
+
+/// A driver for whichever model [`probe`] detected
+pub enum DetectedDriver<R, E>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+{
+    /// A PMS1003 or PMS3003
+    Pms3003(SerialDriver<Pms3003, R>),
+    /// A PMS5003, PMS7003, SEN0177, or compatible sensor
+    Pms5003(Sen0177<R, E>),
+    /// A PMS5003T
+    Pms5003T(SerialDriver<Pms5003T, R>),
+}
+
+impl<R, E> DetectedDriver<R, E>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+{
+    /// Consumes the driver, returning the underlying UART
+    pub fn release(self) -> R {
+        match self {
+            DetectedDriver::Pms3003(driver) => driver.release(),
+            DetectedDriver::Pms5003(sensor) => sensor.release(),
+            DetectedDriver::Pms5003T(driver) => driver.release(),
+        }
+    }
+}
+
+impl<R, E> AirQualitySensor<E> for DetectedDriver<R, E>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        match self {
+            DetectedDriver::Pms3003(driver) => driver.read_frame().map(|(_, reading)| reading),
+            DetectedDriver::Pms5003(sensor) => sensor.read(),
+            DetectedDriver::Pms5003T(driver) => {
+                driver.read_frame().map(|(_, output)| output.reading)
+            }
+        }
+    }
+
+    fn info(&self) -> SensorInfo {
+        match self {
+            DetectedDriver::Pms3003(_) => PMS3003_INFO,
+            DetectedDriver::Pms5003(sensor) => sensor.info(),
+            DetectedDriver::Pms5003T(_) => PMS5003T_INFO,
+        }
+    }
+}
+
+impl<R> TempHumiditySensor<R::Error> for SerialDriver<Pms5003T, R>
+where
+    R: Read<u8>,
+{
+    fn read_temp_humidity(&mut self) -> Result<TempHumidity, SensorError<R::Error>> {
+        self.read_frame()
+            .map(|(_, output)| TempHumidity::new(output.temperature, output.relative_humidity))
+    }
+}
+
+/// Identifies the family of the Plantower-protocol sensor connected to UART
+/// `serial_port`, by inspecting [`PROBE_FRAMES`] valid frames from its data
+/// stream
+///
+/// The sensor must be in active mode.  Frames that fail their checksum, or
+/// that aren't data frames (such as command responses), are ignored.  This
+/// function will block until sufficient data is available, and returns
+/// [`SensorError::BadMagic`] if too few valid frames are found within
+/// [`PROBE_BUDGET`] bytes.
+pub fn probe<R, E>(serial_port: &mut R) -> Result<DetectedModel, SensorError<E>>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+{
+    let mut budget = PROBE_BUDGET;
+    let mut next_byte = || -> Result<u8, SensorError<E>> {
+        budget = budget.checked_sub(1).ok_or(SensorError::BadMagic)?;
+        block!(serial_port.read()).map_err(SensorError::bus)
+    };
+
+    let mut short_frames = 0;
+    let mut long_frames = 0;
+    let mut unordered_counts = 0;
+    let mut buf = [0u8; FRAME_LEN];
+    while short_frames + long_frames < PROBE_FRAMES {
+        // A byte following the first magic byte may itself start a frame
+        let mut byte = next_byte()?;
+        loop {
+            let previous = byte;
+            byte = next_byte()?;
+            if previous == MAGIC_BYTE_0 && byte == MAGIC_BYTE_1 {
+                break;
+            }
+        }
+        buf[0] = MAGIC_BYTE_0;
+        buf[1] = MAGIC_BYTE_1;
+        buf[2] = next_byte()?;
+        buf[3] = next_byte()?;
+        let len = 4 + u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if len != PMS3003_FRAME_LEN && len != FRAME_LEN {
+            debug!("Skipping {}-byte frame while probing", len);
+            continue;
+        }
+        for slot in buf[4..len].iter_mut() {
+            *slot = next_byte()?;
+        }
+        let expected = u16::from_be_bytes([buf[len - 2], buf[len - 1]]);
+        if checksum(&buf[..len - 2]) != expected {
+            debug!("Skipping corrupt frame while probing");
+            continue;
+        }
+
+        if len == PMS3003_FRAME_LEN {
+            short_frames += 1;
+        } else {
+            long_frames += 1;
+            let count = |offset: usize| u16::from_be_bytes([buf[offset], buf[offset + 1]]);
+            if count(22) < count(24) || count(24) < count(26) {
+                unordered_counts += 1;
+            }
+        }
+    }
+
+    let model = if short_frames > long_frames {
+        DetectedModel::Pms1003Or3003
+    } else if unordered_counts * 2 > long_frames {
+        DetectedModel::Pms5003T
+    } else {
+        DetectedModel::Pms5003Or7003
+    };
+    debug!("Detected {:?}", model);
+    Ok(model)
+}
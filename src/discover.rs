@@ -0,0 +1,148 @@
+//! [`discover_ports`] enumerates the system's serial ports (USB adapters
+//! such as the CH340 and CP210x, on-board UARTs, and the Raspberry Pi's
+//! `/dev/serial*` aliases), listens to each one for a short time, and
+//! returns those that delivered valid data frames.  Listening is passive,
+//! so sensors in passive mode or asleep will not be found.
+
+use serialport::{DataBits, FlowControl, Parity, SerialPortType, StopBits};
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::analyze::{analyze, FrameKind};
+
+/// The baud rate used by Plantower-protocol sensors
+pub const BAUD_RATE: u32 = 9600;
+
+/// The baud rates tried by [`detect_baud_rate`], in order
+pub const COMMON_BAUD_RATES: [u32; 8] = [9600, 19200, 38400, 57600, 115200, 4800, 2400, 1200];
+
+/// Aliases created by Raspberry Pi OS for the primary and secondary UARTs
+const SERIAL_ALIASES: [&str; 2] = ["/dev/serial0", "/dev/serial1"];
+
+/// A serial port on which a sensor was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPort {
+    /// The path to the port's device node
+    pub path: String,
+    /// A description of the USB adapter, if the port is USB-attached
+    pub usb_adapter: Option<String>,
+    /// The number of valid data frames received while listening
+    pub frames: usize,
+}
+
+/// Enumerates the system's serial ports and listens to each for up to
+/// `listen` for valid data frames, returning the ports on which any were
+/// received, with the most frames first
+///
+/// Ports are probed concurrently, so this takes roughly `listen` regardless
+/// of the number of ports.  Sensors send a frame roughly once per second, so
+/// a `listen` of two or three seconds is usually enough.  Ports that can't
+/// be opened (e.g. due to permissions) are skipped.
+pub fn discover_ports(listen: Duration) -> io::Result<Vec<DiscoveredPort>> {
+    let mut ports = serialport::available_ports()?
+        .into_iter()
+        .map(|info| {
+            let usb_adapter = match info.port_type {
+                SerialPortType::UsbPort(usb) => Some(
+                    usb.product
+                        .or_else(|| usb_chip(usb.vid).map(ToString::to_string))
+                        .unwrap_or_else(|| format!("{:04x}:{:04x}", usb.vid, usb.pid)),
+                ),
+                _ => None,
+            };
+            (info.port_name, usb_adapter)
+        })
+        .collect::<Vec<_>>();
+
+    for alias in SERIAL_ALIASES {
+        let Ok(target) = fs::canonicalize(alias) else {
+            continue;
+        };
+        match ports.iter_mut().find(|(path, _)| Path::new(path) == target) {
+            Some((path, _)) => *path = alias.to_string(),
+            None => ports.push((alias.to_string(), None)),
+        }
+    }
+
+    let mut found = thread::scope(|scope| {
+        let probes = ports
+            .into_iter()
+            .map(|(path, usb_adapter)| {
+                scope.spawn(move || {
+                    let frames = count_frames(&path, BAUD_RATE, listen).ok()?;
+                    (frames > 0).then_some(DiscoveredPort {
+                        path,
+                        usb_adapter,
+                        frames,
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        probes
+            .into_iter()
+            .filter_map(|probe| probe.join().ok().flatten())
+            .collect::<Vec<_>>()
+    });
+    found.sort_by(|a, b| b.frames.cmp(&a.frames).then_with(|| a.path.cmp(&b.path)));
+    Ok(found)
+}
+
+/// Listens to the port at `path` at each of [`COMMON_BAUD_RATES`] in turn,
+/// for up to `listen` each, returning the first rate at which valid data
+/// frames were received
+///
+/// This is useful for diagnosing
+/// [`SensorError::LikelyBaudMismatch`](crate::SensorError::LikelyBaudMismatch)
+/// errors, e.g. when the sensor is connected through a bridge or converter
+/// with its own baud rate setting.
+pub fn detect_baud_rate(path: &str, listen: Duration) -> io::Result<Option<u32>> {
+    for baud_rate in COMMON_BAUD_RATES {
+        if count_frames(path, baud_rate, listen)? > 0 {
+            return Ok(Some(baud_rate));
+        }
+    }
+    Ok(None)
+}
+
+fn count_frames(path: &str, baud_rate: u32, listen: Duration) -> io::Result<usize> {
+    let mut port = serialport::new(path, baud_rate)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .flow_control(FlowControl::None)
+        .timeout(Duration::from_millis(100))
+        .open()?;
+
+    let start = Instant::now();
+    let mut captured = Vec::new();
+    let mut buf = [0u8; 64];
+    while start.elapsed() < listen {
+        match port.read(&mut buf) {
+            Ok(len) => captured.extend_from_slice(&buf[..len]),
+            Err(error) if error.kind() == io::ErrorKind::TimedOut => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(analyze(&captured)
+        .frames
+        .iter()
+        .filter(|frame| matches!(frame.kind, FrameKind::Data(_)) && frame.checksum.is_ok())
+        .count())
+}
+
+/// Names the USB-to-UART bridge chips commonly used with these sensors
+fn usb_chip(vid: u16) -> Option<&'static str> {
+    match vid {
+        0x0403 => Some("FTDI USB-UART bridge"),
+        0x067b => Some("Prolific PL2303"),
+        0x10c4 => Some("Silicon Labs CP210x"),
+        0x1a86 => Some("WCH CH340"),
+        _ => None,
+    }
+}
@@ -0,0 +1,159 @@
+//! Colors for showing air quality on LED indicators, such as the WS2812
+//! (NeoPixel) in an air quality lamp.
+//!
+//! [`color_for`] gives the official US EPA color of the AQI category, so
+//! that the indicator reads the same as published AQI maps, while
+//! [`gradient_color_for`] blends smoothly between the category colors, so
+//! that a slowly changing reading doesn't make the color jump at category
+//! boundaries.  Both take either an [`Aqi`] or an
+//! [`AqiInput`](crate::aqi::AqiInput), and use integer math only.
+//!
+//! For character LCDs (such as the HD44780) and small OLEDs (such as the
+//! SSD1306) showing text, [`format_compact`] writes a reading as lines of
+//! exactly [`COLUMNS`] characters, without allocating.
+
+use core::fmt;
+
+use crate::{
+    aqi::{Aqi, AqiCategory},
+    Reading,
+};
+
+/// A color with 8 bits per channel
+///
+/// The fields are in the same order as `smart_leds::RGB8`, which WS2812
+/// drivers take, so converting is a matter of copying them across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rgb8 {
+    /// The red channel
+    pub r: u8,
+    /// The green channel
+    pub g: u8,
+    /// The blue channel
+    pub b: u8,
+}
+
+impl Rgb8 {
+    /// Creates a color from its channels
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Scales the color by `brightness`, where 255 leaves it unchanged
+    ///
+    /// LEDs are often far too bright at full power for indoor use.
+    pub fn dimmed(self, brightness: u8) -> Self {
+        let scale = |channel: u8| ((u16::from(channel) * u16::from(brightness) + 127) / 255) as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+}
+
+/// Returns the US EPA color of an AQI category
+pub const fn category_color(category: AqiCategory) -> Rgb8 {
+    match category {
+        AqiCategory::Good => Rgb8::new(0, 228, 0),
+        AqiCategory::Moderate => Rgb8::new(255, 255, 0),
+        AqiCategory::UnhealthyForSensitiveGroups => Rgb8::new(255, 126, 0),
+        AqiCategory::Unhealthy => Rgb8::new(255, 0, 0),
+        AqiCategory::VeryUnhealthy => Rgb8::new(143, 63, 151),
+        AqiCategory::Hazardous => Rgb8::new(126, 0, 35),
+    }
+}
+
+/// Returns the US EPA color of the AQI category of `value` (an [`Aqi`] or
+/// an [`AqiInput`](crate::aqi::AqiInput))
+pub fn color_for(value: impl Into<Aqi>) -> Rgb8 {
+    category_color(value.into().category())
+}
+
+/// The AQI at which the gradient is exactly each category's color: the
+/// middle of each category
+const GRADIENT_STOPS: [(u16, AqiCategory); 6] = [
+    (25, AqiCategory::Good),
+    (75, AqiCategory::Moderate),
+    (125, AqiCategory::UnhealthyForSensitiveGroups),
+    (175, AqiCategory::Unhealthy),
+    (250, AqiCategory::VeryUnhealthy),
+    (400, AqiCategory::Hazardous),
+];
+
+/// Returns a color for `value` (an [`Aqi`] or an
+/// [`AqiInput`](crate::aqi::AqiInput)) that blends smoothly between the US
+/// EPA category colors
+///
+/// The color is exactly the category's color in the middle of each
+/// category, and blends linearly into the next category's color between
+/// the two middles.
+pub fn gradient_color_for(value: impl Into<Aqi>) -> Rgb8 {
+    let aqi = value.into().value();
+    let mut lower = GRADIENT_STOPS[0];
+    for upper in GRADIENT_STOPS {
+        if aqi <= upper.0 {
+            let span = upper.0 - lower.0;
+            if span == 0 {
+                break;
+            }
+            let along = aqi.saturating_sub(lower.0);
+            let (from, to) = (category_color(lower.1), category_color(upper.1));
+            let mix = |from: u8, to: u8| {
+                let from = i32::from(from);
+                (from + (i32::from(to) - from) * i32::from(along) / i32::from(span)) as u8
+            };
+            return Rgb8::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b));
+        }
+        lower = upper;
+    }
+    category_color(lower.1)
+}
+
+/// The width, in characters, of the lines written by [`format_compact`]:
+/// that of a 16x2 character LCD
+pub const COLUMNS: usize = 16;
+
+/// A line of the compact text format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompactLine {
+    /// The PM1 concentration
+    Pm1,
+    /// The PM2.5 concentration
+    Pm2_5,
+    /// The PM10 concentration
+    Pm10,
+}
+
+impl CompactLine {
+    /// All the lines, in the order [`format_compact`] writes them
+    pub const ALL: [CompactLine; 3] = [CompactLine::Pm1, CompactLine::Pm2_5, CompactLine::Pm10];
+}
+
+/// Writes a single line of the compact format for `reading`, without a
+/// line ending
+///
+/// The line is exactly [`COLUMNS`] characters of ASCII (the HD44780's
+/// character set has no µ), e.g. `PM2.5   12 ug/m3`.  Standard (CF=1)
+/// concentrations are used.
+pub fn format_compact_line(
+    reading: &Reading,
+    line: CompactLine,
+    out: &mut impl fmt::Write,
+) -> fmt::Result {
+    let (label, value) = match line {
+        CompactLine::Pm1 => ("PM1", reading.pm1()),
+        CompactLine::Pm2_5 => ("PM2.5", reading.pm2_5()),
+        CompactLine::Pm10 => ("PM10", reading.pm10()),
+    };
+    write!(out, "{:<5}{:>5} ug/m3", label, value)
+}
+
+/// Writes `reading` as the PM1, PM2.5, and PM10 lines of the compact
+/// format, each followed by `\n`
+///
+/// See [`format_compact_line`], e.g. to show only two of the lines on a
+/// two-line display.
+pub fn format_compact(reading: &Reading, out: &mut impl fmt::Write) -> fmt::Result {
+    for line in CompactLine::ALL {
+        format_compact_line(reading, line, out)?;
+        out.write_char('\n')?;
+    }
+    Ok(())
+}
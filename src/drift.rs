@@ -0,0 +1,258 @@
+//! Low-cost sensors age: their fans clog, their lasers dim, and dust
+//! settles on their optics, so a unit's readings slowly wander away from
+//! the truth.  With two sensors side by side (such as two of a
+//! [`MultiSensor`]'s), a failing unit shows up as a growing bias between
+//! their readings, or as the readings no longer rising and falling
+//! together.  A [`DriftDetector`] tracks both over a rolling window of
+//! paired readings, and reports when they leave a [`DriftEnvelope`].
+//!
+//! ```
+//! use sen0177::{
+//!     drift::{DriftDetector, DriftEnvelope, DriftEvent},
+//!     Reading,
+//! };
+//!
+//! # let pairs = [(Reading::default(), Reading::default())];
+//! // Compare the last 60 pairs of readings
+//! let mut detector = DriftDetector::<60>::new(DriftEnvelope::default());
+//! for (a, b) in pairs {
+//!     if let Some(DriftEvent::Drifted(reason)) = detector.update(&a, &b) {
+//!         println!("Sensors disagree ({:?}); schedule maintenance", reason);
+//!     }
+//! }
+//! ```
+
+use crate::{multi::MultiSensor, Reading};
+
+/// The limits within which two co-located sensors are considered to agree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DriftEnvelope {
+    /// The largest acceptable mean difference between the sensors, in
+    /// tenths of a unit, in clean air
+    pub max_bias: u32,
+    /// The largest acceptable mean difference between the sensors, as a
+    /// percentage of the first sensor's mean
+    ///
+    /// The bias is only out of the envelope if it exceeds both this and
+    /// [`max_bias`](DriftEnvelope::max_bias), so small absolute
+    /// differences in clean air don't count as drift.
+    pub max_bias_percent: u16,
+    /// The smallest acceptable correlation between the sensors' readings,
+    /// in thousandths (from -1000 to 1000)
+    pub min_correlation: i16,
+}
+
+impl Default for DriftEnvelope {
+    /// A bias of 5µg/m³ and 30%, and a correlation of 0.8
+    fn default() -> Self {
+        Self {
+            max_bias: 50,
+            max_bias_percent: 30,
+            min_correlation: 800,
+        }
+    }
+}
+
+/// Which part of the envelope two sensors' readings left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriftReason {
+    /// The mean difference between the sensors grew too large
+    Bias,
+    /// The sensors' readings stopped moving together
+    Correlation,
+}
+
+/// An event emitted when two sensors leave or return to their envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriftEvent {
+    /// The sensors' readings left the envelope
+    ///
+    /// If both the bias and the correlation are out of the envelope, the
+    /// reason is [`DriftReason::Bias`].
+    Drifted(DriftReason),
+    /// The sensors' readings returned to the envelope
+    Recovered,
+}
+
+/// Compares the readings of two co-located sensors over a rolling window
+/// of `W` pairs
+///
+/// See the [module documentation](self).  The compared value is the
+/// standard PM2.5 concentration unless changed with
+/// [`field`](DriftDetector::field).  The envelope is only checked once the
+/// window is full.
+///
+/// The correlation is undefined when either sensor's readings don't vary
+/// over the window, as in very stable air; then only the bias is checked.
+#[derive(Debug, Clone)]
+pub struct DriftDetector<const W: usize> {
+    pairs: [(u16, u16); W],
+    next: usize,
+    len: usize,
+    envelope: DriftEnvelope,
+    field: fn(&Reading) -> u16,
+    drifting: bool,
+}
+
+impl<const W: usize> DriftDetector<W> {
+    /// Creates a new detector checking against `envelope`
+    pub fn new(envelope: DriftEnvelope) -> Self {
+        Self {
+            pairs: [(0, 0); W],
+            next: 0,
+            len: 0,
+            envelope,
+            field: Reading::pm2_5,
+            drifting: false,
+        }
+    }
+
+    /// Sets the reading field that is compared
+    pub fn field(mut self, field: fn(&Reading) -> u16) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Returns the detector's envelope
+    pub fn envelope(&self) -> DriftEnvelope {
+        self.envelope
+    }
+
+    /// Adds a pair of readings taken at about the same time by the two
+    /// sensors, returning an event if they left or returned to the
+    /// envelope
+    pub fn update(&mut self, a: &Reading, b: &Reading) -> Option<DriftEvent> {
+        self.update_values((self.field)(a), (self.field)(b))
+    }
+
+    /// Adds the latest readings of sensors `a` and `b` of `multi`, returning
+    /// an event as [`update`](DriftDetector::update) does
+    ///
+    /// Nothing is added unless both sensors' most recent reads succeeded.
+    pub fn update_from<S, const N: usize>(
+        &mut self,
+        multi: &MultiSensor<S, N>,
+        a: usize,
+        b: usize,
+    ) -> Option<DriftEvent> {
+        let health = multi.health();
+        let latest = |index: usize| {
+            health
+                .get(index)
+                .filter(|health| health.is_healthy())
+                .and_then(|health| health.last_reading)
+        };
+        let (a, b) = (latest(a)?, latest(b)?);
+        self.update(&a, &b)
+    }
+
+    /// Adds a pair of values that have already been extracted, returning an
+    /// event as [`update`](DriftDetector::update) does
+    pub fn update_values(&mut self, a: u16, b: u16) -> Option<DriftEvent> {
+        if W == 0 {
+            return None;
+        }
+        self.pairs[self.next] = (a, b);
+        self.next = (self.next + 1) % W;
+        self.len = (self.len + 1).min(W);
+        if self.len < W {
+            return None;
+        }
+
+        match (self.drifting, self.check()) {
+            (false, Some(reason)) => {
+                self.drifting = true;
+                Some(DriftEvent::Drifted(reason))
+            }
+            (true, None) => {
+                self.drifting = false;
+                Some(DriftEvent::Recovered)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the sensors are currently out of the envelope
+    pub fn is_drifting(&self) -> bool {
+        self.drifting
+    }
+
+    /// Discards all pairs, e.g. after servicing a sensor
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.len = 0;
+        self.drifting = false;
+    }
+
+    /// Returns the mean difference of the second sensor's values from the
+    /// first's over the window, in tenths of a unit (rounded towards zero)
+    pub fn bias(&self) -> Option<i32> {
+        let n = self.len as i64;
+        let sum: i64 = self
+            .window()
+            .map(|&(a, b)| i64::from(b) - i64::from(a))
+            .sum();
+        (n > 0).then(|| (sum * 10 / n) as i32)
+    }
+
+    /// Returns the Pearson correlation of the sensors' values over the
+    /// window, in thousandths (from -1000 to 1000)
+    ///
+    /// Returns `None` if there are fewer than two pairs, or if either
+    /// sensor's values are all the same.
+    pub fn correlation(&self) -> Option<i16> {
+        let n = self.len as i128;
+        let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0i128, 0, 0, 0, 0);
+        for &(a, b) in self.window() {
+            let (a, b) = (i128::from(a), i128::from(b));
+            sum_a += a;
+            sum_b += b;
+            sum_aa += a * a;
+            sum_bb += b * b;
+            sum_ab += a * b;
+        }
+        let var_a = (n * sum_aa - sum_a * sum_a) as u128;
+        let var_b = (n * sum_bb - sum_b * sum_b) as u128;
+        let covariance = n * sum_ab - sum_a * sum_b;
+        let denominator = isqrt(var_a * var_b) as i128;
+        (n >= 2 && denominator > 0)
+            .then(|| (covariance * 1000 / denominator).clamp(-1000, 1000) as i16)
+    }
+
+    fn window(&self) -> impl Iterator<Item = &(u16, u16)> {
+        self.pairs.iter().take(self.len)
+    }
+
+    /// Returns the reason the window is out of the envelope, if it is
+    fn check(&self) -> Option<DriftReason> {
+        let bias = self.bias()?.unsigned_abs();
+        let mean_a =
+            self.window().map(|&(a, _)| u64::from(a)).sum::<u64>() * 10 / self.len.max(1) as u64;
+        let relative = mean_a * u64::from(self.envelope.max_bias_percent) / 100;
+        if bias > self.envelope.max_bias && u64::from(bias) > relative {
+            return Some(DriftReason::Bias);
+        }
+        match self.correlation() {
+            Some(correlation) if correlation < self.envelope.min_correlation => {
+                Some(DriftReason::Correlation)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the integer square root of `value`, rounded down
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    // Newton's method, from an initial guess no smaller than the root
+    let mut x = 1u128 << (128 - value.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (x + value / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
@@ -0,0 +1,175 @@
+//! Each frame carries two blocks of mass concentrations: standard (CF=1)
+//! and environmental (atmospheric).  Some clones don't compute the
+//! environmental block, filling it with zeros or mirroring the standard
+//! block into it.  An [`EnvDetector`] compares the two blocks over a number
+//! of frames to decide whether the environmental concentrations can be
+//! trusted, and only returns them if they can.
+//!
+//! A genuine sensor reports the same values in both blocks at low
+//! concentrations, so a mirrored frame is only counted against the sensor
+//! once its standard PM2.5 exceeds [`MIRROR_THRESHOLD`].  In clean air, a
+//! sensor that mirrors its blocks may therefore stay undecided for a long
+//! time, but its environmental values are then correct anyway.
+//!
+//! The drivers run a detector over every reading they return, and mark the
+//! readings whose environmental concentrations can't be trusted (see
+//! [`EnvDetector::apply`]), so that [`Reading::atmospheric`] and the
+//! `env_pm*()` accessors return `None` for them:
+//!
+//! ```
+//! # #[cfg(feature = "mock")] {
+//! use sen0177::serial::Sen0177Builder;
+//!
+//! # let mut serial = sen0177::mock::MockSerial::new();
+//! # serial.feed_reading(&Default::default());
+//! let mut sensor = Sen0177Builder::new().env_detect_frames(5).build(&mut serial);
+//! let reading = sensor.read_raw()?.1;
+//! match reading.env_pm2_5() {
+//!     Some(pm2_5) => println!("PM2.5 (atmospheric): {}µg/m³", pm2_5),
+//!     None => println!("PM2.5 (CF=1): {}µg/m³", reading.pm2_5()),
+//! }
+//! # }
+//! # Ok::<(), sen0177::SensorError<embedded_hal_nb::serial::ErrorKind>>(())
+//! ```
+
+use crate::{Concentrations, Reading};
+
+/// The default number of suspect frames after which the environmental
+/// concentrations are deemed unsupported
+pub const DEFAULT_FRAMES: u16 = 10;
+
+/// The standard PM2.5 concentration (in µg/m³) above which a genuine
+/// sensor's environmental concentrations differ from its standard ones
+pub const MIRROR_THRESHOLD: u16 = 40;
+
+/// Decides whether a sensor reports genuine environmental concentrations
+///
+/// See the [module documentation](self).  A single frame whose blocks
+/// differ plausibly marks the environmental concentrations as supported;
+/// [`frames`](EnvDetector::new) frames with zeroed, mirrored, or
+/// out-of-order environmental concentrations, without such a frame, mark
+/// them as unsupported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvDetector {
+    frames: u16,
+    suspect: u16,
+    supported: Option<bool>,
+}
+
+impl Default for EnvDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAMES)
+    }
+}
+
+impl EnvDetector {
+    /// Creates a detector that decides the environmental concentrations are
+    /// unsupported after `frames` suspect frames (at least one)
+    pub const fn new(frames: u16) -> Self {
+        Self {
+            frames: if frames == 0 { 1 } else { frames },
+            suspect: 0,
+            supported: None,
+        }
+    }
+
+    /// Updates the detector with a reading from the sensor
+    pub fn observe(&mut self, reading: &Reading) {
+        match evidence(reading) {
+            Some(true) => self.supported = Some(true),
+            Some(false) if self.supported.is_none() => {
+                self.suspect += 1;
+                if self.suspect >= self.frames {
+                    self.supported = Some(false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns whether the sensor reports genuine environmental
+    /// concentrations, or `None` if that hasn't been decided yet
+    pub fn supported(&self) -> Option<bool> {
+        self.supported
+    }
+
+    /// Returns the number of suspect frames seen while undecided
+    pub fn suspect_frames(&self) -> u16 {
+        self.suspect
+    }
+
+    /// Forgets everything observed, e.g. after the sensor was replaced
+    pub fn reset(&mut self) {
+        *self = Self::new(self.frames);
+    }
+
+    /// Returns the environmental concentrations of `reading`, or `None` if
+    /// the sensor doesn't support them
+    ///
+    /// While undecided, the concentrations are returned unless `reading`
+    /// itself looks suspect.
+    pub fn atmospheric(&self, reading: &Reading) -> Option<Concentrations> {
+        let trusted = match self.supported {
+            Some(supported) => supported,
+            None => evidence(reading) != Some(false),
+        };
+        reading.atmospheric().filter(|_| trusted)
+    }
+
+    /// Returns `reading`, marked as [without environmental
+    /// concentrations](Reading::without_atmospheric) if they can't be
+    /// trusted
+    ///
+    /// This is what the drivers do with each reading they return, after
+    /// [observing](EnvDetector::observe) it.
+    pub fn apply(&self, reading: Reading) -> Reading {
+        if self.atmospheric(&reading).is_some() {
+            reading
+        } else {
+            reading.without_atmospheric()
+        }
+    }
+
+    /// Returns the environmental PM1 concentration of `reading` in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub fn env_pm1(&self, reading: &Reading) -> Option<u16> {
+        self.atmospheric(reading).map(|env| env.pm1())
+    }
+
+    /// Returns the environmental PM2.5 concentration of `reading` in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub fn env_pm2_5(&self, reading: &Reading) -> Option<u16> {
+        self.atmospheric(reading).map(|env| env.pm2_5())
+    }
+
+    /// Returns the environmental PM10 concentration of `reading` in µg/m³,
+    /// or `None` if the sensor doesn't support it
+    pub fn env_pm10(&self, reading: &Reading) -> Option<u16> {
+        self.atmospheric(reading).map(|env| env.pm10())
+    }
+}
+
+/// Returns `Some(true)` if the environmental concentrations of `reading`
+/// look genuine, `Some(false)` if they look like garbage, or `None` if the
+/// reading can't tell
+fn evidence(reading: &Reading) -> Option<bool> {
+    let cf1 = [reading.pm1(), reading.pm2_5(), reading.pm10()];
+    let Some(env) = reading.atmospheric() else {
+        // Already deemed garbage
+        return Some(false);
+    };
+    let env = [env.pm1(), env.pm2_5(), env.pm10()];
+    if env == [0; 3] {
+        // Zeroed, unless the air is perfectly clean
+        (cf1 != [0; 3]).then_some(false)
+    } else if env.iter().zip(cf1).any(|(&env, cf1)| env > cf1) {
+        // The atmospheric correction never raises a concentration
+        Some(false)
+    } else if env != cf1 {
+        Some(true)
+    } else if reading.pm2_5() > MIRROR_THRESHOLD {
+        Some(false)
+    } else {
+        None
+    }
+}
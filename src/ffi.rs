@@ -0,0 +1,273 @@
+//! Gateway firmware that mixes C and Rust can link this crate as a static
+//! or shared library (e.g. with `cargo rustc --release --features ffi
+//! --crate-type staticlib`) and use it through `include/sen0177.h`, which
+//! is generated from this module by `cbindgen`:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/sen0177.h
+//! ```
+//!
+//! Drivers are opaque `sen0177_handle` pointers, opened with
+//! [`sen0177_open`] and freed with [`sen0177_close`].  Functions that can
+//! fail return one of the `SEN0177_*` status codes, which
+//! [`sen0177_strerror`] describes.
+//!
+//! ```c
+//! sen0177_handle *sensor = sen0177_open("/dev/ttyUSB0", 3000);
+//! struct sen0177_reading reading;
+//! int status = sen0177_read(sensor, &reading);
+//! if (status == SEN0177_OK) {
+//!     printf("PM2.5: %u\n", reading.pm2_5);
+//! } else {
+//!     fprintf(stderr, "Error: %s\n", sen0177_strerror(status));
+//! }
+//! sen0177_close(sensor);
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use core::ffi::{c_char, c_int};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+use std::{ffi::CStr, time::Duration};
+
+use crate::{
+    io::{IoError, IoSerial},
+    protocol::{parse_frame, ProtocolError},
+    serial::{Sen0177, Sen0177Builder},
+    Reading, SensorError,
+};
+
+/// The operation succeeded
+pub const SEN0177_OK: c_int = 0;
+/// A required pointer argument was null, or a string was not valid UTF-8
+pub const SEN0177_ERR_INVALID_ARGUMENT: c_int = -1;
+/// The start of a data frame couldn't be found
+pub const SEN0177_ERR_BAD_MAGIC: c_int = -2;
+/// The start of a data frame couldn't be found, and the data suggests the
+/// wrong baud rate
+pub const SEN0177_ERR_BAUD_MISMATCH: c_int = -3;
+/// A data frame's checksum did not match
+pub const SEN0177_ERR_CHECKSUM: c_int = -4;
+/// A reading failed plausibility validation
+pub const SEN0177_ERR_IMPLAUSIBLE: c_int = -5;
+/// No data arrived before the timeout
+pub const SEN0177_ERR_TIMEOUT: c_int = -6;
+/// The sensor's data has not been updated
+pub const SEN0177_ERR_STALE: c_int = -7;
+/// No valid frame arrived before a watchdog's timeout
+pub const SEN0177_ERR_NO_DATA: c_int = -8;
+/// Reading from or writing to the serial port failed
+pub const SEN0177_ERR_IO: c_int = -9;
+/// A data frame had the wrong length
+pub const SEN0177_ERR_FRAME_LENGTH: c_int = -10;
+
+/// How long each read from the serial port waits for data
+const READ_TIMEOUT_MS: u32 = 100;
+
+/// A reading, as returned to C
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct sen0177_reading {
+    /// The standard (CF=1) PM1 concentration, in µg/m³
+    pub pm1: u16,
+    /// The standard (CF=1) PM2.5 concentration, in µg/m³
+    pub pm2_5: u16,
+    /// The standard (CF=1) PM10 concentration, in µg/m³
+    pub pm10: u16,
+    /// The environmental PM1 concentration, in µg/m³; zero if `has_env` is
+    /// zero
+    pub env_pm1: u16,
+    /// The environmental PM2.5 concentration, in µg/m³
+    pub env_pm2_5: u16,
+    /// The environmental PM10 concentration, in µg/m³
+    pub env_pm10: u16,
+    /// The counts of particles beyond 0.3µm, 0.5µm, 1µm, 2.5µm, 5µm, and
+    /// 10µm, in 0.1L of air
+    pub particle_counts: [u16; 6],
+    /// The firmware version reported by the sensor
+    pub firmware_version: u8,
+    /// The error code reported by the sensor
+    pub device_error_code: u8,
+    /// Nonzero if the environmental concentrations are valid; zero if the
+    /// sensor doesn't support them
+    pub has_env: u8,
+}
+
+impl From<&Reading> for sen0177_reading {
+    fn from(reading: &Reading) -> Self {
+        let env = reading.atmospheric();
+        let env_values = env.unwrap_or_default();
+        Self {
+            pm1: reading.pm1(),
+            pm2_5: reading.pm2_5(),
+            pm10: reading.pm10(),
+            env_pm1: env_values.pm1(),
+            env_pm2_5: env_values.pm2_5(),
+            env_pm10: env_values.pm10(),
+            particle_counts: [
+                reading.particles_0_3(),
+                reading.particles_0_5(),
+                reading.particles_1(),
+                reading.particles_2_5(),
+                reading.particles_5(),
+                reading.particles_10(),
+            ]
+            .map(|count| count.per_deciliter()),
+            firmware_version: reading.firmware_version(),
+            device_error_code: reading.device_error_code(),
+            has_env: env.is_some().into(),
+        }
+    }
+}
+
+/// An open serial sensor, opaque to C
+pub struct sen0177_handle {
+    sensor: Sen0177<IoSerial<Box<dyn serialport::SerialPort>>, IoError>,
+}
+
+fn status<E>(error: &SensorError<E>) -> c_int {
+    match error {
+        SensorError::BadMagic => SEN0177_ERR_BAD_MAGIC,
+        SensorError::LikelyBaudMismatch => SEN0177_ERR_BAUD_MISMATCH,
+        SensorError::ChecksumMismatch => SEN0177_ERR_CHECKSUM,
+        SensorError::ImplausibleData(_) => SEN0177_ERR_IMPLAUSIBLE,
+        SensorError::Timeout => SEN0177_ERR_TIMEOUT,
+        SensorError::StaleData => SEN0177_ERR_STALE,
+        SensorError::NoData { .. } => SEN0177_ERR_NO_DATA,
+        SensorError::ReadError(_) => SEN0177_ERR_IO,
+    }
+}
+
+/// Opens the sensor on the serial port at `path`, at 9600 baud
+///
+/// Reads fail with `SEN0177_ERR_TIMEOUT` after waiting about `timeout_ms`
+/// milliseconds for data; a timeout of zero waits forever.  Returns null
+/// if the port can't be opened.
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sen0177_open(path: *const c_char, timeout_ms: u32) -> *mut sen0177_handle {
+    if path.is_null() {
+        return core::ptr::null_mut();
+    }
+    // SAFETY: the caller guarantees that a non-null `path` is a C string
+    let Ok(path) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return core::ptr::null_mut();
+    };
+    let port = serialport::new(path, 9600)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .flow_control(FlowControl::None)
+        .timeout(Duration::from_millis(READ_TIMEOUT_MS.into()))
+        .open();
+    let Ok(port) = port else {
+        return core::ptr::null_mut();
+    };
+    let mut builder = Sen0177Builder::new();
+    if timeout_ms > 0 {
+        builder = builder.timeout_polls(timeout_ms.div_ceil(READ_TIMEOUT_MS));
+    }
+    let sensor = builder.build(IoSerial::new(port));
+    Box::into_raw(Box::new(sen0177_handle { sensor }))
+}
+
+/// Closes a sensor opened with [`sen0177_open`]
+///
+/// # Safety
+///
+/// `handle` must be null or a handle returned by [`sen0177_open`] that
+/// has not already been closed.
+#[no_mangle]
+pub unsafe extern "C" fn sen0177_close(handle: *mut sen0177_handle) {
+    if !handle.is_null() {
+        // SAFETY: the caller guarantees that the handle came from
+        // `sen0177_open`, which allocated it with `Box`
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Reads a single measurement into `reading`, returning a status code
+///
+/// # Safety
+///
+/// `handle` must be null or a handle returned by [`sen0177_open`] that
+/// has not been closed, and `reading` must be null or point to writable
+/// memory for a `sen0177_reading`.
+#[no_mangle]
+pub unsafe extern "C" fn sen0177_read(
+    handle: *mut sen0177_handle,
+    reading: *mut sen0177_reading,
+) -> c_int {
+    // SAFETY: the caller guarantees that non-null pointers are valid
+    let (Some(handle), Some(out)) = (unsafe { handle.as_mut() }, unsafe { reading.as_mut() })
+    else {
+        return SEN0177_ERR_INVALID_ARGUMENT;
+    };
+    match handle.sensor.read_raw() {
+        Ok((_, reading)) => {
+            *out = (&reading).into();
+            SEN0177_OK
+        }
+        Err(error) => status(&error),
+    }
+}
+
+/// Parses the `len`-byte data frame at `frame` into `reading`, returning a
+/// status code
+///
+/// # Safety
+///
+/// `frame` must be null or point to `len` readable bytes, and `reading`
+/// must be null or point to writable memory for a `sen0177_reading`.
+#[no_mangle]
+pub unsafe extern "C" fn sen0177_parse(
+    frame: *const u8,
+    len: usize,
+    reading: *mut sen0177_reading,
+) -> c_int {
+    // SAFETY: the caller guarantees that non-null pointers are valid
+    let Some(out) = (unsafe { reading.as_mut() }) else {
+        return SEN0177_ERR_INVALID_ARGUMENT;
+    };
+    if frame.is_null() {
+        return SEN0177_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: the caller guarantees that `frame` points to `len` bytes
+    let frame = unsafe { core::slice::from_raw_parts(frame, len) };
+    let Ok(frame) = frame.try_into() else {
+        return SEN0177_ERR_FRAME_LENGTH;
+    };
+    match parse_frame(frame) {
+        Ok(reading) => {
+            *out = (&reading).into();
+            SEN0177_OK
+        }
+        Err(ProtocolError::BadMagic) => SEN0177_ERR_BAD_MAGIC,
+        Err(ProtocolError::ChecksumMismatch { .. }) => SEN0177_ERR_CHECKSUM,
+    }
+}
+
+/// Returns a static, NUL-terminated description of a status code
+#[no_mangle]
+pub extern "C" fn sen0177_strerror(status: c_int) -> *const c_char {
+    let description: &'static CStr = match status {
+        SEN0177_OK => c"Success",
+        SEN0177_ERR_INVALID_ARGUMENT => c"Invalid argument",
+        SEN0177_ERR_BAD_MAGIC => c"Unable to find magic bytes at start of payload",
+        SEN0177_ERR_BAUD_MISMATCH => {
+            c"Unable to find magic bytes at start of payload (check the baud rate)"
+        }
+        SEN0177_ERR_CHECKSUM => c"Data read was corrupt",
+        SEN0177_ERR_IMPLAUSIBLE => c"Implausible data",
+        SEN0177_ERR_TIMEOUT => c"Timed out waiting for data",
+        SEN0177_ERR_STALE => c"Sensor data has not been updated",
+        SEN0177_ERR_NO_DATA => c"No data received",
+        SEN0177_ERR_IO => c"Serial port error",
+        SEN0177_ERR_FRAME_LENGTH => c"Data frame has the wrong length",
+        _ => c"Unknown error",
+    };
+    description.as_ptr()
+}
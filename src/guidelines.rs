@@ -0,0 +1,154 @@
+//! All concentrations are fixed-point values in tenths of a µg/m³, matching
+//! the means produced by [`History`].
+
+use crate::{history::History, Reading};
+
+/// A particulate matter size fraction covered by a guideline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pollutant {
+    /// Particulate matter 2.5µm and smaller
+    Pm2_5,
+    /// Particulate matter 10µm and smaller
+    Pm10,
+}
+
+/// The averaging period a guideline limit applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AveragingPeriod {
+    /// A 24-hour mean
+    Daily,
+    /// An annual mean
+    Annual,
+}
+
+/// A single guideline limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limit {
+    /// The pollutant the limit applies to
+    pub pollutant: Pollutant,
+    /// The averaging period the limit applies to
+    pub period: AveragingPeriod,
+    /// The limit, in tenths of a µg/m³
+    pub value: u32,
+}
+
+/// Mean PM2.5 and PM10 concentrations over some averaging period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Averages {
+    /// The mean PM2.5 concentration, in tenths of a µg/m³
+    pub pm2_5: u32,
+    /// The mean PM10 concentration, in tenths of a µg/m³
+    pub pm10: u32,
+}
+
+impl Averages {
+    /// Computes the mean standard (CF=1) concentrations over the readings in
+    /// `history` taken at or after `since`
+    ///
+    /// Returns `None` if there are no such readings.
+    pub fn from_history<const N: usize>(history: &History<N>, since: u64) -> Option<Self> {
+        Some(Self {
+            pm2_5: history.mean(since, Reading::pm2_5)?,
+            pm10: history.mean(since, Reading::pm10)?,
+        })
+    }
+
+    fn get(&self, pollutant: Pollutant) -> u32 {
+        match pollutant {
+            Pollutant::Pm2_5 => self.pm2_5,
+            Pollutant::Pm10 => self.pm10,
+        }
+    }
+}
+
+/// A limit that was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Exceedance {
+    /// The limit that was exceeded
+    pub limit: Limit,
+    /// The measured mean concentration, in tenths of a µg/m³
+    pub measured: u32,
+}
+
+impl Exceedance {
+    /// Returns how far the limit was exceeded by, in tenths of a µg/m³
+    pub fn excess(&self) -> u32 {
+        self.measured - self.limit.value
+    }
+}
+
+/// A named set of guideline limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuidelineSet {
+    name: &'static str,
+    limits: &'static [Limit],
+}
+
+const fn limit(pollutant: Pollutant, period: AveragingPeriod, ug_m3: u32) -> Limit {
+    Limit {
+        pollutant,
+        period,
+        value: ug_m3 * 10,
+    }
+}
+
+const WHO_2021: [Limit; 4] = [
+    limit(Pollutant::Pm2_5, AveragingPeriod::Daily, 15),
+    limit(Pollutant::Pm2_5, AveragingPeriod::Annual, 5),
+    limit(Pollutant::Pm10, AveragingPeriod::Daily, 45),
+    limit(Pollutant::Pm10, AveragingPeriod::Annual, 15),
+];
+
+const US_EPA: [Limit; 3] = [
+    limit(Pollutant::Pm2_5, AveragingPeriod::Daily, 35),
+    limit(Pollutant::Pm2_5, AveragingPeriod::Annual, 9),
+    limit(Pollutant::Pm10, AveragingPeriod::Daily, 150),
+];
+
+impl GuidelineSet {
+    /// The World Health Organization 2021 air quality guidelines
+    pub fn who_2021() -> Self {
+        Self {
+            name: "WHO 2021",
+            limits: &WHO_2021,
+        }
+    }
+
+    /// The US EPA primary National Ambient Air Quality Standards, as revised
+    /// in 2024
+    pub fn us_epa() -> Self {
+        Self {
+            name: "US EPA NAAQS",
+            limits: &US_EPA,
+        }
+    }
+
+    /// Returns the name of the guideline set
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns all limits in the guideline set
+    pub fn limits(&self) -> &'static [Limit] {
+        self.limits
+    }
+
+    /// Checks means computed over `period` against the applicable limits,
+    /// returning each limit that was exceeded
+    pub fn check(
+        &self,
+        period: AveragingPeriod,
+        averages: Averages,
+    ) -> impl Iterator<Item = Exceedance> + 'static {
+        self.limits
+            .iter()
+            .filter(move |limit| limit.period == period)
+            .filter_map(move |limit| {
+                let measured = averages.get(limit.pollutant);
+                (measured > limit.value).then_some(Exceedance {
+                    limit: *limit,
+                    measured,
+                })
+            })
+    }
+}
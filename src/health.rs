@@ -0,0 +1,143 @@
+//! A sensor can be powered and wired up and still be broken: its fan may
+//! have stalled, so frames arrive but stop changing; its UART may be
+//! dropping bytes, so frames arrive late or corrupted; or its particle
+//! counter may have failed, leaving concentrations with no counts behind
+//! them.  The drivers' `health_check` methods (such as
+//! [`serial::Sen0177::health_check`]) read a few frames and check for all
+//! of this, returning a [`HealthReport`] that is handy to log at boot, or
+//! to send back from a fleet of remote stations.
+//!
+//! [`serial::Sen0177::health_check`]: crate::serial::Sen0177::health_check
+//!
+//! ```
+//! # #[cfg(feature = "mock")] {
+//! use sen0177::{health::DEFAULT_HEALTH_FRAMES, serial::Sen0177Builder};
+//!
+//! # use sen0177::{mock::{Faults, MockSerial}, Reading};
+//! # let mut serial = MockSerial::with_faults(Faults { delay_polls: 1, ..Faults::none() }, 1);
+//! # for _ in 0..DEFAULT_HEALTH_FRAMES {
+//! #     serial.feed_reading(&Reading::default());
+//! # }
+//! # let mut ticks = 0;
+//! # let mut millis = move || { ticks += 1000; ticks };
+//! let mut sensor = Sen0177Builder::new().timeout_polls(10).build(&mut serial);
+//! let report = sensor.health_check(&mut millis, DEFAULT_HEALTH_FRAMES);
+//! if !report.is_healthy() {
+//!     println!("Sensor failed its self-test: {:?}", report);
+//! }
+//! # assert!(report.is_healthy());
+//! # }
+//! ```
+
+use crate::{Reading, SensorError};
+
+/// The default number of frames read by a health check
+pub const DEFAULT_HEALTH_FRAMES: u8 = 3;
+
+/// The shortest interval between frames in active mode, in milliseconds
+///
+/// The datasheet gives 200 to 800ms when the concentration is changing
+/// quickly.
+pub const MIN_FRAME_INTERVAL_MS: u64 = 200;
+
+/// The longest interval between frames in active mode, in milliseconds
+///
+/// The datasheet gives 2.3 seconds when the concentration is stable; this
+/// allows some slack for the host's own latency.
+pub const MAX_FRAME_INTERVAL_MS: u64 = 3000;
+
+/// Returns `true` if `reading`'s particle counts are consistent with its
+/// concentrations
+///
+/// Concentrations are derived from the particle counts, so nonzero
+/// concentrations with every count at zero point to a failed counter or a
+/// corrupted frame.
+pub fn counts_consistent(reading: &Reading) -> bool {
+    let concentrations = [reading.pm1(), reading.pm2_5(), reading.pm10()];
+    let counts = [
+        reading.particles_0_3(),
+        reading.particles_0_5(),
+        reading.particles_1(),
+        reading.particles_2_5(),
+        reading.particles_5(),
+        reading.particles_10(),
+    ];
+    concentrations.iter().all(|&pm| pm == 0) || counts.iter().any(|count| count.per_deciliter() > 0)
+}
+
+/// The results of a driver's health check
+///
+/// The check reads a fixed number of frames, stopping at the first error.
+#[derive(Debug)]
+pub struct HealthReport<E> {
+    /// The number of frames read successfully
+    pub frames: u8,
+    /// The error that ended the check early, if any
+    pub error: Option<SensorError<E>>,
+    /// The shortest interval between frames, in milliseconds, if cadence
+    /// was measured and at least two frames were read
+    pub min_interval_ms: Option<u64>,
+    /// The longest interval between frames, in milliseconds, if cadence was
+    /// measured and at least two frames were read
+    pub max_interval_ms: Option<u64>,
+    /// The number of frames whose particle counts were inconsistent with
+    /// their concentrations (see [`counts_consistent`])
+    pub inconsistent_frames: u8,
+    /// The last reading taken
+    pub last_reading: Option<Reading>,
+}
+
+impl<E> Default for HealthReport<E> {
+    fn default() -> Self {
+        Self {
+            frames: 0,
+            error: None,
+            min_interval_ms: None,
+            max_interval_ms: None,
+            inconsistent_frames: 0,
+            last_reading: None,
+        }
+    }
+}
+
+impl<E> HealthReport<E> {
+    /// Returns whether the frames arrived within [`MIN_FRAME_INTERVAL_MS`]
+    /// and [`MAX_FRAME_INTERVAL_MS`] of each other, or `None` if the
+    /// cadence wasn't measured
+    pub fn cadence_ok(&self) -> Option<bool> {
+        Some(
+            self.min_interval_ms? >= MIN_FRAME_INTERVAL_MS
+                && self.max_interval_ms? <= MAX_FRAME_INTERVAL_MS,
+        )
+    }
+
+    /// Returns `true` if every frame was read, arrived on time (where that
+    /// was measured), and had consistent particle counts
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+            && self.frames > 0
+            && self.cadence_ok() != Some(false)
+            && self.inconsistent_frames == 0
+    }
+
+    /// Records a frame read successfully, `interval_ms` after the previous
+    /// one if that was measured
+    #[cfg(feature = "plantower")]
+    pub(crate) fn record(&mut self, reading: Reading, interval_ms: Option<u64>) {
+        self.frames = self.frames.saturating_add(1);
+        if !counts_consistent(&reading) {
+            self.inconsistent_frames = self.inconsistent_frames.saturating_add(1);
+        }
+        if let Some(interval) = interval_ms {
+            self.min_interval_ms = Some(
+                self.min_interval_ms
+                    .map_or(interval, |min| min.min(interval)),
+            );
+            self.max_interval_ms = Some(
+                self.max_interval_ms
+                    .map_or(interval, |max| max.max(interval)),
+            );
+        }
+        self.last_reading = Some(reading);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{time::Timestamped, Reading};
+
+/// A fixed-capacity ring buffer of timestamped readings
+///
+/// Once `N` readings have been stored, each new reading replaces the oldest
+/// one.  Timestamps are plain `u64` values in whatever units the caller
+/// chooses (seconds since boot, Unix time, etc.), and must be pushed in
+/// non-decreasing order.
+///
+/// Windowed queries take a `since` timestamp and consider only readings
+/// stamped at or after it, along with a `field` function that selects the
+/// value of interest (e.g. `Reading::pm2_5`).  Means are returned as
+/// fixed-point values in tenths of a unit, matching the input expected by
+/// the [`aqi`](crate::aqi) functions.
+#[derive(Debug, Clone)]
+pub struct History<const N: usize> {
+    entries: [(u64, Reading); N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> History<N> {
+    /// Creates a new, empty history
+    pub fn new() -> Self {
+        Self {
+            entries: [(0, Reading::default()); N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds a reading taken at `timestamp`, evicting the oldest reading if
+    /// the history is full
+    pub fn push(&mut self, timestamp: u64, reading: Reading) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.entries[(self.start + self.len) % N] = (timestamp, reading);
+            self.len += 1;
+        } else {
+            self.entries[self.start] = (timestamp, reading);
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Adds a reading stamped by a [`Clock`](crate::time::Clock) with `u64`
+    /// timestamps, evicting the oldest reading if the history is full
+    pub fn push_timestamped(&mut self, reading: Timestamped<Reading>) {
+        self.push(reading.timestamp, reading.value);
+    }
+
+    /// Returns the number of readings stored
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no readings are stored
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all readings
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// Returns the most recently added reading and its timestamp
+    pub fn latest(&self) -> Option<(u64, Reading)> {
+        self.len
+            .checked_sub(1)
+            .map(|last| self.entries[(self.start + last) % N])
+    }
+
+    /// Iterates over the stored readings, from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u64, Reading)> + '_ {
+        (0..self.len).map(move |i| self.entries[(self.start + i) % N])
+    }
+
+    /// Iterates over the readings taken at or after `since`, from oldest to
+    /// newest
+    pub fn window(&self, since: u64) -> impl Iterator<Item = (u64, Reading)> + '_ {
+        self.iter()
+            .skip_while(move |(timestamp, _)| *timestamp < since)
+    }
+
+    /// Returns the number of readings taken at or after `since`
+    pub fn count(&self, since: u64) -> usize {
+        self.window(since).count()
+    }
+
+    /// Returns the mean of `field` over the readings taken at or after
+    /// `since`, in tenths of a unit (rounded to the nearest tenth)
+    pub fn mean<F>(&self, since: u64, field: F) -> Option<u32>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        let (sum, count) = self
+            .window(since)
+            .fold((0u64, 0u64), |(sum, count), (_, reading)| {
+                (sum + field(&reading) as u64, count + 1)
+            });
+        (count > 0).then(|| ((sum * 10 + count / 2) / count) as u32)
+    }
+
+    /// Returns the minimum of `field` over the readings taken at or after
+    /// `since`
+    pub fn min<F>(&self, since: u64, field: F) -> Option<u16>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        self.window(since).map(|(_, reading)| field(&reading)).min()
+    }
+
+    /// Returns the maximum of `field` over the readings taken at or after
+    /// `since`
+    pub fn max<F>(&self, since: u64, field: F) -> Option<u16>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        self.window(since).map(|(_, reading)| field(&reading)).max()
+    }
+
+    /// Returns the `percentile`th percentile (0 to 100) of `field` over the
+    /// readings taken at or after `since`, using the nearest-rank method
+    ///
+    /// This sorts a temporary copy of the values on the stack, so it uses
+    /// `2 * N` bytes of stack space.
+    pub fn percentile<F>(&self, since: u64, field: F, percentile: u8) -> Option<u16>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        let mut values = [0u16; N];
+        let mut count = 0;
+        for (_, reading) in self.window(since) {
+            values[count] = field(&reading);
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+
+        let values = &mut values[..count];
+        values.sort_unstable();
+        let percentile = percentile.min(100) as usize;
+        let rank = (percentile * count).div_ceil(100).max(1);
+        Some(values[rank - 1])
+    }
+}
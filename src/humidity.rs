@@ -0,0 +1,71 @@
+//! Optical sensors size particles as they are in the air, and at high
+//! relative humidity hygroscopic particles take up water and grow, so the
+//! sensor overestimates their dry mass.  Correcting for this needs a
+//! relative humidity measurement from a separate sensor.
+//!
+//! Relative humidity is given in tenths of a percent (as in
+//! [`TempHumidity`](crate::capability::TempHumidity)), and corrected
+//! concentrations are returned in tenths of a µg/m³.  All calculations use
+//! integer arithmetic only.
+
+/// A linear correction of PM2.5 concentrations, with a relative humidity
+/// term
+///
+/// The corrected concentration in µg/m³ is
+/// `slope × PM2.5(CF=1) + humidity × RH + offset`, clamped at zero, with RH
+/// in percent.  The coefficients are fixed-point values in ten-thousandths,
+/// so that correcting needs integer arithmetic only.
+///
+/// Corrections for a particular site can be fitted against a reference
+/// monitor with [`calibrate::fit`](crate::calibrate::fit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Calibration {
+    /// The factor applied to the sensor's concentration, in ten-thousandths
+    pub slope: i32,
+    /// The change per percent of relative humidity, in ten-thousandths of a
+    /// µg/m³
+    pub humidity: i32,
+    /// The offset, in ten-thousandths of a µg/m³
+    pub offset: i32,
+}
+
+impl Calibration {
+    /// The US EPA's nationwide correction for PurpleAir sensors; see
+    /// [`epa_pm2_5`]
+    pub const EPA_2021: Self = Self {
+        slope: 5240,
+        humidity: -862,
+        offset: 57_500,
+    };
+
+    /// A correction that leaves concentrations unchanged
+    pub const IDENTITY: Self = Self {
+        slope: 10_000,
+        humidity: 0,
+        offset: 0,
+    };
+
+    /// Corrects a standard (CF=1) PM2.5 concentration in µg/m³, with
+    /// relative humidity in tenths of a percent, returning the corrected
+    /// concentration in tenths of a µg/m³
+    pub fn apply(&self, pm2_5: u16, relative_humidity: u16) -> u32 {
+        // The result in tenths, scaled by 10⁴
+        let scaled = 10 * i64::from(self.slope) * i64::from(pm2_5)
+            + i64::from(self.humidity) * i64::from(relative_humidity)
+            + 10 * i64::from(self.offset);
+        ((scaled + 5000) / 10_000).clamp(0, u32::MAX.into()) as u32
+    }
+}
+
+/// Applies the US EPA's nationwide correction for PurpleAir sensors
+/// (Barkjohn et al., 2021) to a standard (CF=1) PM2.5 concentration in
+/// µg/m³
+///
+/// PurpleAir sensors are built on the Plantower PMS5003, so the correction
+/// suits this family of sensors.  It is
+/// `PM2.5 = 0.524 × PM2.5(CF=1) − 0.0862 × RH + 5.75`, clamped at zero,
+/// and was fitted on concentrations below roughly 250µg/m³; it
+/// underestimates heavy smoke.
+pub fn epa_pm2_5(pm2_5: u16, relative_humidity: u16) -> u32 {
+    Calibration::EPA_2021.apply(pm2_5, relative_humidity)
+}
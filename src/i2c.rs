@@ -1,8 +1,49 @@
-use crate::{read::*, AirQualitySensor, Reading, SensorError};
-use embedded_hal::i2c::{AddressMode, Error as I2cError, I2c};
+use crate::{
+    env::EnvDetector, health::HealthReport, logging::debug, read::*, AirQualitySensor, ErrorType,
+    Reading, SensorError, SensorInfo,
+};
+use core::fmt;
+use embedded_hal::i2c::{AddressMode, Error as I2cError, I2c, SevenBitAddress};
+use sen0177_protocol::{FrameProtocol, Plantower};
+
+/// The default I2C address of the PMSA003I
+pub const DEFAULT_ADDRESS: u8 = 0x12;
+
+const INFO: SensorInfo = SensorInfo {
+    name: "PMSA003I",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: true,
+    supports_temperature_humidity: false,
+    frame_len: FRAME_LEN,
+    default_i2c_address: Some(DEFAULT_ADDRESS),
+};
+
+/// Describes an I2C address that can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidAddress(pub u8);
+
+impl fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#04x} is not a valid 7-bit I2C device address (expected {:#04x}..={:#04x})",
+            self.0, MIN_ADDRESS, MAX_ADDRESS
+        )
+    }
+}
+
+impl core::error::Error for InvalidAddress {}
+
+/// The lowest 7-bit address not reserved by the I2C specification
+const MIN_ADDRESS: u8 = 0x08;
+/// The highest 7-bit address not reserved by the I2C specification
+const MAX_ADDRESS: u8 = 0x77;
 
 /// A SEN0177 device connected via I2C
-pub struct Sen0177<A, I2C, E>
+///
+/// The address mode `A` defaults to [`SevenBitAddress`], which is what the
+/// sensor uses.
+pub struct Sen0177<I2C, E, A = SevenBitAddress>
 where
     A: AddressMode + Copy,
     I2C: I2c<A, Error = E>,
@@ -10,33 +51,235 @@ where
 {
     i2c_bus: I2C,
     address: A,
+    validate: bool,
+    env: EnvDetector,
+    chunk_len: Option<usize>,
+    max_repeats: Option<u32>,
+    last_frame: Option<[u8; FRAME_LEN]>,
+    repeats: u32,
 }
 
-impl<A, I2C, E> Sen0177<A, I2C, E>
+impl<I2C, E, A> Sen0177<I2C, E, A>
 where
     A: AddressMode + Copy,
     I2C: I2c<A, Error = E>,
     E: I2cError,
 {
     /// Creates a new sensor instance connected to I2C bus `i2c_bus` at address `address`
+    ///
+    /// The address is not checked; see [`try_new`](Self::try_new) for a
+    /// constructor that validates it.
     pub fn new(i2c_bus: I2C, address: A) -> Self {
-        Self { i2c_bus, address }
+        Self {
+            i2c_bus,
+            address,
+            validate: false,
+            env: EnvDetector::default(),
+            chunk_len: None,
+            max_repeats: None,
+            last_frame: None,
+            repeats: 0,
+        }
+    }
+
+    /// Sets whether or not readings should be checked for physical
+    /// plausibility, and rejected with [`SensorError::ImplausibleData`] if
+    /// they fail
+    ///
+    /// Defaults to `false`.  See [`protocol::validate`](crate::protocol::validate)
+    /// for the checks performed.
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets the number of frames with suspect environmental concentrations
+    /// after which the sensor is deemed not to support them
+    ///
+    /// Defaults to [`env::DEFAULT_FRAMES`](crate::env::DEFAULT_FRAMES).  See
+    /// [`env_supported`](Sen0177::env_supported).
+    pub fn env_detect_frames(mut self, frames: u16) -> Self {
+        self.env = EnvDetector::new(frames);
+        self
+    }
+
+    /// Sets the maximum number of bytes read in a single I2C transfer
+    ///
+    /// Some I2C controllers (and bit-banged buses) can't reliably read a
+    /// whole frame at once.  When set, the frame is instead read in chunks
+    /// of at most `chunk_len` bytes, each a write of the chunk's register
+    /// offset followed by a repeated start and the read.  The first chunk
+    /// must start with the frame's magic bytes, and if the sensor updates
+    /// the frame partway through (detected by a checksum mismatch), the
+    /// frame is read once more before giving up.
+    ///
+    /// `chunk_len` is clamped to between 1 and the frame length.  By default,
+    /// the frame is read in a single transfer.
+    pub fn chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = Some(chunk_len.clamp(1, FRAME_LEN));
+        self
+    }
+
+    /// Sets the maximum number of consecutive reads that may return a
+    /// byte-for-byte identical frame before [`SensorError::StaleData`] is
+    /// returned
+    ///
+    /// The sensor has no data-ready indication, and will return its last
+    /// frame for as long as it has nothing newer, so polling faster than it
+    /// produces data (roughly once per second) yields repeated frames.  A
+    /// sensor that has hung also yields repeated frames, indefinitely.  Since
+    /// a steady environment can legitimately produce identical frames, pick
+    /// a limit comfortably above the number of reads you make per second.
+    ///
+    /// By default, repeated frames are returned without complaint.
+    pub fn stale_after(mut self, max_repeats: u32) -> Self {
+        self.max_repeats = Some(max_repeats);
+        self
+    }
+
+    /// Reads a single sensor measurement, returning the raw data frame along
+    /// with the parsed reading
+    pub fn read_raw(&mut self) -> Result<([u8; FRAME_LEN], Reading), SensorError<E>> {
+        let mut buf: [u8; FRAME_LEN] = [0; FRAME_LEN];
+        self.read_frame(&mut buf)?;
+        let reading = match parse_with::<Plantower, _>(&buf, true) {
+            Err(SensorError::ChecksumMismatch) if self.chunk_len.is_some() => {
+                debug!("Frame may have changed between chunks; reading again");
+                self.read_frame(&mut buf)?;
+                parse_with::<Plantower, _>(&buf, true)?
+            }
+            result => result?,
+        };
+        self.check_fresh(&buf)?;
+        let reading = check_plausible(reading, self.validate)?;
+        self.env.observe(&reading);
+        let reading = self.env.apply(reading);
+        Ok((buf, reading))
+    }
+
+    /// Returns whether the sensor reports genuine environmental
+    /// (atmospheric) concentrations, or `None` if that hasn't been decided
+    /// yet
+    ///
+    /// This is detected from the readings returned so far; see
+    /// [`env`](crate::env).
+    pub fn env_supported(&self) -> Option<bool> {
+        self.env.supported()
+    }
+
+    /// Returns the detector of environmental concentration support
+    ///
+    /// Readings returned by the sensor are already marked by the detector,
+    /// so their `env_pm*()` accessors return `None` if the environmental
+    /// concentrations can't be trusted.
+    pub fn env_detector(&self) -> &EnvDetector {
+        &self.env
+    }
+
+    /// Checks the sensor's health by reading `frames` frames
+    ///
+    /// The sensor updates its registers about once a second whenever it is
+    /// read, so cadence isn't measured; repeated frames are only caught if
+    /// [`stale_after`](Sen0177::stale_after) is set.  The check stops at
+    /// the first error.  See [`health`](crate::health).
+    pub fn health_check(&mut self, frames: u8) -> HealthReport<E> {
+        let mut report = HealthReport::default();
+        for _ in 0..frames {
+            match self.read_raw() {
+                Ok((_, reading)) => report.record(reading, None),
+                Err(err) => {
+                    report.error = Some(err);
+                    break;
+                }
+            }
+        }
+        report
+    }
+
+    fn read_frame(&mut self, buf: &mut [u8; FRAME_LEN]) -> Result<(), SensorError<E>> {
+        let Some(chunk_len) = self.chunk_len else {
+            return self
+                .i2c_bus
+                .read(self.address, buf)
+                .map_err(SensorError::bus);
+        };
+        for (index, chunk) in buf.chunks_mut(chunk_len).enumerate() {
+            let offset = (index * chunk_len) as u8;
+            self.i2c_bus
+                .write_read(self.address, &[offset], chunk)
+                .map_err(SensorError::bus)?;
+            if index == 0
+                && !Plantower::HEADER
+                    .starts_with(&chunk[..chunk.len().min(Plantower::HEADER.len())])
+            {
+                debug!("Bad magic bytes in first chunk: {:02x?}", chunk);
+                return Err(SensorError::BadMagic);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_fresh(&mut self, frame: &[u8; FRAME_LEN]) -> Result<(), SensorError<E>> {
+        let Some(max_repeats) = self.max_repeats else {
+            return Ok(());
+        };
+        if self.last_frame.as_ref() == Some(frame) {
+            self.repeats = self.repeats.saturating_add(1);
+            if self.repeats > max_repeats {
+                debug!("Frame repeated {} times", self.repeats);
+                return Err(SensorError::StaleData);
+            }
+        } else {
+            self.last_frame = Some(*frame);
+            self.repeats = 0;
+        }
+        Ok(())
     }
 }
 
-impl<A, I2C, E> AirQualitySensor<E> for Sen0177<A, I2C, E>
+impl<I2C, E> Sen0177<I2C, E>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    E: I2cError,
+{
+    /// Creates a new sensor instance connected to I2C bus `i2c_bus` at the
+    /// sensor's documented address, [`DEFAULT_ADDRESS`]
+    pub fn new_default_addr(i2c_bus: I2C) -> Self {
+        Self::new(i2c_bus, DEFAULT_ADDRESS)
+    }
+
+    /// Creates a new sensor instance connected to I2C bus `i2c_bus` at address
+    /// `address`, checking that it's a valid 7-bit address outside the ranges
+    /// reserved by the I2C specification
+    pub fn try_new(i2c_bus: I2C, address: u8) -> Result<Self, InvalidAddress> {
+        if (MIN_ADDRESS..=MAX_ADDRESS).contains(&address) {
+            Ok(Self::new(i2c_bus, address))
+        } else {
+            Err(InvalidAddress(address))
+        }
+    }
+}
+
+impl<I2C, E, A> AirQualitySensor<E> for Sen0177<I2C, E, A>
 where
     A: AddressMode + Copy,
     I2C: I2c<A, Error = E>,
     E: I2cError,
 {
     fn read(&mut self) -> Result<Reading, SensorError<E>> {
-        let mut buf: [u8; PAYLOAD_LEN] = [0; PAYLOAD_LEN];
-        self.i2c_bus.read(self.address, &mut buf)?;
-        if buf[0] != MAGIC_BYTE_0 || buf[1] != MAGIC_BYTE_1 {
-            Err(SensorError::BadMagic)
-        } else {
-            parse_data(&buf)
-        }
+        self.read_raw().map(|(_, reading)| reading)
     }
+
+    fn info(&self) -> SensorInfo {
+        INFO
+    }
+}
+
+impl<I2C, E, A> ErrorType for Sen0177<I2C, E, A>
+where
+    A: AddressMode + Copy,
+    I2C: I2c<A, Error = E>,
+    E: I2cError,
+{
+    type Error = SensorError<E>;
 }
@@ -0,0 +1,174 @@
+//! Runs the serial drivers over `std::io` streams.
+//!
+//! Sensors are often reached through something other than a local UART: a
+//! ser2net or socat bridge exposing a remote serial port over TCP, a named
+//! pipe, or a file holding recorded data.  [`IoSerial`] adapts any
+//! [`std::io::Read`] (and [`std::io::Write`], for sending commands) into
+//! the UART traits the drivers expect, so all of these work as transports.
+//!
+//! ```no_run
+//! use sen0177::{io::IoSerial, serial::Sen0177};
+//! use std::{net::TcpStream, time::Duration};
+//!
+//! let stream = TcpStream::connect("sensor-bridge.local:4001")?;
+//! stream.set_read_timeout(Some(Duration::from_secs(3)))?;
+//! let mut sensor = Sen0177::new(IoSerial::new(stream));
+//! match sensor.read_raw() {
+//!     Ok((_, reading)) => println!("PM2.5: {}µg/m³", reading.pm2_5()),
+//!     Err(error) => eprintln!("Error: {}", error),
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use core::fmt;
+use embedded_hal_nb::{
+    nb,
+    serial::{Error as SerialError, ErrorKind, ErrorType, Read, Write},
+};
+use std::io;
+
+/// The number of bytes read from the stream at once
+const READ_BUF_LEN: usize = 64;
+
+/// The number of bytes written to the stream at once; a command fits
+const WRITE_BUF_LEN: usize = 16;
+
+/// An error from the stream underlying an [`IoSerial`]
+///
+/// The end of the stream (a file read to the end, or a closed connection)
+/// is reported as an error of kind [`io::ErrorKind::UnexpectedEof`].
+#[derive(Debug)]
+pub struct IoError(pub io::Error);
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// The wrapper is transparent: it displays as the I/O error, so the chain
+// continues from that error's own source
+impl std::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl SerialError for IoError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Adapts a `std::io` stream `T` into a UART for the serial drivers
+///
+/// Reads from the stream are buffered.  A read that times out (as a
+/// [`TcpStream`](std::net::TcpStream) with a read timeout does) is reported
+/// as no data being available yet, so the driver's
+/// [`timeout_polls`](crate::serial::Sen0177Builder::timeout_polls) limit
+/// applies as it does to a UART.  Written bytes are buffered until the
+/// driver flushes them, so that each command goes out in one write (and
+/// one TCP segment).
+pub struct IoSerial<T> {
+    inner: T,
+    read_buf: [u8; READ_BUF_LEN],
+    pos: usize,
+    len: usize,
+    write_buf: [u8; WRITE_BUF_LEN],
+    pending: usize,
+}
+
+impl<T> IoSerial<T> {
+    /// Wraps `inner`
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            read_buf: [0; READ_BUF_LEN],
+            pos: 0,
+            len: 0,
+            write_buf: [0; WRITE_BUF_LEN],
+            pending: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying stream
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying stream
+    ///
+    /// Reading from the stream directly will skip over any data already
+    /// buffered.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the adapter, returning the underlying stream
+    ///
+    /// Any bytes read from the stream but not yet processed, or written but
+    /// not yet flushed, are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> ErrorType for IoSerial<T> {
+    type Error = IoError;
+}
+
+fn convert(error: io::Error) -> nb::Error<IoError> {
+    match error.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted => {
+            nb::Error::WouldBlock
+        }
+        _ => nb::Error::Other(IoError(error)),
+    }
+}
+
+impl<T: io::Read> Read<u8> for IoSerial<T> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        if self.pos >= self.len {
+            match self.inner.read(&mut self.read_buf) {
+                Ok(0) => {
+                    return Err(nb::Error::Other(IoError(
+                        io::ErrorKind::UnexpectedEof.into(),
+                    )))
+                }
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Err(error) => return Err(convert(error)),
+            }
+        }
+        let byte = self.read_buf.get(self.pos).copied().unwrap_or_default();
+        self.pos += 1;
+        Ok(byte)
+    }
+}
+
+impl<T: io::Write> Write<u8> for IoSerial<T> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if self.pending == WRITE_BUF_LEN {
+            self.flush()?;
+        }
+        self.write_buf[self.pending] = word;
+        self.pending += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        while self.pending > 0 {
+            match self.inner.write(&self.write_buf[..self.pending]) {
+                Ok(0) => return Err(nb::Error::Other(IoError(io::ErrorKind::WriteZero.into()))),
+                Ok(len) => {
+                    self.write_buf.copy_within(len..self.pending, 0);
+                    self.pending -= len;
+                }
+                Err(error) => return Err(convert(error)),
+            }
+        }
+        self.inner.flush().map_err(convert)
+    }
+}
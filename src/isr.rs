@@ -0,0 +1,85 @@
+//! Rather than blocking in [`read`](crate::AirQualitySensor::read) until a
+//! whole frame has arrived, the UART's receive interrupt handler pushes each
+//! byte into a [`heapless::spsc`] queue, and the main loop (or a
+//! lower-priority RTIC task) periodically drains the queue through a
+//! [`FrameDecoder`], which yields each frame as it is completed:
+//!
+//! ```
+//! use heapless::spsc::Queue;
+//! use sen0177::{decoder::FrameDecoder, isr::ByteQueue};
+//!
+//! let mut queue: ByteQueue<64> = Queue::new();
+//! let (mut producer, mut consumer) = queue.split();
+//!
+//! // In the receive interrupt handler, for each byte read from the UART
+//! // (dropping it if the queue is full):
+//! # let byte = 0x42;
+//! let _ = producer.enqueue(byte);
+//!
+//! // In the main loop:
+//! let mut decoder: FrameDecoder = FrameDecoder::new();
+//! for result in decoder.drain(&mut consumer) {
+//!     match result {
+//!         Ok((_frame, reading)) => { /* use the reading */ }
+//!         Err(_error) => { /* count the error, or ignore it */ }
+//!     }
+//! }
+//! ```
+//!
+//! The producer is usually moved into the interrupt handler's state (an
+//! RTIC local resource, or a `static` set up at startup), with the queue
+//! itself in a `static` so both halves can borrow it for `'static`.
+//!
+//! The sensor sends up to [`BYTES_PER_SECOND`](crate::serial::BYTES_PER_SECOND)
+//! bytes per second, so the queue needs to hold at least as many bytes as
+//! arrive between two drains; a queue of `N` holds `N - 1` bytes.  Bytes
+//! dropped because the queue was full corrupt the frame they belong to,
+//! which the decoder rejects before resynchronizing with the next one.
+
+use heapless::spsc::{Consumer, Queue};
+
+use crate::decoder::{DecodeResult, FrameDecoder};
+use sen0177_protocol::FrameProtocol;
+
+/// A queue for passing received bytes from an interrupt handler to a
+/// [`FrameDecoder`]
+pub type ByteQueue<const N: usize> = Queue<u8, N>;
+
+/// An iterator over the frames decoded from the bytes in a queue
+///
+/// Returned by [`FrameDecoder::drain`].
+pub struct Drain<'d, 'q, P: FrameProtocol, const N: usize> {
+    decoder: &'d mut FrameDecoder<P>,
+    consumer: &'d mut Consumer<'q, u8, N>,
+}
+
+impl<P: FrameProtocol> FrameDecoder<P> {
+    /// Pushes the bytes waiting in `consumer` into the decoder, yielding the
+    /// result of each frame completed along the way
+    ///
+    /// The iterator ends once the queue is empty, which may leave a frame
+    /// partially decoded; it is completed by a later drain.  This never
+    /// blocks, so it's safe to call from a main loop or a low-priority task.
+    pub fn drain<'d, 'q, const N: usize>(
+        &'d mut self,
+        consumer: &'d mut Consumer<'q, u8, N>,
+    ) -> Drain<'d, 'q, P, N> {
+        Drain {
+            decoder: self,
+            consumer,
+        }
+    }
+}
+
+impl<P: FrameProtocol, const N: usize> Iterator for Drain<'_, '_, P, N> {
+    type Item = DecodeResult<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(byte) = self.consumer.dequeue() {
+            if let Some(result) = self.decoder.push(byte) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
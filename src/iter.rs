@@ -0,0 +1,154 @@
+#[cfg(feature = "async")]
+use crate::serial::AsyncSen0177;
+use crate::{
+    backoff::{Backoff, BackoffReadings},
+    AirQualitySensor, Reading, SensorError,
+};
+use core::marker::PhantomData;
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+#[cfg(feature = "async")]
+use embedded_io_async::Read as AsyncRead;
+
+/// An infinite iterator over readings from a sensor
+///
+/// Created by [`AirQualitySensor::readings`].  Each call to `next()` blocks
+/// until a reading (or error) is available.
+pub struct Readings<'a, S: ?Sized, E> {
+    sensor: &'a mut S,
+    _error: PhantomData<E>,
+}
+
+impl<'a, S, E> Readings<'a, S, E>
+where
+    S: AirQualitySensor<E> + ?Sized,
+{
+    pub(crate) fn new(sensor: &'a mut S) -> Self {
+        Self {
+            sensor,
+            _error: PhantomData,
+        }
+    }
+
+    /// Limits the rate of readings by waiting `interval_ms` milliseconds
+    /// (using `delay`) between each one
+    pub fn paced<D: DelayNs>(self, delay: D, interval_ms: u32) -> PacedReadings<'a, S, E, D> {
+        PacedReadings {
+            readings: self,
+            delay,
+            interval_ms,
+            started: false,
+        }
+    }
+
+    /// Waits (using `delay`) after each error before reading again, for
+    /// longer after each consecutive error, as set by `backoff`
+    ///
+    /// See [`Backoff`].
+    pub fn with_backoff<D: DelayNs>(
+        self,
+        delay: D,
+        backoff: Backoff,
+    ) -> BackoffReadings<'a, S, E, D> {
+        BackoffReadings::new(self, delay, backoff)
+    }
+}
+
+impl<S, E> Iterator for Readings<'_, S, E>
+where
+    S: AirQualitySensor<E> + ?Sized,
+{
+    type Item = Result<Reading, SensorError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.sensor.read())
+    }
+}
+
+/// An infinite, rate-limited iterator over readings from a sensor
+///
+/// Created by [`Readings::paced`].
+pub struct PacedReadings<'a, S: ?Sized, E, D> {
+    readings: Readings<'a, S, E>,
+    delay: D,
+    interval_ms: u32,
+    started: bool,
+}
+
+impl<S, E, D> Iterator for PacedReadings<'_, S, E, D>
+where
+    S: AirQualitySensor<E> + ?Sized,
+    D: DelayNs,
+{
+    type Item = Result<Reading, SensorError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            self.delay.delay_ms(self.interval_ms);
+        }
+        self.started = true;
+        self.readings.next()
+    }
+}
+
+/// An infinite cursor over readings from an async sensor
+///
+/// Created by [`AsyncSen0177::readings`].  Each call to
+/// [`next`](AsyncReadings::next) waits until a reading (or error) is
+/// available, and is cancel-safe as [`AsyncSen0177::read`] is.
+#[cfg(feature = "async")]
+pub struct AsyncReadings<'a, R> {
+    sensor: &'a mut AsyncSen0177<R>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, R: AsyncRead> AsyncReadings<'a, R> {
+    pub(crate) fn new(sensor: &'a mut AsyncSen0177<R>) -> Self {
+        Self { sensor }
+    }
+
+    /// Limits the rate of readings by waiting `interval_ms` milliseconds
+    /// (using `delay`) between each one
+    pub fn paced<D: AsyncDelayNs>(
+        self,
+        delay: D,
+        interval_ms: u32,
+    ) -> AsyncPacedReadings<'a, R, D> {
+        AsyncPacedReadings {
+            readings: self,
+            delay,
+            interval_ms,
+            started: false,
+        }
+    }
+
+    /// Reads the next reading; this never returns `None`
+    pub async fn next(&mut self) -> Option<Result<Reading, SensorError<R::Error>>> {
+        Some(self.sensor.read().await)
+    }
+}
+
+/// An infinite, rate-limited cursor over readings from an async sensor
+///
+/// Created by [`AsyncReadings::paced`].
+#[cfg(feature = "async")]
+pub struct AsyncPacedReadings<'a, R, D> {
+    readings: AsyncReadings<'a, R>,
+    delay: D,
+    interval_ms: u32,
+    started: bool,
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead, D: AsyncDelayNs> AsyncPacedReadings<'_, R, D> {
+    /// Waits out the interval since the previous reading, then reads the
+    /// next one; this never returns `None`
+    pub async fn next(&mut self) -> Option<Result<Reading, SensorError<R::Error>>> {
+        if self.started {
+            self.delay.delay_ms(self.interval_ms).await;
+        }
+        self.started = true;
+        self.readings.next().await
+    }
+}
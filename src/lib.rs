@@ -15,142 +15,511 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+/// Threshold alerting with hysteresis
+pub mod alerts;
+/// Protocol analyzer for captured UART traffic
+#[cfg(feature = "std")]
+pub mod analyze;
+/// Integer-only US EPA Air Quality Index calculations
+pub mod aqi;
+/// Exponential backoff with jitter for retrying after persistent errors
+pub mod backoff;
+/// Bluetooth Environmental Sensing Service characteristic encoding
+pub mod ble;
+/// Transport-agnostic classification of bus errors
+pub mod bus;
+/// Least-squares fitting of PM2.5 corrections against a reference monitor
+#[cfg(feature = "float")]
+pub mod calibrate;
+/// Capability traits for particulate, temperature/humidity, and gas sensors
+pub mod capability;
+/// Recording of raw serial traffic to pcap files
+#[cfg(feature = "std")]
+pub mod capture;
+/// Detection of significant changes between readings for change-based reporting
+pub mod change;
+/// Delta and varint compression of reading sequences for transmission
+pub mod compress;
+/// CSV formatting of timestamped readings
+pub mod csv;
+/// Piecewise-linear calibration curves for concentrations
+pub mod curve;
+/// The sans-I/O frame decoder at the core of the serial drivers
+#[cfg(feature = "plantower")]
+pub mod decoder;
+/// Detection and skipping of repeated identical frames
+pub mod dedup;
+/// Metrics derived from readings using empirical relationships
+#[cfg(feature = "float")]
+pub mod derived;
+/// Detection of the connected Plantower sensor model
+#[cfg(feature = "plantower")]
+pub mod detect;
+/// Discovery of serial ports with a sensor attached
+#[cfg(feature = "linux")]
+pub mod discover;
+/// Colors and compact text for showing readings on LEDs and small displays
+pub mod display;
+/// Detection of drift between co-located sensors
+pub mod drift;
+/// Detection of sensors that don't report environmental concentrations
+pub mod env;
+/// A C ABI for the parser and the Linux serial driver
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// WHO and US EPA particulate matter guideline exceedance checks
+pub mod guidelines;
+/// Self-tests of a sensor's data stream
+pub mod health;
+/// Fixed-capacity history of timestamped readings with windowed statistics
+pub mod history;
+/// Humidity correction of PM2.5 concentrations
+pub mod humidity;
 /// Sensors connected to the I2C bus
+#[cfg(feature = "plantower")]
 pub mod i2c;
+/// Serial transports over `std::io` streams, such as TCP bridges
+#[cfg(all(feature = "std", feature = "plantower"))]
+pub mod io;
+/// Interrupt-driven reception through a lock-free byte queue
+#[cfg(feature = "isr")]
+pub mod isr;
+/// Iterator adapters over sensor readings
+pub mod iter;
+/// Ring-buffered logging of readings to NOR flash
+#[cfg(feature = "logger")]
+pub mod logger;
+#[cfg(feature = "plantower")]
+mod logging;
+/// Mapping of readings onto Matter air quality cluster values
+pub mod matter;
+/// An in-memory UART with fault injection, for testing without hardware
+#[cfg(feature = "mock")]
+pub mod mock;
+/// A Modbus holding-register map of readings
+#[cfg(feature = "modbus")]
+pub mod modbus;
+/// Polling and aggregation of several sensors
+pub mod multi;
+/// Commonly used types and traits, for glob importing
+pub mod prelude;
+/// Per-reading data quality flags, such as checksum and warm-up status
+pub mod quality;
+#[cfg(feature = "plantower")]
 pub(crate) mod read;
+/// Transparent reconnection to serial ports that disappear and reappear
+#[cfg(feature = "linux")]
+pub mod reconnect;
+/// An escalating ladder of recovery steps for a failing serial sensor
+#[cfg(feature = "plantower")]
+pub mod recovery;
+/// Replay of logged readings through the `AirQualitySensor` trait
+#[cfg(feature = "std")]
+pub mod replay;
+/// Rolling 24-hour and annual averages with data completeness tracking
+pub mod rolling;
+/// UART access through an SC16IS752 I2C/SPI-to-UART bridge
+#[cfg(feature = "sc16is752")]
+pub mod sc16is752;
+/// JSON Schemas for serialized readings
+#[cfg(feature = "schema")]
+pub mod schema;
+/// CSV logging of readings to SD cards
+#[cfg(feature = "sdcard")]
+pub mod sdcard;
+/// SenML (RFC 8428) encoding of readings
+#[cfg(feature = "senml")]
+pub mod senml;
 /// Sensors connected to a serial UART
+#[cfg(feature = "plantower")]
 pub mod serial;
+/// Compact 12-byte payloads for Sigfox and similar uplinks
+pub mod sigfox;
+/// Simulated sensors and time series, for demos and tests without hardware
+pub mod sim;
+/// Pairing of a particulate sensor with a temperature/humidity sensor
+pub mod station;
+/// Timestamped readings with a pluggable clock
+pub mod time;
+/// Rising, falling, or stable trends of readings over a history window
+pub mod trend;
+/// Detection of a sensor that has stopped sending data
+#[cfg(feature = "plantower")]
+pub mod watchdog;
+/// An `embedded-graphics` widget showing a reading and its recent trend
+#[cfg(feature = "embedded-graphics")]
+pub mod widget;
 
 use core::fmt;
 
+/// The bus-agnostic protocol parser and encoder
+pub use sen0177_protocol as protocol;
+pub use sen0177_protocol::{
+    Concentrations, FieldId, Implausibility, ParticleCount, Reading, ReadingDelta,
+};
+
+/// The SEN0177 connected to a serial UART
+///
+/// This is an alias for [`serial::Sen0177`], to distinguish it from the
+/// I2C driver when both are in scope.
+#[cfg(feature = "plantower")]
+pub type Sen0177Uart<R, E, S = serial::Active, W = serial::Spin> = serial::Sen0177<R, E, S, W>;
+
+/// The PMSA003I connected to the I2C bus
+///
+/// This is an alias for [`i2c::Sen0177`], to distinguish it from the
+/// serial driver when both are in scope.
+#[cfg(feature = "plantower")]
+pub type Sen0177I2c<I2C, E, A = embedded_hal::i2c::SevenBitAddress> = i2c::Sen0177<I2C, E, A>;
+
 /// Trait representing a bus-agnostic air quality sensor
-pub trait AirQualitySensor<E: fmt::Debug> {
+pub trait AirQualitySensor<E> {
     /// Reads a single sensor measurement
     ///
     /// This function will block until sufficient data is available.
     fn read(&mut self) -> Result<Reading, SensorError<E>>;
+
+    /// Returns a description of the device's capabilities
+    ///
+    /// Sensors that don't describe themselves report
+    /// [`SensorInfo::UNKNOWN`].
+    fn info(&self) -> SensorInfo {
+        SensorInfo::UNKNOWN
+    }
+
+    /// Returns an infinite iterator that reads from the sensor each time it
+    /// is advanced
+    fn readings(&mut self) -> iter::Readings<'_, Self, E> {
+        iter::Readings::new(self)
+    }
+}
+
+/// Describes the capabilities of a particular sensor device, so that
+/// generic code can adapt at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorInfo {
+    /// The name of the device
+    pub name: &'static str,
+    /// Whether the device reports meaningful environmental (atmospheric)
+    /// PM concentrations
+    pub supports_atmospheric_pm: bool,
+    /// Whether the device reports particle counts
+    pub supports_particle_counts: bool,
+    /// Whether the device reports temperature and relative humidity
+    pub supports_temperature_humidity: bool,
+    /// The length, in bytes, of a data frame
+    pub frame_len: usize,
+    /// The device's default I2C address, if it can be connected via I2C
+    pub default_i2c_address: Option<u8>,
 }
 
-/// A single air quality sensor reading
-#[derive(Debug, Clone, Copy)]
-pub struct Reading {
-    pm1: u16,
-    pm2_5: u16,
-    pm10: u16,
-    env_pm1: u16,
-    env_pm2_5: u16,
-    env_pm10: u16,
-    particles_0_3: u16,
-    particles_0_5: u16,
-    particles_1: u16,
-    particles_2_5: u16,
-    particles_5: u16,
-    particles_10: u16,
+impl SensorInfo {
+    /// A generic description of a device that claims no capabilities beyond
+    /// the standard PM concentrations
+    pub const UNKNOWN: SensorInfo = SensorInfo {
+        name: "Unknown",
+        supports_atmospheric_pm: false,
+        supports_particle_counts: false,
+        supports_temperature_humidity: false,
+        frame_len: protocol::FRAME_LEN,
+        default_i2c_address: None,
+    };
+}
+
+/// Describes errors returned by the air quality sensor
+///
+/// The underlying bus error type `E` only needs to implement [`fmt::Debug`]
+/// if you want to format the error.
+#[derive(Debug)]
+pub enum SensorError<E> {
+    /// Couldn't find the "magic" bytes that indicate the start of a data frame
+    ///
+    /// This likely means that you've set an incorrect baud rate, or there is something
+    /// noisy about your connection to the device.
+    BadMagic,
+    /// Couldn't find the start of a data frame, and the bytes received
+    /// suggest that the UART is set to the wrong baud rate
+    ///
+    /// This is returned instead of [`BadMagic`](SensorError::BadMagic) when
+    /// every byte discarded while searching for a frame was 0x00 or 0xff,
+    /// which is typical of a receiver seeing framing errors.  The SEN0177
+    /// communicates at 9600 baud.
+    LikelyBaudMismatch,
+    /// The checksum provided in the sensor data did not match the checksum of the data itself
+    ///
+    /// Retrying the read will usually clear up the problem.
+    ChecksumMismatch,
+    /// The reading failed plausibility validation
+    ///
+    /// This is only returned when validation has been enabled on the driver.
+    ImplausibleData(Implausibility),
+    /// No data was received from the sensor before the configured timeout elapsed
+    Timeout,
+    /// The sensor has returned the same frame more times in a row than the
+    /// configured limit, and has likely not produced new data
+    StaleData,
+    /// No valid frame has been received for longer than a
+    /// [`Watchdog`](watchdog::Watchdog)'s timeout, as happens when the
+    /// sensor's fan has died or its TX line is broken
+    NoData {
+        /// The time of the last valid frame (or of the watchdog's first
+        /// check, if there hasn't been one), from the watchdog's clock
+        since: u64,
+    },
+    /// Read or write error from the serial device or I2C bus
+    ReadError(E),
 }
 
-impl Reading {
-    /// Returns the standard PM1 concentration in µg/m³
-    pub fn pm1(&self) -> u16 {
-        self.pm1
+impl<E: fmt::Debug> fmt::Display for SensorError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SensorError::*;
+        match self {
+            BadMagic => f.write_str("Unable to find magic bytes at start of payload"),
+            LikelyBaudMismatch => {
+                f.write_str("Unable to find magic bytes at start of payload (check the baud rate)")
+            }
+            ChecksumMismatch => f.write_str("Data read was corrupt"),
+            ImplausibleData(reason) => write!(f, "Implausible data: {}", reason),
+            Timeout => f.write_str("Timed out waiting for data"),
+            StaleData => f.write_str("Sensor data has not been updated"),
+            NoData { since } => write!(f, "No data received since {}", since),
+            ReadError(error) => write!(f, "Read error: {:?}", error),
+        }
     }
+}
+
+impl<E: fmt::Debug> core::error::Error for SensorError<E> {}
 
-    /// Returns the standard PM2.5 concentration in µg/m³
-    pub fn pm2_5(&self) -> u16 {
-        self.pm2_5
+/// A [`SensorError`] that reports its bus error as its
+/// [`source`](core::error::Error::source)
+///
+/// A `SensorError` formats its bus error into its own message, since most
+/// HAL error types only implement [`fmt::Debug`].  When the bus error is
+/// itself an [`Error`](core::error::Error), wrapping the `SensorError`
+/// (see [`SensorError::chained`]) leaves the bus error out of the message
+/// and returns it from `source()` instead, so that error reports (such as
+/// `anyhow`'s) show the whole chain of causes.
+#[derive(Debug)]
+pub struct Chained<E>(pub SensorError<E>);
+
+impl<E: fmt::Debug> fmt::Display for Chained<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            SensorError::ReadError(_) => f.write_str("Read error"),
+            error => fmt::Display::fmt(error, f),
+        }
     }
+}
 
-    /// Returns the standard PM10 concentration in µg/m³
-    pub fn pm10(&self) -> u16 {
-        self.pm10
+impl<E: core::error::Error + 'static> core::error::Error for Chained<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.0
+            .bus_error()
+            .map(|error| error as &(dyn core::error::Error + 'static))
     }
+}
 
-    /// Returns the environmental PM1 concentration in µg/m³
-    ///
-    /// Note that some devices do not support this reading and will
-    /// return garbage data for this value.
-    pub fn env_pm1(&self) -> u16 {
-        self.env_pm1
+impl<E> From<SensorError<E>> for Chained<E> {
+    fn from(error: SensorError<E>) -> Self {
+        Chained(error)
     }
+}
 
-    /// Returns the environmental PM2.5 concentration in µg/m³
-    ///
-    /// Note that some devices do not support this reading and will
-    /// return garbage data for this value.
-    pub fn env_pm2_5(&self) -> u16 {
-        self.env_pm2_5
+#[cfg(feature = "ufmt")]
+impl<E: ufmt::uDebug> ufmt::uDisplay for SensorError<E> {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        use SensorError::*;
+        match self {
+            BadMagic => f.write_str("Unable to find magic bytes at start of payload"),
+            LikelyBaudMismatch => {
+                f.write_str("Unable to find magic bytes at start of payload (check the baud rate)")
+            }
+            ChecksumMismatch => f.write_str("Data read was corrupt"),
+            ImplausibleData(reason) => ufmt::uwrite!(f, "Implausible data: {}", reason),
+            Timeout => f.write_str("Timed out waiting for data"),
+            StaleData => f.write_str("Sensor data has not been updated"),
+            NoData { since } => ufmt::uwrite!(f, "No data received since {}", since),
+            ReadError(error) => ufmt::uwrite!(f, "Read error: {:?}", error),
+        }
     }
+}
 
-    /// Returns the environmental PM10 concentration in µg/m³
+#[cfg(feature = "ufmt")]
+impl<E: ufmt::uDebug> ufmt::uDebug for SensorError<E> {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        use SensorError::*;
+        match self {
+            BadMagic => f.write_str("BadMagic"),
+            LikelyBaudMismatch => f.write_str("LikelyBaudMismatch"),
+            ChecksumMismatch => f.write_str("ChecksumMismatch"),
+            ImplausibleData(reason) => f.debug_tuple("ImplausibleData")?.field(reason)?.finish(),
+            Timeout => f.write_str("Timeout"),
+            StaleData => f.write_str("StaleData"),
+            NoData { since } => f.debug_struct("NoData")?.field("since", since)?.finish(),
+            ReadError(error) => f.debug_tuple("ReadError")?.field(error)?.finish(),
+        }
+    }
+}
+
+impl<E> SensorError<E> {
+    /// Wraps an error returned by the underlying serial device or I2C bus
     ///
-    /// Note that some devices do not support this reading and will
-    /// return garbage data for this value.
-    pub fn env_pm10(&self) -> u16 {
-        self.env_pm10
+    /// This is an explicit constructor (rather than a blanket `From` impl) so
+    /// that downstream code remains free to provide its own `From`
+    /// conversions into `SensorError`.
+    pub fn bus(error: E) -> Self {
+        SensorError::ReadError(error)
     }
 
-    /// Returns count of particles smaller than 0.3µm
-    pub fn particles_0_3(&self) -> u16 {
-        self.particles_0_3
+    /// Returns the error from the underlying serial device or I2C bus, if
+    /// this is a [`ReadError`](SensorError::ReadError)
+    pub fn bus_error(&self) -> Option<&E> {
+        match self {
+            SensorError::ReadError(error) => Some(error),
+            _ => None,
+        }
     }
 
-    /// Returns count of particles smaller than 0.5µm
-    pub fn particles_0_5(&self) -> u16 {
-        self.particles_0_5
+    /// Wraps the error to report its bus error as its source
+    ///
+    /// See [`Chained`].
+    pub fn chained(self) -> Chained<E> {
+        Chained(self)
     }
+}
 
-    /// Returns count of particles smaller than 1µm
-    pub fn particles_1(&self) -> u16 {
-        self.particles_1
+/// The category of a sensor driver error, following the `embedded-hal`
+/// `ErrorKind` pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// No valid frame could be found in the data received, or its checksum
+    /// didn't match
+    InvalidFrame,
+    /// The reading failed plausibility validation
+    Implausible,
+    /// No data was received before the configured timeout elapsed
+    Timeout,
+    /// The sensor kept returning the same frame
+    Stale,
+    /// No valid frame has been received for longer than a watchdog's timeout
+    NoData,
+    /// The serial device or I2C bus returned an error
+    Bus,
+    /// Any other error
+    Other,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::InvalidFrame => "Invalid or corrupt data frame",
+            ErrorKind::Implausible => "Implausible data",
+            ErrorKind::Timeout => "Timed out waiting for data",
+            ErrorKind::Stale => "Sensor data has not been updated",
+            ErrorKind::NoData => "No data received",
+            ErrorKind::Bus => "Bus error",
+            ErrorKind::Other => "Sensor error",
+        })
     }
+}
 
-    /// Returns count of particles smaller than 2.5µm
-    pub fn particles_2_5(&self) -> u16 {
-        self.particles_2_5
+/// A sensor driver error
+///
+/// Like the error traits of `embedded-hal`, this lets code that composes
+/// drivers (such as a board-support crate) inspect an error generically,
+/// through its [`ErrorKind`].
+pub trait Error: fmt::Debug {
+    /// Returns the category of the error
+    fn kind(&self) -> ErrorKind;
+}
+
+impl Error for ErrorKind {
+    fn kind(&self) -> ErrorKind {
+        *self
     }
+}
 
-    /// Returns count of particles smaller than 5µm
-    pub fn particles_5(&self) -> u16 {
-        self.particles_5
+impl Error for core::convert::Infallible {
+    fn kind(&self) -> ErrorKind {
+        match *self {}
     }
+}
 
-    /// Returns count of particles smaller than 10µm
-    pub fn particles_10(&self) -> u16 {
-        self.particles_10
+impl<E: fmt::Debug> Error for SensorError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SensorError::BadMagic
+            | SensorError::LikelyBaudMismatch
+            | SensorError::ChecksumMismatch => ErrorKind::InvalidFrame,
+            SensorError::ImplausibleData(_) => ErrorKind::Implausible,
+            SensorError::Timeout => ErrorKind::Timeout,
+            SensorError::StaleData => ErrorKind::Stale,
+            SensorError::NoData { .. } => ErrorKind::NoData,
+            SensorError::ReadError(_) => ErrorKind::Bus,
+        }
     }
 }
 
-/// Describes errors returned by the air quality sensor
-#[derive(Debug)]
-pub enum SensorError<E: fmt::Debug> {
-    /// Couldn't find the "magic" bytes that indicate the start of a data frame
-    ///
-    /// This likely means that you've set an incorrect baud rate, or there is something
-    /// noisy about your connection to the device.
-    BadMagic,
-    /// The checksum provided in the sensor data did not match the checksum of the data itself
-    ///
-    /// Retrying the read will usually clear up the problem.
-    ChecksumMismatch,
-    /// Read error from the serial device or I2C bus
-    ReadError(E),
+/// A type with an associated sensor driver [`Error`], following the
+/// `embedded-hal` `ErrorType` pattern
+///
+/// The drivers implement this with their [`SensorError`], so that code
+/// wrapping them can name the error type as `<S as ErrorType>::Error`.
+pub trait ErrorType {
+    /// The error type returned by the driver
+    type Error: Error;
 }
 
-impl<E: fmt::Debug> fmt::Display for SensorError<E> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use SensorError::*;
+impl<T: ErrorType + ?Sized> ErrorType for &mut T {
+    type Error = T::Error;
+}
+
+/// A `SensorError` is itself a serial error, reporting the kind of its bus
+/// error (or, for a frame that couldn't be found, a framing error), so
+/// that the driver can stand in for a UART in a composed driver stack
+impl<E: embedded_hal_nb::serial::Error> embedded_hal_nb::serial::Error for SensorError<E> {
+    fn kind(&self) -> embedded_hal_nb::serial::ErrorKind {
+        use embedded_hal_nb::serial::ErrorKind;
         match self {
-            BadMagic => f.write_str("Unable to find magic bytes at start of payload"),
-            ChecksumMismatch => f.write_str("Data read was corrupt"),
-            ReadError(error) => write!(f, "Read error: {:?}", error),
+            SensorError::ReadError(error) => error.kind(),
+            SensorError::LikelyBaudMismatch => ErrorKind::FrameFormat,
+            _ => ErrorKind::Other,
         }
     }
 }
 
-#[cfg(feature = "std")]
-impl<E: fmt::Debug> std::error::Error for SensorError<E> {}
+/// A `SensorError` is itself an I2C error, reporting the kind of its bus
+/// error
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for SensorError<E> {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        match self {
+            SensorError::ReadError(error) => error.kind(),
+            _ => embedded_hal::i2c::ErrorKind::Other,
+        }
+    }
+}
 
-impl<E: fmt::Debug> From<E> for SensorError<E> {
-    fn from(error: E) -> Self {
-        SensorError::ReadError(error)
+/// A `SensorError` is itself an `embedded-io` error, reporting the kind of
+/// its bus error, or invalid data or a timeout
+#[cfg(feature = "async")]
+impl<E: embedded_io_async::Error> embedded_io_async::Error for SensorError<E> {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        use embedded_io_async::ErrorKind;
+        match self {
+            SensorError::ReadError(error) => error.kind(),
+            SensorError::BadMagic
+            | SensorError::LikelyBaudMismatch
+            | SensorError::ChecksumMismatch
+            | SensorError::ImplausibleData(_) => ErrorKind::InvalidData,
+            SensorError::Timeout | SensorError::NoData { .. } => ErrorKind::TimedOut,
+            SensorError::StaleData => ErrorKind::Other,
+        }
     }
 }
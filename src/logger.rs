@@ -0,0 +1,432 @@
+//! Stores timestamped readings in a region of NOR flash, for offline data
+//! loggers.
+//!
+//! The region is used as a ring of fixed-size records.  Records are written
+//! sequentially, and a sector is only erased when the logger is about to
+//! write its first record, at which point it holds the oldest data; every
+//! sector is therefore erased exactly once per trip around the ring,
+//! spreading wear evenly.  After a reset, [`Logger::open`] scans the region
+//! to find where writing left off, skipping past any record that was
+//! interrupted by a power loss.
+//!
+//! # Format
+//!
+//! Each record is [`RECORD_LEN`] bytes:
+//!
+//! | Offset | Length | Contents                                           |
+//! |--------|--------|----------------------------------------------------|
+//! | 0      | 1      | Marker byte (`0x5a`)                               |
+//! | 1      | 4      | Sequence number (little-endian)                    |
+//! | 5      | 8      | Timestamp (little-endian)                          |
+//! | 13     | 26     | The reading, laid out as bytes 4–29 of a data frame |
+//! | 39     | 2      | CRC-16/CCITT-FALSE of bytes 0–38 (little-endian)   |
+//! | 41     | 7      | Padding (`0xff`)                                   |
+//!
+//! With the `logger-async` feature, [`AsyncLogger`] provides the same
+//! functionality over `embedded-storage-async`.
+
+use core::{fmt, ops::Range};
+use embedded_storage::nor_flash::NorFlash;
+use sen0177_protocol::{encode_frame, parse_frame_unchecked, FRAME_LEN};
+
+use crate::{time::Timestamped, Reading};
+
+/// The size, in bytes, of each stored record
+pub const RECORD_LEN: usize = 48;
+
+const MARKER: u8 = 0x5a;
+const ERASED: u8 = 0xff;
+const CRC_OFFSET: usize = 39;
+
+/// Describes errors encountered while logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoggerError<E> {
+    /// The region is not aligned to the flash's erase size, is smaller than
+    /// two sectors, or the flash's read or write size does not divide
+    /// [`RECORD_LEN`]
+    BadRegion,
+    /// Error from the flash device
+    Flash(E),
+}
+
+impl<E: fmt::Debug> fmt::Display for LoggerError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggerError::BadRegion => f.write_str("Unusable flash region for logging"),
+            LoggerError::Flash(error) => write!(f, "Flash error: {:?}", error),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for LoggerError<E> {}
+
+/// A logger writing to a region of blocking NOR flash
+pub struct Logger<F> {
+    flash: F,
+    ring: Ring,
+}
+
+impl<F: NorFlash> Logger<F> {
+    /// Opens a logger over the byte range `region` of `flash`, picking up
+    /// after the newest record already stored there
+    ///
+    /// `region` must start and end on sector (erase size) boundaries and span
+    /// at least two sectors.  This reads the whole region.
+    pub fn open(mut flash: F, region: Range<u32>) -> Result<Self, LoggerError<F::Error>> {
+        let mut ring = Ring::new(region, F::ERASE_SIZE, F::READ_SIZE.max(F::WRITE_SIZE))?;
+        let mut buf = [0u8; RECORD_LEN];
+        for slot in 0..ring.capacity {
+            flash
+                .read(ring.address(slot), &mut buf)
+                .map_err(LoggerError::Flash)?;
+            ring.scan(slot, &buf);
+        }
+        while !ring.at_sector_start() {
+            flash
+                .read(ring.address(ring.head), &mut buf)
+                .map_err(LoggerError::Flash)?;
+            if is_erased(&buf) {
+                break;
+            }
+            ring.head = ring.next(ring.head);
+        }
+        Ok(Self { flash, ring })
+    }
+
+    /// Returns the maximum number of records the region can hold
+    ///
+    /// Because a whole sector is erased at a time, the number of records
+    /// available to read back is between this less one sector's worth, and
+    /// this.
+    pub fn capacity(&self) -> u32 {
+        self.ring.capacity
+    }
+
+    /// Appends a record, erasing the oldest sector first if necessary
+    pub fn append(&mut self, record: &Timestamped<Reading>) -> Result<(), LoggerError<F::Error>> {
+        if self.ring.at_sector_start() {
+            let sector = self.ring.sector(self.ring.head);
+            self.flash
+                .erase(sector.start, sector.end)
+                .map_err(LoggerError::Flash)?;
+        }
+        let buf = encode(self.ring.next_seq, record);
+        self.flash
+            .write(self.ring.address(self.ring.head), &buf)
+            .map_err(LoggerError::Flash)?;
+        self.ring.advance();
+        Ok(())
+    }
+
+    /// Iterates over the stored records, from oldest to newest
+    ///
+    /// Records that fail their CRC (for example, because writing them was
+    /// interrupted) are skipped.
+    pub fn records(&mut self) -> Records<'_, F> {
+        Records {
+            slots: self.ring.readable(),
+            logger: self,
+        }
+    }
+
+    /// Consumes the logger, returning the underlying flash
+    pub fn release(self) -> F {
+        self.flash
+    }
+}
+
+/// An iterator over the records stored by a [`Logger`]
+///
+/// Created by [`Logger::records`].
+pub struct Records<'a, F> {
+    logger: &'a mut Logger<F>,
+    slots: Slots,
+}
+
+impl<F: NorFlash> Iterator for Records<'_, F> {
+    type Item = Result<Timestamped<Reading>, F::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0u8; RECORD_LEN];
+        while let Some(slot) = self.slots.next(&self.logger.ring) {
+            if let Err(error) = self
+                .logger
+                .flash
+                .read(self.logger.ring.address(slot), &mut buf)
+            {
+                return Some(Err(error));
+            }
+            if let Some((_, record)) = decode(&buf) {
+                return Some(Ok(record));
+            }
+        }
+        None
+    }
+}
+
+/// A logger writing to a region of async NOR flash
+#[cfg(feature = "logger-async")]
+pub struct AsyncLogger<F> {
+    flash: F,
+    ring: Ring,
+}
+
+#[cfg(feature = "logger-async")]
+impl<F: embedded_storage_async::nor_flash::NorFlash> AsyncLogger<F> {
+    /// Opens a logger over the byte range `region` of `flash`, picking up
+    /// after the newest record already stored there
+    ///
+    /// See [`Logger::open`].
+    pub async fn open(mut flash: F, region: Range<u32>) -> Result<Self, LoggerError<F::Error>> {
+        let mut ring = Ring::new(region, F::ERASE_SIZE, F::READ_SIZE.max(F::WRITE_SIZE))?;
+        let mut buf = [0u8; RECORD_LEN];
+        for slot in 0..ring.capacity {
+            flash
+                .read(ring.address(slot), &mut buf)
+                .await
+                .map_err(LoggerError::Flash)?;
+            ring.scan(slot, &buf);
+        }
+        while !ring.at_sector_start() {
+            flash
+                .read(ring.address(ring.head), &mut buf)
+                .await
+                .map_err(LoggerError::Flash)?;
+            if is_erased(&buf) {
+                break;
+            }
+            ring.head = ring.next(ring.head);
+        }
+        Ok(Self { flash, ring })
+    }
+
+    /// Returns the maximum number of records the region can hold
+    pub fn capacity(&self) -> u32 {
+        self.ring.capacity
+    }
+
+    /// Appends a record, erasing the oldest sector first if necessary
+    pub async fn append(
+        &mut self,
+        record: &Timestamped<Reading>,
+    ) -> Result<(), LoggerError<F::Error>> {
+        if self.ring.at_sector_start() {
+            let sector = self.ring.sector(self.ring.head);
+            self.flash
+                .erase(sector.start, sector.end)
+                .await
+                .map_err(LoggerError::Flash)?;
+        }
+        let buf = encode(self.ring.next_seq, record);
+        self.flash
+            .write(self.ring.address(self.ring.head), &buf)
+            .await
+            .map_err(LoggerError::Flash)?;
+        self.ring.advance();
+        Ok(())
+    }
+
+    /// Returns a cursor over the stored records, from oldest to newest
+    ///
+    /// Records that fail their CRC are skipped.
+    pub fn records(&mut self) -> AsyncRecords<'_, F> {
+        AsyncRecords {
+            slots: self.ring.readable(),
+            logger: self,
+        }
+    }
+
+    /// Consumes the logger, returning the underlying flash
+    pub fn release(self) -> F {
+        self.flash
+    }
+}
+
+/// A cursor over the records stored by an [`AsyncLogger`]
+///
+/// Created by [`AsyncLogger::records`].
+#[cfg(feature = "logger-async")]
+pub struct AsyncRecords<'a, F> {
+    logger: &'a mut AsyncLogger<F>,
+    slots: Slots,
+}
+
+#[cfg(feature = "logger-async")]
+impl<F: embedded_storage_async::nor_flash::NorFlash> AsyncRecords<'_, F> {
+    /// Reads the next record, or returns `None` once all have been read
+    pub async fn next(&mut self) -> Option<Result<Timestamped<Reading>, F::Error>> {
+        let mut buf = [0u8; RECORD_LEN];
+        while let Some(slot) = self.slots.next(&self.logger.ring) {
+            if let Err(error) = self
+                .logger
+                .flash
+                .read(self.logger.ring.address(slot), &mut buf)
+                .await
+            {
+                return Some(Err(error));
+            }
+            if let Some((_, record)) = decode(&buf) {
+                return Some(Ok(record));
+            }
+        }
+        None
+    }
+}
+
+/// The layout of the ring, and the position of its head
+struct Ring {
+    start: u32,
+    sector_len: u32,
+    slots_per_sector: u32,
+    capacity: u32,
+    /// The slot the next record will be written to
+    head: u32,
+    next_seq: u32,
+    newest_seq: Option<u32>,
+}
+
+impl Ring {
+    fn new<E>(
+        region: Range<u32>,
+        erase_size: usize,
+        access_size: usize,
+    ) -> Result<Self, LoggerError<E>> {
+        let sector_len = erase_size as u32;
+        if sector_len == 0
+            || access_size == 0
+            || RECORD_LEN % access_size != 0
+            || erase_size < RECORD_LEN
+            || region.start % sector_len != 0
+            || region.end % sector_len != 0
+            || region.end < region.start
+            || (region.end - region.start) / sector_len < 2
+        {
+            return Err(LoggerError::BadRegion);
+        }
+        let slots_per_sector = sector_len / RECORD_LEN as u32;
+        Ok(Self {
+            start: region.start,
+            sector_len,
+            slots_per_sector,
+            capacity: (region.end - region.start) / sector_len * slots_per_sector,
+            head: 0,
+            next_seq: 0,
+            newest_seq: None,
+        })
+    }
+
+    fn address(&self, slot: u32) -> u32 {
+        self.start
+            + slot / self.slots_per_sector * self.sector_len
+            + slot % self.slots_per_sector * RECORD_LEN as u32
+    }
+
+    fn sector(&self, slot: u32) -> Range<u32> {
+        let start = self.start + slot / self.slots_per_sector * self.sector_len;
+        start..start + self.sector_len
+    }
+
+    fn next(&self, slot: u32) -> u32 {
+        (slot + 1) % self.capacity
+    }
+
+    fn at_sector_start(&self) -> bool {
+        self.head % self.slots_per_sector == 0
+    }
+
+    /// Updates the head from a record read while scanning the region
+    fn scan(&mut self, slot: u32, buf: &[u8; RECORD_LEN]) {
+        if let Some((seq, _)) = decode(buf) {
+            if self.newest_seq.map_or(true, |newest| seq > newest) {
+                self.newest_seq = Some(seq);
+                self.head = self.next(slot);
+                self.next_seq = seq.wrapping_add(1);
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        self.newest_seq = Some(self.next_seq);
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.head = self.next(self.head);
+    }
+
+    /// Returns the slots that may hold records, oldest first
+    ///
+    /// When the head is partway through a sector, the oldest records are in
+    /// the following sector; otherwise the head's own sector holds them, as
+    /// it hasn't yet been erased.
+    fn readable(&self) -> Slots {
+        let first = if self.at_sector_start() {
+            self.head
+        } else {
+            (self.head / self.slots_per_sector + 1) * self.slots_per_sector % self.capacity
+        };
+        let remaining = match (self.head + self.capacity - first) % self.capacity {
+            0 => self.capacity,
+            count => count,
+        };
+        Slots {
+            slot: first,
+            remaining,
+        }
+    }
+}
+
+struct Slots {
+    slot: u32,
+    remaining: u32,
+}
+
+impl Slots {
+    fn next(&mut self, ring: &Ring) -> Option<u32> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let slot = self.slot;
+        self.slot = ring.next(slot);
+        Some(slot)
+    }
+}
+
+fn encode(seq: u32, record: &Timestamped<Reading>) -> [u8; RECORD_LEN] {
+    let mut buf = [ERASED; RECORD_LEN];
+    buf[0] = MARKER;
+    buf[1..5].copy_from_slice(&seq.to_le_bytes());
+    buf[5..13].copy_from_slice(&record.timestamp.to_le_bytes());
+    buf[13..CRC_OFFSET].copy_from_slice(&encode_frame(&record.value)[4..FRAME_LEN - 2]);
+    let crc = crc16(&buf[..CRC_OFFSET]);
+    buf[CRC_OFFSET..CRC_OFFSET + 2].copy_from_slice(&crc.to_le_bytes());
+    buf
+}
+
+fn decode(buf: &[u8; RECORD_LEN]) -> Option<(u32, Timestamped<Reading>)> {
+    let crc = u16::from_le_bytes([buf[CRC_OFFSET], buf[CRC_OFFSET + 1]]);
+    if buf[0] != MARKER || crc16(&buf[..CRC_OFFSET]) != crc {
+        return None;
+    }
+    let seq = u32::from_le_bytes([buf[1], buf[2], buf[3], buf[4]]);
+    let mut timestamp = [0u8; 8];
+    timestamp.copy_from_slice(&buf[5..13]);
+    let mut frame = [0u8; FRAME_LEN];
+    frame[4..FRAME_LEN - 2].copy_from_slice(&buf[13..CRC_OFFSET]);
+    Some((
+        seq,
+        Timestamped::new(u64::from_le_bytes(timestamp), parse_frame_unchecked(&frame)),
+    ))
+}
+
+fn is_erased(buf: &[u8]) -> bool {
+    buf.iter().all(|&byte| byte == ERASED)
+}
+
+/// CRC-16/CCITT-FALSE
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
@@ -0,0 +1,33 @@
+// Internal logging macros that forward to `log` or `tracing` when the
+// corresponding feature is enabled, and compile to nothing otherwise.  With
+// both enabled, events only go to `tracing`, which can forward them to `log`
+// itself, so that they aren't emitted twice.
+
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        ::log::trace!($($arg)+);
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!($($arg)+);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        ::log::debug!($($arg)+);
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!($($arg)+);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+pub(crate) use debug;
+pub(crate) use trace;
@@ -0,0 +1,148 @@
+//! Maps readings onto the attribute values of the Matter Air Quality cluster
+//! and the PM1, PM2.5, and PM10 Concentration Measurement clusters, so
+//! firmware built on a Matter stack (such as `rs-matter`) only needs to
+//! copy the values into its attributes.
+//!
+//! The enums here are `#[repr(u8)]` with the discriminants the Matter
+//! specification assigns, so `as u8` gives the value to report.  Standard
+//! (CF=1) concentrations are used throughout, since not every device reports
+//! environmental ones.
+//!
+//! The Air Quality cluster's levels are mapped one-to-one from the US EPA
+//! [`AqiCategory`]; the Concentration Measurement clusters' level values
+//! are mapped from the AQI of the individual pollutant.  There are no AQI
+//! breakpoints for PM1, so its level is always [`LevelValue::Unknown`].
+
+use crate::{
+    aqi::{self, Aqi, AqiCategory},
+    Reading,
+};
+
+/// The ID of the Air Quality cluster
+pub const AIR_QUALITY_CLUSTER_ID: u32 = 0x005b;
+
+/// The ID of the PM2.5 Concentration Measurement cluster
+pub const PM2_5_CLUSTER_ID: u32 = 0x042a;
+
+/// The ID of the PM1 Concentration Measurement cluster
+pub const PM1_CLUSTER_ID: u32 = 0x042c;
+
+/// The ID of the PM10 Concentration Measurement cluster
+pub const PM10_CLUSTER_ID: u32 = 0x042d;
+
+/// The `MeasurementUnit` attribute value for µg/m³, the unit of all
+/// measured values produced here
+pub const MEASUREMENT_UNIT_UGM3: u8 = 4;
+
+/// The `MeasurementMedium` attribute value for air
+pub const MEASUREMENT_MEDIUM_AIR: u8 = 0;
+
+/// The Air Quality cluster's `AirQuality` attribute value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum AirQuality {
+    /// The air quality is unknown
+    Unknown = 0,
+    /// Good air quality
+    Good = 1,
+    /// Fair air quality
+    Fair = 2,
+    /// Moderate air quality
+    Moderate = 3,
+    /// Poor air quality
+    Poor = 4,
+    /// Very poor air quality
+    VeryPoor = 5,
+    /// Extremely poor air quality
+    ExtremelyPoor = 6,
+}
+
+impl From<AqiCategory> for AirQuality {
+    fn from(category: AqiCategory) -> Self {
+        match category {
+            AqiCategory::Good => AirQuality::Good,
+            AqiCategory::Moderate => AirQuality::Fair,
+            AqiCategory::UnhealthyForSensitiveGroups => AirQuality::Moderate,
+            AqiCategory::Unhealthy => AirQuality::Poor,
+            AqiCategory::VeryUnhealthy => AirQuality::VeryPoor,
+            AqiCategory::Hazardous => AirQuality::ExtremelyPoor,
+        }
+    }
+}
+
+impl From<Aqi> for AirQuality {
+    fn from(aqi: Aqi) -> Self {
+        aqi.category().into()
+    }
+}
+
+/// A Concentration Measurement cluster's `LevelValue` attribute value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum LevelValue {
+    /// The level is unknown
+    Unknown = 0,
+    /// A low level
+    Low = 1,
+    /// A medium level
+    Medium = 2,
+    /// A high level
+    High = 3,
+    /// A critical level
+    Critical = 4,
+}
+
+impl From<AqiCategory> for LevelValue {
+    fn from(category: AqiCategory) -> Self {
+        match category {
+            AqiCategory::Good => LevelValue::Low,
+            AqiCategory::Moderate => LevelValue::Medium,
+            AqiCategory::UnhealthyForSensitiveGroups | AqiCategory::Unhealthy => LevelValue::High,
+            AqiCategory::VeryUnhealthy | AqiCategory::Hazardous => LevelValue::Critical,
+        }
+    }
+}
+
+impl From<Aqi> for LevelValue {
+    fn from(aqi: Aqi) -> Self {
+        aqi.category().into()
+    }
+}
+
+/// Returns the `AirQuality` attribute value for a reading, from the higher
+/// of its PM2.5 and PM10 AQI values
+pub fn air_quality(reading: &Reading) -> AirQuality {
+    pm2_5_aqi(reading).max(pm10_aqi(reading)).into()
+}
+
+/// Returns the `LevelValue` attribute values for the PM1, PM2.5, and PM10
+/// clusters, in that order
+pub fn level_values(reading: &Reading) -> [LevelValue; 3] {
+    [
+        LevelValue::Unknown,
+        pm2_5_aqi(reading).into(),
+        pm10_aqi(reading).into(),
+    ]
+}
+
+/// Returns the `MeasuredValue` attribute values, in µg/m³, for the PM1,
+/// PM2.5, and PM10 clusters, in that order
+///
+/// Requires the `float` feature, as the attribute is a single precision
+/// float.
+#[cfg(feature = "float")]
+pub fn measured_values(reading: &Reading) -> [f32; 3] {
+    [
+        f32::from(reading.pm1()),
+        f32::from(reading.pm2_5()),
+        f32::from(reading.pm10()),
+    ]
+}
+
+fn pm2_5_aqi(reading: &Reading) -> Aqi {
+    aqi::pm2_5(u32::from(reading.pm2_5()) * 10)
+}
+
+fn pm10_aqi(reading: &Reading) -> Aqi {
+    aqi::pm10(u32::from(reading.pm10()) * 10)
+}
@@ -0,0 +1,185 @@
+//! Bytes queued with [`MockSerial::feed`] (or whole frames, with
+//! [`MockSerial::feed_reading`]) are returned by [`Read::read`], after
+//! passing through the configured [`Faults`].  Once the queue runs dry,
+//! reads return `WouldBlock`; pair the driver with
+//! [`Sen0177Builder::timeout_polls`](crate::serial::Sen0177Builder::timeout_polls)
+//! so that this surfaces as [`SensorError::Timeout`](crate::SensorError::Timeout)
+//! rather than blocking forever.
+//!
+//! Fault injection is driven by a small seeded PRNG, so a given seed and
+//! input always produce the same byte stream.
+
+use embedded_hal_nb::{
+    nb,
+    serial::{ErrorKind, ErrorType, Read, Write},
+};
+use sen0177_protocol::encode_frame;
+use std::collections::VecDeque;
+
+use crate::Reading;
+
+/// Faults to inject into the data returned by a [`MockSerial`]
+///
+/// Rates are expressed in parts per million, so that rates typical of real
+/// line noise (one bad bit in tens of thousands) can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Faults {
+    /// Probability, per bit, that the bit is flipped
+    pub bit_flip_ppm: u32,
+    /// Probability, per byte, that the byte is dropped
+    pub drop_ppm: u32,
+    /// Probability, per chunk passed to [`MockSerial::feed`], that the
+    /// chunk is truncated at a random point
+    pub truncate_ppm: u32,
+    /// Number of times a read returns `WouldBlock` before the first byte of
+    /// each chunk passed to [`MockSerial::feed`] is delivered
+    pub delay_polls: u32,
+}
+
+impl Faults {
+    /// No faults
+    pub fn none() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    Byte(u8),
+    Error(ErrorKind),
+    Stall(u32),
+}
+
+/// A UART backed by an in-memory queue, with optional fault injection
+#[derive(Debug, Clone)]
+pub struct MockSerial {
+    rx: VecDeque<Event>,
+    tx: Vec<u8>,
+    faults: Faults,
+    rng: u64,
+}
+
+impl Default for MockSerial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockSerial {
+    /// Creates a new mock UART with nothing queued and no faults
+    pub fn new() -> Self {
+        Self::with_faults(Faults::none(), 1)
+    }
+
+    /// Creates a new mock UART that injects `faults` into everything fed to
+    /// it, using a PRNG seeded with `seed`
+    pub fn with_faults(faults: Faults, seed: u64) -> Self {
+        Self {
+            rx: VecDeque::new(),
+            tx: Vec::new(),
+            faults,
+            rng: seed | 1,
+        }
+    }
+
+    /// Queues `bytes` to be read, subject to the configured faults
+    pub fn feed(&mut self, bytes: &[u8]) -> &mut Self {
+        let mut len = bytes.len();
+        if len > 0 && self.chance(self.faults.truncate_ppm) {
+            len = self.below(len as u32) as usize;
+        }
+        if self.faults.delay_polls > 0 {
+            self.rx.push_back(Event::Stall(self.faults.delay_polls));
+        }
+        for &byte in &bytes[..len] {
+            if self.chance(self.faults.drop_ppm) {
+                continue;
+            }
+            let mut byte = byte;
+            for bit in 0..8 {
+                if self.chance(self.faults.bit_flip_ppm) {
+                    byte ^= 1 << bit;
+                }
+            }
+            self.rx.push_back(Event::Byte(byte));
+        }
+        self
+    }
+
+    /// Queues a data frame encoding `reading`, subject to the configured
+    /// faults
+    pub fn feed_reading(&mut self, reading: &Reading) -> &mut Self {
+        self.feed(&encode_frame(reading))
+    }
+
+    /// Queues a bus error, to be returned by the read following any bytes
+    /// already queued
+    pub fn feed_error(&mut self, error: ErrorKind) -> &mut Self {
+        self.rx.push_back(Event::Error(error));
+        self
+    }
+
+    /// Returns the number of bytes queued and not yet read
+    pub fn pending(&self) -> usize {
+        self.rx
+            .iter()
+            .filter(|event| matches!(event, Event::Byte(_)))
+            .count()
+    }
+
+    /// Returns everything written to the UART so far
+    pub fn written(&self) -> &[u8] {
+        &self.tx
+    }
+
+    /// Discards everything written to the UART so far
+    pub fn clear_written(&mut self) {
+        self.tx.clear();
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    fn below(&mut self, bound: u32) -> u32 {
+        (self.next_random() % bound as u64) as u32
+    }
+
+    fn chance(&mut self, ppm: u32) -> bool {
+        ppm > 0 && self.below(1_000_000) < ppm
+    }
+}
+
+impl ErrorType for MockSerial {
+    type Error = ErrorKind;
+}
+
+impl Read<u8> for MockSerial {
+    fn read(&mut self) -> nb::Result<u8, ErrorKind> {
+        match self.rx.pop_front() {
+            Some(Event::Byte(byte)) => Ok(byte),
+            Some(Event::Error(error)) => Err(nb::Error::Other(error)),
+            Some(Event::Stall(polls)) => {
+                if polls > 1 {
+                    self.rx.push_front(Event::Stall(polls - 1));
+                }
+                Err(nb::Error::WouldBlock)
+            }
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+impl Write<u8> for MockSerial {
+    fn write(&mut self, word: u8) -> nb::Result<(), ErrorKind> {
+        self.tx.push(word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ErrorKind> {
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! Lays readings and read statistics out as a Modbus holding-register map,
+//! so the sensor can be fronted by a Modbus RTU or TCP slave.
+//!
+//! This module doesn't implement the Modbus protocol itself; the slave
+//! implementation decodes requests and passes Read Holding Registers
+//! (function 0x03) requests to [`RegisterMap::read`], which produces the
+//! response data or the exception code to reply with.
+//!
+//! # Register map
+//!
+//! Addresses are zero-based protocol addresses (add 40001 for the
+//! traditional "4x" register numbers).  All registers are read-only.
+//! Two-register counters are sent high word first, and wrap on overflow.
+//!
+//! | Address | Registers | Contents                                             |
+//! |---------|-----------|------------------------------------------------------|
+//! | 0       | 1         | Standard PM1 concentration, in µg/m³                 |
+//! | 1       | 1         | Standard PM2.5 concentration, in µg/m³               |
+//! | 2       | 1         | Standard PM10 concentration, in µg/m³                |
+//! | 3       | 1         | Environmental PM1 concentration, in µg/m³            |
+//! | 4       | 1         | Environmental PM2.5 concentration, in µg/m³          |
+//! | 5       | 1         | Environmental PM10 concentration, in µg/m³           |
+//! | 6–11    | 6         | Particles beyond 0.3/0.5/1/2.5/5/10µm, per 0.1L      |
+//! | 12      | 1         | Firmware version reported by the sensor              |
+//! | 13      | 1         | Device error code reported by the sensor             |
+//! | 14      | 1         | Status flags (see below)                             |
+//! | 15–16   | 2         | Successful reads                                     |
+//! | 17–18   | 2         | Framing errors (bad magic, baud mismatch, checksum)  |
+//! | 19–20   | 2         | Timeouts                                             |
+//! | 21–22   | 2         | Other errors (implausible or stale data, bus errors) |
+//!
+//! Status flags:
+//!
+//! * bit 0 ([`STATUS_HAS_READING`]): registers 0–13 hold a reading
+//! * bit 1 ([`STATUS_LAST_READ_FAILED`]): the most recent read failed, so
+//!   registers 0–13 hold an older reading
+//! * bit 2 ([`STATUS_NO_ENV`]): the sensor doesn't report genuine
+//!   environmental concentrations, so registers 3–5 hold zero
+
+use crate::{Reading, SensorError};
+
+/// The number of registers in the map
+pub const REGISTER_COUNT: u16 = 23;
+
+/// The address of the status flags register
+pub const STATUS_REGISTER: u16 = 14;
+
+/// Status flag set once a reading has been stored
+pub const STATUS_HAS_READING: u16 = 1 << 0;
+
+/// Status flag set when the most recent read failed
+pub const STATUS_LAST_READ_FAILED: u16 = 1 << 1;
+
+/// Status flag set when the stored reading has no environmental
+/// concentrations
+pub const STATUS_NO_ENV: u16 = 1 << 2;
+
+/// The most registers a single Read Holding Registers request may ask for
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+const READS_REGISTER: usize = 15;
+const FRAMING_ERRORS_REGISTER: usize = 17;
+const TIMEOUTS_REGISTER: usize = 19;
+const OTHER_ERRORS_REGISTER: usize = 21;
+
+/// A Modbus exception, to be returned to the master in place of data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exception {
+    /// The requested registers are not all within the map
+    IllegalDataAddress,
+    /// The requested register count is zero or more than
+    /// [`MAX_READ_REGISTERS`]
+    IllegalDataValue,
+}
+
+impl Exception {
+    /// Returns the exception code to send in the exception response
+    pub fn code(&self) -> u8 {
+        match self {
+            Exception::IllegalDataAddress => 0x02,
+            Exception::IllegalDataValue => 0x03,
+        }
+    }
+}
+
+/// The register map, updated with the result of each read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterMap {
+    registers: [u16; REGISTER_COUNT as usize],
+}
+
+impl Default for RegisterMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegisterMap {
+    /// Creates a register map with no reading and all counters at zero
+    pub fn new() -> Self {
+        Self {
+            registers: [0; REGISTER_COUNT as usize],
+        }
+    }
+
+    /// Updates the map with the result of a read
+    ///
+    /// A successful read replaces the stored reading; a failed read leaves
+    /// it in place, and sets [`STATUS_LAST_READ_FAILED`].
+    pub fn update<E>(&mut self, result: &Result<Reading, SensorError<E>>) {
+        let counter = match result {
+            Ok(reading) => {
+                self.store(reading);
+                READS_REGISTER
+            }
+            Err(SensorError::BadMagic)
+            | Err(SensorError::LikelyBaudMismatch)
+            | Err(SensorError::ChecksumMismatch) => FRAMING_ERRORS_REGISTER,
+            Err(SensorError::Timeout) | Err(SensorError::NoData { .. }) => TIMEOUTS_REGISTER,
+            Err(SensorError::ImplausibleData(_))
+            | Err(SensorError::StaleData)
+            | Err(SensorError::ReadError(_)) => OTHER_ERRORS_REGISTER,
+        };
+        let status = &mut self.registers[STATUS_REGISTER as usize];
+        if result.is_ok() {
+            *status &= !STATUS_LAST_READ_FAILED;
+        } else {
+            *status |= STATUS_LAST_READ_FAILED;
+        }
+        let count = self.counter(counter).wrapping_add(1);
+        self.registers[counter] = (count >> 16) as u16;
+        self.registers[counter + 1] = count as u16;
+    }
+
+    /// Returns the contents of every register
+    pub fn registers(&self) -> &[u16] {
+        &self.registers
+    }
+
+    /// Serves a Read Holding Registers request for `count` registers
+    /// starting at `address`, writing the register values big-endian into
+    /// `buf` and returning the number of bytes written
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than twice `count` bytes.
+    pub fn read(&self, address: u16, count: u16, buf: &mut [u8]) -> Result<usize, Exception> {
+        if count == 0 || count > MAX_READ_REGISTERS {
+            return Err(Exception::IllegalDataValue);
+        }
+        let start = usize::from(address);
+        let end = start + usize::from(count);
+        let registers = self
+            .registers
+            .get(start..end)
+            .ok_or(Exception::IllegalDataAddress)?;
+        let len = registers.len() * 2;
+        for (chunk, register) in buf[..len].chunks_exact_mut(2).zip(registers) {
+            chunk.copy_from_slice(&register.to_be_bytes());
+        }
+        Ok(len)
+    }
+
+    fn store(&mut self, reading: &Reading) {
+        let env = reading.atmospheric();
+        let env_values = env.unwrap_or_default();
+        self.registers[..6].copy_from_slice(&[
+            reading.pm1(),
+            reading.pm2_5(),
+            reading.pm10(),
+            env_values.pm1(),
+            env_values.pm2_5(),
+            env_values.pm10(),
+        ]);
+        self.registers[6..12].copy_from_slice(&[
+            reading.particles_0_3().per_deciliter(),
+            reading.particles_0_5().per_deciliter(),
+            reading.particles_1().per_deciliter(),
+            reading.particles_2_5().per_deciliter(),
+            reading.particles_5().per_deciliter(),
+            reading.particles_10().per_deciliter(),
+        ]);
+        self.registers[12] = reading.firmware_version().into();
+        self.registers[13] = reading.device_error_code().into();
+        let status = &mut self.registers[STATUS_REGISTER as usize];
+        *status |= STATUS_HAS_READING;
+        if env.is_some() {
+            *status &= !STATUS_NO_ENV;
+        } else {
+            *status |= STATUS_NO_ENV;
+        }
+    }
+
+    fn counter(&self, register: usize) -> u32 {
+        (u32::from(self.registers[register]) << 16) | u32::from(self.registers[register + 1])
+    }
+}
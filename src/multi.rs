@@ -0,0 +1,291 @@
+//! Polls several sensors and combines their readings, to average out the
+//! unit-to-unit variance of low-cost sensors.
+//!
+//! [`MultiSensor`] owns a fixed number of sensors, polls them round-robin,
+//! and keeps the latest reading and some health statistics for each.  The
+//! combined reading is the mean or median of each field over the sensors
+//! whose most recent read succeeded, after excluding any sensor whose PM2.5
+//! concentration is far from the others'.  The environmental
+//! concentrations are combined over just those sensors that report them,
+//! and are left out if none do.
+
+use crate::{AirQualitySensor, Concentrations, Reading, SensorError, SensorInfo};
+
+/// How the readings of several sensors are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Combine {
+    /// The mean of each field, rounded to the nearest integer
+    Mean,
+    /// The median of each field; with an even number of sensors, the mean
+    /// of the two middle values
+    Median,
+}
+
+/// Health statistics for a single sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SensorHealth {
+    /// The number of successful reads
+    pub reads: u32,
+    /// The number of failed reads
+    pub failures: u32,
+    /// The number of failed reads since the last successful one
+    pub consecutive_failures: u32,
+    /// The number of times the sensor's reading was excluded from the
+    /// combined reading as an outlier
+    pub outliers: u32,
+    /// The sensor's most recent successful reading
+    pub last_reading: Option<Reading>,
+}
+
+impl SensorHealth {
+    /// Returns whether the sensor's most recent read succeeded
+    pub fn is_healthy(&self) -> bool {
+        self.last_reading.is_some() && self.consecutive_failures == 0
+    }
+}
+
+/// A combined reading, and which sensors contributed to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Combined<const N: usize> {
+    /// The combined reading
+    ///
+    /// Its firmware version and device error code are always zero.
+    pub reading: Reading,
+    /// Whether each sensor's reading was included
+    pub included: [bool; N],
+}
+
+impl<const N: usize> Combined<N> {
+    /// Returns the number of sensors whose readings were included
+    pub fn sensors_used(&self) -> usize {
+        self.included.iter().filter(|&&included| included).count()
+    }
+}
+
+/// Owns `N` sensors of the same type, polls them, and combines their
+/// readings
+pub struct MultiSensor<S, const N: usize> {
+    sensors: [S; N],
+    health: [SensorHealth; N],
+    next: usize,
+    combine: Combine,
+    outlier_percent: u16,
+    outlier_floor: u16,
+}
+
+impl<S, const N: usize> MultiSensor<S, N> {
+    /// Takes ownership of `sensors`, combining their readings with the
+    /// median and excluding outliers as per
+    /// [`outlier_tolerance(50, 5)`](MultiSensor::outlier_tolerance)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn new(sensors: [S; N]) -> Self {
+        assert!(N > 0, "MultiSensor needs at least one sensor");
+        Self {
+            sensors,
+            health: [SensorHealth::default(); N],
+            next: 0,
+            combine: Combine::Median,
+            outlier_percent: 50,
+            outlier_floor: 5,
+        }
+    }
+
+    /// Sets how readings are combined
+    pub fn combine(mut self, combine: Combine) -> Self {
+        self.combine = combine;
+        self
+    }
+
+    /// Sets how far a sensor's PM2.5 concentration may be from the median
+    /// of all healthy sensors' before it is excluded as an outlier
+    ///
+    /// A sensor is excluded when its deviation exceeds both `percent` of the
+    /// median and `floor` µg/m³; the floor keeps sensors from being excluded
+    /// over small absolute differences in clean air.  Outliers are only
+    /// excluded when at least three sensors are healthy, since with two
+    /// there's no telling which one is wrong.
+    pub fn outlier_tolerance(mut self, percent: u16, floor: u16) -> Self {
+        self.outlier_percent = percent;
+        self.outlier_floor = floor;
+        self
+    }
+
+    /// Returns the health statistics for each sensor
+    pub fn health(&self) -> &[SensorHealth; N] {
+        &self.health
+    }
+
+    /// Returns mutable access to the sensors, e.g. to reconfigure them
+    pub fn sensors(&mut self) -> &mut [S; N] {
+        &mut self.sensors
+    }
+
+    /// Consumes the manager, returning the sensors
+    pub fn release(self) -> [S; N] {
+        self.sensors
+    }
+
+    /// Reads from the next sensor in round-robin order, returning its index
+    /// along with the result
+    pub fn poll_next<E>(&mut self) -> (usize, Result<Reading, SensorError<E>>)
+    where
+        S: AirQualitySensor<E>,
+    {
+        let index = self.next;
+        self.next = (self.next + 1) % N;
+        let result = self.sensors[index].read();
+        let health = &mut self.health[index];
+        match &result {
+            Ok(reading) => {
+                health.reads = health.reads.wrapping_add(1);
+                health.consecutive_failures = 0;
+                health.last_reading = Some(*reading);
+            }
+            Err(_) => {
+                health.failures = health.failures.wrapping_add(1);
+                health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+            }
+        }
+        (index, result)
+    }
+
+    /// Reads from every sensor once, returning the combined reading, or the
+    /// last error if no sensor is healthy
+    pub fn poll_all<E>(&mut self) -> Result<Combined<N>, SensorError<E>>
+    where
+        S: AirQualitySensor<E>,
+    {
+        let mut error = None;
+        for _ in 0..N {
+            if let (_, Err(e)) = self.poll_next() {
+                error = Some(e);
+            }
+        }
+        match (self.combined(), error) {
+            (Some(combined), _) => Ok(combined),
+            (None, Some(error)) => Err(error),
+            (None, None) => unreachable!("a sensor either produced a reading or failed"),
+        }
+    }
+
+    /// Combines the latest readings of the healthy sensors, excluding
+    /// outliers, or returns `None` if no sensor is healthy
+    ///
+    /// Each call that excludes a sensor counts towards its
+    /// [`outliers`](SensorHealth::outliers), so this should be called once
+    /// per polling round.
+    pub fn combined(&mut self) -> Option<Combined<N>> {
+        let mut included = self.health.map(|health| health.is_healthy());
+        let healthy = included.iter().filter(|&&included| included).count();
+        if healthy == 0 {
+            return None;
+        }
+
+        if healthy >= 3 {
+            let median = self.aggregate(&included, Combine::Median, Reading::pm2_5);
+            let tolerance = (u32::from(median) * u32::from(self.outlier_percent) / 100)
+                .max(u32::from(self.outlier_floor));
+            for (included, health) in included.iter_mut().zip(self.health.iter_mut()) {
+                let Some(reading) = health.last_reading.filter(|_| *included) else {
+                    continue;
+                };
+                if u32::from(reading.pm2_5().abs_diff(median)) > tolerance {
+                    *included = false;
+                    health.outliers = health.outliers.wrapping_add(1);
+                }
+            }
+        }
+
+        let field = |f: fn(&Reading) -> u16| self.aggregate(&included, self.combine, f);
+        // Only sensors that report environmental concentrations contribute
+        // to them
+        let mut with_env = included;
+        for (with_env, health) in with_env.iter_mut().zip(&self.health) {
+            *with_env &= health
+                .last_reading
+                .is_some_and(|reading| reading.atmospheric().is_some());
+        }
+        let env_field = |f: fn(&Concentrations) -> u16| {
+            self.aggregate(&with_env, self.combine, |reading| {
+                reading.atmospheric().map_or(0, |env| f(&env))
+            })
+        };
+        let atmospheric = with_env.contains(&true).then(|| {
+            Concentrations::new(
+                env_field(Concentrations::pm1),
+                env_field(Concentrations::pm2_5),
+                env_field(Concentrations::pm10),
+            )
+        });
+        let reading = Reading::new(
+            Concentrations::new(
+                field(Reading::pm1),
+                field(Reading::pm2_5),
+                field(Reading::pm10),
+            ),
+            atmospheric.unwrap_or_default(),
+            [
+                field(|r| r.particles_0_3().per_deciliter()),
+                field(|r| r.particles_0_5().per_deciliter()),
+                field(|r| r.particles_1().per_deciliter()),
+                field(|r| r.particles_2_5().per_deciliter()),
+                field(|r| r.particles_5().per_deciliter()),
+                field(|r| r.particles_10().per_deciliter()),
+            ],
+        );
+        let reading = match atmospheric {
+            Some(_) => reading,
+            None => reading.without_atmospheric(),
+        };
+        Some(Combined { reading, included })
+    }
+
+    fn aggregate(
+        &self,
+        included: &[bool; N],
+        combine: Combine,
+        field: impl Fn(&Reading) -> u16,
+    ) -> u16 {
+        let mut values = [0u16; N];
+        let mut len = 0;
+        for (health, _) in self.health.iter().zip(included).filter(|(_, &i)| i) {
+            if let Some(reading) = &health.last_reading {
+                values[len] = field(reading);
+                len += 1;
+            }
+        }
+        let values = &mut values[..len];
+        match combine {
+            Combine::Mean => {
+                let sum: u32 = values.iter().map(|&v| u32::from(v)).sum();
+                ((sum + len as u32 / 2) / len as u32) as u16
+            }
+            Combine::Median => {
+                values.sort_unstable();
+                if len % 2 == 1 {
+                    values[len / 2]
+                } else {
+                    (u32::from(values[len / 2 - 1]) + u32::from(values[len / 2])).div_ceil(2) as u16
+                }
+            }
+        }
+    }
+}
+
+/// Reading through this trait polls every sensor once and returns the
+/// combined reading; the device info is that of the first sensor
+impl<S, E, const N: usize> AirQualitySensor<E> for MultiSensor<S, N>
+where
+    S: AirQualitySensor<E>,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        self.poll_all().map(|combined| combined.reading)
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensors[0].info()
+    }
+}
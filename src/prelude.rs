@@ -0,0 +1,11 @@
+//! Glob-import this module to bring the commonly used types and traits into
+//! scope:
+//!
+//! ```
+//! use sen0177::prelude::*;
+//! ```
+
+pub use crate::capability::{Calibratable, GasSensor, ParticulateSensor, TempHumiditySensor};
+#[cfg(feature = "plantower")]
+pub use crate::{serial::Sen0177Builder, Sen0177I2c, Sen0177Uart};
+pub use crate::{AirQualitySensor, Reading, SensorError, SensorInfo};
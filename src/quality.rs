@@ -0,0 +1,212 @@
+//! Data quality flags carried alongside readings.
+//!
+//! Research deployments need to know not just a value but how far it can
+//! be trusted: whether its checksum was verified, whether it passed
+//! plausibility checks, whether the sensor had finished warming up, and
+//! whether it was corrected or filled in after the fact.  [`QualityFlags`]
+//! records all of this in a single byte, and [`Qualified`] pairs it with a
+//! reading (or any value derived from one), much as
+//! [`Timestamped`](crate::time::Timestamped) pairs a value with a time, so
+//! the flags can travel with the value through filtering, aggregation, and
+//! serialization.
+//!
+//! ```
+//! use sen0177::{
+//!     quality::{Qualified, QualityFlags},
+//!     Reading,
+//! };
+//!
+//! # let reading = Reading::default();
+//! # let uptime_secs = 60;
+//! let qualified = Qualified::assess(reading, true, uptime_secs >= 30);
+//! if qualified.flags.contains(QualityFlags::VALID) {
+//!     // store it
+//! }
+//! ```
+
+use core::ops::{BitAnd, BitOr, BitOrAssign};
+
+use crate::Reading;
+
+/// A set of data quality flags, stored as a bitfield
+///
+/// Flags either vouch for a value ([`CHECKSUM_VERIFIED`], [`PLAUSIBLE`],
+/// [`WARMED_UP`]), or note that it is not exactly what the sensor reported
+/// ([`HUMIDITY_CORRECTED`], [`INTERPOLATED`]).  Bits not assigned to a flag
+/// are reserved, and cleared by [`from_bits`](QualityFlags::from_bits).
+///
+/// With the `serde` feature, the flags serialize as their bits.
+///
+/// [`CHECKSUM_VERIFIED`]: QualityFlags::CHECKSUM_VERIFIED
+/// [`PLAUSIBLE`]: QualityFlags::PLAUSIBLE
+/// [`WARMED_UP`]: QualityFlags::WARMED_UP
+/// [`HUMIDITY_CORRECTED`]: QualityFlags::HUMIDITY_CORRECTED
+/// [`INTERPOLATED`]: QualityFlags::INTERPOLATED
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct QualityFlags(u8);
+
+impl QualityFlags {
+    /// No flags set
+    pub const NONE: Self = Self(0);
+    /// The frame's checksum matched
+    pub const CHECKSUM_VERIFIED: Self = Self(1 << 0);
+    /// The reading passed [plausibility validation](crate::protocol::validate)
+    pub const PLAUSIBLE: Self = Self(1 << 1);
+    /// The reading was taken after the sensor's warm-up period (30 seconds
+    /// after power-up or wake-up, per the datasheet)
+    pub const WARMED_UP: Self = Self(1 << 2);
+    /// The value was corrected for humidity (see
+    /// [`humidity`](crate::humidity))
+    pub const HUMIDITY_CORRECTED: Self = Self(1 << 3);
+    /// The value was interpolated to fill a gap, rather than measured
+    pub const INTERPOLATED: Self = Self(1 << 4);
+
+    /// The flags a measured value needs to be considered valid: verified,
+    /// plausible, and warmed up
+    pub const VALID: Self = Self(Self::CHECKSUM_VERIFIED.0 | Self::PLAUSIBLE.0 | Self::WARMED_UP.0);
+
+    const ALL: u8 = Self::VALID.0 | Self::HUMIDITY_CORRECTED.0 | Self::INTERPOLATED.0;
+
+    /// Returns the flags' bits
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Creates flags from bits, clearing any reserved bits
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::ALL)
+    }
+
+    /// Returns `true` if all of `other`'s flags are set
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if no flags are set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Sets `other`'s flags
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Clears `other`'s flags
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Sets or clears `other`'s flags according to `value`
+    pub fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.insert(other);
+        } else {
+            self.remove(other);
+        }
+    }
+
+    /// Returns the flags of a value aggregated from values flagged with
+    /// `self` and `other`
+    ///
+    /// An aggregate is only vouched for if all of its inputs are, but is
+    /// corrected or interpolated if any of them is.
+    pub const fn aggregate(self, other: Self) -> Self {
+        const VOUCHING: u8 = QualityFlags::VALID.0;
+        Self((self.0 & other.0 & VOUCHING) | ((self.0 | other.0) & !VOUCHING))
+    }
+
+    /// Returns the flags of a value aggregated from values with each of
+    /// `flags`, or `None` if there are none
+    pub fn aggregate_all<I>(flags: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        flags.into_iter().reduce(Self::aggregate)
+    }
+}
+
+impl BitOr for QualityFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for QualityFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.insert(other);
+    }
+}
+
+impl BitAnd for QualityFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+/// A value along with its data quality flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Qualified<R = Reading> {
+    /// The value itself
+    pub value: R,
+    /// The value's quality flags
+    pub flags: QualityFlags,
+}
+
+impl<R> Qualified<R> {
+    /// Pairs `value` with `flags`
+    pub fn new(value: R, flags: QualityFlags) -> Self {
+        Self { value, flags }
+    }
+
+    /// Applies `f` to the value, keeping the flags
+    ///
+    /// Add any flags describing what `f` did, such as
+    /// [`HUMIDITY_CORRECTED`](QualityFlags::HUMIDITY_CORRECTED), with
+    /// [`with`](Qualified::with).
+    pub fn map<U, F>(self, f: F) -> Qualified<U>
+    where
+        F: FnOnce(R) -> U,
+    {
+        Qualified {
+            value: f(self.value),
+            flags: self.flags,
+        }
+    }
+
+    /// Returns this value with `flags` added
+    pub fn with(mut self, flags: QualityFlags) -> Self {
+        self.flags.insert(flags);
+        self
+    }
+}
+
+impl Qualified<Reading> {
+    /// Flags a reading straight from the sensor
+    ///
+    /// The reading is checked for plausibility here, regardless of whether
+    /// the driver validated it.  `checksum_verified` and `warmed_up` come
+    /// from the caller, e.g. from a
+    /// [`LenientReading`](crate::serial::LenientReading) and the time since
+    /// the sensor was woken.
+    pub fn assess(reading: Reading, checksum_verified: bool, warmed_up: bool) -> Self {
+        let mut flags = QualityFlags::NONE;
+        flags.set(QualityFlags::CHECKSUM_VERIFIED, checksum_verified);
+        flags.set(
+            QualityFlags::PLAUSIBLE,
+            sen0177_protocol::validate(&reading).is_ok(),
+        );
+        flags.set(QualityFlags::WARMED_UP, warmed_up);
+        Self::new(reading, flags)
+    }
+}
@@ -0,0 +1,289 @@
+//! A USB-serial adapter that is unplugged (or reset by a flaky hub) takes
+//! its device node with it, and every read from the open port then fails
+//! with `EIO`.  [`ReconnectingPort`] is a UART that notices this, closes
+//! the port, and keeps trying to reopen it, backing off between attempts;
+//! while it is disconnected, reads report that no data is available yet, so
+//! a driver built on it simply resumes reading once the adapter is back.
+//!
+//! When the adapter reappears it may be given a different device node
+//! (`/dev/ttyUSB1` instead of `/dev/ttyUSB0`), so a port can be selected by
+//! its adapter's USB serial number instead of by path.
+//!
+//! ```no_run
+//! use sen0177::{
+//!     reconnect::{PortTarget, ReconnectEvent, ReconnectingPort},
+//!     serial::Sen0177,
+//! };
+//!
+//! let port = ReconnectingPort::new(PortTarget::UsbSerialNumber("A10KZP4S".into()))
+//!     .with_callback(|event| match event {
+//!         ReconnectEvent::Connected { path } => eprintln!("Connected to {}", path),
+//!         ReconnectEvent::Disconnected { error } => eprintln!("Disconnected: {}", error),
+//!     });
+//! let mut sensor = Sen0177::new(port);
+//! loop {
+//!     match sensor.read_raw() {
+//!         Ok((_, reading)) => println!("PM2.5: {}µg/m³", reading.pm2_5()),
+//!         Err(error) => eprintln!("Error: {}", error),
+//!     }
+//! }
+//! ```
+
+use embedded_hal_nb::{
+    nb,
+    serial::{ErrorKind, ErrorType, Read, Write},
+};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, SerialPortType, StopBits};
+use std::{
+    io::{self, Read as _, Write as _},
+    time::{Duration, Instant},
+};
+
+use crate::backoff::Backoff;
+
+/// The delay before the first attempt to reopen a port, and the longest
+/// delay between attempts
+///
+/// USB adapters usually take a second or so to reappear after being
+/// plugged back in.
+const RECONNECT_BACKOFF: Backoff = Backoff::new(250, 5000);
+
+/// How long each read from the OS waits for data before reporting that
+/// none is available
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The errno values returned when a serial device has gone away: `ENOENT`,
+/// `EIO`, `ENXIO`, and `ENODEV`
+const DISCONNECT_ERRNOS: [i32; 4] = [2, 5, 6, 19];
+
+/// How to find the port to (re)connect to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortTarget {
+    /// The port at this path, e.g. `/dev/ttyUSB0` or a udev symlink
+    Path(String),
+    /// The USB serial port whose adapter reports this serial number,
+    /// wherever it is enumerated
+    UsbSerialNumber(String),
+}
+
+/// A change in a [`ReconnectingPort`]'s connection
+#[derive(Debug)]
+pub enum ReconnectEvent<'a> {
+    /// The port was opened, either for the first time or after a
+    /// disconnection
+    Connected {
+        /// The path of the port that was opened
+        path: &'a str,
+    },
+    /// The port failed in a way that indicates that the device has gone
+    /// away, and has been closed
+    Disconnected {
+        /// The error that the port failed with
+        error: &'a io::Error,
+    },
+}
+
+fn ignore(_event: ReconnectEvent<'_>) {}
+
+/// A serial port that reopens itself after its device disappears and
+/// reappears
+///
+/// See the [module documentation](self).  The port is opened at 9600 baud
+/// (8N1, no flow control) on the first read, and reopened as needed; while
+/// disconnected, reads return [`nb::Error::WouldBlock`] and writes fail.
+/// The `F` type parameter is the callback notified of
+/// [`ReconnectEvent`]s.
+pub struct ReconnectingPort<F = fn(ReconnectEvent<'_>)> {
+    target: PortTarget,
+    baud_rate: u32,
+    port: Option<Box<dyn SerialPort>>,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+    backoff: Backoff,
+    next_attempt: Option<Instant>,
+    on_event: F,
+}
+
+impl ReconnectingPort {
+    /// Creates a port that connects to `target`
+    ///
+    /// No attempt is made to open the port until it is first read from.
+    pub fn new(target: PortTarget) -> Self {
+        Self {
+            target,
+            baud_rate: crate::discover::BAUD_RATE,
+            port: None,
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+            backoff: RECONNECT_BACKOFF,
+            next_attempt: None,
+            on_event: ignore,
+        }
+    }
+}
+
+impl<F: FnMut(ReconnectEvent<'_>)> ReconnectingPort<F> {
+    /// Sets the callback notified each time the port is connected or
+    /// disconnected, replacing the current one
+    pub fn with_callback<F2>(self, on_event: F2) -> ReconnectingPort<F2>
+    where
+        F2: FnMut(ReconnectEvent<'_>),
+    {
+        ReconnectingPort {
+            target: self.target,
+            baud_rate: self.baud_rate,
+            port: self.port,
+            buf: self.buf,
+            pos: self.pos,
+            len: self.len,
+            backoff: self.backoff,
+            next_attempt: self.next_attempt,
+            on_event,
+        }
+    }
+
+    /// Sets the baud rate used when opening the port
+    ///
+    /// This takes effect the next time the port is opened.
+    pub fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    /// Sets the backoff between attempts to reopen the port
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Returns `true` if the port is currently open
+    pub fn is_connected(&self) -> bool {
+        self.port.is_some()
+    }
+
+    /// Tries to open the port, unless the last attempt was too recent
+    fn try_connect(&mut self) {
+        let now = Instant::now();
+        if self.next_attempt.is_some_and(|next| now < next) {
+            return;
+        }
+        match self.open() {
+            Ok((port, path)) => {
+                self.port = Some(port);
+                self.backoff.reset();
+                self.next_attempt = None;
+                (self.on_event)(ReconnectEvent::Connected { path: &path });
+            }
+            Err(_) => {
+                let delay = Duration::from_millis(self.backoff.next_delay_ms().into());
+                self.next_attempt = Some(now + delay);
+            }
+        }
+    }
+
+    fn open(&self) -> io::Result<(Box<dyn SerialPort>, String)> {
+        let path = match &self.target {
+            PortTarget::Path(path) => path.clone(),
+            PortTarget::UsbSerialNumber(serial_number) => serialport::available_ports()?
+                .into_iter()
+                .find_map(|info| match info.port_type {
+                    SerialPortType::UsbPort(usb)
+                        if usb.serial_number.as_ref() == Some(serial_number) =>
+                    {
+                        Some(info.port_name)
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "no USB adapter with serial number")
+                })?,
+        };
+        let port = serialport::new(path.as_str(), self.baud_rate)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .flow_control(FlowControl::None)
+            .timeout(READ_TIMEOUT)
+            .open()?;
+        Ok((port, path))
+    }
+
+    /// Handles an error from the open port, closing it if the device has
+    /// gone away
+    fn fail<T>(&mut self, error: io::Error) -> nb::Result<T, ErrorKind> {
+        match error.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => {
+                Err(nb::Error::WouldBlock)
+            }
+            _ if is_disconnect(&error) => {
+                self.port = None;
+                self.pos = 0;
+                self.len = 0;
+                (self.on_event)(ReconnectEvent::Disconnected { error: &error });
+                Err(nb::Error::WouldBlock)
+            }
+            _ => Err(nb::Error::Other(ErrorKind::Other)),
+        }
+    }
+}
+
+fn is_disconnect(error: &io::Error) -> bool {
+    error
+        .raw_os_error()
+        .is_some_and(|errno| DISCONNECT_ERRNOS.contains(&errno))
+        || matches!(
+            error.kind(),
+            io::ErrorKind::NotFound | io::ErrorKind::BrokenPipe | io::ErrorKind::UnexpectedEof
+        )
+}
+
+impl<F> ErrorType for ReconnectingPort<F> {
+    type Error = ErrorKind;
+}
+
+impl<F: FnMut(ReconnectEvent<'_>)> Read<u8> for ReconnectingPort<F> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        if let Some(&byte) = self.buf.get(self.pos..self.len).and_then(<[u8]>::first) {
+            self.pos += 1;
+            return Ok(byte);
+        }
+        let Some(port) = self.port.as_mut() else {
+            self.try_connect();
+            return Err(nb::Error::WouldBlock);
+        };
+        match port.read(&mut self.buf) {
+            // A tty whose device has been removed reports end of file
+            Ok(0) => self.fail(io::ErrorKind::UnexpectedEof.into()),
+            Ok(len) => {
+                self.pos = 1;
+                self.len = len;
+                Ok(self.buf[0])
+            }
+            Err(error) => self.fail(error),
+        }
+    }
+}
+
+impl<F: FnMut(ReconnectEvent<'_>)> Write<u8> for ReconnectingPort<F> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        let Some(port) = self.port.as_mut() else {
+            return Err(nb::Error::Other(ErrorKind::Other));
+        };
+        match port.write_all(&[word]) {
+            Ok(()) => Ok(()),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        let Some(port) = self.port.as_mut() else {
+            return Err(nb::Error::Other(ErrorKind::Other));
+        };
+        match port.flush() {
+            Ok(()) => Ok(()),
+            Err(error) => self.fail(error),
+        }
+    }
+}
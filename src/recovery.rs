@@ -0,0 +1,362 @@
+//! A read that fails once is usually fine on the next try, but a sensor
+//! that keeps failing needs progressively firmer handling: discarding
+//! whatever is buffered, re-sending its mode commands, and finally
+//! resetting it in hardware, before giving up and letting the caller
+//! decide.  [`Recovering`] wraps an active [`Sen0177`] and climbs this
+//! ladder on each consecutive failure, as set out by an
+//! [`EscalationPolicy`], reporting each step it takes (and each recovery)
+//! to an optional callback.
+//!
+//! ```
+//! # #[cfg(feature = "mock")] {
+//! use sen0177::{
+//!     recovery::{EscalationPolicy, Recovering, RecoveryEvent},
+//!     serial::Sen0177Builder,
+//! };
+//! # use sen0177::mock::MockSerial;
+//! # let mut serial = MockSerial::new();
+//! # serial.feed_reading(&Default::default());
+//!
+//! let sensor = Sen0177Builder::new().timeout_polls(10_000).build(&mut serial);
+//! let mut sensor = Recovering::new(sensor, EscalationPolicy::new().retries(3))
+//!     .on_event(|event: RecoveryEvent| println!("{:?}", event));
+//! let reading = sensor.read()?;
+//! # }
+//! # Ok::<(), sen0177::SensorError<embedded_hal_nb::serial::ErrorKind>>(())
+//! ```
+
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
+use embedded_hal_nb::serial::{Error as SerialError, Read, Write};
+
+use crate::{
+    logging::debug,
+    serial::{Active, Idle, Sen0177},
+    AirQualitySensor, Reading, SensorError, SensorInfo,
+};
+
+/// How long [`ResetPin`] holds the RESET pin low, in milliseconds
+pub const RESET_PULSE_MS: u32 = 100;
+
+/// A step taken to recover from a failed read, in order of escalation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RecoveryStep {
+    /// Read again
+    Retry,
+    /// Discard any buffered data, then read again
+    Flush,
+    /// Re-send the wake-up and active mode commands (see
+    /// [`Sen0177::reinit`]), then read again
+    Reinit,
+    /// Reset the sensor through its RESET pin, then read again
+    ///
+    /// This step is skipped unless a [`HardReset`] is configured.
+    HardReset,
+    /// Stop, returning the error to the caller
+    ///
+    /// The next read starts again from the bottom of the ladder.
+    GiveUp,
+}
+
+/// An event reported while recovering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecoveryEvent {
+    /// A read (or a recovery step) failed, and `step` is being taken
+    Escalated {
+        /// The step being taken
+        step: RecoveryStep,
+        /// The number of consecutive failures so far
+        failures: u32,
+    },
+    /// A read succeeded after `failures` consecutive failures
+    Recovered {
+        /// The number of consecutive failures before the success
+        failures: u32,
+    },
+}
+
+/// The number of consecutive failures handled by each step of the ladder
+///
+/// Each failure climbs one rung: the first [`retries`] failures are
+/// retried, the next [`flushes`] flush, and so on, and the failure after
+/// the last rung gives up.
+///
+/// [`retries`]: EscalationPolicy::retries
+/// [`flushes`]: EscalationPolicy::flushes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EscalationPolicy {
+    retries: u8,
+    flushes: u8,
+    reinits: u8,
+    hard_resets: u8,
+}
+
+impl Default for EscalationPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscalationPolicy {
+    /// Creates the default policy: two retries, then one each of flushing,
+    /// re-initializing, and resetting
+    pub const fn new() -> Self {
+        Self {
+            retries: 2,
+            flushes: 1,
+            reinits: 1,
+            hard_resets: 1,
+        }
+    }
+
+    /// Sets the number of failures that are simply retried
+    pub const fn retries(mut self, count: u8) -> Self {
+        self.retries = count;
+        self
+    }
+
+    /// Sets the number of failures handled by flushing buffered data
+    pub const fn flushes(mut self, count: u8) -> Self {
+        self.flushes = count;
+        self
+    }
+
+    /// Sets the number of failures handled by re-sending mode commands
+    pub const fn reinits(mut self, count: u8) -> Self {
+        self.reinits = count;
+        self
+    }
+
+    /// Sets the number of failures handled by a hardware reset
+    pub const fn hard_resets(mut self, count: u8) -> Self {
+        self.hard_resets = count;
+        self
+    }
+
+    /// Returns the step taken after `failures` consecutive failures
+    /// (counting from one), skipping hardware resets unless
+    /// `can_hard_reset`
+    pub fn step(&self, failures: u32, can_hard_reset: bool) -> RecoveryStep {
+        let hard_resets = if can_hard_reset { self.hard_resets } else { 0 };
+        let ladder = [
+            (RecoveryStep::Retry, self.retries),
+            (RecoveryStep::Flush, self.flushes),
+            (RecoveryStep::Reinit, self.reinits),
+            (RecoveryStep::HardReset, hard_resets),
+        ];
+        let mut rung = 0u32;
+        for (step, count) in ladder {
+            rung += u32::from(count);
+            if failures <= rung {
+                return step;
+            }
+        }
+        RecoveryStep::GiveUp
+    }
+}
+
+/// Resets the sensor in hardware
+pub trait HardReset {
+    /// Resets the sensor, returning `false` if that failed
+    fn hard_reset(&mut self) -> bool;
+
+    /// Returns `true` if a reset is possible at all
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// No hardware reset is configured, so [`RecoveryStep::HardReset`] is
+/// skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoReset;
+
+impl HardReset for NoReset {
+    fn hard_reset(&mut self) -> bool {
+        false
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
+
+/// Resets the sensor by pulsing its (active low) RESET pin for
+/// [`RESET_PULSE_MS`]
+#[derive(Debug)]
+pub struct ResetPin<P, D> {
+    pin: P,
+    delay: D,
+}
+
+impl<P: OutputPin, D: DelayNs> ResetPin<P, D> {
+    /// Creates a reset through `pin`, timed by `delay`
+    ///
+    /// The pin should already be high.
+    pub fn new(pin: P, delay: D) -> Self {
+        Self { pin, delay }
+    }
+
+    /// Returns the pin and delay
+    pub fn release(self) -> (P, D) {
+        (self.pin, self.delay)
+    }
+}
+
+impl<P: OutputPin, D: DelayNs> HardReset for ResetPin<P, D> {
+    fn hard_reset(&mut self) -> bool {
+        if self.pin.set_low().is_err() {
+            return false;
+        }
+        self.delay.delay_ms(RESET_PULSE_MS);
+        self.pin.set_high().is_ok()
+    }
+}
+
+/// An active serial sensor that escalates through recovery steps when
+/// reads fail
+///
+/// See the [module documentation](self).  [`read`](Recovering::read) only
+/// returns an error once the policy gives up, so a single call may take
+/// as long as several failed reads; set a
+/// [timeout](crate::serial::Sen0177Builder::timeout_polls) (or a
+/// [`Watchdog`](crate::watchdog::Watchdog)) so that they fail rather than
+/// blocking forever.
+pub struct Recovering<S, H = NoReset, F = fn(RecoveryEvent)> {
+    sensor: S,
+    policy: EscalationPolicy,
+    reset: H,
+    on_event: Option<F>,
+    failures: u32,
+}
+
+impl<S> Recovering<S> {
+    /// Wraps `sensor`, recovering from failures as set out by `policy`
+    pub fn new(sensor: S, policy: EscalationPolicy) -> Self {
+        Self {
+            sensor,
+            policy,
+            reset: NoReset,
+            on_event: None,
+            failures: 0,
+        }
+    }
+}
+
+impl<S, H, F> Recovering<S, H, F> {
+    /// Sets how the sensor is reset in hardware, e.g. with a [`ResetPin`]
+    pub fn hard_reset<H2: HardReset>(self, reset: H2) -> Recovering<S, H2, F> {
+        Recovering {
+            sensor: self.sensor,
+            policy: self.policy,
+            reset,
+            on_event: self.on_event,
+            failures: self.failures,
+        }
+    }
+
+    /// Sets a callback called with each recovery event, replacing the
+    /// current one
+    pub fn on_event<F2: FnMut(RecoveryEvent)>(self, callback: F2) -> Recovering<S, H, F2> {
+        Recovering {
+            sensor: self.sensor,
+            policy: self.policy,
+            reset: self.reset,
+            on_event: Some(callback),
+            failures: self.failures,
+        }
+    }
+
+    /// Returns the number of consecutive failures so far
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Consumes the wrapper, returning the sensor
+    pub fn release(self) -> S {
+        self.sensor
+    }
+}
+
+impl<R, E, W, H, F> Recovering<Sen0177<R, E, Active, W>, H, F>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+    H: HardReset,
+    F: FnMut(RecoveryEvent),
+{
+    /// Reads a single sensor measurement, escalating through the recovery
+    /// steps until a read succeeds or the policy gives up
+    pub fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        loop {
+            match self.sensor.read_raw() {
+                Ok((_, reading)) => {
+                    if self.failures > 0 {
+                        let failures = self.failures;
+                        self.failures = 0;
+                        self.report(RecoveryEvent::Recovered { failures });
+                    }
+                    return Ok(reading);
+                }
+                Err(error) => self.escalate(error)?,
+            }
+        }
+    }
+
+    /// Takes the next recovery step after `error`, returning the error if
+    /// the policy gives up
+    fn escalate(&mut self, mut error: SensorError<E>) -> Result<(), SensorError<E>> {
+        loop {
+            self.failures = self.failures.saturating_add(1);
+            let step = self.policy.step(self.failures, self.reset.is_available());
+            debug!(
+                "Read failed {} times; escalating to {:?}",
+                self.failures, step
+            );
+            self.report(RecoveryEvent::Escalated {
+                step,
+                failures: self.failures,
+            });
+            let result = match step {
+                RecoveryStep::Retry => Ok(()),
+                RecoveryStep::Flush => self.sensor.flush_stale().map(drop),
+                RecoveryStep::Reinit => self.sensor.reinit(),
+                // A failed reset counts as another failure, keeping the
+                // error that prompted it
+                RecoveryStep::HardReset if !self.reset.hard_reset() => continue,
+                RecoveryStep::HardReset => Ok(()),
+                RecoveryStep::GiveUp => {
+                    self.failures = 0;
+                    return Err(error);
+                }
+            };
+            match result {
+                Ok(()) => return Ok(()),
+                Err(step_error) => error = step_error,
+            }
+        }
+    }
+
+    fn report(&mut self, event: RecoveryEvent) {
+        if let Some(callback) = self.on_event.as_mut() {
+            callback(event);
+        }
+    }
+}
+
+impl<R, E, W, H, F> AirQualitySensor<E> for Recovering<Sen0177<R, E, Active, W>, H, F>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+    H: HardReset,
+    F: FnMut(RecoveryEvent),
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        Recovering::read(self)
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensor.info()
+    }
+}
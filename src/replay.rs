@@ -0,0 +1,245 @@
+//! A [`ReplaySensor`] reads a log of timestamped readings, one per line,
+//! and returns them in order from [`read`](AirQualitySensor::read), so that
+//! analysis and alerting pipelines written against a live sensor can be
+//! rerun against a historical incident.  Logs may be in the CSV format of
+//! the [`csv`](crate::csv) module (with or without its header row) or, with
+//! the `json` feature, JSON lines of `Timestamped<Reading>` as serialized
+//! with the `serde` feature; the format is detected line by line.
+//!
+//! By default, readings are returned as fast as they are read.
+//! [`paced`](ReplaySensor::paced) instead spaces them out as they were
+//! originally taken, optionally sped up.
+//!
+//! ```no_run
+//! use sen0177::{replay::ReplaySensor, AirQualitySensor};
+//! use std::time::Duration;
+//!
+//! // Timestamps in the log are in seconds; replay an hour per minute
+//! let mut sensor = ReplaySensor::open("smoke-2024-09-08.csv")?
+//!     .paced(Duration::from_secs(1))
+//!     .speedup(60);
+//! while let Ok(record) = sensor.read_timestamped() {
+//!     println!("{}: {}µg/m³", record.timestamp, record.value.pm2_5());
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    csv::CsvError, protocol::FRAME_LEN, time::Timestamped, AirQualitySensor, Reading, SensorError,
+    SensorInfo,
+};
+
+/// The information reported by a [`ReplaySensor`]
+pub const INFO: SensorInfo = SensorInfo {
+    name: "Replay",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: true,
+    supports_temperature_humidity: false,
+    frame_len: FRAME_LEN,
+    default_i2c_address: None,
+};
+
+/// Describes errors encountered while replaying a log
+///
+/// These are returned as [`SensorError::ReadError`].
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Every reading in the log has been returned
+    End,
+    /// Reading the log failed
+    Io(io::Error),
+    /// A line (numbered from one) was not a valid CSV row
+    Csv {
+        /// The line number
+        line: usize,
+        /// Why the row was invalid
+        error: CsvError,
+    },
+    /// A line (numbered from one) was not a valid JSON record
+    #[cfg(feature = "json")]
+    Json {
+        /// The line number
+        line: usize,
+        /// Why the record was invalid
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::End => f.write_str("End of log"),
+            ReplayError::Io(_) => f.write_str("Failed to read log"),
+            ReplayError::Csv { line, .. } => write!(f, "Invalid CSV row on line {}", line),
+            #[cfg(feature = "json")]
+            ReplayError::Json { line, .. } => write!(f, "Invalid JSON record on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::End => None,
+            ReplayError::Io(error) => Some(error),
+            ReplayError::Csv { error, .. } => Some(error),
+            #[cfg(feature = "json")]
+            ReplayError::Json { error, .. } => Some(error),
+        }
+    }
+}
+
+/// A sensor that returns the readings from a log
+///
+/// See the [module documentation](self).  Once the log is exhausted, reads
+/// fail with [`ReplayError::End`].  A line that can't be parsed fails that
+/// read, and the next read continues from the following line.
+pub struct ReplaySensor<R> {
+    reader: R,
+    line: usize,
+    buf: String,
+    pacing: Option<Pacing>,
+}
+
+/// The state of paced replay
+struct Pacing {
+    tick: Duration,
+    speedup: u32,
+    /// The first timestamp replayed, and when it was returned
+    start: Option<(u64, Instant)>,
+}
+
+impl ReplaySensor<BufReader<File>> {
+    /// Opens the log at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> ReplaySensor<R> {
+    /// Creates a sensor replaying the log read from `reader`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            buf: String::new(),
+            pacing: None,
+        }
+    }
+
+    /// Spaces readings out as they were originally taken, taking each unit
+    /// of the log's timestamps to last `tick` (e.g. one second for
+    /// timestamps from [`UnixClock`](crate::time::UnixClock))
+    ///
+    /// Each read sleeps until its reading is due, measured from when the
+    /// first reading was returned.  Timestamps that go backwards are
+    /// returned without waiting.
+    pub fn paced(mut self, tick: Duration) -> Self {
+        self.pacing = Some(Pacing {
+            tick,
+            speedup: self.pacing.as_ref().map_or(1, |pacing| pacing.speedup),
+            start: None,
+        });
+        self
+    }
+
+    /// Divides the time between paced readings by `factor`, so that an
+    /// incident can be replayed faster than it happened
+    ///
+    /// This has no effect unless [`paced`](ReplaySensor::paced) is set.
+    pub fn speedup(mut self, factor: u32) -> Self {
+        if let Some(pacing) = self.pacing.as_mut() {
+            pacing.speedup = factor.max(1);
+        }
+        self
+    }
+
+    /// Returns the number of lines read from the log so far
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Consumes the sensor, returning the reader
+    pub fn release(self) -> R {
+        self.reader
+    }
+
+    /// Returns the next reading in the log, with its original timestamp
+    pub fn read_timestamped(&mut self) -> Result<Timestamped<Reading>, SensorError<ReplayError>> {
+        let record = self.next_record().map_err(SensorError::ReadError)?;
+        if let Some(pacing) = self.pacing.as_mut() {
+            pacing.wait_for(record.timestamp);
+        }
+        Ok(record)
+    }
+
+    fn next_record(&mut self) -> Result<Timestamped<Reading>, ReplayError> {
+        loop {
+            self.buf.clear();
+            if self
+                .reader
+                .read_line(&mut self.buf)
+                .map_err(ReplayError::Io)?
+                == 0
+            {
+                return Err(ReplayError::End);
+            }
+            self.line += 1;
+            let line = self.buf.trim();
+            if line.is_empty() || line.starts_with("timestamp,") {
+                continue;
+            }
+            #[cfg(feature = "json")]
+            if line.starts_with('{') {
+                return serde_json::from_str(line).map_err(|error| ReplayError::Json {
+                    line: self.line,
+                    error,
+                });
+            }
+            return Timestamped::from_csv_row(line).map_err(|error| ReplayError::Csv {
+                line: self.line,
+                error,
+            });
+        }
+    }
+}
+
+impl Pacing {
+    /// Sleeps until the reading taken at `timestamp` is due
+    fn wait_for(&mut self, timestamp: u64) {
+        let Some((first, started)) = self.start else {
+            self.start = Some((timestamp, Instant::now()));
+            return;
+        };
+        let Some(ticks) = timestamp.checked_sub(first) else {
+            return;
+        };
+        let offset = self.tick.as_nanos() * u128::from(ticks) / u128::from(self.speedup);
+        let offset = Duration::from_nanos(u64::try_from(offset).unwrap_or(u64::MAX));
+        if let Some(remaining) = started
+            .checked_add(offset)
+            .and_then(|due| due.checked_duration_since(Instant::now()))
+        {
+            thread::sleep(remaining);
+        }
+    }
+}
+
+impl<R: BufRead> AirQualitySensor<ReplayError> for ReplaySensor<R> {
+    fn read(&mut self) -> Result<Reading, SensorError<ReplayError>> {
+        self.read_timestamped().map(|record| record.value)
+    }
+
+    fn info(&self) -> SensorInfo {
+        INFO
+    }
+}
@@ -0,0 +1,227 @@
+//! Rolling 24-hour and annual averages, with data completeness, for
+//! comparison against regulatory limits.
+//!
+//! PM limits such as those in [`guidelines`](crate::guidelines) are
+//! evaluated over long periods: a 24-hour mean of hourly means, and an
+//! annual mean of daily means, each counted only if enough of the expected
+//! samples were actually collected.  Keeping every reading for a year is
+//! out of the question on a microcontroller, so a [`RollingAverage`]
+//! downsamples as it goes, keeping only a running sum and count for each
+//! of its `B` bins (an hour, or a day), and forgetting bins as they fall
+//! out of the window.  Its memory use is fixed at about 24 bytes per bin.
+//!
+//! ```
+//! use sen0177::{
+//!     guidelines::{AveragingPeriod, GuidelineSet},
+//!     rolling::DailyAverage,
+//!     Reading,
+//! };
+//!
+//! # let readings = [(0, Reading::default())];
+//! // Timestamps in seconds, with a reading expected every minute
+//! let mut daily = DailyAverage::daily(60);
+//! for (timestamp, reading) in readings {
+//!     daily.push(timestamp, reading);
+//! }
+//! // The US EPA requires 75% of the data for a valid 24-hour mean
+//! if daily.completeness() >= 75 {
+//!     if let Some(averages) = daily.averages() {
+//!         for exceedance in GuidelineSet::us_epa().check(AveragingPeriod::Daily, averages) {
+//!             println!("{} limit exceeded by {}", exceedance.limit.value, exceedance.excess());
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{guidelines::Averages, Reading};
+
+/// The number of seconds in an hour, the bin length of a [`DailyAverage`]
+/// with timestamps in seconds
+pub const SECONDS_PER_HOUR: u64 = 3600;
+
+/// The number of seconds in a day, the bin length of an [`AnnualAverage`]
+/// with timestamps in seconds
+pub const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+
+/// A rolling 24-hour average over hourly bins
+pub type DailyAverage = RollingAverage<24>;
+
+/// A rolling annual average over daily bins
+pub type AnnualAverage = RollingAverage<365>;
+
+/// The accumulated readings of one bin
+#[derive(Debug, Clone, Copy, Default)]
+struct Bin {
+    pm2_5: u64,
+    pm10: u64,
+    count: u32,
+}
+
+impl Bin {
+    /// Returns the bin's mean of `sum`, in tenths of a unit (rounded to the
+    /// nearest tenth)
+    fn mean(&self, sum: u64) -> Option<u64> {
+        let count = u64::from(self.count);
+        (count > 0).then(|| (sum * 10 + count / 2) / count)
+    }
+}
+
+/// A rolling average of PM2.5 and PM10 over the last `B` bins, each
+/// spanning a fixed length of time
+///
+/// Timestamps are plain `u64` values in whatever units the caller chooses,
+/// as in [`History`](crate::history::History), and bins are aligned to
+/// multiples of the bin length.  The window is the `B` most recent bins,
+/// including the one currently being filled.  Readings should be pushed in
+/// non-decreasing timestamp order; readings older than the window are
+/// ignored.
+///
+/// The average is the mean of the bins' means, over the bins holding any
+/// readings, so that each hour (or day) carries equal weight however many
+/// readings it holds, as in regulatory averaging.
+#[derive(Debug, Clone)]
+pub struct RollingAverage<const B: usize> {
+    bins: [Bin; B],
+    bin_len: u64,
+    expected_per_bin: u32,
+    /// The number (timestamp divided by bin length) of the newest bin, if
+    /// any reading has been pushed
+    current: Option<u64>,
+}
+
+impl DailyAverage {
+    /// Creates a rolling 24-hour average over timestamps in seconds,
+    /// expecting a reading every `interval_secs` seconds
+    pub fn daily(interval_secs: u32) -> Self {
+        Self::new(SECONDS_PER_HOUR, expected(SECONDS_PER_HOUR, interval_secs))
+    }
+}
+
+impl AnnualAverage {
+    /// Creates a rolling annual average over timestamps in seconds,
+    /// expecting a reading every `interval_secs` seconds
+    pub fn annual(interval_secs: u32) -> Self {
+        Self::new(SECONDS_PER_DAY, expected(SECONDS_PER_DAY, interval_secs))
+    }
+}
+
+fn expected(bin_len: u64, interval: u32) -> u32 {
+    (bin_len / u64::from(interval.max(1)))
+        .try_into()
+        .unwrap_or(u32::MAX)
+}
+
+impl<const B: usize> RollingAverage<B> {
+    /// Creates a new, empty rolling average with bins `bin_len` timestamp
+    /// units long, each expected to hold `expected_per_bin` readings
+    pub fn new(bin_len: u64, expected_per_bin: u32) -> Self {
+        Self {
+            bins: [Bin::default(); B],
+            bin_len: bin_len.max(1),
+            expected_per_bin,
+            current: None,
+        }
+    }
+
+    /// Adds a reading taken at `timestamp`, moving the window forward if it
+    /// falls after the current bin
+    pub fn push(&mut self, timestamp: u64, reading: Reading) {
+        if B == 0 {
+            return;
+        }
+        let number = timestamp / self.bin_len;
+        let current = *self.current.get_or_insert(number);
+        if number > current {
+            // Clear the bins skipped over, which no readings fell into, and
+            // the one being reused; a long gap clears the whole window
+            let advance = (number - current).min(B as u64);
+            for skipped in (number - advance + 1)..=number {
+                self.bins[(skipped % B as u64) as usize] = Bin::default();
+            }
+            self.current = Some(number);
+        } else if current - number >= B as u64 {
+            return;
+        }
+        let bin = &mut self.bins[(number % B as u64) as usize];
+        bin.pm2_5 += u64::from(reading.pm2_5());
+        bin.pm10 += u64::from(reading.pm10());
+        bin.count = bin.count.saturating_add(1);
+    }
+
+    /// Removes all readings
+    pub fn clear(&mut self) {
+        self.bins = [Bin::default(); B];
+        self.current = None;
+    }
+
+    /// Returns the number of readings in the window
+    pub fn count(&self) -> u32 {
+        self.bins
+            .iter()
+            .fold(0u32, |count, bin| count.saturating_add(bin.count))
+    }
+
+    /// Returns the number of bins in the window holding any readings
+    pub fn bins_with_data(&self) -> usize {
+        self.bins.iter().filter(|bin| bin.count > 0).count()
+    }
+
+    /// Returns the percentage (0 to 100) of the readings expected over the
+    /// whole window that are present
+    ///
+    /// Readings beyond the number expected in a bin don't count towards
+    /// completeness, so extra readings in one hour can't make up for
+    /// missing ones in another.
+    pub fn completeness(&self) -> u8 {
+        let expected = u64::from(self.expected_per_bin) * B as u64;
+        if expected == 0 {
+            return 0;
+        }
+        let present: u64 = self
+            .bins
+            .iter()
+            .map(|bin| u64::from(bin.count.min(self.expected_per_bin)))
+            .sum();
+        (present * 100 / expected) as u8
+    }
+
+    /// Iterates over the mean standard (CF=1) PM2.5 and PM10
+    /// concentrations of each bin, in tenths of a µg/m³, from the newest
+    /// (the one currently being filled) to the oldest
+    ///
+    /// Bins without readings yield `None`.
+    pub fn bin_averages(&self) -> impl Iterator<Item = Option<Averages>> + '_ {
+        (0..B as u64).map(move |age| {
+            let number = self.current?.checked_sub(age)?;
+            let bin = &self.bins[(number % B as u64) as usize];
+            Some(Averages {
+                pm2_5: bin.mean(bin.pm2_5)? as u32,
+                pm10: bin.mean(bin.pm10)? as u32,
+            })
+        })
+    }
+
+    /// Returns the mean standard (CF=1) PM2.5 and PM10 concentrations over
+    /// the window, in tenths of a µg/m³, for checking against
+    /// [`GuidelineSet`](crate::guidelines::GuidelineSet)s
+    ///
+    /// Returns `None` if the window holds no readings.  Check
+    /// [`completeness`](RollingAverage::completeness) before relying on
+    /// the result.
+    pub fn averages(&self) -> Option<Averages> {
+        let (pm2_5, pm10, bins) = self
+            .bins
+            .iter()
+            .filter_map(|bin| Some((bin.mean(bin.pm2_5)?, bin.mean(bin.pm10)?)))
+            .fold(
+                (0u64, 0u64, 0u64),
+                |(pm2_5, pm10, bins), (bin_pm2_5, bin_pm10)| {
+                    (pm2_5 + bin_pm2_5, pm10 + bin_pm10, bins + 1)
+                },
+            );
+        (bins > 0).then(|| Averages {
+            pm2_5: ((pm2_5 + bins / 2) / bins) as u32,
+            pm10: ((pm10 + bins / 2) / bins) as u32,
+        })
+    }
+}
@@ -0,0 +1,296 @@
+//! Reaches the sensor through an SC16IS752 (or single-channel SC16IS750)
+//! I2C/SPI-to-UART bridge.
+//!
+//! Boards without a free UART can hang the sensor off one of the bridge's
+//! channels.  [`Sc16is752`] configures the channel for the sensor (9600
+//! baud, 8N1, FIFOs enabled) and then drains the bridge's 64-byte receive
+//! FIFO over the bus, exposing the byte stream as a UART that the
+//! [serial driver](crate::serial::Sen0177) reads from as usual.  The FIFO
+//! holds about 65ms of data, so it must be drained at least that often to
+//! avoid overruns, which are reported as [`BridgeError::Overrun`].
+//!
+//! To use both channels, share the bus between two instances (e.g. with
+//! `embedded-hal-bus`).
+//!
+//! ```no_run
+//! # fn example<I: embedded_hal::i2c::I2c>(i2c: I) -> Result<(), I::Error> {
+//! use sen0177::{
+//!     sc16is752::{Channel, Sc16is752, XTAL_14_7456_MHZ},
+//!     serial::Sen0177,
+//! };
+//!
+//! // A1 and A0 tied to VDD
+//! let bridge = Sc16is752::new_i2c(i2c, 0x48, Channel::A, XTAL_14_7456_MHZ)?;
+//! let mut sensor = Sen0177::new(bridge);
+//! # Ok(())
+//! # }
+//! ```
+
+use core::fmt;
+use embedded_hal::{
+    i2c::I2c,
+    spi::{Operation, SpiDevice},
+};
+use embedded_hal_nb::{
+    nb,
+    serial::{Error as SerialError, ErrorKind, ErrorType, Read, Write},
+};
+
+/// The crystal frequency of most SC16IS752 breakout boards
+pub const XTAL_14_7456_MHZ: u32 = 14_745_600;
+
+/// The baud rate the sensor communicates at
+const BAUD_RATE: u32 = 9600;
+
+/// The size of each of the bridge's FIFOs
+const FIFO_LEN: usize = 64;
+
+// Register addresses (the general register set, with LCR[7] clear)
+const RHR: u8 = 0x00;
+const THR: u8 = 0x00;
+const FCR: u8 = 0x02;
+const LCR: u8 = 0x03;
+const LSR: u8 = 0x05;
+const TXLVL: u8 = 0x08;
+const RXLVL: u8 = 0x09;
+// The divisor latch, in place of RHR/THR and IER while LCR[7] is set
+const DLL: u8 = 0x00;
+const DLH: u8 = 0x01;
+
+/// LCR: enable access to the divisor latch
+const LCR_DIVISOR_LATCH: u8 = 0x80;
+/// LCR: 8 data bits, no parity, 1 stop bit
+const LCR_8N1: u8 = 0x03;
+/// FCR: enable the FIFOs, and reset both of them
+const FCR_ENABLE_AND_RESET: u8 = 0x07;
+/// LSR: receive overrun
+const LSR_OVERRUN: u8 = 0x02;
+/// LSR: the transmit FIFO and shift register are both empty
+const LSR_TX_EMPTY: u8 = 0x40;
+
+/// One of the bridge's two UART channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Channel A (the only channel of the SC16IS750)
+    A,
+    /// Channel B
+    B,
+}
+
+/// Describes errors returned by the bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeError<E> {
+    /// The bridge's receive FIFO overflowed, so bytes were lost
+    ///
+    /// Drain the FIFO more often.
+    Overrun,
+    /// Error from the I2C or SPI bus
+    Bus(E),
+}
+
+impl<E: fmt::Debug> fmt::Display for BridgeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::Overrun => f.write_str("Bridge receive FIFO overrun"),
+            BridgeError::Bus(error) => write!(f, "Bridge bus error: {:?}", error),
+        }
+    }
+}
+
+impl<E: fmt::Debug> SerialError for BridgeError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            BridgeError::Overrun => ErrorKind::Overrun,
+            BridgeError::Bus(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Register access to the bridge over a particular bus
+pub trait Registers {
+    /// The bus's error type
+    type Error: fmt::Debug;
+
+    /// Reads `buf.len()` bytes from register `reg` of `channel`
+    ///
+    /// Reading more than one byte from RHR reads successive bytes from the
+    /// receive FIFO.
+    fn read(&mut self, channel: Channel, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `value` to register `reg` of `channel`
+    fn write(&mut self, channel: Channel, reg: u8, value: u8) -> Result<(), Self::Error>;
+}
+
+/// Builds the sub-address byte selecting register `reg` of `channel`
+fn sub_address(channel: Channel, reg: u8) -> u8 {
+    let channel = match channel {
+        Channel::A => 0,
+        Channel::B => 1,
+    };
+    (reg << 3) | (channel << 1)
+}
+
+/// The bridge connected via I2C, at 7-bit address `address`
+pub struct I2cRegisters<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C: I2c> Registers for I2cRegisters<I2C> {
+    type Error = I2C::Error;
+
+    fn read(&mut self, channel: Channel, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c
+            .write_read(self.address, &[sub_address(channel, reg)], buf)
+    }
+
+    fn write(&mut self, channel: Channel, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.i2c
+            .write(self.address, &[sub_address(channel, reg), value])
+    }
+}
+
+/// The bridge connected via SPI
+pub struct SpiRegisters<SPI> {
+    spi: SPI,
+}
+
+impl<SPI: SpiDevice> Registers for SpiRegisters<SPI> {
+    type Error = SPI::Error;
+
+    fn read(&mut self, channel: Channel, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        // The top bit of the sub-address selects a read over SPI
+        let address = [0x80 | sub_address(channel, reg)];
+        self.spi
+            .transaction(&mut [Operation::Write(&address), Operation::Read(buf)])
+    }
+
+    fn write(&mut self, channel: Channel, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.spi.write(&[sub_address(channel, reg), value])
+    }
+}
+
+/// One channel of an SC16IS752 bridge, used as the sensor's UART
+///
+/// See the [module documentation](self).  Received bytes are read from the
+/// bridge's FIFO in bursts and buffered, so most reads don't touch the bus.
+pub struct Sc16is752<B> {
+    registers: B,
+    channel: Channel,
+    buf: [u8; FIFO_LEN],
+    pos: usize,
+    len: usize,
+}
+
+impl<I2C: I2c> Sc16is752<I2cRegisters<I2C>> {
+    /// Configures `channel` of the bridge at I2C address `address`, clocked
+    /// by a crystal of `xtal_hz`, for the sensor
+    pub fn new_i2c(
+        i2c: I2C,
+        address: u8,
+        channel: Channel,
+        xtal_hz: u32,
+    ) -> Result<Self, I2C::Error> {
+        Self::new(I2cRegisters { i2c, address }, channel, xtal_hz)
+    }
+
+    /// Consumes the adapter, returning the I2C bus
+    pub fn release(self) -> I2C {
+        self.registers.i2c
+    }
+}
+
+impl<SPI: SpiDevice> Sc16is752<SpiRegisters<SPI>> {
+    /// Configures `channel` of the bridge on SPI device `spi`, clocked by a
+    /// crystal of `xtal_hz`, for the sensor
+    pub fn new_spi(spi: SPI, channel: Channel, xtal_hz: u32) -> Result<Self, SPI::Error> {
+        Self::new(SpiRegisters { spi }, channel, xtal_hz)
+    }
+
+    /// Consumes the adapter, returning the SPI device
+    pub fn release(self) -> SPI {
+        self.registers.spi
+    }
+}
+
+impl<B: Registers> Sc16is752<B> {
+    /// Configures `channel` of the bridge behind `registers`, clocked by a
+    /// crystal of `xtal_hz`, for the sensor
+    pub fn new(mut registers: B, channel: Channel, xtal_hz: u32) -> Result<Self, B::Error> {
+        let divisor = (xtal_hz + 8 * BAUD_RATE) / (16 * BAUD_RATE);
+        let [_, _, hi, lo] = divisor.to_be_bytes();
+        registers.write(channel, LCR, LCR_DIVISOR_LATCH)?;
+        registers.write(channel, DLL, lo)?;
+        registers.write(channel, DLH, hi)?;
+        registers.write(channel, LCR, LCR_8N1)?;
+        registers.write(channel, FCR, FCR_ENABLE_AND_RESET)?;
+        Ok(Self {
+            registers,
+            channel,
+            buf: [0; FIFO_LEN],
+            pos: 0,
+            len: 0,
+        })
+    }
+
+    fn read_register(&mut self, reg: u8) -> Result<u8, BridgeError<B::Error>> {
+        let mut value = [0];
+        self.registers
+            .read(self.channel, reg, &mut value)
+            .map_err(BridgeError::Bus)?;
+        Ok(value[0])
+    }
+
+    /// Refills the buffer from the receive FIFO, returning the number of
+    /// bytes read
+    fn fill(&mut self) -> Result<usize, BridgeError<B::Error>> {
+        // Reading LSR clears the overrun flag
+        if self.read_register(LSR)? & LSR_OVERRUN != 0 {
+            return Err(BridgeError::Overrun);
+        }
+        let available = usize::from(self.read_register(RXLVL)?).min(FIFO_LEN);
+        if available > 0 {
+            let (burst, _) = self.buf.split_at_mut(available);
+            self.registers
+                .read(self.channel, RHR, burst)
+                .map_err(BridgeError::Bus)?;
+        }
+        self.pos = 0;
+        self.len = available;
+        Ok(available)
+    }
+}
+
+impl<B: Registers> ErrorType for Sc16is752<B> {
+    type Error = BridgeError<B::Error>;
+}
+
+impl<B: Registers> Read<u8> for Sc16is752<B> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        if self.pos >= self.len && self.fill()? == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let byte = self.buf.get(self.pos).copied().unwrap_or_default();
+        self.pos += 1;
+        Ok(byte)
+    }
+}
+
+impl<B: Registers> Write<u8> for Sc16is752<B> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if self.read_register(TXLVL)? == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.registers
+            .write(self.channel, THR, word)
+            .map_err(BridgeError::Bus)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if self.read_register(LSR)? & LSR_TX_EMPTY == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! JSON Schemas describing readings as serialized with the `serde` feature,
+//! for use as a machine-readable contract by services that consume them.
+//!
+//! The schemas are versioned by [`SCHEMA_VERSION`], which is incremented
+//! whenever the serialized form changes; the version is embedded in each
+//! schema's `$id`.
+
+use std::fmt::Write;
+
+/// The version of the serialized form described by the schemas
+pub const SCHEMA_VERSION: u32 = 1;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A serialized field: its name, description, unit, and maximum value
+const READING_FIELDS: [(&str, &str, Option<&str>, u32); 14] = [
+    (
+        "pm1",
+        "Standard (CF=1) PM1 concentration",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "pm2_5",
+        "Standard (CF=1) PM2.5 concentration",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "pm10",
+        "Standard (CF=1) PM10 concentration",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "env_pm1",
+        "Environmental (atmospheric) PM1 concentration; 65535 if the sensor doesn't report them",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "env_pm2_5",
+        "Environmental (atmospheric) PM2.5 concentration; 65535 if the sensor doesn't report them",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "env_pm10",
+        "Environmental (atmospheric) PM10 concentration; 65535 if the sensor doesn't report them",
+        Some("µg/m³"),
+        65535,
+    ),
+    (
+        "particles_0_3",
+        "Count of particles beyond 0.3µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "particles_0_5",
+        "Count of particles beyond 0.5µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "particles_1",
+        "Count of particles beyond 1µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "particles_2_5",
+        "Count of particles beyond 2.5µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "particles_5",
+        "Count of particles beyond 5µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "particles_10",
+        "Count of particles beyond 10µm in diameter",
+        Some("particles/0.1L"),
+        65535,
+    ),
+    (
+        "firmware_version",
+        "Firmware version reported by the sensor",
+        None,
+        255,
+    ),
+    (
+        "device_error_code",
+        "Error code reported by the sensor; zero if none",
+        None,
+        255,
+    ),
+];
+
+/// Returns a JSON Schema (draft 2020-12) for a serialized
+/// [`Reading`](crate::Reading)
+///
+/// Each property carries a `description` and, where applicable, its unit in
+/// a non-standard `unit` keyword.
+pub fn reading_json_schema() -> String {
+    let mut schema = String::new();
+    write_reading_schema(&mut schema, true);
+    schema
+}
+
+/// Returns a JSON Schema (draft 2020-12) for a serialized
+/// [`Timestamped`](crate::time::Timestamped) reading with a `u64`
+/// timestamp
+pub fn timestamped_reading_json_schema() -> String {
+    let mut schema = String::new();
+    let _ = write!(
+        schema,
+        "{{\n  \"$schema\": \"{}\",\n  \"$id\": \"urn:sen0177:timestamped-reading:v{}\",\n  \
+         \"title\": \"Timestamped air quality reading\",\n  \"type\": \"object\",\n  \
+         \"properties\": {{\n    \"timestamp\": {{\n      \"description\": \
+         \"Time at which the reading was taken, in the units of the clock that stamped it\",\n      \
+         \"type\": \"integer\",\n      \"minimum\": 0\n    }},\n    \"value\": ",
+        DIALECT, SCHEMA_VERSION
+    );
+    write_reading_schema(&mut schema, false);
+    schema.push_str(
+        "\n  },\n  \"required\": [\"timestamp\", \"value\"],\n  \"additionalProperties\": false\n}",
+    );
+    schema
+}
+
+fn write_reading_schema(schema: &mut String, root: bool) {
+    let indent = if root { "" } else { "    " };
+    schema.push_str("{\n");
+    if root {
+        let _ = writeln!(schema, "  \"$schema\": \"{}\",", DIALECT);
+        let _ = writeln!(
+            schema,
+            "  \"$id\": \"urn:sen0177:reading:v{}\",",
+            SCHEMA_VERSION
+        );
+    }
+    let _ = write!(
+        schema,
+        "{0}  \"title\": \"Air quality reading\",\n{0}  \"type\": \"object\",\n{0}  \"properties\": {{\n",
+        indent
+    );
+    for (index, (name, description, unit, maximum)) in READING_FIELDS.iter().enumerate() {
+        let _ = write!(
+            schema,
+            "{0}    \"{1}\": {{\n{0}      \"description\": \"{2}\",\n",
+            indent, name, description
+        );
+        if let Some(unit) = unit {
+            let _ = writeln!(schema, "{}      \"unit\": \"{}\",", indent, unit);
+        }
+        let _ = write!(
+            schema,
+            "{0}      \"type\": \"integer\",\n{0}      \"minimum\": 0,\n{0}      \"maximum\": {1}\n{0}    }}{2}\n",
+            indent,
+            maximum,
+            if index + 1 < READING_FIELDS.len() { "," } else { "" }
+        );
+    }
+    let _ = write!(schema, "{}  }},\n{}  \"required\": [", indent, indent);
+    for (index, (name, ..)) in READING_FIELDS.iter().enumerate() {
+        let separator = if index > 0 { ", " } else { "" };
+        let _ = write!(schema, "{}\"{}\"", separator, name);
+    }
+    let _ = write!(
+        schema,
+        "],\n{0}  \"additionalProperties\": false\n{0}}}",
+        indent
+    );
+}
@@ -0,0 +1,127 @@
+//! Appends readings as CSV rows to files on an SD card (or any other
+//! FAT-formatted block device) through `embedded-sdmmc`, without needing an
+//! allocator.
+//!
+//! [`CsvWriter`] starts a new file for each day, named `YYYYMMDD.CSV` after
+//! the reading's timestamp, and writes the [`csv::HEADER`](crate::csv::HEADER)
+//! row when it creates a file.  Timestamps must be seconds since the Unix
+//! epoch (see [`UnixClock`](crate::time::UnixClock) or use an RTC).  Each
+//! row is flushed as it is written, so at most the row being written is
+//! lost if power fails.
+
+use core::fmt::Write;
+use embedded_sdmmc::{BlockDevice, Error, Mode, RawDirectory, RawFile, TimeSource, VolumeManager};
+
+use crate::{
+    csv::{Row, StrBuf, HEADER},
+    time::Timestamped,
+    Reading,
+};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Writes readings as CSV rows to a file per day
+///
+/// The generic parameters mirror those of the [`VolumeManager`]; the
+/// writer keeps at most one file open at a time.
+pub struct CsvWriter<
+    'a,
+    D,
+    T,
+    const MAX_DIRS: usize = 4,
+    const MAX_FILES: usize = 4,
+    const MAX_VOLUMES: usize = 1,
+> where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    volume_mgr: &'a VolumeManager<D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+    directory: RawDirectory,
+    file: Option<(u64, RawFile)>,
+}
+
+impl<'a, D, T, const MAX_DIRS: usize, const MAX_FILES: usize, const MAX_VOLUMES: usize>
+    CsvWriter<'a, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    /// Creates a writer that will create its files in `directory`, an open
+    /// directory on a volume managed by `volume_mgr`
+    pub fn new(
+        volume_mgr: &'a VolumeManager<D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+        directory: RawDirectory,
+    ) -> Self {
+        Self {
+            volume_mgr,
+            directory,
+            file: None,
+        }
+    }
+
+    /// Appends `record` to the file for the day of its timestamp, starting a
+    /// new file if necessary
+    pub fn append(&mut self, record: &Timestamped<Reading>) -> Result<(), Error<D::Error>> {
+        let day = record.timestamp / SECONDS_PER_DAY;
+        let file = match self.file {
+            Some((file_day, file)) if file_day == day => file,
+            _ => {
+                if let Some((_, file)) = self.file.take() {
+                    self.volume_mgr.close_file(file)?;
+                }
+                let file = self.volume_mgr.open_file_in_dir(
+                    self.directory,
+                    file_name(day).as_str(),
+                    Mode::ReadWriteCreateOrAppend,
+                )?;
+                self.file = Some((day, file));
+                if self.volume_mgr.file_length(file)? == 0 {
+                    self.volume_mgr.write(file, HEADER.as_bytes())?;
+                }
+                file
+            }
+        };
+        self.volume_mgr
+            .write(file, Row::new(record).as_str().as_bytes())?;
+        self.volume_mgr.flush_file(file)
+    }
+
+    /// Closes the current file, returning the directory the writer was
+    /// created with
+    pub fn close(mut self) -> Result<RawDirectory, Error<D::Error>> {
+        if let Some((_, file)) = self.file.take() {
+            self.volume_mgr.close_file(file)?;
+        }
+        Ok(self.directory)
+    }
+}
+
+/// Returns the 8.3 file name for the day `day` days after the Unix epoch
+fn file_name(day: u64) -> StrBuf<12> {
+    let (year, month, day) = civil_from_days(day);
+    let mut name = StrBuf::new();
+    let _ = write!(name, "{:04}{:02}{:02}.CSV", year % 10_000, month, day);
+    name
+}
+
+/// Converts a count of days since the Unix epoch into a (year, month, day)
+/// date in the proleptic Gregorian calendar
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,168 @@
+//! Encodes readings as SenML packs ([RFC 8428]), for IoT platforms that
+//! ingest sensor data in that format.
+//!
+//! A [`Pack`] implements `serde::Serialize` as a SenML JSON pack, so it can
+//! be encoded without an allocator using e.g. `serde-json-core`, or with
+//! `serde_json` where `std` is available.  With the `senml-cbor` feature, it
+//! also implements `minicbor::Encode` as a SenML CBOR pack, using the
+//! integer labels that RFC 8428 defines for CBOR.
+//!
+//! The pack holds one record per reading value, leaving out the
+//! environmental concentrations if the reading is marked as [without
+//! them](Reading::without_atmospheric).  The first record carries
+//! the base name (conventionally a device URN ending in `:`) and, if set,
+//! the base time; each record's name is appended to the base name.
+//! Concentrations use the unit `ug/m3`, and particle counts `/dL` (counts per
+//! 0.1L, which is not a registered SenML unit).
+//!
+//! [RFC 8428]: https://www.rfc-editor.org/rfc/rfc8428
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{time::Timestamped, Reading};
+
+/// The SenML unit for mass concentrations
+pub const CONCENTRATION_UNIT: &str = "ug/m3";
+
+/// The unit used for particle counts
+pub const COUNT_UNIT: &str = "/dL";
+
+/// The most records in a pack
+pub const RECORDS: usize = 12;
+
+/// A SenML pack holding a single reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pack<'a> {
+    base_name: &'a str,
+    base_time: Option<u64>,
+    reading: Reading,
+}
+
+impl<'a> Pack<'a> {
+    /// Creates a pack for `reading`, with base name `base_name` and no base
+    /// time
+    ///
+    /// Without a time, consumers will take the reading to have been made
+    /// when they received it.
+    pub fn new(base_name: &'a str, reading: &Reading) -> Self {
+        Self {
+            base_name,
+            base_time: None,
+            reading: *reading,
+        }
+    }
+
+    /// Creates a pack for a reading stamped with the number of seconds since
+    /// the Unix epoch, which is used as the base time
+    pub fn timestamped(base_name: &'a str, reading: &Timestamped<Reading>) -> Self {
+        Self {
+            base_name,
+            base_time: Some(reading.timestamp),
+            reading: reading.value,
+        }
+    }
+
+    /// Returns the name, unit, and value of each record, in order
+    ///
+    /// Readings [without environmental
+    /// concentrations](Reading::without_atmospheric) have no records for
+    /// them.
+    pub fn records(&self) -> impl ExactSizeIterator<Item = (&'static str, &'static str, u16)> {
+        self.reading.fields().map(|(field, name, value, _)| {
+            let unit = if field.is_count() {
+                COUNT_UNIT
+            } else {
+                CONCENTRATION_UNIT
+            };
+            (name, unit, value)
+        })
+    }
+}
+
+/// A single record of a pack, which carries the base fields if it's first
+struct Record<'a> {
+    base: Option<(&'a str, Option<u64>)>,
+    name: &'static str,
+    unit: &'static str,
+    value: u16,
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let base_fields = match self.base {
+            Some((_, Some(_))) => 2,
+            Some((_, None)) => 1,
+            None => 0,
+        };
+        let mut map = serializer.serialize_map(Some(base_fields + 3))?;
+        if let Some((base_name, base_time)) = self.base {
+            map.serialize_entry("bn", base_name)?;
+            if let Some(base_time) = base_time {
+                map.serialize_entry("bt", &base_time)?;
+            }
+        }
+        map.serialize_entry("n", self.name)?;
+        map.serialize_entry("u", self.unit)?;
+        map.serialize_entry("v", &self.value)?;
+        map.end()
+    }
+}
+
+impl Serialize for Pack<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.records().len()))?;
+        for (index, (name, unit, value)) in self.records().enumerate() {
+            seq.serialize_element(&Record {
+                base: (index == 0).then_some((self.base_name, self.base_time)),
+                name,
+                unit,
+                value,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "senml-cbor")]
+mod cbor {
+    use minicbor::{
+        encode::{Error, Write},
+        Encode, Encoder,
+    };
+
+    use super::Pack;
+
+    const BASE_NAME: i8 = -2;
+    const BASE_TIME: i8 = -3;
+    const NAME: i8 = 0;
+    const UNIT: i8 = 1;
+    const VALUE: i8 = 2;
+
+    impl<C> Encode<C> for Pack<'_> {
+        fn encode<W: Write>(
+            &self,
+            e: &mut Encoder<W>,
+            _ctx: &mut C,
+        ) -> Result<(), Error<W::Error>> {
+            e.array(self.records().len() as u64)?;
+            for (index, (name, unit, value)) in self.records().enumerate() {
+                if index == 0 {
+                    let base_fields = if self.base_time.is_some() { 2 } else { 1 };
+                    e.map(base_fields + 3)?.i8(BASE_NAME)?.str(self.base_name)?;
+                    if let Some(base_time) = self.base_time {
+                        e.i8(BASE_TIME)?.u64(base_time)?;
+                    }
+                } else {
+                    e.map(3)?;
+                }
+                e.i8(NAME)?
+                    .str(name)?
+                    .i8(UNIT)?
+                    .str(unit)?
+                    .i8(VALUE)?
+                    .u16(value)?;
+            }
+            Ok(())
+        }
+    }
+}
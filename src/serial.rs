@@ -1,66 +1,965 @@
-use crate::{read::*, AirQualitySensor, Reading, SensorError};
+use crate::{
+    decoder::{FrameDecoder, FrameResult},
+    env::EnvDetector,
+    health::HealthReport,
+    logging::debug,
+    read::*,
+    time::Clock,
+    AirQualitySensor, ErrorType, Reading, SensorError, SensorInfo,
+};
+use core::marker::PhantomData;
+use embedded_hal::delay::DelayNs;
 use embedded_hal_nb::{
-    nb::block,
-    serial::{Error as SerialError, Read},
+    nb,
+    serial::{Error as SerialError, Read, Write},
+};
+#[cfg(feature = "async")]
+use embedded_io_async::Read as AsyncRead;
+use sen0177_protocol::{encode_command, parse_frame, Command, FrameProtocol, Plantower};
+
+const INFO: SensorInfo = SensorInfo {
+    name: "SEN0177",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: true,
+    supports_temperature_humidity: false,
+    frame_len: FRAME_LEN,
+    default_i2c_address: None,
 };
 
+/// The number of bytes per second the sensor sends at most, at 9600 baud
+/// with 8N1 framing (ten bits per byte)
+pub const BYTES_PER_SECOND: u32 = 960;
+
+/// The default [resync budget](Sen0177Builder::resync_budget), in bytes
+///
+/// This is the size of the Linux tty receive buffer, so that a port opened
+/// with a full buffer of garbage (about four seconds' worth) can still be
+/// synchronized in a single read.
+pub const DEFAULT_RESYNC_BUDGET: u32 = 4096;
+
+/// The default number of [sync attempts](Sen0177Builder::sync_attempts)
+///
+/// Random garbage contains a byte matching the start of a frame header
+/// about once every 256 bytes, so a full [`DEFAULT_RESYNC_BUDGET`] of it
+/// holds 16 false starts on average; this allows twice that.
+pub const DEFAULT_SYNC_ATTEMPTS: u32 = 2 * DEFAULT_RESYNC_BUDGET / 256;
+
+/// A reading that may have been parsed from a frame with a bad checksum
+///
+/// Returned by [`Sen0177::read_lenient`], for field debugging, where
+/// possibly corrupt values are more useful than none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LenientReading {
+    /// The parsed reading
+    pub reading: Reading,
+    /// Whether the frame's checksum matched; if not, any of the values in
+    /// `reading` may be corrupt
+    pub verified: bool,
+}
+
+impl LenientReading {
+    fn from_frame(frame: &[u8; FRAME_LEN], reading: Reading) -> Self {
+        Self {
+            reading,
+            verified: parse_frame(frame).is_ok(),
+        }
+    }
+}
+
+/// Sensor state in which the sensor continuously sends data frames
+///
+/// This is the state the sensor is in after power-on.
+pub struct Active;
+
+/// Sensor state in which the sensor only sends a data frame when asked
+pub struct Passive;
+
+/// Sensor state in which the sensor's fan and laser are turned off
+///
+/// A sleeping sensor sends no data, so it cannot be read from until it
+/// is woken up.
+pub struct Sleeping;
+
+/// Called by the blocking drivers each time the UART has no data available
+/// (or can't accept more), before polling it again
+///
+/// By default the drivers poll in a tight loop ([`Spin`]), which keeps a
+/// CPU core busy for the whole of each read (about 33ms per frame at 9600
+/// baud).  Sleeping instead ([`Sleep`], or any closure) frees it up, but
+/// only do so if the UART buffers received data (as OS serial ports and
+/// DMA- or interrupt-driven HALs do); a UART with a single-byte receive
+/// register will overrun and drop bytes while the driver sleeps.
+///
+/// The [`timeout_polls`](Sen0177Builder::timeout_polls) limit counts polls,
+/// so with a sleeping idle hook it becomes a time limit of roughly that many
+/// sleeps.
+///
+/// An idle hook can also act as a watchdog, ending a read that has waited
+/// too long for data (see [`Watchdog`](crate::watchdog::Watchdog)).
+pub trait Idle {
+    /// Waits before the next poll
+    fn idle(&mut self);
+
+    /// Called each time the driver receives a valid frame
+    fn frame_received(&mut self) {}
+
+    /// Returns the time of the last valid frame if the driver should stop
+    /// waiting for data, as [`SensorError::NoData`]
+    ///
+    /// This is checked each time the UART has no data available, before
+    /// [`idle`](Idle::idle) is called.
+    fn no_data_since(&mut self) -> Option<u64> {
+        None
+    }
+}
+
+/// Polls again immediately, without waiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Spin;
+
+impl Idle for Spin {
+    fn idle(&mut self) {}
+}
+
+/// Sleeps for a fixed time using a [`DelayNs`] implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sleep<D> {
+    delay: D,
+    us: u32,
+}
+
+impl<D: DelayNs> Sleep<D> {
+    /// Sleeps for `us` microseconds using `delay` before each poll
+    ///
+    /// At 9600 baud a byte arrives roughly every millisecond, so a sleep of
+    /// a few milliseconds is reasonable when the UART has a buffer to hold
+    /// the bytes that arrive in the meantime.
+    pub fn new(delay: D, us: u32) -> Self {
+        Self { delay, us }
+    }
+}
+
+impl<D: DelayNs> Idle for Sleep<D> {
+    fn idle(&mut self) {
+        self.delay.delay_us(self.us);
+    }
+}
+
+impl<F: FnMut()> Idle for F {
+    fn idle(&mut self) {
+        self()
+    }
+}
+
+/// Like [`nb::block!`], but calls `idle` between polls
+fn block_idle<T, E>(
+    idle: &mut impl Idle,
+    mut poll: impl FnMut() -> nb::Result<T, E>,
+) -> Result<T, E> {
+    loop {
+        match poll() {
+            Ok(value) => return Ok(value),
+            Err(nb::Error::WouldBlock) => idle.idle(),
+            Err(nb::Error::Other(error)) => return Err(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Config {
+    resync_budget: u32,
+    sync_attempts: u32,
+    timeout_polls: Option<u32>,
+    strict_checksum: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            resync_budget: DEFAULT_RESYNC_BUDGET,
+            sync_attempts: DEFAULT_SYNC_ATTEMPTS,
+            timeout_polls: None,
+            strict_checksum: true,
+        }
+    }
+}
+
+/// Builder for a [`Sen0177`] with non-default parameters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sen0177Builder {
+    config: Config,
+    validate: bool,
+    env: EnvDetector,
+}
+
+impl Sen0177Builder {
+    /// Creates a new builder with the default parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of bytes to discard while searching for the
+    /// start of a data frame (at least one)
+    ///
+    /// Defaults to [`DEFAULT_RESYNC_BUDGET`].  Set it to at least the size
+    /// of any buffer that may hold stale data when reading starts.
+    pub fn resync_budget(mut self, bytes: u32) -> Self {
+        self.config.resync_budget = bytes.max(1);
+        self
+    }
+
+    /// Sets the resync budget to the number of bytes the sensor sends in
+    /// `ms` milliseconds
+    ///
+    /// See [`resync_budget`](Sen0177Builder::resync_budget) and
+    /// [`BYTES_PER_SECOND`].
+    pub fn resync_time_ms(self, ms: u32) -> Self {
+        let bytes = u64::from(ms) * u64::from(BYTES_PER_SECOND) / 1000;
+        self.resync_budget(bytes.try_into().unwrap_or(u32::MAX))
+    }
+
+    /// Sets the maximum number of times to attempt to synchronize to the
+    /// start of a data frame before giving up with [`SensorError::BadMagic`]
+    /// (at least one)
+    ///
+    /// An attempt starts at each byte matching the first byte of a frame
+    /// header, and fails if the following bytes don't form a data frame.
+    /// Defaults to [`DEFAULT_SYNC_ATTEMPTS`].
+    pub fn sync_attempts(mut self, attempts: u32) -> Self {
+        self.config.sync_attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets the maximum number of consecutive times the UART may be polled
+    /// without returning a byte before giving up with [`SensorError::Timeout`]
+    ///
+    /// By default, reads will block forever waiting for data.
+    pub fn timeout_polls(mut self, polls: u32) -> Self {
+        self.config.timeout_polls = Some(polls);
+        self
+    }
+
+    /// Sets whether or not frames with an invalid checksum should be rejected
+    /// with [`SensorError::ChecksumMismatch`]
+    ///
+    /// Defaults to `true`.  Disabling this may be useful when debugging, but
+    /// means that corrupt data may be returned; use
+    /// [`Sen0177::read_lenient`] to find out which readings are affected.
+    pub fn strict_checksum(mut self, strict: bool) -> Self {
+        self.config.strict_checksum = strict;
+        self
+    }
+
+    /// Sets whether or not readings should be checked for physical
+    /// plausibility, and rejected with [`SensorError::ImplausibleData`] if
+    /// they fail
+    ///
+    /// Defaults to `false`.  See [`protocol::validate`](crate::protocol::validate)
+    /// for the checks performed.
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets the number of frames with suspect environmental concentrations
+    /// after which the sensor is deemed not to support them
+    ///
+    /// Defaults to [`env::DEFAULT_FRAMES`](crate::env::DEFAULT_FRAMES).  See
+    /// [`Sen0177::env_supported`].
+    pub fn env_detect_frames(mut self, frames: u16) -> Self {
+        self.env = EnvDetector::new(frames);
+        self
+    }
+
+    /// Creates a new sensor instance connected to UART `serial_port`
+    ///
+    /// The sensor is assumed to be in its power-on (active) state.
+    pub fn build<R, E>(self, serial_port: R) -> Sen0177<R, E, Active>
+    where
+        R: Read<u8, Error = E>,
+        E: SerialError,
+    {
+        Sen0177 {
+            driver: self.build_driver(serial_port),
+            validate: self.validate,
+            env: self.env,
+            _state: PhantomData,
+        }
+    }
+
+    /// Creates a new generic serial driver for frame protocol `P` connected
+    /// to UART `serial_port`
+    ///
+    /// The [`validate`](Sen0177Builder::validate) setting does not apply to
+    /// generic drivers.
+    pub fn build_driver<P, R>(self, serial_port: R) -> SerialDriver<P, R>
+    where
+        P: FrameProtocol,
+        R: Read<u8>,
+    {
+        SerialDriver {
+            serial_port,
+            decoder: self.build_decoder(),
+            timeout_polls: self.config.timeout_polls,
+            idle: Spin,
+        }
+    }
+
+    /// Creates a new [`FrameDecoder`] for frame protocol `P`
+    ///
+    /// The [`validate`](Sen0177Builder::validate) and
+    /// [`timeout_polls`](Sen0177Builder::timeout_polls) settings do not apply
+    /// to decoders.
+    pub fn build_decoder<P: FrameProtocol>(self) -> FrameDecoder<P> {
+        FrameDecoder::with_limits(
+            self.config.resync_budget,
+            self.config.sync_attempts,
+            self.config.strict_checksum,
+        )
+    }
+
+    /// Creates a new async sensor instance connected to UART `serial_port`
+    ///
+    /// The sensor is assumed to be in its power-on (active) state.  The
+    /// [`timeout_polls`](Sen0177Builder::timeout_polls) setting does not
+    /// apply to async sensors; race reads against a timer instead.
+    #[cfg(feature = "async")]
+    pub fn build_async<R>(self, serial_port: R) -> AsyncSen0177<R>
+    where
+        R: AsyncRead,
+    {
+        AsyncSen0177 {
+            driver: self.build_async_driver(serial_port),
+            validate: self.validate,
+            env: self.env,
+        }
+    }
+
+    /// Creates a new generic async serial driver for frame protocol `P`
+    /// connected to UART `serial_port`
+    ///
+    /// The [`validate`](Sen0177Builder::validate) and
+    /// [`timeout_polls`](Sen0177Builder::timeout_polls) settings do not apply
+    /// to generic async drivers.
+    #[cfg(feature = "async")]
+    pub fn build_async_driver<P, R>(self, serial_port: R) -> AsyncSerialDriver<P, R>
+    where
+        P: FrameProtocol,
+        R: AsyncRead,
+    {
+        AsyncSerialDriver {
+            serial_port,
+            decoder: self.build_decoder(),
+            buf: [0; FRAME_LEN],
+            pos: 0,
+            len: 0,
+        }
+    }
+}
+
+/// A generic driver that reads frames of protocol `P` from UART
+/// `serial_port`
+///
+/// This feeds bytes from the UART into a [`FrameDecoder`], which handles
+/// synchronizing to the start of a frame, skipping non-data frames, and
+/// checksum verification, so that supporting a new sensor family mostly
+/// requires implementing [`FrameProtocol`].
+///
+/// The `W` type parameter is the [`Idle`] hook called while waiting for
+/// data.
+pub struct SerialDriver<P: FrameProtocol, R, W = Spin> {
+    serial_port: R,
+    decoder: FrameDecoder<P>,
+    timeout_polls: Option<u32>,
+    idle: W,
+}
+
+impl<P, R> SerialDriver<P, R>
+where
+    P: FrameProtocol,
+    R: Read<u8>,
+{
+    /// Creates a new driver connected to UART `serial_port`, with the default
+    /// parameters
+    pub fn new(serial_port: R) -> Self {
+        Sen0177Builder::new().build_driver(serial_port)
+    }
+}
+
+impl<P, R, W> SerialDriver<P, R, W>
+where
+    P: FrameProtocol,
+    R: Read<u8>,
+    W: Idle,
+{
+    /// Sets the hook called each time the UART has no data available,
+    /// replacing the current one
+    pub fn with_idle<W2: Idle>(self, idle: W2) -> SerialDriver<P, R, W2> {
+        SerialDriver {
+            serial_port: self.serial_port,
+            decoder: self.decoder,
+            timeout_polls: self.timeout_polls,
+            idle,
+        }
+    }
+
+    /// Returns a mutable reference to the underlying UART, e.g. for sending
+    /// commands
+    pub fn serial_port_mut(&mut self) -> &mut R {
+        &mut self.serial_port
+    }
+
+    /// Consumes the driver, returning the underlying UART
+    pub fn release(self) -> R {
+        self.serial_port
+    }
+
+    /// Discards any bytes already buffered by the UART, without blocking,
+    /// returning the number of bytes discarded
+    ///
+    /// Sensors in active mode send a frame every second or so, so after a
+    /// pause in reading, the UART (or the OS serial driver behind it) may
+    /// hold many stale frames.  This reads until the UART reports that no
+    /// more data is available; with a UART that waits for a read timeout
+    /// before doing so, this will take at least that long.
+    pub fn flush_stale(&mut self) -> Result<usize, SensorError<R::Error>> {
+        let mut discarded = 0usize;
+        loop {
+            match self.serial_port.read() {
+                Ok(_) => discarded += 1,
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(error)) => return Err(SensorError::bus(error)),
+            }
+        }
+        if discarded > 0 {
+            debug!("Flushed {} stale bytes", discarded);
+        }
+        Ok(discarded)
+    }
+
+    /// Reads a single frame, returning the raw frame along with its parsed
+    /// contents
+    ///
+    /// This function will block until sufficient data is available.  If
+    /// reading from the UART fails or times out, any partially received
+    /// frame is discarded.
+    pub fn read_frame(&mut self) -> FrameResult<P, R::Error> {
+        loop {
+            let byte = self.read_byte().inspect_err(|_| self.decoder.reset())?;
+            if let Some(result) = self.decoder.push_for(byte) {
+                if result.is_ok() {
+                    self.idle.frame_received();
+                }
+                return result;
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, SensorError<R::Error>> {
+        let mut polls = 0u32;
+        loop {
+            match self.serial_port.read() {
+                Ok(byte) => break Ok(byte),
+                Err(nb::Error::WouldBlock) => {
+                    polls = polls.saturating_add(1);
+                    if self.timeout_polls.is_some_and(|max| polls > max) {
+                        debug!("Timed out waiting for data");
+                        break Err(SensorError::Timeout);
+                    }
+                    if let Some(since) = self.idle.no_data_since() {
+                        debug!("No valid frame since {}", since);
+                        break Err(SensorError::NoData { since });
+                    }
+                    self.idle.idle();
+                }
+                Err(nb::Error::Other(error)) => break Err(SensorError::bus(error)),
+            }
+        }
+    }
+}
+
 /// A SEN0177 device connected via serial UART
-pub struct Sen0177<R, E>
+///
+/// The `S` type parameter tracks the sensor's current state (one of
+/// [`Active`], [`Passive`], or [`Sleeping`]), so that operations that are
+/// invalid in the current state (such as reading from a sleeping sensor)
+/// fail to compile.  Changing states requires that the UART also
+/// implements [`Write`].  The `W` type parameter is the [`Idle`] hook
+/// called while waiting for the UART.
+pub struct Sen0177<R, E, S = Active, W = Spin>
 where
     R: Read<u8, Error = E>,
     E: SerialError,
 {
-    serial_port: R,
+    driver: SerialDriver<Plantower, R, W>,
+    validate: bool,
+    env: EnvDetector,
+    _state: PhantomData<S>,
 }
 
-impl<R, E> Sen0177<R, E>
+impl<R, E> Sen0177<R, E, Active>
 where
     R: Read<u8, Error = E>,
     E: SerialError,
 {
     /// Creates a new sensor instance connected to UART `serial_port`
+    ///
+    /// The sensor is assumed to be in its power-on (active) state.
     pub fn new(serial_port: R) -> Self {
-        Self { serial_port }
+        Sen0177Builder::new().build(serial_port)
+    }
+}
+
+impl<R, E, W> Sen0177<R, E, Active, W>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+    W: Idle,
+{
+    /// Reads a single sensor measurement, returning the raw data frame along
+    /// with the parsed reading
+    ///
+    /// This function will block until sufficient data is available.
+    pub fn read_raw(&mut self) -> Result<([u8; FRAME_LEN], Reading), SensorError<E>> {
+        self.read_validated()
+    }
+
+    /// Discards any stale buffered data, then reads a freshly received
+    /// measurement
+    ///
+    /// See [`flush_stale`](Sen0177::flush_stale).  This will block until the
+    /// sensor sends its next frame.
+    pub fn read_latest(&mut self) -> Result<Reading, SensorError<E>> {
+        self.flush_stale()?;
+        self.read_raw().map(|(_, reading)| reading)
     }
 
-    fn find_byte(&mut self, byte: u8, attempts: u32) -> Result<bool, SensorError<E>> {
-        let mut attempts_left = attempts;
-        let mut byte_read = 0u8;
-        while byte_read != byte && attempts_left > 0 {
-            byte_read = block!(self.serial_port.read())?;
-            attempts_left -= 1;
+    /// Reads a single sensor measurement, flagging whether its checksum
+    /// matched
+    ///
+    /// Frames with a bad checksum are only returned (rather than rejected
+    /// with [`SensorError::ChecksumMismatch`]) once
+    /// [`strict_checksum`](Sen0177Builder::strict_checksum) has been
+    /// disabled; otherwise every reading returned is verified.
+    pub fn read_lenient(&mut self) -> Result<LenientReading, SensorError<E>> {
+        self.read_raw()
+            .map(|(frame, reading)| LenientReading::from_frame(&frame, reading))
+    }
+
+    /// Checks the sensor's health by reading `frames` fresh frames, timing
+    /// their arrival with `clock` (in milliseconds)
+    ///
+    /// Stale buffered data is discarded first, so that the intervals
+    /// measured are the sensor's own cadence.  Each read is bounded by the
+    /// driver's [timeout](Sen0177Builder::timeout_polls), and the check
+    /// stops at the first error.  See [`health`](crate::health).
+    pub fn health_check<C>(&mut self, clock: &mut C, frames: u8) -> HealthReport<E>
+    where
+        C: Clock<Instant = u64>,
+    {
+        let mut report = HealthReport::default();
+        if let Err(err) = self.flush_stale() {
+            report.error = Some(err);
+            return report;
+        }
+        let mut previous = None;
+        for _ in 0..frames {
+            match self.read_raw() {
+                Ok((_, reading)) => {
+                    let now = clock.now();
+                    report.record(reading, previous.map(|at: u64| now.saturating_sub(at)));
+                    previous = Some(now);
+                }
+                Err(err) => {
+                    report.error = Some(err);
+                    break;
+                }
+            }
+        }
+        report
+    }
+}
+
+impl<R, E, S, W> Sen0177<R, E, S, W>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+    W: Idle,
+{
+    /// Consumes the sensor instance, returning the underlying UART
+    pub fn release(self) -> R {
+        self.driver.release()
+    }
+
+    /// Discards any bytes already buffered by the UART, without blocking,
+    /// returning the number of bytes discarded
+    ///
+    /// See [`SerialDriver::flush_stale`].
+    pub fn flush_stale(&mut self) -> Result<usize, SensorError<E>> {
+        self.driver.flush_stale()
+    }
+
+    /// Sets the hook called each time the UART has no data available or
+    /// can't accept more, replacing the current one
+    ///
+    /// See [`Idle`].
+    pub fn with_idle<W2: Idle>(self, idle: W2) -> Sen0177<R, E, S, W2> {
+        Sen0177 {
+            driver: self.driver.with_idle(idle),
+            validate: self.validate,
+            env: self.env,
+            _state: PhantomData,
+        }
+    }
+
+    fn into_state<T>(self) -> Sen0177<R, E, T, W> {
+        Sen0177 {
+            driver: self.driver,
+            validate: self.validate,
+            env: self.env,
+            _state: PhantomData,
+        }
+    }
+
+    /// Returns whether the sensor reports genuine environmental
+    /// (atmospheric) concentrations, or `None` if that hasn't been decided
+    /// yet
+    ///
+    /// This is detected from the readings returned so far; see
+    /// [`env`](crate::env).
+    pub fn env_supported(&self) -> Option<bool> {
+        self.env.supported()
+    }
+
+    /// Returns the detector of environmental concentration support
+    ///
+    /// Readings returned by the sensor are already marked by the detector,
+    /// so their `env_pm*()` accessors return `None` if the environmental
+    /// concentrations can't be trusted.
+    pub fn env_detector(&self) -> &EnvDetector {
+        &self.env
+    }
+
+    fn read_validated(&mut self) -> Result<([u8; FRAME_LEN], Reading), SensorError<E>> {
+        let (frame, reading) = self.driver.read_frame()?;
+        let reading = check_plausible(reading, self.validate)?;
+        self.env.observe(&reading);
+        let reading = self.env.apply(reading);
+        Ok((frame, reading))
+    }
+}
+
+impl<R, E, S, W> Sen0177<R, E, S, W>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+{
+    fn send_command(&mut self, command: Command) -> Result<(), SensorError<E>> {
+        debug!("Sending command {:?}", command);
+        let SerialDriver {
+            serial_port, idle, ..
+        } = &mut self.driver;
+        for byte in encode_command(command) {
+            block_idle(idle, || serial_port.write(byte)).map_err(SensorError::bus)?;
         }
-        Ok(byte_read == byte)
+        block_idle(idle, || serial_port.flush()).map_err(SensorError::bus)?;
+        Ok(())
+    }
+}
+
+impl<R, E, W> Sen0177<R, E, Active, W>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+{
+    /// Sends the wake-up and active mode commands again, without changing
+    /// state
+    ///
+    /// This can recover a sensor that has stopped sending data, e.g.
+    /// because a glitch on its RX line put it to sleep or into passive mode.
+    pub fn reinit(&mut self) -> Result<(), SensorError<E>> {
+        self.send_command(Command::Wakeup)?;
+        self.send_command(Command::ActiveMode)
+    }
+
+    /// Switches the sensor to passive mode
+    pub fn into_passive(mut self) -> Result<Sen0177<R, E, Passive, W>, SensorError<E>> {
+        self.send_command(Command::PassiveMode)?;
+        Ok(self.into_state())
+    }
+
+    /// Puts the sensor to sleep
+    pub fn sleep(mut self) -> Result<Sen0177<R, E, Sleeping, W>, SensorError<E>> {
+        self.send_command(Command::Sleep)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<R, E, W> Sen0177<R, E, Passive, W>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+{
+    /// Requests and reads a single sensor measurement, returning the raw data
+    /// frame along with the parsed reading
+    ///
+    /// This function will block until sufficient data is available.
+    pub fn read_raw(&mut self) -> Result<([u8; FRAME_LEN], Reading), SensorError<E>> {
+        self.send_command(Command::PassiveRead)?;
+        self.read_validated()
+    }
+
+    /// Discards any stale buffered data (such as responses to earlier
+    /// requests that were never read), then requests and reads a single
+    /// sensor measurement
+    pub fn read_latest(&mut self) -> Result<Reading, SensorError<E>> {
+        self.flush_stale()?;
+        self.read_raw().map(|(_, reading)| reading)
+    }
+
+    /// Requests and reads a single sensor measurement, flagging whether its
+    /// checksum matched
+    ///
+    /// See [`Sen0177::read_lenient`] in active mode.
+    pub fn read_lenient(&mut self) -> Result<LenientReading, SensorError<E>> {
+        self.read_raw()
+            .map(|(frame, reading)| LenientReading::from_frame(&frame, reading))
+    }
+
+    /// Switches the sensor to active mode
+    pub fn into_active(mut self) -> Result<Sen0177<R, E, Active, W>, SensorError<E>> {
+        self.send_command(Command::ActiveMode)?;
+        Ok(self.into_state())
+    }
+
+    /// Puts the sensor to sleep
+    pub fn sleep(mut self) -> Result<Sen0177<R, E, Sleeping, W>, SensorError<E>> {
+        self.send_command(Command::Sleep)?;
+        Ok(self.into_state())
+    }
+}
+
+impl<R, E, W> Sen0177<R, E, Sleeping, W>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+{
+    /// Wakes the sensor up, returning it to active mode
+    ///
+    /// Note that the sensor's fan needs some time to spin up after waking;
+    /// the datasheet recommends waiting at least 30 seconds before trusting
+    /// the readings.
+    pub fn wake(mut self) -> Result<Sen0177<R, E, Active, W>, SensorError<E>> {
+        self.send_command(Command::Wakeup)?;
+        self.send_command(Command::ActiveMode)?;
+        Ok(self.into_state())
     }
 }
 
-impl<R, E> AirQualitySensor<E> for Sen0177<R, E>
+impl<R, E, W> AirQualitySensor<E> for Sen0177<R, E, Active, W>
 where
     R: Read<u8, Error = E>,
     E: SerialError,
+    W: Idle,
 {
     fn read(&mut self) -> Result<Reading, SensorError<E>> {
-        let mut attempts_left = 10;
-        let mut byte_read = 0u8;
-        while byte_read != MAGIC_BYTE_1
-            && attempts_left > 0
-            && self.find_byte(MAGIC_BYTE_0, PAYLOAD_LEN as u32 * 4)?
-        {
-            byte_read = block!(self.serial_port.read())?;
-            attempts_left -= 1;
-        }
-
-        if byte_read == MAGIC_BYTE_1 {
-            let mut buf: [u8; PAYLOAD_LEN] = [0; PAYLOAD_LEN];
-            buf[0] = MAGIC_BYTE_0;
-            buf[1] = MAGIC_BYTE_1;
-            for buf_slot in buf[2..PAYLOAD_LEN].iter_mut() {
-                *buf_slot = block!(self.serial_port.read())?;
+        self.read_raw().map(|(_, reading)| reading)
+    }
+
+    fn info(&self) -> SensorInfo {
+        INFO
+    }
+}
+
+impl<R, E, W> AirQualitySensor<E> for Sen0177<R, E, Passive, W>
+where
+    R: Read<u8, Error = E> + Write<u8>,
+    E: SerialError,
+    W: Idle,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        self.read_raw().map(|(_, reading)| reading)
+    }
+
+    fn info(&self) -> SensorInfo {
+        INFO
+    }
+}
+
+impl<R, E, S, W> ErrorType for Sen0177<R, E, S, W>
+where
+    R: Read<u8, Error = E>,
+    E: SerialError,
+{
+    type Error = SensorError<E>;
+}
+
+/// A generic driver that reads frames of protocol `P` from async UART
+/// `serial_port`
+///
+/// This is the async counterpart of [`SerialDriver`].
+///
+/// # Cancellation safety
+///
+/// [`read_frame`](AsyncSerialDriver::read_frame) is cancel-safe, provided
+/// the UART's own `read` is (as Embassy's UART drivers are): if the future
+/// is dropped before completing (e.g. because it lost an
+/// `embassy_futures::select` against a timer), the bytes already received
+/// and the progress through the current frame are kept in the driver, and
+/// the next read picks up where the cancelled one left off.
+#[cfg(feature = "async")]
+pub struct AsyncSerialDriver<P: FrameProtocol, R> {
+    serial_port: R,
+    decoder: FrameDecoder<P>,
+    buf: [u8; FRAME_LEN],
+    pos: usize,
+    len: usize,
+}
+
+#[cfg(feature = "async")]
+impl<P, R> AsyncSerialDriver<P, R>
+where
+    P: FrameProtocol,
+    R: AsyncRead,
+{
+    /// Creates a new driver connected to UART `serial_port`, with the default
+    /// parameters
+    pub fn new(serial_port: R) -> Self {
+        Sen0177Builder::new().build_async_driver(serial_port)
+    }
+
+    /// Returns a mutable reference to the underlying UART, e.g. for sending
+    /// commands
+    pub fn serial_port_mut(&mut self) -> &mut R {
+        &mut self.serial_port
+    }
+
+    /// Consumes the driver, returning the underlying UART
+    ///
+    /// Any bytes already read from the UART but not yet processed are lost.
+    pub fn release(self) -> R {
+        self.serial_port
+    }
+
+    /// Reads a single frame, returning the raw frame along with its parsed
+    /// contents
+    ///
+    /// If the UART reports end of file, this returns
+    /// [`SensorError::Timeout`].  If reading from the UART fails (as opposed
+    /// to the read being cancelled), any partially received frame is
+    /// discarded.
+    pub async fn read_frame(&mut self) -> FrameResult<P, R::Error> {
+        loop {
+            let mut frames = self.decoder.push_slice(&self.buf[self.pos..self.len]);
+            let result = frames.next();
+            self.pos = self.len - frames.remaining().len();
+            if let Some(result) = result {
+                return result.map_err(crate::decoder::widen);
             }
 
-            parse_data(&buf)
-        } else {
-            Err(SensorError::BadMagic)
+            // Only the await point below can be cancelled, and all state
+            // is in `self` by the time it's reached
+            let len = self
+                .serial_port
+                .read(&mut self.buf)
+                .await
+                .map_err(|error| {
+                    self.decoder.reset();
+                    SensorError::bus(error)
+                })?;
+            if len == 0 {
+                debug!("UART reported end of file");
+                self.decoder.reset();
+                return Err(SensorError::Timeout);
+            }
+            self.pos = 0;
+            self.len = len;
         }
     }
 }
+
+/// A SEN0177 device in active mode, connected via async serial UART
+///
+/// See [`AsyncSerialDriver`] for the cancellation safety of reads.
+#[cfg(feature = "async")]
+pub struct AsyncSen0177<R> {
+    driver: AsyncSerialDriver<Plantower, R>,
+    validate: bool,
+    env: EnvDetector,
+}
+
+#[cfg(feature = "async")]
+impl<R> AsyncSen0177<R>
+where
+    R: AsyncRead,
+{
+    /// Creates a new sensor instance connected to UART `serial_port`
+    ///
+    /// The sensor is assumed to be in its power-on (active) state.
+    pub fn new(serial_port: R) -> Self {
+        Sen0177Builder::new().build_async(serial_port)
+    }
+
+    /// Consumes the sensor instance, returning the underlying UART
+    pub fn release(self) -> R {
+        self.driver.release()
+    }
+
+    /// Returns whether the sensor reports genuine environmental
+    /// (atmospheric) concentrations, or `None` if that hasn't been decided
+    /// yet
+    ///
+    /// See [`Sen0177::env_supported`].
+    pub fn env_supported(&self) -> Option<bool> {
+        self.env.supported()
+    }
+
+    /// Returns the detector of environmental concentration support
+    ///
+    /// See [`Sen0177::env_detector`].
+    pub fn env_detector(&self) -> &EnvDetector {
+        &self.env
+    }
+
+    /// Reads a single sensor measurement, returning the raw data frame along
+    /// with the parsed reading
+    pub async fn read_raw(&mut self) -> Result<([u8; FRAME_LEN], Reading), SensorError<R::Error>> {
+        let (frame, reading) = self.driver.read_frame().await?;
+        let reading = check_plausible(reading, self.validate)?;
+        self.env.observe(&reading);
+        let reading = self.env.apply(reading);
+        Ok((frame, reading))
+    }
+
+    /// Reads a single sensor measurement
+    pub async fn read(&mut self) -> Result<Reading, SensorError<R::Error>> {
+        self.read_raw().await.map(|(_, reading)| reading)
+    }
+
+    /// Returns an infinite cursor that reads from the sensor each time it
+    /// is advanced
+    ///
+    /// This is the async counterpart of [`AirQualitySensor::readings`].
+    pub fn readings(&mut self) -> crate::iter::AsyncReadings<'_, R> {
+        crate::iter::AsyncReadings::new(self)
+    }
+
+    /// Reads a single sensor measurement, flagging whether its checksum
+    /// matched
+    ///
+    /// See [`Sen0177::read_lenient`].
+    pub async fn read_lenient(&mut self) -> Result<LenientReading, SensorError<R::Error>> {
+        self.read_raw()
+            .await
+            .map(|(frame, reading)| LenientReading::from_frame(&frame, reading))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead> ErrorType for AsyncSen0177<R> {
+    type Error = SensorError<R::Error>;
+}
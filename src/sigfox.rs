@@ -0,0 +1,144 @@
+//! Packs readings into the 12 bytes available in a Sigfox uplink (or any
+//! similarly constrained link), with a matching decoder for the backend.
+//!
+//! # Layout
+//!
+//! All multi-byte values are big-endian.
+//!
+//! | Offset | Length | Contents                                         |
+//! |--------|--------|--------------------------------------------------|
+//! | 0      | 1      | Layout version ([`VERSION`])                     |
+//! | 1      | 2      | Standard PM1 concentration, in µg/m³             |
+//! | 3      | 2      | Standard PM2.5 concentration, in µg/m³           |
+//! | 5      | 2      | Standard PM10 concentration, in µg/m³            |
+//! | 7      | 2      | AQI, or `0xffff` if not supplied                 |
+//! | 9      | 1      | Battery level, or `0xff` if not supplied         |
+//! | 10     | 1      | Device error code reported by the sensor         |
+//! | 11     | 1      | Reserved; always zero                            |
+//!
+//! The battery level is an opaque byte supplied by the caller (for example,
+//! a percentage, or a voltage in some fixed unit); `0xff` can't be sent.
+
+use core::fmt;
+
+use crate::{aqi::Aqi, Reading};
+
+/// The length, in bytes, of an encoded payload
+pub const PAYLOAD_LEN: usize = 12;
+
+/// The version of the layout produced by [`Payload::encode`]
+pub const VERSION: u8 = 1;
+
+const NO_AQI: u16 = 0xffff;
+const NO_BATTERY: u8 = 0xff;
+
+/// The values carried by a payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Payload {
+    /// The standard PM1 concentration, in µg/m³
+    pub pm1: u16,
+    /// The standard PM2.5 concentration, in µg/m³
+    pub pm2_5: u16,
+    /// The standard PM10 concentration, in µg/m³
+    pub pm10: u16,
+    /// The AQI value, if supplied
+    pub aqi: Option<u16>,
+    /// The battery level, if supplied
+    pub battery: Option<u8>,
+    /// The error code reported by the sensor
+    pub device_error_code: u8,
+}
+
+impl Payload {
+    /// Creates a payload holding the concentrations and device error code
+    /// from `reading`, with no AQI or battery level
+    pub fn new(reading: &Reading) -> Self {
+        Self {
+            pm1: reading.pm1(),
+            pm2_5: reading.pm2_5(),
+            pm10: reading.pm10(),
+            aqi: None,
+            battery: None,
+            device_error_code: reading.device_error_code(),
+        }
+    }
+
+    /// Adds an AQI value to the payload
+    pub fn with_aqi(self, aqi: Aqi) -> Self {
+        Self {
+            aqi: Some(aqi.value()),
+            ..self
+        }
+    }
+
+    /// Adds a battery level to the payload
+    ///
+    /// A level of `0xff` is indistinguishable from no level, and will be
+    /// decoded as `None`.
+    pub fn with_battery(self, battery: u8) -> Self {
+        Self {
+            battery: Some(battery),
+            ..self
+        }
+    }
+
+    /// Encodes the payload
+    pub fn encode(&self) -> [u8; PAYLOAD_LEN] {
+        let mut buf = [0u8; PAYLOAD_LEN];
+        buf[0] = VERSION;
+        buf[1..3].copy_from_slice(&self.pm1.to_be_bytes());
+        buf[3..5].copy_from_slice(&self.pm2_5.to_be_bytes());
+        buf[5..7].copy_from_slice(&self.pm10.to_be_bytes());
+        buf[7..9].copy_from_slice(&self.aqi.unwrap_or(NO_AQI).to_be_bytes());
+        buf[9] = self.battery.unwrap_or(NO_BATTERY);
+        buf[10] = self.device_error_code;
+        buf
+    }
+
+    /// Decodes a payload produced by [`encode`](Payload::encode)
+    pub fn decode(buf: &[u8]) -> Result<Self, PayloadError> {
+        if buf.len() != PAYLOAD_LEN {
+            return Err(PayloadError::WrongLength(buf.len()));
+        }
+        if buf[0] != VERSION {
+            return Err(PayloadError::UnknownVersion(buf[0]));
+        }
+        let word = |offset: usize| u16::from_be_bytes([buf[offset], buf[offset + 1]]);
+        Ok(Self {
+            pm1: word(1),
+            pm2_5: word(3),
+            pm10: word(5),
+            aqi: Some(word(7)).filter(|&aqi| aqi != NO_AQI),
+            battery: Some(buf[9]).filter(|&battery| battery != NO_BATTERY),
+            device_error_code: buf[10],
+        })
+    }
+}
+
+/// Describes errors encountered while decoding a payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadError {
+    /// The payload was not [`PAYLOAD_LEN`] bytes long; holds its length
+    WrongLength(usize),
+    /// The payload's layout version is not one this decoder understands
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::WrongLength(len) => {
+                write!(
+                    f,
+                    "Expected a {}-byte payload, got {} bytes",
+                    PAYLOAD_LEN, len
+                )
+            }
+            PayloadError::UnknownVersion(version) => {
+                write!(f, "Unknown payload version {}", version)
+            }
+        }
+    }
+}
+
+impl core::error::Error for PayloadError {}
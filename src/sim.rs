@@ -0,0 +1,632 @@
+//! A [`SimulatedSensor`] implements [`AirQualitySensor`] by computing each
+//! reading from a [`Profile`] of concentration over time, such as one of
+//! the [`Pattern`]s, so that dashboards, alerting, and filters can be
+//! exercised with the crate's real logic.  Nothing here touches a bus or a
+//! clock (simulated time advances by a fixed interval per reading), so the
+//! module works on any target, including `wasm32-unknown-unknown` in the
+//! browser.
+//!
+//! ```
+//! use sen0177::{
+//!     sim::{Pattern, SimulatedSensor},
+//!     AirQualitySensor,
+//! };
+//!
+//! // Smoke drifting in: 5µg/m³ rising to 150µg/m³ over ten minutes
+//! let mut sensor = SimulatedSensor::new(Pattern::Ramp {
+//!     from: 5,
+//!     to: 150,
+//!     duration_ms: 600_000,
+//! });
+//! let reading = AirQualitySensor::<()>::read(&mut sensor)?;
+//! assert_eq!(reading.pm2_5(), 5);
+//! # Ok::<(), sen0177::SensorError<()>>(())
+//! ```
+//!
+//! For realistic time series, a [`Scenario`] combines a daily cycle of
+//! background pollution with indoor events (cooking, smoke) and multi-day
+//! wildfire episodes, and adds sensor noise:
+//!
+//! ```
+//! use sen0177::sim::{Event, Noise, Scenario, HOUR_MS};
+//!
+//! let events = [
+//!     Event::cooking(18 * HOUR_MS, 120),
+//!     Event::wildfire(30 * HOUR_MS, 48 * HOUR_MS, 250),
+//! ];
+//! let mut scenario = Scenario::new(8)
+//!     .diurnal(4, 21)
+//!     .events(&events)
+//!     .noise(Noise::TYPICAL)
+//!     .seed(42);
+//! // Three days of readings, one a minute
+//! for (elapsed_ms, reading) in scenario.samples(60_000).take(3 * 24 * 60) {
+//!     // feed alerting, averaging, or a dashboard
+//! #   let _ = (elapsed_ms, reading);
+//! }
+//! ```
+
+use crate::{
+    protocol::{encode_frame, FRAME_LEN},
+    Concentrations, Reading, SensorError, SensorInfo,
+};
+
+/// The information reported by a [`SimulatedSensor`]
+pub const INFO: SensorInfo = SensorInfo {
+    name: "Simulated",
+    supports_atmospheric_pm: true,
+    supports_particle_counts: true,
+    supports_temperature_humidity: false,
+    frame_len: FRAME_LEN,
+    default_i2c_address: None,
+};
+
+/// The interval between simulated readings unless changed, matching a real
+/// sensor's active mode in stable air
+pub const DEFAULT_INTERVAL_MS: u32 = 1000;
+
+/// The length of an hour, in milliseconds
+pub const HOUR_MS: u64 = 60 * 60 * 1000;
+
+/// The length of a day, in milliseconds
+pub const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// Returns a plausible reading with a standard (CF=1) PM2.5 concentration
+/// of `pm2_5` µg/m³
+///
+/// The other concentrations and the particle counts are scaled from PM2.5
+/// by ratios typical of urban air, and the environmental concentrations
+/// fall below the standard ones above 30µg/m³, as they do on a real sensor.
+pub fn reading_for(pm2_5: u16) -> Reading {
+    let scale = |numerator: u32, denominator: u32| {
+        let scaled = (u32::from(pm2_5) * numerator + denominator / 2) / denominator;
+        u16::try_from(scaled).unwrap_or(u16::MAX)
+    };
+    let (pm1, pm10) = (scale(7, 10), scale(13, 10));
+    let atmospheric = |pm: u16| {
+        if pm <= 30 {
+            pm
+        } else {
+            30 + ((u32::from(pm) - 30) * 2 / 3) as u16
+        }
+    };
+    Reading::new(
+        Concentrations::new(pm1, pm2_5, pm10),
+        Concentrations::new(atmospheric(pm1), atmospheric(pm2_5), atmospheric(pm10)),
+        [
+            scale(180, 1),
+            scale(50, 1),
+            scale(8, 1),
+            scale(4, 5),
+            scale(1, 5),
+            scale(1, 20),
+        ],
+    )
+}
+
+/// A source of simulated readings over time
+pub trait Profile {
+    /// Returns the reading `elapsed_ms` milliseconds into the simulation
+    ///
+    /// This is called with increasing times.
+    fn reading_at(&mut self, elapsed_ms: u64) -> Reading;
+}
+
+impl<F: FnMut(u64) -> Reading> Profile for F {
+    fn reading_at(&mut self, elapsed_ms: u64) -> Reading {
+        self(elapsed_ms)
+    }
+}
+
+/// A simple shape of PM2.5 concentration over time, in µg/m³
+///
+/// Readings are built from the concentration by [`reading_for`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    /// A steady concentration
+    Constant(u16),
+    /// A linear change from one concentration to another, which then holds
+    Ramp {
+        /// The starting concentration
+        from: u16,
+        /// The final concentration
+        to: u16,
+        /// How long the change takes, in milliseconds
+        duration_ms: u64,
+    },
+    /// A sudden rise from a baseline, decaying linearly back to it, as when
+    /// someone cooks or lights a candle nearby
+    Spike {
+        /// The concentration before and after the spike
+        baseline: u16,
+        /// The concentration at the top of the spike
+        peak: u16,
+        /// When the spike starts, in milliseconds
+        start_ms: u64,
+        /// How long the concentration takes to decay back to the baseline,
+        /// in milliseconds
+        decay_ms: u64,
+    },
+    /// Alternating between two concentrations, spending half of each
+    /// period at each, e.g. for testing alert hysteresis
+    Square {
+        /// The concentration during the first half of each period
+        low: u16,
+        /// The concentration during the second half of each period
+        high: u16,
+        /// The length of a period, in milliseconds
+        period_ms: u64,
+    },
+}
+
+impl Pattern {
+    /// Returns the PM2.5 concentration `elapsed_ms` milliseconds into the
+    /// pattern
+    pub fn pm2_5_at(&self, elapsed_ms: u64) -> u16 {
+        match *self {
+            Pattern::Constant(pm2_5) => pm2_5,
+            Pattern::Ramp {
+                from,
+                to,
+                duration_ms,
+            } => {
+                if elapsed_ms >= duration_ms {
+                    to
+                } else {
+                    interpolate(from, to, elapsed_ms, duration_ms)
+                }
+            }
+            Pattern::Spike {
+                baseline,
+                peak,
+                start_ms,
+                decay_ms,
+            } => match elapsed_ms.checked_sub(start_ms) {
+                Some(since) if since < decay_ms => interpolate(peak, baseline, since, decay_ms),
+                _ => baseline,
+            },
+            Pattern::Square {
+                low,
+                high,
+                period_ms,
+            } => {
+                if period_ms == 0 || elapsed_ms % period_ms < period_ms / 2 {
+                    low
+                } else {
+                    high
+                }
+            }
+        }
+    }
+}
+
+impl Profile for Pattern {
+    fn reading_at(&mut self, elapsed_ms: u64) -> Reading {
+        reading_for(self.pm2_5_at(elapsed_ms))
+    }
+}
+
+/// Returns the value `elapsed` of the way through `duration` from `from`
+/// to `to`, rounded to the nearest
+fn interpolate(from: u16, to: u16, elapsed: u64, duration: u64) -> u16 {
+    let (from, to) = (i128::from(from), i128::from(to));
+    let (elapsed, duration) = (i128::from(elapsed), i128::from(duration));
+    let delta = (to - from) * elapsed;
+    let step = if delta < 0 {
+        (delta - duration / 2) / duration
+    } else {
+        (delta + duration / 2) / duration
+    };
+    (from + step) as u16
+}
+
+/// A sensor whose readings are computed from a [`Profile`]
+///
+/// See the [module documentation](self).  Each read returns the reading at
+/// the current simulated time, then advances it by the
+/// [interval](SimulatedSensor::interval_ms); reads never block.
+#[derive(Debug, Clone)]
+pub struct SimulatedSensor<P> {
+    profile: P,
+    elapsed_ms: u64,
+    interval_ms: u32,
+    fail_every: u32,
+    reads: u32,
+    limit: Option<u32>,
+    last_frame_ms: u64,
+}
+
+impl<P: Profile> SimulatedSensor<P> {
+    /// Creates a sensor reading from `profile`, a reading every
+    /// [`DEFAULT_INTERVAL_MS`]
+    pub fn new(profile: P) -> Self {
+        Self {
+            profile,
+            elapsed_ms: 0,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            fail_every: 0,
+            reads: 0,
+            limit: None,
+            last_frame_ms: 0,
+        }
+    }
+
+    /// Sets the simulated time between readings, in milliseconds
+    pub fn interval_ms(mut self, interval_ms: u32) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+
+    /// Makes every `n`th read fail with [`SensorError::Timeout`], as if a
+    /// frame had been lost; zero (the default) never fails
+    ///
+    /// Simulated time still advances on a failed read.
+    pub fn fail_every(mut self, n: u32) -> Self {
+        self.fail_every = n;
+        self
+    }
+
+    /// Makes every read after the first `count` (successful or not) fail
+    /// with [`SensorError::NoData`], as if the sensor had died
+    ///
+    /// The error's time is the simulated time of the last successful read.
+    pub fn limit(mut self, count: u32) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Returns the current simulated time, in milliseconds since the
+    /// first reading
+    pub fn elapsed_ms(&self) -> u64 {
+        self.elapsed_ms
+    }
+
+    /// Consumes the sensor, returning its profile
+    pub fn release(self) -> P {
+        self.profile
+    }
+}
+
+impl<P: Profile, E> crate::AirQualitySensor<E> for SimulatedSensor<P> {
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        if self.limit.is_some_and(|limit| self.reads >= limit) {
+            return Err(SensorError::NoData {
+                since: self.last_frame_ms,
+            });
+        }
+        let elapsed_ms = self.elapsed_ms;
+        self.elapsed_ms += u64::from(self.interval_ms);
+        self.reads += 1;
+        if self.fail_every > 0 && self.reads % self.fail_every == 0 {
+            return Err(SensorError::Timeout);
+        }
+        self.last_frame_ms = elapsed_ms;
+        Ok(self.profile.reading_at(elapsed_ms))
+    }
+
+    fn info(&self) -> SensorInfo {
+        INFO
+    }
+}
+
+/// A pollution event added on top of a [`Scenario`]'s background, in
+/// µg/m³ of PM2.5
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// A local source that quickly raises the concentration, which then
+    /// decays exponentially as the air is exchanged
+    Spike {
+        /// When the source starts, in milliseconds
+        start_ms: u64,
+        /// The concentration added at the top of the spike
+        peak: u16,
+        /// How long the concentration takes to reach the peak, in
+        /// milliseconds
+        rise_ms: u64,
+        /// How long the added concentration takes to halve after the peak,
+        /// in milliseconds
+        half_life_ms: u64,
+    },
+    /// A regional episode lasting hours or days, which builds up over its
+    /// first quarter, holds, and clears over its last quarter
+    Episode {
+        /// When the episode starts, in milliseconds
+        start_ms: u64,
+        /// How long the episode lasts, in milliseconds
+        duration_ms: u64,
+        /// The concentration added while the episode holds
+        peak: u16,
+    },
+}
+
+impl Event {
+    /// Cooking on a gas stove: a rise over ten minutes, and a half-life of
+    /// twenty minutes
+    pub const fn cooking(start_ms: u64, peak: u16) -> Self {
+        Event::Spike {
+            start_ms,
+            peak,
+            rise_ms: 10 * 60 * 1000,
+            half_life_ms: 20 * 60 * 1000,
+        }
+    }
+
+    /// A burst of smoke, such as a candle blown out or a cigarette: a rise
+    /// over a minute, and a half-life of ten minutes
+    pub const fn smoke(start_ms: u64, peak: u16) -> Self {
+        Event::Spike {
+            start_ms,
+            peak,
+            rise_ms: 60 * 1000,
+            half_life_ms: 10 * 60 * 1000,
+        }
+    }
+
+    /// Smoke from a distant wildfire lasting `duration_ms`
+    pub const fn wildfire(start_ms: u64, duration_ms: u64, peak: u16) -> Self {
+        Event::Episode {
+            start_ms,
+            duration_ms,
+            peak,
+        }
+    }
+
+    /// Returns the concentration the event adds `elapsed_ms` milliseconds
+    /// into the scenario
+    pub fn pm2_5_at(&self, elapsed_ms: u64) -> u16 {
+        match *self {
+            Event::Spike {
+                start_ms,
+                peak,
+                rise_ms,
+                half_life_ms,
+            } => match elapsed_ms.checked_sub(start_ms) {
+                None => 0,
+                Some(since) if since < rise_ms => interpolate(0, peak, since, rise_ms),
+                Some(since) => decay(peak, since - rise_ms, half_life_ms),
+            },
+            Event::Episode {
+                start_ms,
+                duration_ms,
+                peak,
+            } => {
+                let Some(since) = elapsed_ms.checked_sub(start_ms) else {
+                    return 0;
+                };
+                let ramp = duration_ms / 4;
+                if since >= duration_ms {
+                    0
+                } else if since < ramp {
+                    interpolate(0, peak, since, ramp)
+                } else if since >= duration_ms - ramp {
+                    interpolate(peak, 0, since - (duration_ms - ramp), ramp)
+                } else {
+                    peak
+                }
+            }
+        }
+    }
+}
+
+/// Returns `value` after decaying with a half-life of `half_life` for
+/// `elapsed`, interpolating linearly between halvings
+fn decay(value: u16, elapsed: u64, half_life: u64) -> u16 {
+    if half_life == 0 {
+        return 0;
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= 16 {
+        return 0;
+    }
+    let start = value >> halvings;
+    interpolate(start, start / 2, elapsed % half_life, half_life)
+}
+
+/// A model of the noise in a sensor's readings
+///
+/// Noise is roughly normally distributed, with a standard deviation of
+/// [`percent`](Noise::percent) of the concentration plus
+/// [`floor`](Noise::floor).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Noise {
+    /// The standard deviation proportional to the concentration, in percent
+    pub percent: u8,
+    /// The standard deviation independent of the concentration, in tenths
+    /// of a µg/m³
+    pub floor: u16,
+}
+
+impl Noise {
+    /// No noise
+    pub const NONE: Self = Self {
+        percent: 0,
+        floor: 0,
+    };
+
+    /// Noise typical of a Plantower sensor: 10% of the concentration, plus
+    /// 1µg/m³
+    pub const TYPICAL: Self = Self {
+        percent: 10,
+        floor: 10,
+    };
+}
+
+/// A generator of realistic PM2.5 time series
+///
+/// The concentration is the sum of a background, optionally following a
+/// daily cycle, and any [`Event`]s, to which noise is added.  Without
+/// noise, the series is a pure function of time; with noise, it depends on
+/// the [seed](Scenario::seed), so a given scenario always produces the same
+/// readings.
+///
+/// A scenario is a [`Profile`], so it can drive a [`SimulatedSensor`]; or
+/// its readings can be taken directly, with [`samples`](Scenario::samples)
+/// or [`frame_at`](Scenario::frame_at).
+#[derive(Debug, Clone)]
+pub struct Scenario<'a> {
+    baseline: u16,
+    amplitude: u16,
+    peak_ms: u64,
+    start_ms: u64,
+    events: &'a [Event],
+    noise: Noise,
+    rng: u64,
+}
+
+impl<'a> Scenario<'a> {
+    /// Creates a scenario with a steady background of `baseline` µg/m³,
+    /// starting at midnight
+    pub fn new(baseline: u16) -> Self {
+        Self {
+            baseline,
+            amplitude: 0,
+            peak_ms: 0,
+            start_ms: 0,
+            events: &[],
+            noise: Noise::NONE,
+            rng: 1,
+        }
+    }
+
+    /// Makes the background follow a daily cycle, `amplitude` µg/m³ above
+    /// the baseline at `peak_hour` (typically the evening, when the air
+    /// near the ground stops mixing) and as far below it twelve hours later
+    ///
+    /// The background is clamped at zero.
+    pub fn diurnal(mut self, amplitude: u16, peak_hour: u8) -> Self {
+        self.amplitude = amplitude;
+        self.peak_ms = u64::from(peak_hour) * HOUR_MS % DAY_MS;
+        self
+    }
+
+    /// Sets the time of day at which the scenario starts
+    pub fn starting_at_hour(mut self, hour: u8) -> Self {
+        self.start_ms = u64::from(hour) * HOUR_MS % DAY_MS;
+        self
+    }
+
+    /// Sets the events added to the background
+    pub fn events(mut self, events: &'a [Event]) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Sets the noise added to each reading
+    pub fn noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Seeds the noise generator
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = seed | 1;
+        self
+    }
+
+    /// Returns the true PM2.5 concentration, without noise, `elapsed_ms`
+    /// milliseconds into the scenario
+    pub fn pm2_5_at(&self, elapsed_ms: u64) -> u16 {
+        let phase = (self.start_ms + elapsed_ms + DAY_MS - self.peak_ms) % DAY_MS;
+        let cycle = i64::from(self.amplitude) * i64::from(cosine_permille(phase, DAY_MS)) / 1000;
+        let background = (i64::from(self.baseline) + cycle).max(0) as u16;
+        self.events.iter().fold(background, |total, event| {
+            total.saturating_add(event.pm2_5_at(elapsed_ms))
+        })
+    }
+
+    /// Returns a reading `elapsed_ms` milliseconds into the scenario, with
+    /// noise added
+    pub fn reading_at(&mut self, elapsed_ms: u64) -> Reading {
+        let pm2_5 = self.pm2_5_at(elapsed_ms);
+        reading_for(self.add_noise(pm2_5))
+    }
+
+    /// Returns a data frame carrying the reading `elapsed_ms` milliseconds
+    /// into the scenario, as a sensor would send it
+    pub fn frame_at(&mut self, elapsed_ms: u64) -> [u8; FRAME_LEN] {
+        encode_frame(&self.reading_at(elapsed_ms))
+    }
+
+    /// Returns an infinite iterator over readings taken every
+    /// `interval_ms`, with the time of each
+    pub fn samples(&mut self, interval_ms: u32) -> Samples<'_, 'a> {
+        Samples {
+            scenario: self,
+            elapsed_ms: 0,
+            interval_ms,
+        }
+    }
+
+    fn add_noise(&mut self, pm2_5: u16) -> u16 {
+        if self.noise == Noise::NONE {
+            return pm2_5;
+        }
+        // In tenths of a µg/m³, scaled by 1000
+        let proportional = i64::from(pm2_5) * i64::from(self.noise.percent) * self.normal() / 10;
+        let floor = i64::from(self.noise.floor) * self.normal();
+        let tenths = i64::from(pm2_5) * 10 + (proportional + floor) / 1000;
+        ((tenths + 5) / 10).clamp(0, u16::MAX.into()) as u16
+    }
+
+    /// Returns a roughly normally distributed value with a mean of zero and
+    /// a standard deviation of 1000
+    fn normal(&mut self) -> i64 {
+        // The sum of three uniform values between -1000 and 1000
+        (0..3)
+            .map(|_| (self.next_random() % 2001) as i64 - 1000)
+            .sum()
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+impl Profile for Scenario<'_> {
+    fn reading_at(&mut self, elapsed_ms: u64) -> Reading {
+        Scenario::reading_at(self, elapsed_ms)
+    }
+}
+
+/// An infinite iterator over a [`Scenario`]'s readings, returned by
+/// [`Scenario::samples`]
+#[derive(Debug)]
+pub struct Samples<'s, 'a> {
+    scenario: &'s mut Scenario<'a>,
+    elapsed_ms: u64,
+    interval_ms: u32,
+}
+
+impl Iterator for Samples<'_, '_> {
+    type Item = (u64, Reading);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elapsed_ms = self.elapsed_ms;
+        self.elapsed_ms += u64::from(self.interval_ms);
+        Some((elapsed_ms, self.scenario.reading_at(elapsed_ms)))
+    }
+}
+
+/// Returns the cosine of `phase` as a fraction of `period`, in thousandths
+///
+/// This uses Bhaskara I's approximation of the sine, which is within about
+/// 0.2% everywhere, to avoid floating point math.
+fn cosine_permille(phase: u64, period: u64) -> i32 {
+    if period < 2 {
+        return 1000;
+    }
+    // The cosine is the sine a quarter period later
+    let position = (phase % period + period / 4) % period;
+    let half = i128::from(period / 2);
+    let (x, sign) = if i128::from(position) < half {
+        (i128::from(position), 1)
+    } else {
+        (i128::from(position) - half, -1)
+    };
+    let product = x * (half - x);
+    let sine = 16_000 * product / (5 * half * half - 4 * product);
+    sign * sine as i32
+}
@@ -0,0 +1,97 @@
+//! Combines a particulate sensor with a separate temperature and humidity
+//! sensor.
+//!
+//! [`EnvironmentalStation`] pairs any [`AirQualitySensor`] with any
+//! [`TempHumiditySensor`] (for example, a wrapper around a BME280 driver),
+//! reads both, and feeds the relative humidity into the
+//! [`humidity`](crate::humidity) correction.  The two sensors may use
+//! different bus error types.
+
+use core::fmt;
+
+use crate::{
+    capability::{TempHumidity, TempHumiditySensor},
+    humidity, AirQualitySensor, Reading, SensorError,
+};
+
+/// A particulate reading with the temperature and humidity measured
+/// alongside it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvReading {
+    /// The particulate reading
+    pub reading: Reading,
+    /// The temperature and relative humidity
+    pub temp_humidity: TempHumidity,
+    /// The humidity-corrected PM2.5 concentration, in tenths of a µg/m³,
+    /// as computed by [`humidity::epa_pm2_5`]
+    pub corrected_pm2_5: u32,
+}
+
+/// Describes errors returned by an [`EnvironmentalStation`]
+#[derive(Debug)]
+pub enum StationError<P, T> {
+    /// The particulate sensor failed
+    Particulate(SensorError<P>),
+    /// The temperature and humidity sensor failed
+    TempHumidity(SensorError<T>),
+}
+
+impl<P: fmt::Debug, T: fmt::Debug> fmt::Display for StationError<P, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StationError::Particulate(error) => write!(f, "Particulate sensor: {}", error),
+            StationError::TempHumidity(error) => {
+                write!(f, "Temperature/humidity sensor: {}", error)
+            }
+        }
+    }
+}
+
+impl<P: fmt::Debug, T: fmt::Debug> core::error::Error for StationError<P, T> {}
+
+/// A particulate sensor paired with a temperature and humidity sensor
+pub struct EnvironmentalStation<P, T> {
+    particulate: P,
+    temp_humidity: T,
+}
+
+impl<P, T> EnvironmentalStation<P, T> {
+    /// Pairs `particulate` with `temp_humidity`
+    pub fn new(particulate: P, temp_humidity: T) -> Self {
+        Self {
+            particulate,
+            temp_humidity,
+        }
+    }
+
+    /// Consumes the station, returning the particulate sensor and the
+    /// temperature and humidity sensor
+    pub fn release(self) -> (P, T) {
+        (self.particulate, self.temp_humidity)
+    }
+
+    /// Reads both sensors and combines their measurements
+    ///
+    /// The particulate sensor is read first, so that the humidity is
+    /// measured as close as possible to the end of the particulate sensor's
+    /// (comparatively long) sampling period.
+    pub fn read<EP, ET>(&mut self) -> Result<EnvReading, StationError<EP, ET>>
+    where
+        P: AirQualitySensor<EP>,
+        T: TempHumiditySensor<ET>,
+    {
+        let reading = self.particulate.read().map_err(StationError::Particulate)?;
+        let temp_humidity = self
+            .temp_humidity
+            .read_temp_humidity()
+            .map_err(StationError::TempHumidity)?;
+        Ok(EnvReading {
+            reading,
+            temp_humidity,
+            corrected_pm2_5: humidity::epa_pm2_5(
+                reading.pm2_5(),
+                temp_humidity.relative_humidity(),
+            ),
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! A [`Clock`] supplies the current time in whatever representation suits
+//! the platform: [`SystemClock`] and [`UnixClock`] with `std`,
+//! [`EmbassyClock`] with the `embassy-time` feature, or any closure
+//! returning a tick count (for example, from a hardware timer or an RTIC
+//! monotonic).  Wrapping a sensor in a [`Stamped`] adapter stamps each
+//! reading with the time it was parsed, which the windowed statistics in
+//! [`history`](crate::history) depend on.
+
+use crate::{AirQualitySensor, Reading, SensorError, SensorInfo};
+
+/// A source of timestamps
+pub trait Clock {
+    /// The type of timestamp returned by the clock
+    type Instant: Copy;
+
+    /// Returns the current time
+    fn now(&mut self) -> Self::Instant;
+}
+
+/// Any closure returning a timestamp (such as a tick count from a hardware
+/// timer) can be used as a clock
+impl<F, T> Clock for F
+where
+    F: FnMut() -> T,
+    T: Copy,
+{
+    type Instant = T;
+
+    fn now(&mut self) -> T {
+        self()
+    }
+}
+
+/// The system's wall-clock time
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    type Instant = std::time::SystemTime;
+
+    fn now(&mut self) -> Self::Instant {
+        std::time::SystemTime::now()
+    }
+}
+
+/// The system's wall-clock time, as whole seconds since the Unix epoch
+///
+/// These timestamps can be pushed directly into a
+/// [`History`](crate::history::History).
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnixClock;
+
+#[cfg(feature = "std")]
+impl Clock for UnixClock {
+    type Instant = u64;
+
+    fn now(&mut self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// The Embassy time driver's monotonic clock
+#[cfg(feature = "embassy-time")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbassyClock;
+
+#[cfg(feature = "embassy-time")]
+impl Clock for EmbassyClock {
+    type Instant = embassy_time::Instant;
+
+    fn now(&mut self) -> Self::Instant {
+        embassy_time::Instant::now()
+    }
+}
+
+/// A value along with the time at which it was produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamped<R, T = u64> {
+    /// The time at which the value was produced
+    pub timestamp: T,
+    /// The value itself
+    pub value: R,
+}
+
+impl<R, T> Timestamped<R, T> {
+    /// Pairs `value` with `timestamp`
+    pub fn new(timestamp: T, value: R) -> Self {
+        Self { timestamp, value }
+    }
+
+    /// Applies `f` to the value, keeping the timestamp
+    pub fn map<U, F>(self, f: F) -> Timestamped<U, T>
+    where
+        F: FnOnce(R) -> U,
+    {
+        Timestamped {
+            timestamp: self.timestamp,
+            value: f(self.value),
+        }
+    }
+}
+
+/// Wraps a sensor to stamp each reading with the time it was taken
+pub struct Stamped<S, C> {
+    sensor: S,
+    clock: C,
+}
+
+impl<S, C: Clock> Stamped<S, C> {
+    /// Wraps `sensor`, taking timestamps from `clock`
+    pub fn new(sensor: S, clock: C) -> Self {
+        Self { sensor, clock }
+    }
+
+    /// Consumes the wrapper, returning the underlying sensor and clock
+    pub fn release(self) -> (S, C) {
+        (self.sensor, self.clock)
+    }
+
+    /// Reads a single sensor measurement, stamped with the time at which it
+    /// was parsed
+    pub fn read_timestamped<E>(
+        &mut self,
+    ) -> Result<Timestamped<Reading, C::Instant>, SensorError<E>>
+    where
+        S: AirQualitySensor<E>,
+    {
+        let reading = self.sensor.read()?;
+        Ok(Timestamped::new(self.clock.now(), reading))
+    }
+}
+
+impl<S, C, E> AirQualitySensor<E> for Stamped<S, C>
+where
+    S: AirQualitySensor<E>,
+{
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        self.sensor.read()
+    }
+
+    fn info(&self) -> SensorInfo {
+        self.sensor.info()
+    }
+}
@@ -0,0 +1,196 @@
+//! Classification of readings in a [`History`] as rising, falling, or
+//! stable.
+//!
+//! Thresholds react only once a level has been reached; a trend can warn
+//! earlier, e.g. to start ventilating while PM2.5 is still climbing
+//! towards an alert threshold.  A [`TrendAnalyzer`] fits a least-squares
+//! line through the most recent readings of a field and compares the
+//! change that line predicts over its window against a threshold, so a
+//! single noisy reading does not flip the result.
+//!
+//! ```
+//! use sen0177::{
+//!     history::History,
+//!     trend::{Trend, TrendAnalyzer},
+//!     Reading,
+//! };
+//!
+//! # let readings = [Reading::default(); 10];
+//! let mut history = History::<60>::new();
+//! for (second, reading) in readings.into_iter().enumerate() {
+//!     history.push(second as u64, reading);
+//! }
+//! // Over the last 5 minutes, a change of 5µg/m³ or more is a trend
+//! let analyzer = TrendAnalyzer::new(300, 50);
+//! if analyzer.trend(&history, Reading::pm2_5) == Some(Trend::Rising) {
+//!     // turn on the fan
+//! }
+//! ```
+
+use crate::{
+    aqi::{Aqi, AqiInput},
+    history::History,
+    Reading,
+};
+
+/// The direction in which a value is moving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trend {
+    /// The value is increasing by at least the threshold over the window
+    Rising,
+    /// The value is decreasing by at least the threshold over the window
+    Falling,
+    /// The value is changing by less than the threshold over the window
+    Stable,
+}
+
+/// Trends of a reading's standard (CF=1) concentrations and its AQI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReadingTrends {
+    /// The trend of the PM1 concentration
+    pub pm1: Trend,
+    /// The trend of the PM2.5 concentration
+    pub pm2_5: Trend,
+    /// The trend of the PM10 concentration
+    pub pm10: Trend,
+    /// The trend of the hourly AQI, or `None` while the history is too
+    /// short to compute one (see [`TrendAnalyzer::aqi_trend`])
+    pub aqi: Option<Trend>,
+}
+
+/// Classifies the trend of values in a [`History`]
+///
+/// The trend is taken over the readings stamped within `window` (in the
+/// history's timestamp units) of the most recent one.  A line is fitted
+/// through them, and the change along that line over the whole window, in
+/// tenths of a unit, is compared against `threshold`: a change of at least
+/// `threshold` upwards is [`Trend::Rising`], one of at least `threshold`
+/// downwards is [`Trend::Falling`], and anything smaller is
+/// [`Trend::Stable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrendAnalyzer {
+    window: u64,
+    threshold: u32,
+}
+
+impl TrendAnalyzer {
+    /// Creates a new analyzer over the last `window` timestamp units,
+    /// treating a change of `threshold` tenths of a unit over that window
+    /// as a trend
+    pub const fn new(window: u64, threshold: u32) -> Self {
+        Self { window, threshold }
+    }
+
+    /// Returns the analyzer's window
+    pub const fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Returns the analyzer's threshold, in tenths of a unit
+    pub const fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Returns the change in `field` over the window along the line fitted
+    /// through the readings in it, in tenths of a unit
+    ///
+    /// Returns `None` unless the window holds at least two readings with
+    /// different timestamps, or if the timestamps in the window ever step
+    /// backwards (e.g. after the clock was reset).
+    pub fn change<const N: usize, F>(&self, history: &History<N>, field: F) -> Option<i32>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        let (latest, _) = history.latest()?;
+        let since = latest.saturating_sub(self.window);
+        self.fit(
+            history
+                .window(since)
+                .map(|(timestamp, reading)| (timestamp, field(&reading))),
+        )
+    }
+
+    /// Fits a line through `points` and returns the change along it over
+    /// the window, in tenths of a unit
+    fn fit(&self, points: impl Iterator<Item = (u64, u16)>) -> Option<i32> {
+        let mut first = None;
+        let mut previous = 0;
+        let (mut n, mut sum_x, mut sum_y, mut sum_xx, mut sum_xy) = (0i128, 0, 0, 0, 0);
+        for (timestamp, value) in points {
+            if timestamp < previous {
+                return None;
+            }
+            previous = timestamp;
+            // Offsets from the first reading keep the sums small
+            let x = i128::from(timestamp - *first.get_or_insert(timestamp));
+            let y = i128::from(value);
+            n += 1;
+            sum_x += x;
+            sum_y += y;
+            sum_xx += x * x;
+            sum_xy += x * y;
+        }
+        let denominator = n * sum_xx - sum_x * sum_x;
+        if denominator == 0 {
+            return None;
+        }
+        let numerator = (n * sum_xy - sum_x * sum_y) * i128::from(self.window) * 10;
+        let change = numerator / denominator;
+        Some(change.clamp(i32::MIN.into(), i32::MAX.into()) as i32)
+    }
+
+    /// Classifies a change over the window against the threshold
+    fn classify(&self, change: i32) -> Trend {
+        if change.unsigned_abs() < self.threshold.max(1) {
+            Trend::Stable
+        } else if change > 0 {
+            Trend::Rising
+        } else {
+            Trend::Falling
+        }
+    }
+
+    /// Classifies the trend of `field` over the window
+    ///
+    /// `field` can select any reading field (e.g. `Reading::pm2_5`) or
+    /// compute a derived value.  Returns `None` under the same conditions
+    /// as [`change`](TrendAnalyzer::change).
+    pub fn trend<const N: usize, F>(&self, history: &History<N>, field: F) -> Option<Trend>
+    where
+        F: Fn(&Reading) -> u16,
+    {
+        Some(self.classify(self.change(history, field)?))
+    }
+
+    /// Classifies the trend of the hourly AQI over the window, with the
+    /// threshold in tenths of an AQI point
+    ///
+    /// Each reading in the window is given the AQI of the hour up to it
+    /// (see [`AqiInput::hourly`]), so the history's timestamps must be in
+    /// seconds, and it should hold an hour of readings.  Readings with less
+    /// than 45 minutes of readings before them are skipped, so this returns
+    /// `None` until the history spans at least 45 minutes.
+    pub fn aqi_trend<const N: usize>(&self, history: &History<N>) -> Option<Trend> {
+        let (latest, _) = history.latest()?;
+        let since = latest.saturating_sub(self.window);
+        let change = self.fit(history.window(since).filter_map(|(timestamp, _)| {
+            let aqi = Aqi::from(AqiInput::hourly(history, timestamp)?);
+            Some((timestamp, aqi.value()))
+        }))?;
+        Some(self.classify(change))
+    }
+
+    /// Classifies the trends of the standard concentrations and the AQI
+    /// over the window, all against the same threshold
+    ///
+    /// Returns `None` unless the concentrations have a trend; the AQI's
+    /// trend may still be missing.
+    pub fn reading_trends<const N: usize>(&self, history: &History<N>) -> Option<ReadingTrends> {
+        Some(ReadingTrends {
+            pm1: self.trend(history, Reading::pm1)?,
+            pm2_5: self.trend(history, Reading::pm2_5)?,
+            pm10: self.trend(history, Reading::pm10)?,
+            aqi: self.aqi_trend(history),
+        })
+    }
+}
@@ -0,0 +1,131 @@
+//! If the sensor's fan dies, or its TX line breaks, a blocking serial
+//! driver with no [timeout](crate::serial::Sen0177Builder::timeout_polls)
+//! waits forever, and one with a timeout can't tell a dead sensor from a
+//! momentary hiccup.  A [`Watchdog`] is an [idle hook](Idle) that tracks
+//! the time since the last valid frame on an injected clock, and ends the
+//! read with [`SensorError::NoData`] once that exceeds its timeout, calling
+//! an optional callback first, so that a supervisor can power-cycle the
+//! sensor (e.g. by pulsing its RESET pin) and carry on.
+//!
+//! ```
+//! use sen0177::{serial::Sen0177, watchdog::Watchdog, SensorError};
+//! # use embedded_hal_nb::{nb, serial::{ErrorKind, ErrorType, Read}};
+//! # struct Dead;
+//! # impl ErrorType for Dead { type Error = ErrorKind; }
+//! # impl Read<u8> for Dead {
+//! #     fn read(&mut self) -> nb::Result<u8, ErrorKind> { Err(nb::Error::WouldBlock) }
+//! # }
+//! # let uart = Dead;
+//! # let mut ticks = 0u64;
+//! # let millis = move || { ticks += 100; ticks };
+//! # fn pulse_reset_pin() {}
+//!
+//! // Give up after five seconds without a valid frame
+//! let watchdog = Watchdog::new(millis, 5000).on_no_data(|_since| pulse_reset_pin());
+//! let mut sensor = Sen0177::new(uart).with_idle(watchdog);
+//! assert!(matches!(sensor.read_raw(), Err(SensorError::NoData { .. })));
+//! ```
+//!
+//! [`SensorError::NoData`]: crate::SensorError::NoData
+
+use crate::{
+    serial::{Idle, Spin},
+    time::Clock,
+};
+
+/// An idle hook that ends reads after a timeout without a valid frame
+///
+/// Times come from clock `C`, in milliseconds.  The timer starts at the
+/// first check, and restarts with each valid frame and each time the
+/// watchdog fires, so a sensor that stays silent is reported once per
+/// timeout.  Between checks, the watchdog calls the idle hook `W` that it
+/// wraps ([`Spin`] unless set with [`with_idle`](Watchdog::with_idle)).
+#[derive(Debug, Clone)]
+pub struct Watchdog<C, W = Spin, F = fn(u64)> {
+    clock: C,
+    timeout_ms: u64,
+    idle: W,
+    on_no_data: Option<F>,
+    last_frame: Option<u64>,
+}
+
+impl<C> Watchdog<C>
+where
+    C: Clock<Instant = u64>,
+{
+    /// Creates a watchdog firing after `timeout_ms` milliseconds without a
+    /// valid frame, timed by `clock`
+    pub fn new(clock: C, timeout_ms: u64) -> Self {
+        Self {
+            clock,
+            timeout_ms,
+            idle: Spin,
+            on_no_data: None,
+            last_frame: None,
+        }
+    }
+}
+
+impl<C, W, F> Watchdog<C, W, F>
+where
+    C: Clock<Instant = u64>,
+    W: Idle,
+    F: FnMut(u64),
+{
+    /// Sets the idle hook called between checks, replacing the current one
+    pub fn with_idle<W2: Idle>(self, idle: W2) -> Watchdog<C, W2, F> {
+        Watchdog {
+            clock: self.clock,
+            timeout_ms: self.timeout_ms,
+            idle,
+            on_no_data: self.on_no_data,
+            last_frame: self.last_frame,
+        }
+    }
+
+    /// Sets a callback called with the time of the last valid frame each
+    /// time the watchdog fires, replacing the current one
+    pub fn on_no_data<F2: FnMut(u64)>(self, callback: F2) -> Watchdog<C, W, F2> {
+        Watchdog {
+            clock: self.clock,
+            timeout_ms: self.timeout_ms,
+            idle: self.idle,
+            on_no_data: Some(callback),
+            last_frame: self.last_frame,
+        }
+    }
+
+    /// Returns the time of the last valid frame (or of the first check, or
+    /// the last time the watchdog fired, if later)
+    pub fn last_frame(&self) -> Option<u64> {
+        self.last_frame
+    }
+}
+
+impl<C, W, F> Idle for Watchdog<C, W, F>
+where
+    C: Clock<Instant = u64>,
+    W: Idle,
+    F: FnMut(u64),
+{
+    fn idle(&mut self) {
+        self.idle.idle();
+    }
+
+    fn frame_received(&mut self) {
+        self.last_frame = Some(self.clock.now());
+    }
+
+    fn no_data_since(&mut self) -> Option<u64> {
+        let now = self.clock.now();
+        let since = *self.last_frame.get_or_insert(now);
+        if now.saturating_sub(since) <= self.timeout_ms {
+            return None;
+        }
+        self.last_frame = Some(now);
+        if let Some(callback) = self.on_no_data.as_mut() {
+            callback(since);
+        }
+        Some(since)
+    }
+}
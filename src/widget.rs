@@ -0,0 +1,199 @@
+//! [`ReadingWidget`] draws, within its bounds, the current PM2.5
+//! concentration with its unit, given an [`Aqi`], a swatch in the color of
+//! its category (see [`display::color_for`]), and, given a [`History`], a
+//! sparkline of recent PM2.5 concentrations along the bottom:
+//!
+//! ```text
+//! ██ 12 µg/m³
+//! ██    PM2.5
+//! ___/\__/\___
+//! ```
+//!
+//! It needs a color display; colors are converted from [`Rgb888`], as all
+//! of `embedded-graphics`' RGB color types can be.
+//!
+//! ```
+//! # use embedded_graphics::{prelude::*, primitives::Rectangle};
+//! # fn example<D: DrawTarget<Color = Rgb565>>(display: &mut D, reading: sen0177::Reading) -> Result<(), D::Error> {
+//! use sen0177::{aqi::AqiCalculator, history::History, widget::ReadingWidget};
+//!
+//! let mut history = History::<120>::new();
+//! history.push(0, reading);
+//! let mut calculator = AqiCalculator::new(60);
+//! calculator.push(0, reading);
+//! let mut widget =
+//!     ReadingWidget::new(reading, Rectangle::new(Point::zero(), Size::new(128, 64)), Rgb565::WHITE)
+//!         .with_history(&history);
+//! if let Some(aqi) = calculator.nowcast() {
+//!     widget = widget.with_aqi(aqi);
+//! }
+//! widget.draw(display)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    mono_font::{
+        iso_8859_1::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
+    pixelcolor::{PixelColor, Rgb888},
+    primitives::{Line, Primitive, PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+    Drawable,
+};
+
+use crate::{aqi::Aqi, display, history::History, Reading};
+
+/// The height of the value row, matching the large font
+const VALUE_HEIGHT: u32 = 20;
+
+/// The width of the AQI color swatch
+const SWATCH_WIDTH: u32 = 8;
+
+/// The space between the elements of the widget
+const GAP: i32 = 4;
+
+/// A widget showing a reading's PM2.5 concentration, and optionally an
+/// AQI category color and a sparkline of recent readings
+///
+/// See the [module documentation](self).  The sparkline is drawn when the
+/// bounds leave room for it below the value row, with one pixel column per
+/// reading, the newest on the right, scaled to the highest of the readings
+/// shown.
+pub struct ReadingWidget<'a, C, const N: usize = 0> {
+    reading: Reading,
+    aqi: Option<Aqi>,
+    history: Option<&'a History<N>>,
+    bounds: Rectangle,
+    text_color: C,
+}
+
+impl<C: PixelColor> ReadingWidget<'static, C> {
+    /// Creates a widget showing `reading` within `bounds`, with text (and
+    /// the sparkline) in `text_color`
+    pub fn new(reading: Reading, bounds: Rectangle, text_color: C) -> Self {
+        Self {
+            reading,
+            aqi: None,
+            history: None,
+            bounds,
+            text_color,
+        }
+    }
+}
+
+impl<'a, C: PixelColor, const N: usize> ReadingWidget<'a, C, N> {
+    /// Adds a swatch in the color of the category of `aqi`, e.g. an
+    /// [`AqiCalculator`](crate::aqi::AqiCalculator)'s NowCast
+    pub fn with_aqi(mut self, aqi: Aqi) -> Self {
+        self.aqi = Some(aqi);
+        self
+    }
+
+    /// Adds a sparkline of the PM2.5 concentrations in `history`
+    pub fn with_history<'b, const M: usize>(
+        self,
+        history: &'b History<M>,
+    ) -> ReadingWidget<'b, C, M> {
+        ReadingWidget {
+            reading: self.reading,
+            aqi: self.aqi,
+            history: Some(history),
+            bounds: self.bounds,
+            text_color: self.text_color,
+        }
+    }
+
+    fn draw_sparkline<D>(&self, target: &mut D, history: &History<N>) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let area_top = self.bounds.top_left.y + VALUE_HEIGHT as i32 + GAP;
+        let bottom = self.bounds.top_left.y + self.bounds.size.height as i32 - 1;
+        let height = bottom - area_top;
+        if height < 1 {
+            return Ok(());
+        }
+        let columns = self.bounds.size.width as usize;
+        let max = history
+            .iter()
+            .rev()
+            .take(columns)
+            .map(|(_, reading)| reading.pm2_5())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let style = PrimitiveStyle::with_stroke(self.text_color, 1);
+        let right = self.bounds.top_left.x + self.bounds.size.width as i32 - 1;
+        let mut newer: Option<Point> = None;
+        for (column, (_, reading)) in history.iter().rev().take(columns).enumerate() {
+            let y = bottom - (i32::from(reading.pm2_5()) * height / i32::from(max));
+            let point = Point::new(right - column as i32, y);
+            Line::new(newer.unwrap_or(point), point)
+                .into_styled(style)
+                .draw(target)?;
+            newer = Some(point);
+        }
+        Ok(())
+    }
+}
+
+impl<C, const N: usize> Drawable for ReadingWidget<'_, C, N>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    type Color = C;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let origin = self.bounds.top_left;
+        if let Some(aqi) = self.aqi {
+            let aqi_color = display::color_for(aqi);
+            Rectangle::new(origin, Size::new(SWATCH_WIDTH, VALUE_HEIGHT))
+                .into_styled(PrimitiveStyle::with_fill(
+                    Rgb888::new(aqi_color.r, aqi_color.g, aqi_color.b).into(),
+                ))
+                .draw(target)?;
+        }
+
+        let mut digits = [0u8; 5];
+        let value = Text::with_baseline(
+            format_digits(self.reading.pm2_5(), &mut digits),
+            origin + Point::new(SWATCH_WIDTH as i32 + GAP, 0),
+            MonoTextStyle::new(&FONT_10X20, self.text_color),
+            Baseline::Top,
+        )
+        .draw(target)?;
+        let small = MonoTextStyle::new(&FONT_6X10, self.text_color);
+        let label = value + Point::new(GAP, 0);
+        Text::with_baseline("µg/m³", label, small, Baseline::Top).draw(target)?;
+        Text::with_baseline("PM2.5", label + Point::new(0, 10), small, Baseline::Top)
+            .draw(target)?;
+
+        if let Some(history) = self.history {
+            self.draw_sparkline(target, history)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats `value` in decimal into `buf`, without `core::fmt`
+fn format_digits(mut value: u16, buf: &mut [u8; 5]) -> &str {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    core::str::from_utf8(&buf[start..]).unwrap_or_default()
+}
@@ -0,0 +1,58 @@
+//! Tests of threshold alerting with hysteresis
+
+use sen0177::{
+    alerts::{AlertEvent, Threshold, ThresholdWatcher},
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm2_5);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+fn watcher() -> ThresholdWatcher<fn(&Reading) -> u16> {
+    let threshold = Threshold {
+        rising: 35,
+        falling: 25,
+    };
+    ThresholdWatcher::new(threshold, Reading::pm2_5)
+}
+
+#[test]
+fn crossing_the_rising_threshold_enters() {
+    let mut watcher = watcher();
+    assert_eq!(watcher.update(&reading(34)), None);
+    assert!(!watcher.is_active());
+
+    assert_eq!(watcher.update(&reading(35)), Some(AlertEvent::Entered));
+    assert!(watcher.is_active());
+    // Staying above only reports once
+    assert_eq!(watcher.update(&reading(80)), None);
+}
+
+#[test]
+fn hysteresis_band_holds_the_state() {
+    let mut watcher = watcher();
+    // Rising into the band doesn't enter
+    assert_eq!(watcher.update_value(30), None);
+    assert!(!watcher.is_active());
+
+    assert_eq!(watcher.update_value(40), Some(AlertEvent::Entered));
+    // Falling into the band, even below the rising threshold, doesn't exit
+    for value in [34, 25, 30, 34] {
+        assert_eq!(watcher.update_value(value), None);
+        assert!(watcher.is_active());
+    }
+}
+
+#[test]
+fn falling_below_the_falling_threshold_clears() {
+    let mut watcher = watcher();
+    assert_eq!(watcher.update(&reading(50)), Some(AlertEvent::Entered));
+    assert_eq!(watcher.update(&reading(24)), Some(AlertEvent::Exited));
+    assert!(!watcher.is_active());
+    assert_eq!(watcher.update(&reading(0)), None);
+
+    // And the alert can be entered again
+    assert_eq!(watcher.update(&reading(35)), Some(AlertEvent::Entered));
+}
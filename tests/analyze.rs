@@ -0,0 +1,50 @@
+mod common;
+
+use common::reading;
+use sen0177::{
+    analyze::{analyze, parse_hex, FrameKind, HexError},
+    protocol::{encode_frame, ProtocolError},
+};
+
+#[test]
+fn finds_frames_and_garbage() {
+    let ack = [0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74];
+    let mut corrupted = encode_frame(&reading(2));
+    corrupted[7] ^= 0x01;
+
+    let mut capture = vec![0x00, 0xff, 0x42];
+    capture.extend_from_slice(&encode_frame(&reading(1)));
+    capture.extend_from_slice(&ack);
+    capture.extend_from_slice(b"noise");
+    capture.extend_from_slice(&corrupted);
+    capture.extend_from_slice(&encode_frame(&reading(3))[..12]);
+
+    let analysis = analyze(&capture);
+    assert_eq!(analysis.frames.len(), 3);
+    assert_eq!(analysis.data_frames(), 2);
+    assert_eq!(analysis.checksum_failures(), 1);
+    assert_eq!(analysis.garbage_bytes, 8);
+    assert_eq!(analysis.garbage_runs, 2);
+    assert_eq!(analysis.longest_garbage_run, 5);
+    assert_eq!(analysis.truncated_bytes, 12);
+
+    assert_eq!(analysis.frames[0].offset, 3);
+    assert_eq!(analysis.frames[0].kind, FrameKind::Data(reading(1)));
+    assert_eq!(analysis.frames[0].checksum, Ok(()));
+    assert_eq!(analysis.frames[1].kind, FrameKind::Other { payload_len: 2 });
+    assert_eq!(analysis.frames[2].garbage_before, 5);
+    assert!(matches!(
+        analysis.frames[2].checksum,
+        Err(ProtocolError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn parses_hex_dumps() {
+    assert_eq!(
+        parse_hex("00000000: 42 4d, 0x00 0X1c\n424d # comment 99\n").unwrap(),
+        [0x42, 0x4d, 0x00, 0x1c, 0x42, 0x4d]
+    );
+    assert_eq!(parse_hex("42 4d 4g"), Err(HexError::InvalidToken(6)));
+    assert_eq!(parse_hex("42\n123"), Err(HexError::InvalidToken(3)));
+}
@@ -0,0 +1,110 @@
+//! Tests of AQI calculation from averaged concentrations
+
+use sen0177::{
+    aqi::{self, Aqi, AqiCalculator, AqiInput, AqiWindow},
+    history::History,
+    rolling::{DailyAverage, SECONDS_PER_HOUR},
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn refuses_a_single_reading() {
+    let mut calculator = AqiCalculator::new(60);
+    calculator.push(0, reading(80, 100));
+    assert_eq!(calculator.nowcast(), None);
+    assert_eq!(calculator.daily(), None);
+
+    let mut history = History::<8>::new();
+    history.push(0, reading(80, 100));
+    assert_eq!(AqiInput::hourly(&history, 0), None);
+}
+
+#[test]
+fn hourly_mean_of_a_history() {
+    let mut history = History::<64>::new();
+    for minute in 0..60 {
+        history.push(minute * 60, reading(if minute < 30 { 10 } else { 20 }, 30));
+    }
+    let input = AqiInput::hourly(&history, 59 * 60).unwrap();
+    assert_eq!(input.window(), AqiWindow::Hour);
+    assert_eq!(input.averages().pm2_5, 150);
+    assert_eq!(Aqi::from(input), aqi::pm2_5(150));
+
+    // Half an hour of readings isn't enough
+    assert_eq!(AqiInput::hourly(&history, 89 * 60), None);
+
+    // Readings after `now` don't count
+    let input = AqiInput::hourly(&history, 50 * 60).unwrap();
+    // (30 × 10µg/m³ + 21 × 20µg/m³) / 51
+    assert_eq!(input.averages().pm2_5, 141);
+}
+
+#[test]
+fn hourly_tolerates_timestamps_going_backwards() {
+    let mut history = History::<64>::new();
+    history.push(3_000, reading(10, 30));
+    for minute in 0..10 {
+        history.push(minute * 60, reading(10, 30));
+    }
+    assert_eq!(AqiInput::hourly(&history, 3_600), None);
+}
+
+#[test]
+fn nowcast_of_steady_air_is_the_mean() {
+    let mut calculator = AqiCalculator::new(60);
+    for minute in 0..3 * 60 {
+        calculator.push(minute * 60, reading(12, 40));
+    }
+    let input = AqiInput::nowcast(calculator.hours()).unwrap();
+    assert_eq!(input.window(), AqiWindow::NowCast);
+    assert_eq!(input.averages().pm2_5, 120);
+    assert_eq!(input.averages().pm10, 400);
+    assert_eq!(calculator.nowcast(), Some(aqi::pm2_5(120)));
+}
+
+#[test]
+fn nowcast_favors_recent_hours_when_changing() {
+    let mut hours = DailyAverage::daily(SECONDS_PER_HOUR as u32);
+    // Hourly means of 10, 10, ..., then 50 in the most recent hour
+    for hour in 0..11 {
+        hours.push(hour * SECONDS_PER_HOUR, reading(10, 10));
+    }
+    hours.push(11 * SECONDS_PER_HOUR, reading(50, 10));
+
+    // The ratio is 0.2, so the weight is clamped to a half
+    let input = AqiInput::nowcast(&hours).unwrap();
+    let expected = (500.0 + (1..11).map(|age| 100.0 * 0.5f64.powi(age)).sum::<f64>())
+        / (0..11).map(|age| 0.5f64.powi(age)).sum::<f64>();
+    assert!((f64::from(input.averages().pm2_5) - expected).abs() <= 1.0);
+    assert!(input.averages().pm2_5 > 250);
+}
+
+#[test]
+fn nowcast_needs_two_of_the_last_three_hours() {
+    let mut hours = DailyAverage::daily(SECONDS_PER_HOUR as u32);
+    hours.push(0, reading(10, 10));
+    hours.push(3 * SECONDS_PER_HOUR, reading(10, 10));
+    assert_eq!(AqiInput::nowcast(&hours), None);
+
+    hours.push(4 * SECONDS_PER_HOUR, reading(10, 10));
+    assert!(AqiInput::nowcast(&hours).is_some());
+}
+
+#[test]
+fn daily_needs_a_complete_day() {
+    let mut calculator = AqiCalculator::new(SECONDS_PER_HOUR as u32);
+    for hour in 0..17 {
+        calculator.push(hour * SECONDS_PER_HOUR, reading(30, 60));
+    }
+    assert_eq!(calculator.daily(), None);
+    calculator.push(17 * SECONDS_PER_HOUR, reading(30, 60));
+    assert_eq!(
+        calculator.daily(),
+        Some(aqi::pm2_5(300).max(aqi::pm10(600)))
+    );
+}
@@ -0,0 +1,185 @@
+//! Tests of the async serial driver, including cancellation part way
+//! through a frame
+
+mod common;
+
+use core::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::collections::VecDeque;
+
+use common::reading;
+use embassy_futures::{
+    block_on,
+    select::{select, Either},
+    yield_now,
+};
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read};
+use sen0177::{
+    protocol::encode_frame,
+    serial::{AsyncSen0177, Sen0177Builder},
+    SensorError,
+};
+
+/// An async UART that delivers chunks of data, each after being polled a
+/// given number of times without data
+#[derive(Default)]
+struct FakeUart {
+    chunks: VecDeque<(u32, Vec<u8>)>,
+}
+
+impl FakeUart {
+    fn chunk(mut self, delay_polls: u32, data: &[u8]) -> Self {
+        self.chunks.push_back((delay_polls, data.to_vec()));
+        self
+    }
+}
+
+impl ErrorType for FakeUart {
+    type Error = Infallible;
+}
+
+impl Read for FakeUart {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        loop {
+            match self.chunks.front_mut() {
+                None => return Ok(0),
+                Some((0, _)) => break,
+                Some((delay_polls, _)) => {
+                    *delay_polls -= 1;
+                    yield_now().await;
+                }
+            }
+        }
+        let (_, data) = self.chunks.front_mut().unwrap();
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        data.drain(..len);
+        if data.is_empty() {
+            self.chunks.pop_front();
+        }
+        Ok(len)
+    }
+}
+
+/// A delay that only adds up how long it was asked to wait
+#[derive(Default)]
+struct TotalDelay {
+    ns: u64,
+}
+
+impl DelayNs for TotalDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.ns += u64::from(ns);
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.ns += u64::from(ms) * 1_000_000;
+    }
+}
+
+/// A stand-in for a timer, which completes after being polled a given
+/// number of times
+struct Polls(u32);
+
+impl Future for Polls {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 == 0 {
+            Poll::Ready(())
+        } else {
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn reads_frames_across_chunks() {
+    let frame = encode_frame(&reading(12));
+    let uart = FakeUart::default()
+        .chunk(0, &[0x00, 0x13])
+        .chunk(2, &frame[..5])
+        .chunk(1, &frame[5..])
+        .chunk(0, &encode_frame(&reading(13)));
+    let mut sensor = AsyncSen0177::new(uart);
+
+    block_on(async {
+        assert_eq!(sensor.read().await.unwrap(), reading(12));
+        assert_eq!(sensor.read().await.unwrap(), reading(13));
+        assert!(matches!(sensor.read().await, Err(SensorError::Timeout)));
+    });
+}
+
+#[test]
+fn resumes_after_cancellation_mid_frame() {
+    let first = encode_frame(&reading(20));
+    let second = encode_frame(&reading(21));
+    let mut tail = first[10..].to_vec();
+    tail.extend_from_slice(&second);
+    let uart = FakeUart::default()
+        .chunk(0, &[0x42, 0x00])
+        .chunk(0, &first[..10])
+        .chunk(50, &tail);
+    let mut sensor = Sen0177Builder::new().build_async(uart);
+
+    block_on(async {
+        // Times out after the first part of the frame has been consumed
+        match select(sensor.read(), Polls(5)).await {
+            Either::First(result) => panic!("read should have timed out: {:?}", result),
+            Either::Second(()) => {}
+        }
+        assert_eq!(sensor.read().await.unwrap(), reading(20));
+        assert_eq!(sensor.read().await.unwrap(), reading(21));
+    });
+}
+
+#[test]
+fn resync_limits_survive_cancellation() {
+    let uart = FakeUart::default()
+        .chunk(0, &[0x00; 100])
+        .chunk(50, &[0x00; 100]);
+    let mut sensor = Sen0177Builder::new().resync_budget(150).build_async(uart);
+
+    block_on(async {
+        assert!(matches!(
+            select(sensor.read(), Polls(5)).await,
+            Either::Second(())
+        ));
+        assert!(matches!(
+            sensor.read().await,
+            Err(SensorError::LikelyBaudMismatch)
+        ));
+    });
+}
+
+#[test]
+fn readings_can_be_paced() {
+    let uart = FakeUart::default()
+        .chunk(0, &encode_frame(&reading(30)))
+        .chunk(0, &encode_frame(&reading(31)))
+        .chunk(0, &encode_frame(&reading(32)));
+    let mut sensor = AsyncSen0177::new(uart);
+    let mut delay = TotalDelay::default();
+
+    block_on(async {
+        let mut readings = sensor.readings();
+        assert_eq!(readings.next().await.unwrap().unwrap(), reading(30));
+
+        let mut paced = readings.paced(&mut delay, 1_000);
+        assert_eq!(paced.next().await.unwrap().unwrap(), reading(31));
+        assert_eq!(paced.next().await.unwrap().unwrap(), reading(32));
+        assert!(matches!(
+            paced.next().await,
+            Some(Err(SensorError::Timeout))
+        ));
+    });
+    // No wait before the first paced reading, then one before each other
+    assert_eq!(delay.ns, 2 * 1_000_000_000);
+}
@@ -0,0 +1,90 @@
+//! Tests of exponential backoff after read errors
+
+use std::collections::VecDeque;
+
+use sen0177::{backoff::Backoff, AirQualitySensor, Concentrations, Reading, SensorError};
+
+/// A sensor that returns a scripted sequence of successes and timeouts
+struct FakeSensor(VecDeque<bool>);
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        if self.0.pop_front().unwrap_or(false) {
+            let concentrations = Concentrations::new(5, 10, 20);
+            Ok(Reading::new(concentrations, concentrations, [0; 6]))
+        } else {
+            Err(SensorError::Timeout)
+        }
+    }
+}
+
+/// Records the delays requested, in milliseconds
+#[derive(Default)]
+struct Delays(Vec<u32>);
+
+impl embedded_hal::delay::DelayNs for &mut Delays {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.push(ns / 1_000_000);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.0.push(ms);
+    }
+}
+
+#[test]
+fn doubles_up_to_the_maximum_with_jitter() {
+    let mut backoff = Backoff::new(100, 1000).with_seed(42);
+    for nominal in [100, 200, 400, 800, 1000, 1000] {
+        let delay = backoff.next_delay_ms();
+        assert!(
+            (nominal / 2..=nominal).contains(&delay),
+            "{} outside jitter around {}",
+            delay,
+            nominal
+        );
+    }
+    assert_eq!(backoff.failures(), 6);
+
+    backoff.reset();
+    assert_eq!(backoff.failures(), 0);
+    assert!(backoff.next_delay_ms() <= 100);
+}
+
+#[test]
+fn survives_endless_failures() {
+    let mut backoff = Backoff::new(u32::MAX / 3, u32::MAX);
+    for _ in 0..100 {
+        assert!(backoff.next_delay_ms() >= u32::MAX / 6);
+    }
+}
+
+#[test]
+fn seeds_spread_retries() {
+    let delays = |seed| {
+        let mut backoff = Backoff::new(1000, 60_000).with_seed(seed);
+        (0..8).map(|_| backoff.next_delay_ms()).collect::<Vec<_>>()
+    };
+    assert_ne!(delays(1), delays(2));
+    assert_eq!(delays(1), delays(1));
+}
+
+#[test]
+fn readings_back_off_after_errors_and_reset_on_success() {
+    let script = [false, false, false, true, false, true];
+    let mut sensor = FakeSensor(script.into_iter().collect());
+    let mut delays = Delays::default();
+    let results = sensor
+        .readings()
+        .with_backoff(&mut delays, Backoff::new(100, 10_000))
+        .take(script.len())
+        .map(|result| result.is_ok())
+        .collect::<Vec<_>>();
+
+    assert_eq!(results, script);
+    // One delay after each error, the third longer than the first; none
+    // after a success
+    assert_eq!(delays.0.len(), 4);
+    assert!(delays.0[2] > delays.0[0]);
+    assert!(delays.0[3] <= 100);
+}
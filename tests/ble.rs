@@ -0,0 +1,28 @@
+//! Tests of the BLE characteristic encoding
+
+use sen0177::{ble, Concentrations, Reading};
+
+#[test]
+fn encodes_sfloat_kilograms_per_cubic_meter() {
+    // 0 × 10⁻⁸
+    assert_eq!(ble::encode_concentration(0), [0x00, 0x80]);
+    // 3 × 10⁻⁸ kg/m³ = 30µg/m³, rounded from 25µg/m³
+    assert_eq!(ble::encode_concentration(25), [0x03, 0x80]);
+    assert_eq!(ble::encode_concentration(24), [0x02, 0x80]);
+    // 2045 × 10⁻⁸ is the largest value before the exponent increases
+    assert_eq!(ble::encode_concentration(20450), [0xfd, 0x87]);
+    // 655 × 10⁻⁷
+    assert_eq!(ble::encode_concentration(u16::MAX), [0x8f, 0x92]);
+}
+
+#[test]
+fn encodes_reading_concentrations() {
+    let reading = Reading::new(
+        Concentrations::new(10, 120, 1000),
+        Concentrations::new(0, 0, 0),
+        [0; 6],
+    );
+    assert_eq!(ble::pm1(&reading), [0x01, 0x80]);
+    assert_eq!(ble::pm2_5(&reading), [0x0c, 0x80]);
+    assert_eq!(ble::pm10(&reading), [0x64, 0x80]);
+}
@@ -0,0 +1,117 @@
+//! Tests of bus error classification
+
+use std::collections::VecDeque;
+
+use embedded_hal::i2c::{self, NoAcknowledgeSource};
+use embedded_hal_nb::serial;
+use sen0177::{
+    bus::{BusErrorKind, Classified},
+    AirQualitySensor, Reading, SensorError, SensorInfo,
+};
+
+/// A sensor that returns a scripted sequence of results
+struct FakeSensor<E>(VecDeque<Result<Reading, SensorError<E>>>);
+
+impl<E> AirQualitySensor<E> for FakeSensor<E> {
+    fn read(&mut self) -> Result<Reading, SensorError<E>> {
+        self.0.pop_front().unwrap_or(Err(SensorError::Timeout))
+    }
+
+    fn info(&self) -> SensorInfo {
+        SensorInfo {
+            name: "Fake",
+            supports_atmospheric_pm: true,
+            supports_particle_counts: true,
+            supports_temperature_humidity: false,
+            frame_len: 32,
+            default_i2c_address: None,
+        }
+    }
+}
+
+#[test]
+fn classifies_serial_errors() {
+    let sensor = FakeSensor(VecDeque::from([
+        Err(SensorError::ReadError(serial::ErrorKind::FrameFormat)),
+        Err(SensorError::ReadError(serial::ErrorKind::Overrun)),
+        Err(SensorError::ChecksumMismatch),
+        Ok(Reading::default()),
+    ]));
+    let mut sensor = Classified::serial(sensor);
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ReadError(BusErrorKind::Framing))
+    ));
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ReadError(BusErrorKind::Overrun))
+    ));
+    assert!(matches!(sensor.read(), Err(SensorError::ChecksumMismatch)));
+    assert!(sensor.read().is_ok());
+    assert_eq!(sensor.info().name, "Fake");
+}
+
+#[test]
+fn classifies_i2c_errors() {
+    let kinds = [
+        (
+            i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            BusErrorKind::Disconnected,
+        ),
+        (
+            i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+            BusErrorKind::NoAcknowledge,
+        ),
+        (
+            i2c::ErrorKind::ArbitrationLoss,
+            BusErrorKind::ArbitrationLoss,
+        ),
+        (i2c::ErrorKind::Bus, BusErrorKind::Bus),
+        (i2c::ErrorKind::Other, BusErrorKind::Other),
+    ];
+    for (kind, expected) in kinds {
+        assert_eq!(BusErrorKind::from(kind), expected);
+    }
+
+    #[derive(Debug)]
+    struct HalError;
+    impl i2c::Error for HalError {
+        fn kind(&self) -> i2c::ErrorKind {
+            i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+        }
+    }
+    let mut sensor = Classified::i2c(FakeSensor(VecDeque::from([Err(SensorError::ReadError(
+        HalError,
+    ))])));
+    match sensor.read() {
+        Err(SensorError::ReadError(kind)) => {
+            assert_eq!(kind, BusErrorKind::Disconnected);
+            assert!(!kind.is_transient());
+        }
+        other => panic!("expected a bus error, got {:?}", other),
+    }
+}
+
+#[test]
+fn classifies_errors_in_place() {
+    let error = SensorError::ReadError(serial::ErrorKind::Parity);
+    assert_eq!(
+        error.bus_error_kind(BusErrorKind::of_serial),
+        Some(BusErrorKind::Parity)
+    );
+    assert!(BusErrorKind::Parity.is_transient());
+    let timeout: SensorError<serial::ErrorKind> = SensorError::Timeout;
+    assert_eq!(timeout.bus_error_kind(BusErrorKind::of_serial), None);
+}
+
+#[test]
+fn classifies_io_errors() {
+    assert_eq!(
+        BusErrorKind::from(std::io::ErrorKind::BrokenPipe),
+        BusErrorKind::Disconnected
+    );
+    assert_eq!(
+        BusErrorKind::from(std::io::ErrorKind::TimedOut),
+        BusErrorKind::Timeout
+    );
+}
@@ -0,0 +1,78 @@
+//! Tests of fitting corrections against a reference monitor
+#![cfg(feature = "float")]
+
+use sen0177::{
+    calibrate::{fit, Calibration, FitError, Model, Sample},
+    humidity,
+};
+
+/// Samples whose reference follows the EPA correction exactly
+fn epa_samples() -> Vec<Sample> {
+    let mut state = 3u32;
+    let mut next = |range: u32| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 16) % range
+    };
+    (0..200)
+        .map(|_| {
+            let sensor = 5 + next(150) as u16;
+            let relative_humidity = 200 + next(700) as u16;
+            let reference = humidity::epa_pm2_5(sensor, relative_humidity);
+            Sample::new(sensor, relative_humidity, reference)
+        })
+        .collect()
+}
+
+#[test]
+fn recovers_a_known_correction() {
+    let fitted = fit(&epa_samples(), Model::WithHumidity).unwrap();
+    let Calibration {
+        slope,
+        humidity,
+        offset,
+    } = fitted.calibration;
+    // The reference is rounded to tenths, so allow a little slack
+    assert!((slope - 5240).abs() <= 5, "slope {}", slope);
+    assert!((humidity - -862).abs() <= 5, "humidity {}", humidity);
+    assert!((offset - 57_500).abs() <= 300, "offset {}", offset);
+    assert!(fitted.r_squared > 0.999);
+    assert!(fitted.mean_absolute_error < 0.05);
+    assert_eq!(
+        fitted.calibration.apply(40, 500),
+        humidity::epa_pm2_5(40, 500)
+    );
+}
+
+#[test]
+fn linear_fit_has_no_humidity_term() {
+    let samples: Vec<_> = (1..=20)
+        .map(|sensor| Sample::new(sensor, 500, u32::from(sensor) * 6 + 20))
+        .collect();
+    let fitted = fit(&samples, Model::Linear).unwrap();
+    assert_eq!(
+        fitted.calibration,
+        Calibration {
+            slope: 6000,
+            humidity: 0,
+            offset: 20_000,
+        }
+    );
+    assert_eq!(fitted.calibration.apply(10, 0), 80);
+
+    // The humidity never varies, so it can't be fitted
+    assert_eq!(
+        fit(&samples, Model::WithHumidity),
+        Err(FitError::Degenerate)
+    );
+}
+
+#[test]
+fn rejects_underdetermined_samples() {
+    let sample = Sample::new(10, 500, 80);
+    assert_eq!(fit(&[sample], Model::Linear), Err(FitError::TooFewSamples));
+    assert_eq!(
+        fit(&[sample, sample], Model::Linear),
+        Err(FitError::Degenerate)
+    );
+    assert_eq!(Calibration::IDENTITY.apply(10, 500), 100);
+}
@@ -0,0 +1,79 @@
+//! Tests of calibrating sensors through the common trait
+
+use sen0177::{
+    capability::{Adjusted, Calibratable, CalibrationSite},
+    AirQualitySensor, Concentrations, Reading, SensorError, SensorInfo,
+};
+
+struct FakeSensor;
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        Ok(Reading::new(
+            Concentrations::new(5, 12, 20),
+            Concentrations::new(4, 10, 18),
+            [600, 200, 40, 5, 1, 0],
+        )
+        .with_device_status(0x91, 0))
+    }
+}
+
+/// A device that takes the coefficient as a command, like the HPMA115S0
+#[derive(Default)]
+struct FakeHpma {
+    sent: Vec<u8>,
+}
+
+impl Calibratable<()> for FakeHpma {
+    fn set_adjustment(&mut self, percent: u8) -> Result<(), SensorError<()>> {
+        self.sent.push(percent.clamp(30, 200));
+        Ok(())
+    }
+
+    fn adjustment(&self) -> u8 {
+        self.sent.last().copied().unwrap_or(100)
+    }
+
+    fn calibration_site(&self) -> CalibrationSite {
+        CalibrationSite::Device
+    }
+}
+
+/// Application code that doesn't care where the adjustment is applied
+fn calibrate<E>(sensor: &mut impl Calibratable<E>, percent: u8) -> Result<(), SensorError<E>> {
+    sensor.set_adjustment(percent)
+}
+
+#[test]
+fn sensors_without_info_are_unknown() {
+    assert_eq!(FakeSensor.info(), SensorInfo::UNKNOWN);
+    assert_eq!(Adjusted::new(FakeSensor).info(), SensorInfo::UNKNOWN);
+}
+
+#[test]
+fn driver_adjustment_scales_concentrations() {
+    let mut sensor = Adjusted::new(FakeSensor);
+    assert_eq!(sensor.read().unwrap(), FakeSensor.read().unwrap());
+
+    calibrate(&mut sensor, 150).unwrap();
+    assert_eq!(sensor.adjustment(), 150);
+    assert_eq!(sensor.calibration_site(), CalibrationSite::Driver);
+    let reading = sensor.read().unwrap();
+    assert_eq!(reading.cf1(), Concentrations::new(8, 18, 30));
+    assert_eq!(reading.atmospheric(), Some(Concentrations::new(6, 15, 27)));
+    assert_eq!(reading.particles_0_3().per_deciliter(), 600);
+    assert_eq!(reading.firmware_version(), 0x91);
+}
+
+#[test]
+fn adjustment_is_clamped_either_way() {
+    let mut adjusted = Adjusted::new(FakeSensor);
+    let mut device = FakeHpma::default();
+    for percent in [10, 250] {
+        calibrate(&mut adjusted, percent).unwrap();
+        calibrate(&mut device, percent).unwrap();
+        assert_eq!(adjusted.adjustment(), device.adjustment());
+    }
+    assert_eq!(device.sent, [30, 200]);
+    assert_eq!(device.calibration_site(), CalibrationSite::Device);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{reading, sensor};
+use sen0177::{
+    capture::{read_received, CaptureError, Recorder},
+    mock::MockSerial,
+    protocol::{encode_command, encode_frame, Command},
+    AirQualitySensor,
+};
+
+#[test]
+fn records_and_replays_traffic() {
+    let mut stream = b"junk".to_vec();
+    stream.extend_from_slice(&encode_frame(&reading(10)));
+    stream.extend_from_slice(&encode_frame(&reading(11)));
+
+    let mut serial = MockSerial::new();
+    serial.feed(&stream);
+    let recorder = Recorder::new(serial, Vec::new()).unwrap();
+    let mut recording = sensor(recorder);
+    assert_eq!(recording.read().unwrap(), reading(10));
+    let mut recording = recording.into_passive().unwrap();
+    assert_eq!(recording.read().unwrap(), reading(11));
+    let (serial, capture) = recording.release().finish().unwrap();
+
+    assert_eq!(read_received(&capture).unwrap(), stream);
+    let mut written = encode_command(Command::PassiveMode).to_vec();
+    written.extend_from_slice(&encode_command(Command::PassiveRead));
+    assert_eq!(serial.written(), written);
+
+    let mut replay = MockSerial::new();
+    replay.feed(&read_received(&capture).unwrap());
+    let mut sensor = sensor(replay);
+    assert_eq!(sensor.read().unwrap(), reading(10));
+    assert_eq!(sensor.read().unwrap(), reading(11));
+}
+
+#[test]
+fn rejects_other_files() {
+    assert_eq!(read_received(b"not a capture"), Err(CaptureError::NotPcap));
+
+    let mut capture = Vec::new();
+    Recorder::new(MockSerial::new(), &mut capture).unwrap();
+    capture[20] = 1;
+    assert_eq!(read_received(&capture), Err(CaptureError::WrongLinkType(1)));
+}
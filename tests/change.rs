@@ -0,0 +1,77 @@
+//! Tests of reading deltas and change detection
+
+mod common;
+
+use common::reading;
+use sen0177::{
+    change::{ChangeDetector, ChangeThresholds},
+    Concentrations, Reading, ReadingDelta,
+};
+
+#[test]
+fn delta_is_signed_per_field() {
+    let earlier = Reading::new(
+        Concentrations::new(5, 10, 20),
+        Concentrations::new(4, 9, 18),
+        [600, 200, 40, 5, 1, 0],
+    );
+    let later = Reading::new(
+        Concentrations::new(7, 8, 20),
+        Concentrations::new(4, 12, 15),
+        [650, 180, 40, 5, 0, 2],
+    );
+
+    assert_eq!(
+        earlier.delta(&later),
+        ReadingDelta {
+            pm1: 2,
+            pm2_5: -2,
+            pm10: 0,
+            env_pm1: 0,
+            env_pm2_5: 3,
+            env_pm10: -3,
+            particle_counts: [50, -20, 0, 0, -1, 2],
+        }
+    );
+    assert!(earlier.delta(&earlier).is_zero());
+    assert!(!earlier.delta(&later).is_zero());
+}
+
+#[test]
+fn reports_first_and_significant_readings() {
+    let thresholds = ChangeThresholds {
+        pm2_5: Some(2),
+        ..ChangeThresholds::none()
+    };
+    let mut detector = ChangeDetector::new(thresholds);
+
+    assert!(detector.check(&reading(10)));
+    assert!(!detector.check(&reading(11)));
+    // Still compared with the last reading sent, so the drift adds up
+    assert!(detector.check(&reading(12)));
+    assert_eq!(detector.last_sent(), Some(&reading(12)));
+    assert!(!detector.check(&reading(11)));
+
+    detector.reset();
+    assert!(detector.check(&reading(11)));
+}
+
+#[test]
+fn ignores_fields_without_thresholds() {
+    let thresholds = ChangeThresholds {
+        particle_counts: Some(100),
+        ..ChangeThresholds::none()
+    };
+    let mut detector = ChangeDetector::new(thresholds);
+    let base = reading(10);
+    let more_particles = Reading::new(
+        base.cf1(),
+        base.atmospheric().unwrap(),
+        [720, 200, 40, 5, 1, 0],
+    );
+
+    assert!(detector.check(&base));
+    assert!(!detector.is_significant(&reading(500)));
+    assert!(detector.is_significant(&more_particles));
+    assert_eq!(detector.last_sent(), Some(&base));
+}
@@ -0,0 +1,40 @@
+//! Fixtures shared by the integration tests
+//!
+//! Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use sen0177::{Concentrations, Reading};
+
+/// A reading with the given standard PM2.5 concentration and plausible
+/// values elsewhere
+///
+/// The atmospheric concentrations differ from the standard ones, so drivers
+/// don't mistake the reading for one from a sensor without them.
+pub fn reading(pm2_5: u16) -> Reading {
+    Reading::new(
+        Concentrations::new(pm2_5 / 2, pm2_5, pm2_5 * 2),
+        Concentrations::new(pm2_5 / 2, pm2_5 * 3 / 4, pm2_5 * 3 / 2),
+        [600, 200, 40, 5, 1, 0],
+    )
+}
+
+/// A serial sensor on `serial` that gives up after a few empty polls
+#[cfg(feature = "plantower")]
+pub fn sensor<R, E>(serial: R) -> sen0177::serial::Sen0177<R, E>
+where
+    R: embedded_hal_nb::serial::Read<u8, Error = E>,
+    E: embedded_hal_nb::serial::Error,
+{
+    sen0177::serial::Sen0177Builder::new()
+        .timeout_polls(10)
+        .build(serial)
+}
+
+/// A clock advancing by `step` milliseconds each time it is read
+pub fn clock(step: u64) -> impl FnMut() -> u64 {
+    let mut now = 0;
+    move || {
+        now += step;
+        now
+    }
+}
@@ -0,0 +1,145 @@
+//! Tests of the compact encoding of reading sequences
+
+use sen0177::{
+    compress::{CompressError, Decoder, Encoder, MAX_READING_LEN, VERSION},
+    Concentrations, Reading,
+};
+
+/// Slowly varying readings, as from a sensor in still air
+fn readings(count: usize) -> Vec<Reading> {
+    let mut state = 7u32;
+    let mut pm2_5 = 12i32;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            pm2_5 = (pm2_5 + (state >> 16) as i32 % 5 - 2).max(0);
+            let pm2_5 = pm2_5 as u16;
+            let concentrations = Concentrations::new(pm2_5 * 2 / 3, pm2_5, pm2_5 * 3 / 2);
+            Reading::new(
+                concentrations,
+                concentrations,
+                [pm2_5 * 100, pm2_5 * 30, pm2_5 * 6, pm2_5, pm2_5 / 5, 0],
+            )
+            .with_device_status(0x91, 0)
+        })
+        .collect()
+}
+
+fn decode(payload: &[u8]) -> Result<Vec<Reading>, CompressError> {
+    Decoder::new(payload)?.collect()
+}
+
+#[test]
+fn round_trips() {
+    let readings = readings(100);
+    let mut buf = [0u8; 4096];
+    let mut encoder = Encoder::new(&mut buf).unwrap();
+    for reading in &readings {
+        encoder.push(reading).unwrap();
+    }
+    let payload = encoder.finish();
+
+    assert_eq!(payload[0], VERSION);
+    assert_eq!(decode(payload).unwrap(), readings);
+    // At most half the size of the sensor's 32-byte frames
+    assert!(
+        payload.len() <= readings.len() * 32 / 2,
+        "{} bytes for {} readings",
+        payload.len(),
+        readings.len()
+    );
+}
+
+#[test]
+fn round_trips_extremes() {
+    let max = Reading::new(
+        Concentrations::new(u16::MAX, u16::MAX, u16::MAX),
+        Concentrations::new(u16::MAX, 0, u16::MAX),
+        [u16::MAX; 6],
+    )
+    .with_device_status(u8::MAX, u8::MAX);
+    let readings = [max, Reading::default(), max];
+    let mut buf = [0u8; 1 + 3 * MAX_READING_LEN];
+    let mut encoder = Encoder::new(&mut buf).unwrap();
+    for reading in &readings {
+        encoder.push(reading).unwrap();
+    }
+
+    assert_eq!(decode(encoder.finish()).unwrap(), readings);
+}
+
+#[test]
+fn round_trips_readings_without_environmental_values() {
+    let mut readings = readings(4);
+    readings[1] = readings[1].without_atmospheric();
+    readings[2] = readings[2].without_atmospheric();
+    let mut buf = [0u8; 200];
+    let mut encoder = Encoder::new(&mut buf).unwrap();
+    for reading in &readings {
+        encoder.push(reading).unwrap();
+    }
+
+    let decoded = decode(encoder.finish()).unwrap();
+    assert_eq!(decoded, readings);
+    assert_eq!(decoded[1].atmospheric(), None);
+    assert!(decoded[3].atmospheric().is_some());
+}
+
+#[test]
+fn full_buffer_keeps_earlier_readings() {
+    let readings = readings(3);
+    let mut buf = [0u8; 30];
+    let mut encoder = Encoder::new(&mut buf).unwrap();
+    encoder.push(&readings[0]).unwrap();
+    let len = encoder.len();
+
+    assert_eq!(encoder.push(&readings[1]), Err(CompressError::BufferFull));
+    assert_eq!(encoder.len(), len);
+    assert_eq!(decode(encoder.finish()).unwrap(), &readings[..1]);
+    assert!(matches!(
+        Encoder::new(&mut []),
+        Err(CompressError::BufferFull)
+    ));
+}
+
+#[test]
+fn rejects_bad_data() {
+    let mut buf = [0u8; 100];
+    let mut encoder = Encoder::new(&mut buf).unwrap();
+    encoder.push(&readings(1)[0]).unwrap();
+    let payload = encoder.finish().to_vec();
+
+    assert_eq!(decode(&[]), Err(CompressError::Truncated));
+    assert_eq!(decode(&[VERSION]).unwrap(), []);
+    assert_eq!(decode(&[2, 0]), Err(CompressError::UnknownVersion(2)));
+    assert_eq!(
+        decode(&payload[..payload.len() - 1]),
+        Err(CompressError::Truncated)
+    );
+    // A varint running past 32 bits
+    assert_eq!(
+        decode(&[VERSION, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        Err(CompressError::Malformed)
+    );
+    // A varint whose fifth byte has bits beyond 32
+    assert_eq!(
+        decode(&[VERSION, 0xff, 0xff, 0xff, 0xff, 0x1f]),
+        Err(CompressError::Malformed)
+    );
+    // A delta of i32::MAX on top of a positive PM1 concentration
+    let mut overflowing = payload.clone();
+    overflowing.extend_from_slice(&[0xfe, 0xff, 0xff, 0xff, 0x07]);
+    overflowing.extend_from_slice(&[0; 13]);
+    let mut decoder = Decoder::new(&overflowing).unwrap();
+    assert_eq!(decoder.next(), Some(Ok(readings(1)[0])));
+    assert_eq!(decoder.next(), Some(Err(CompressError::Malformed)));
+    // A PM1 concentration below zero
+    assert_eq!(
+        decode(&[VERSION, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+        Err(CompressError::Malformed)
+    );
+
+    let mut decoder = Decoder::new(&payload[..5]).unwrap();
+    assert_eq!(decoder.next(), Some(Err(CompressError::Truncated)));
+    assert_eq!(decoder.next(), None);
+}
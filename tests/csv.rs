@@ -0,0 +1,93 @@
+//! Tests of CSV formatting of readings
+
+use sen0177::{
+    csv::{CsvError, Row, HEADER, MAX_ROW_LEN},
+    time::Timestamped,
+    Concentrations, Reading,
+};
+
+#[test]
+fn formats_rows_in_header_order() {
+    let reading = Reading::new(
+        Concentrations::new(5, 12, 20),
+        Concentrations::new(4, 10, 18),
+        [600, 200, 40, 5, 1, 0],
+    )
+    .with_device_status(0x91, 0);
+    let row = Row::new(&Timestamped::new(1_700_000_000, reading));
+
+    assert_eq!(
+        row.as_str(),
+        "1700000000,5,12,20,4,10,18,600,200,40,5,1,0,145,0\n"
+    );
+    assert_eq!(
+        HEADER.trim_end().split(',').count(),
+        row.as_str().trim_end().split(',').count()
+    );
+}
+
+#[test]
+fn fits_longest_row() {
+    let max = Concentrations::new(u16::MAX, u16::MAX, u16::MAX);
+    let reading = Reading::new(max, max, [u16::MAX; 6]).with_device_status(u8::MAX, u8::MAX);
+    let row = Row::new(&Timestamped::new(u64::MAX, reading));
+
+    assert!(row.as_str().ends_with(",255,255\n"));
+    assert!(row.as_str().len() <= MAX_ROW_LEN);
+}
+
+#[test]
+fn round_trips_rows() {
+    let reading = Reading::new(
+        Concentrations::new(5, 12, 20),
+        Concentrations::new(4, 10, 18),
+        [600, 200, 40, 5, 1, 0],
+    )
+    .with_device_status(0x91, 3);
+    let record = Timestamped::new(1_700_000_000, reading);
+
+    assert_eq!(
+        Timestamped::from_csv_row(Row::new(&record).as_str()),
+        Ok(record)
+    );
+    assert_eq!(
+        Timestamped::from_csv_row("1700000000,5,12,20,4,10,18,600,200,40,5,1,0,145,3\r\n"),
+        Ok(record)
+    );
+}
+
+#[test]
+fn leaves_unsupported_environmental_values_empty() {
+    let concentrations = Concentrations::new(5, 12, 20);
+    let reading = Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0])
+        .with_device_status(0x91, 0)
+        .without_atmospheric();
+    let record = Timestamped::new(1_700_000_000, reading);
+    let row = Row::new(&record);
+
+    assert_eq!(
+        row.as_str(),
+        "1700000000,5,12,20,,,,600,200,40,5,1,0,145,0\n"
+    );
+    assert_eq!(Timestamped::from_csv_row(row.as_str()), Ok(record));
+    assert_eq!(
+        Timestamped::from_csv_row("1700000000,5,12,20,,10,,600,200,40,5,1,0,145,0\n"),
+        Err(CsvError::InvalidValue(4))
+    );
+}
+
+#[test]
+fn rejects_malformed_rows() {
+    assert_eq!(
+        Timestamped::from_csv_row(HEADER),
+        Err(CsvError::InvalidValue(0))
+    );
+    assert_eq!(
+        Timestamped::from_csv_row("1700000000,5,12,20\n"),
+        Err(CsvError::WrongColumnCount(4))
+    );
+    assert_eq!(
+        Timestamped::from_csv_row("1700000000,5,12,20,4,10,18,600,200,40,5,1,0,256,3\n"),
+        Err(CsvError::InvalidValue(13))
+    );
+}
@@ -0,0 +1,94 @@
+//! Tests of piecewise-linear calibration curves
+
+use sen0177::{
+    curve::{CalibrationCurves, Curve, CurveError, CurvePoint},
+    Concentrations, Reading,
+};
+
+fn smoke_curve() -> Curve<4> {
+    Curve::from_points(&[
+        CurvePoint::new(10, 80),
+        CurvePoint::new(100, 700),
+        CurvePoint::new(300, 3500),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn interpolates_between_and_extends_beyond_points() {
+    let curve = smoke_curve();
+    assert_eq!(curve.apply(10), 80);
+    assert_eq!(curve.apply(55), 390);
+    assert_eq!(curve.apply(100), 700);
+    assert_eq!(curve.apply(150), 1400);
+    // Extending the last segment
+    assert_eq!(curve.apply(400), 4900);
+    // Extending the first segment, clamped at zero
+    assert_eq!(curve.apply(5), 46);
+    assert_eq!(curve.apply(0), 11);
+
+    let shifted = Curve::<4>::from_points(&[CurvePoint::new(10, 50)]).unwrap();
+    assert_eq!(shifted.apply(20), 150);
+    assert_eq!(shifted.apply(2), 0);
+    assert_eq!(Curve::<4>::identity().apply(20), 200);
+}
+
+#[test]
+fn rejects_invalid_points() {
+    let points = [CurvePoint::new(10, 80), CurvePoint::new(10, 90)];
+    assert_eq!(
+        Curve::<4>::from_points(&points),
+        Err(CurveError::NotIncreasing)
+    );
+    assert_eq!(
+        Curve::<1>::from_points(&smoke_curve().points()[..2]),
+        Err(CurveError::TooManyPoints)
+    );
+}
+
+#[test]
+fn corrects_standard_concentrations_of_readings() {
+    let curves = CalibrationCurves {
+        pm2_5: smoke_curve(),
+        ..CalibrationCurves::default()
+    };
+    let reading = Reading::new(
+        Concentrations::new(40, 150, 200),
+        Concentrations::new(30, 120, 180),
+        [600, 200, 40, 5, 1, 0],
+    );
+    assert_eq!(curves.apply_tenths(&reading), [400, 1400, 2000]);
+
+    let corrected = curves.apply(&reading);
+    assert_eq!(corrected.cf1(), Concentrations::new(40, 140, 200));
+    assert_eq!(corrected.atmospheric(), reading.atmospheric());
+    assert_eq!(corrected.particles_0_3(), reading.particles_0_3());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn round_trips_through_serde() {
+    let curves = CalibrationCurves {
+        pm2_5: smoke_curve(),
+        ..CalibrationCurves::default()
+    };
+    let json = serde_json::to_string(&curves).unwrap();
+    assert_eq!(
+        json,
+        r#"{"pm1":[],"pm2_5":[{"raw":10,"corrected":80},{"raw":100,"corrected":700},{"raw":300,"corrected":3500}],"pm10":[]}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<CalibrationCurves<4>>(&json).unwrap(),
+        curves
+    );
+
+    // Too many points for the capacity, and out of order
+    assert!(serde_json::from_str::<Curve<2>>(
+        r#"[{"raw":1,"corrected":1},{"raw":2,"corrected":2},{"raw":3,"corrected":3}]"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<Curve<4>>(
+        r#"[{"raw":2,"corrected":1},{"raw":1,"corrected":2}]"#
+    )
+    .is_err());
+}
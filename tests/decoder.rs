@@ -0,0 +1,176 @@
+//! Tests of the sans-I/O frame decoder
+
+mod common;
+
+use common::reading;
+use sen0177::{
+    decoder::FrameDecoder,
+    protocol::{encode_frame, Plantower, PlantowerStandard},
+    serial::Sen0177Builder,
+    SensorError,
+};
+
+#[test]
+fn decodes_frames_pushed_bytewise() {
+    let mut decoder = FrameDecoder::<Plantower>::new();
+    // Garbage, then a command response, then a data frame
+    let mut stream = vec![0x13, 0x37, 0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74];
+    stream.extend_from_slice(&encode_frame(&reading(7)));
+
+    let results = stream
+        .iter()
+        .filter_map(|&byte| decoder.push(byte))
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let (frame, decoded) = results[0].as_ref().unwrap();
+    assert_eq!(*frame, encode_frame(&reading(7)));
+    assert_eq!(*decoded, reading(7));
+}
+
+#[test]
+fn gives_up_after_resync_budget() {
+    let mut decoder = Sen0177Builder::new()
+        .resync_budget(40)
+        .build_decoder::<Plantower>();
+    let results = [0xffu8; 40]
+        .iter()
+        .filter_map(|&byte| decoder.push(byte))
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        results[..],
+        [Err(SensorError::LikelyBaudMismatch)]
+    ));
+}
+
+#[test]
+fn reset_discards_partial_frame() {
+    let mut decoder = FrameDecoder::<Plantower>::new();
+    let frame = encode_frame(&reading(9));
+    assert!(frame[..20].iter().all(|&byte| decoder.push(byte).is_none()));
+    decoder.reset();
+    let result = frame.iter().find_map(|&byte| decoder.push(byte));
+    assert_eq!(result.unwrap().unwrap().1, reading(9));
+}
+
+#[test]
+fn decodes_frames_spanning_chunks() {
+    let mut decoder = FrameDecoder::<Plantower>::new();
+    let mut stream = vec![0x00; 5];
+    for pm2_5 in 10..13 {
+        stream.extend_from_slice(&encode_frame(&reading(pm2_5)));
+    }
+
+    // Chunks of a size unrelated to the frame length, as DMA delivers them
+    let readings = stream
+        .chunks(20)
+        .flat_map(|chunk| decoder.push_slice(chunk).collect::<Vec<_>>())
+        .map(|result| result.unwrap().1)
+        .collect::<Vec<_>>();
+    assert_eq!(readings, [reading(10), reading(11), reading(12)]);
+}
+
+#[test]
+fn push_slice_stops_after_each_frame() {
+    let mut decoder = FrameDecoder::<Plantower>::new();
+    let mut chunk = encode_frame(&reading(20)).to_vec();
+    chunk.extend_from_slice(&encode_frame(&reading(21))[..8]);
+
+    let mut frames = decoder.push_slice(&chunk);
+    assert_eq!(frames.next().unwrap().unwrap().1, reading(20));
+    assert_eq!(frames.remaining().len(), 8);
+    assert!(frames.next().is_none());
+    assert!(frames.remaining().is_empty());
+}
+
+#[test]
+fn standard_protocol_decodes_only_concentrations() {
+    let mut decoder = FrameDecoder::<PlantowerStandard>::new();
+    let frame = encode_frame(&reading(30));
+    let result = frame.iter().find_map(|&byte| decoder.push(byte));
+    assert_eq!(result.unwrap().unwrap().1, reading(30).cf1());
+
+    let mut corrupted = encode_frame(&reading(31));
+    corrupted[10] ^= 0x01;
+    let result = corrupted.iter().find_map(|&byte| decoder.push(byte));
+    assert!(matches!(result, Some(Err(SensorError::ChecksumMismatch))));
+}
+
+#[test]
+fn push_slice_matches_bytewise_push() {
+    // A pseudo-random mix of garbage, idle line, command responses, good
+    // frames, and corrupted frames
+    let mut state = 7u32;
+    let mut next = move || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 16) as u16
+    };
+    let mut stream = Vec::new();
+    for _ in 0..200 {
+        match next() % 5 {
+            0 => stream.extend((0..next() % 50).map(|_| next() as u8)),
+            1 => stream.extend(std::iter::repeat(0xff).take(usize::from(next() % 40))),
+            2 => stream.extend_from_slice(&[0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74]),
+            3 => stream.extend_from_slice(&encode_frame(&reading(next() % 500))),
+            _ => {
+                let mut frame = encode_frame(&reading(next() % 500));
+                frame[usize::from(next()) % frame.len()] ^= 1 << (next() % 8);
+                stream.extend_from_slice(&frame);
+            }
+        }
+    }
+
+    for (budget, attempts) in [(4096, 32), (40, 3), (1, 1), (0, 0)] {
+        let builder = Sen0177Builder::new()
+            .resync_budget(budget)
+            .sync_attempts(attempts);
+        let mut decoder = builder.build_decoder::<Plantower>();
+        let expected = stream
+            .iter()
+            .filter_map(|&byte| decoder.push(byte))
+            .map(|result| format!("{:?}", result))
+            .collect::<Vec<_>>();
+        for chunk_len in [1, 7, 32, 100, stream.len()] {
+            let mut decoder = builder.build_decoder::<Plantower>();
+            let results = stream
+                .chunks(chunk_len)
+                .flat_map(|chunk| decoder.push_slice(chunk).collect::<Vec<_>>())
+                .map(|result| format!("{:?}", result))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                results, expected,
+                "budget {}, chunks of {}",
+                budget, chunk_len
+            );
+        }
+    }
+}
+
+#[test]
+fn survives_arbitrary_input() {
+    let mut state = 11u32;
+    let stream = (0..100_000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            // Bias towards header bytes so that frames get started
+            match (state >> 16) % 8 {
+                0 => 0x42,
+                1 => 0x4d,
+                _ => (state >> 8) as u8,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (budget, attempts) in [(u32::MAX, u32::MAX), (4096, 32), (1, 1), (0, 0)] {
+        let builder = Sen0177Builder::new()
+            .resync_budget(budget)
+            .sync_attempts(attempts);
+        let mut decoder = builder.build_decoder::<Plantower>();
+        stream.iter().for_each(|&byte| {
+            decoder.push(byte);
+        });
+        let mut decoder = builder.build_decoder::<PlantowerStandard>();
+        for chunk in stream.chunks(37) {
+            decoder.push_slice(chunk).for_each(drop);
+        }
+    }
+}
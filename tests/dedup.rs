@@ -0,0 +1,44 @@
+//! Tests of repeated frame detection
+
+use sen0177::{
+    dedup::{Dedup, Deduplicated},
+    sim::{reading_for, SimulatedSensor},
+    AirQualitySensor, SensorError,
+};
+
+/// A sensor sending each measurement three times, one a second
+fn sensor() -> Dedup<SimulatedSensor<impl FnMut(u64) -> sen0177::Reading>> {
+    let sensor = SimulatedSensor::new(|elapsed_ms: u64| reading_for((elapsed_ms / 3_000) as u16))
+        .interval_ms(1_000);
+    Dedup::new(sensor)
+}
+
+#[test]
+fn repeats_are_reported() {
+    let mut dedup = sensor();
+    let mut read = || dedup.read_deduplicated::<()>().unwrap();
+    assert_eq!(read(), Deduplicated::New(reading_for(0)));
+    assert_eq!(read(), Deduplicated::Repeated(reading_for(0)));
+    assert_eq!(read(), Deduplicated::Repeated(reading_for(0)));
+    assert_eq!(read(), Deduplicated::New(reading_for(1)));
+}
+
+#[test]
+fn repeats_are_suppressed_and_changes_pass_through() {
+    let mut dedup = sensor();
+    let mut read = || -> Result<_, SensorError<()>> { dedup.read() };
+    assert_eq!(read().unwrap(), reading_for(0));
+    assert_eq!(read().unwrap(), reading_for(1));
+    assert_eq!(read().unwrap(), reading_for(2));
+    assert_eq!(dedup.release().elapsed_ms(), 7_000);
+}
+
+#[test]
+fn long_runs_of_repeats_are_returned_eventually() {
+    let mut dedup = sensor().max_skipped(1);
+    let mut read = || -> Result<_, SensorError<()>> { dedup.read() };
+    assert_eq!(read().unwrap(), reading_for(0));
+    // The second repeat is returned after skipping one
+    assert_eq!(read().unwrap(), reading_for(0));
+    assert_eq!(read().unwrap(), reading_for(1));
+}
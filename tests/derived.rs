@@ -0,0 +1,86 @@
+//! Tests of metrics derived from readings
+#![cfg(feature = "float")]
+
+use sen0177::{
+    derived::{mass_from_counts, size_distribution, visibility_km, MassModel, ALVEOLAR_DEPOSITION},
+    Concentrations, Reading,
+};
+
+fn reading(counts: [u16; 6]) -> Reading {
+    let concentrations = Concentrations::new(5, 10, 20);
+    Reading::new(concentrations, concentrations, counts)
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * 1e-4,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn spheres_of_the_bin_diameter() {
+    // 100 particles per 0.1L between 1µm and 2.5µm, and nothing else
+    let distribution = size_distribution(&reading([100, 100, 100, 0, 0, 0]), &MassModel::default());
+    let bin = distribution.bins[2];
+    let d = bin.diameter_um;
+    assert_close(bin.number, 1.0);
+    assert_close(bin.surface_area, core::f32::consts::PI * d * d);
+    assert_close(bin.volume, core::f32::consts::PI * d * d * d / 6.0);
+    assert_close(bin.mass, bin.volume * 1.65);
+    assert_close(
+        bin.lung_deposited_surface_area,
+        bin.surface_area * ALVEOLAR_DEPOSITION[2],
+    );
+
+    assert_close(distribution.surface_area(), bin.surface_area);
+    assert_close(distribution.volume(), bin.volume);
+    for (i, bin) in distribution.bins.iter().enumerate() {
+        if i != 2 {
+            assert_eq!(bin.number, 0.0);
+        }
+    }
+}
+
+#[test]
+fn mass_matches_the_mass_reconstruction() {
+    let reading = reading([1200, 400, 80, 12, 3, 1]);
+    let model = MassModel {
+        density: 2.0,
+        diameter_scale: 1.2,
+    };
+    let distribution = size_distribution(&reading, &model);
+    assert_close(distribution.mass(), mass_from_counts(&reading, &model).pm10);
+
+    // Surface area and volume scale with the diameter, but not the density
+    let unscaled = size_distribution(&reading, &MassModel::default());
+    assert_close(unscaled.volume(), distribution.volume() / 1.2f32.powi(3));
+    assert_close(
+        unscaled.surface_area(),
+        distribution.surface_area() / 1.2f32.powi(2),
+    );
+}
+
+#[test]
+fn visibility_from_extinction() {
+    // 3m²/g × 10µg/m³ + 10Mm⁻¹ = 40Mm⁻¹, so 3.912 / 40Mm⁻¹ = 97.8km
+    let reading = reading([0; 6]);
+    assert_close(visibility_km(&reading, 0.0), 97.8);
+    // f(50%) = 1.625, so 3m²/g × 1.625 × 10µg/m³ + 10Mm⁻¹ = 58.75Mm⁻¹
+    assert_close(visibility_km(&reading, 50.0), 3912.0 / 58.75);
+    // Humidity beyond 95% is clamped
+    assert_close(
+        visibility_km(&reading, 100.0),
+        visibility_km(&reading, 95.0),
+    );
+}
+
+#[test]
+fn clean_air_visibility_is_limited_by_rayleigh_scattering() {
+    let concentrations = Concentrations::new(0, 0, 0);
+    let clean = Reading::new(concentrations, concentrations, [0; 6]);
+    assert_close(visibility_km(&clean, 0.0), 391.2);
+    assert_close(visibility_km(&clean, 90.0), 391.2);
+}
@@ -0,0 +1,89 @@
+use sen0177::{
+    capability::TempHumiditySensor,
+    detect::{probe, DetectedDriver, DetectedModel},
+    mock::MockSerial,
+    protocol::{checksum, encode_frame, PMS3003_FRAME_LEN},
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+fn reading(pm2_5: u16, counts: [u16; 6]) -> Reading {
+    let concentrations = Concentrations::new(pm2_5 / 2, pm2_5, pm2_5 * 2);
+    Reading::new(concentrations, concentrations, counts)
+}
+
+fn pms3003_frame(pm2_5: u16) -> [u8; PMS3003_FRAME_LEN] {
+    let mut frame = [0u8; PMS3003_FRAME_LEN];
+    frame.copy_from_slice(&encode_frame(&reading(pm2_5, [0; 6]))[..PMS3003_FRAME_LEN]);
+    frame[3] = (PMS3003_FRAME_LEN - 4) as u8;
+    frame[16..22].fill(0);
+    let sum = checksum(&frame[..PMS3003_FRAME_LEN - 2]);
+    frame[PMS3003_FRAME_LEN - 2..].copy_from_slice(&sum.to_be_bytes());
+    frame
+}
+
+#[test]
+fn detects_pms5003() {
+    let mut serial = MockSerial::new();
+    serial.feed(&[0x42, 0x00]);
+    for pm2_5 in 0..4 {
+        serial.feed(&encode_frame(&reading(pm2_5, [600, 200, 40, 5, 1, 0])));
+    }
+
+    assert_eq!(probe(&mut serial).unwrap(), DetectedModel::Pms5003Or7003);
+}
+
+#[test]
+fn resynchronizes_on_a_repeated_first_magic_byte() {
+    let mut serial = MockSerial::new();
+    for pm2_5 in 0..4 {
+        serial
+            .feed(&[0x42])
+            .feed(&encode_frame(&reading(pm2_5, [600, 200, 40, 5, 1, 0])));
+    }
+    // Only reached if the frames above were missed
+    for pm2_5 in 10..14 {
+        serial.feed(&pms3003_frame(pm2_5));
+    }
+
+    assert_eq!(probe(&mut serial).unwrap(), DetectedModel::Pms5003Or7003);
+}
+
+#[test]
+fn detects_pms3003() {
+    let mut serial = MockSerial::new();
+    for pm2_5 in 10..20 {
+        serial.feed(&pms3003_frame(pm2_5));
+    }
+
+    assert_eq!(probe(&mut serial).unwrap(), DetectedModel::Pms1003Or3003);
+    let mut sensor = DetectedModel::Pms1003Or3003.connect(serial);
+    assert!(matches!(sensor, DetectedDriver::Pms3003(_)));
+    assert_eq!(sensor.read().unwrap(), reading(14, [0; 6]));
+    assert!(!sensor.info().supports_particle_counts);
+}
+
+#[test]
+fn detects_pms5003t() {
+    let mut serial = MockSerial::new();
+    for pm2_5 in 0..5 {
+        // 23.5°C, 45.0% RH in place of the 5µm and 10µm counts
+        serial.feed(&encode_frame(&reading(pm2_5, [600, 200, 40, 5, 235, 450])));
+    }
+
+    assert_eq!(probe(&mut serial).unwrap(), DetectedModel::Pms5003T);
+    let sensor = DetectedModel::Pms5003T.connect(serial);
+    let DetectedDriver::Pms5003T(mut driver) = sensor else {
+        panic!("wrong driver");
+    };
+    let temp_humidity = driver.read_temp_humidity().unwrap();
+    assert_eq!(temp_humidity.temperature(), 235);
+    assert_eq!(temp_humidity.relative_humidity(), 450);
+}
+
+#[test]
+fn gives_up_on_noise() {
+    let mut serial = MockSerial::new();
+    serial.feed(&[0x42; 600]);
+
+    assert!(matches!(probe(&mut serial), Err(SensorError::BadMagic)));
+}
@@ -0,0 +1,87 @@
+//! Tests of the AQI colors for LED indicators
+
+use sen0177::{
+    aqi::{self, Aqi, AqiInput},
+    display::{
+        color_for, format_compact, format_compact_line, gradient_color_for, CompactLine, Rgb8,
+        COLUMNS,
+    },
+    history::History,
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn bands_use_epa_colors() {
+    assert_eq!(color_for(aqi::pm2_5(50)), Rgb8::new(0, 228, 0));
+    assert_eq!(color_for(aqi::pm2_5(200)), Rgb8::new(255, 255, 0));
+    assert_eq!(color_for(aqi::pm2_5(400)), Rgb8::new(255, 126, 0));
+    assert_eq!(color_for(aqi::pm2_5(1000)), Rgb8::new(255, 0, 0));
+    assert_eq!(color_for(aqi::pm2_5(2000)), Rgb8::new(143, 63, 151));
+    assert_eq!(color_for(Aqi::MAX), Rgb8::new(126, 0, 35));
+}
+
+fn hour_of(pm2_5: u16, pm10: u16) -> AqiInput {
+    let mut history = History::<60>::new();
+    for minute in 0..60 {
+        history.push(minute * 60, reading(pm2_5, pm10));
+    }
+    AqiInput::hourly(&history, 59 * 60).unwrap()
+}
+
+#[test]
+fn inputs_use_the_worse_of_pm2_5_and_pm10() {
+    // Clean PM2.5, but PM10 in the moderate band
+    assert_eq!(color_for(hour_of(5, 100)), Rgb8::new(255, 255, 0));
+    assert_eq!(color_for(hour_of(5, 20)), Rgb8::new(0, 228, 0));
+}
+
+#[test]
+fn gradient_is_continuous_and_matches_bands_mid_category() {
+    assert_eq!(gradient_color_for(aqi::pm2_5(0)), Rgb8::new(0, 228, 0));
+    assert_eq!(gradient_color_for(Aqi::MAX), Rgb8::new(126, 0, 35));
+
+    let mut previous = gradient_color_for(aqi::pm2_5(0));
+    for tenths in 1..=5000 {
+        let aqi = aqi::pm2_5(tenths);
+        let color = gradient_color_for(aqi);
+        for (a, b) in [
+            (previous.r, color.r),
+            (previous.g, color.g),
+            (previous.b, color.b),
+        ] {
+            assert!(a.abs_diff(b) <= 8, "jump at AQI {}", aqi.value());
+        }
+        if [25, 75, 125, 175, 250, 400].contains(&aqi.value()) {
+            assert_eq!(color, color_for(aqi));
+        }
+        previous = color;
+    }
+}
+
+#[test]
+fn dims_colors() {
+    let color = Rgb8::new(255, 126, 0);
+    assert_eq!(color.dimmed(255), color);
+    assert_eq!(color.dimmed(0), Rgb8::default());
+    assert_eq!(color.dimmed(64), Rgb8::new(64, 32, 0));
+}
+
+#[test]
+fn compact_lines_fill_the_display_width() {
+    let mut text = String::new();
+    format_compact(&reading(12, 65535), &mut text).unwrap();
+    assert_eq!(
+        text,
+        "PM1     12 ug/m3\nPM2.5   12 ug/m3\nPM10 65535 ug/m3\n"
+    );
+    assert!(text.lines().all(|line| line.len() == COLUMNS));
+
+    let mut line = String::new();
+    format_compact_line(&reading(0, 7), CompactLine::Pm10, &mut line).unwrap();
+    assert_eq!(line, "PM10     7 ug/m3");
+}
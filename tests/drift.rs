@@ -0,0 +1,86 @@
+//! Tests of drift detection between co-located sensors
+
+use sen0177::{
+    drift::{DriftDetector, DriftEnvelope, DriftEvent, DriftReason},
+    multi::MultiSensor,
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+/// Pseudo-random "true" concentrations between 5 and 54
+fn ambient(count: usize) -> impl Iterator<Item = u16> {
+    let mut state = 7u32;
+    (0..count).map(move |_| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        5 + ((state >> 16) % 50) as u16
+    })
+}
+
+#[test]
+fn agreeing_sensors_stay_in_the_envelope() {
+    let mut detector = DriftDetector::<30>::new(DriftEnvelope::default());
+    for (i, value) in ambient(100).enumerate() {
+        // A small, constant offset and a little noise
+        let event = detector.update_values(value, value + 1 + (i % 2) as u16);
+        assert_eq!(event, None);
+    }
+    assert!(!detector.is_drifting());
+    assert_eq!(detector.bias(), Some(15));
+    assert!(detector.correlation().unwrap() > 990);
+}
+
+#[test]
+fn reports_growing_bias_and_recovery() {
+    let mut detector = DriftDetector::<20>::new(DriftEnvelope::default());
+    let mut events = Vec::new();
+    for (i, value) in ambient(200).enumerate() {
+        // The second sensor's readings creep upwards by 1% every 5 readings
+        let b = u32::from(value) * (100 + i as u32 / 5) / 100;
+        if let Some(event) = detector.update_values(value, b as u16) {
+            events.push((i, event));
+        }
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].1, DriftEvent::Drifted(DriftReason::Bias));
+    assert!(events[0].0 > 100);
+
+    // Servicing the sensor
+    for value in ambient(20) {
+        detector.update_values(value, value);
+    }
+    assert!(!detector.is_drifting());
+}
+
+#[test]
+fn reports_loss_of_correlation() {
+    let mut detector = DriftDetector::<20>::new(DriftEnvelope::default());
+    let mut event = None;
+    for (value, other) in ambient(40).zip(ambient(60).skip(20)) {
+        // Similar levels, but unrelated to each other
+        event = event.or(detector.update_values(value, other));
+    }
+    assert!(detector.correlation().unwrap() < 800);
+    assert_eq!(event, Some(DriftEvent::Drifted(DriftReason::Correlation)));
+}
+
+struct FakeSensor(u16);
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        let concentrations = Concentrations::new(self.0, self.0, self.0);
+        Ok(Reading::new(concentrations, concentrations, [0; 6]))
+    }
+}
+
+#[test]
+fn compares_sensors_of_a_multi_sensor() {
+    let mut multi = MultiSensor::new([FakeSensor(10), FakeSensor(30)]);
+    let mut detector = DriftDetector::<1>::new(DriftEnvelope::default());
+    assert_eq!(detector.update_from(&multi, 0, 1), None);
+
+    multi.poll_all().unwrap();
+    assert_eq!(
+        detector.update_from(&multi, 0, 1),
+        Some(DriftEvent::Drifted(DriftReason::Bias))
+    );
+    assert_eq!(detector.bias(), Some(200));
+}
@@ -0,0 +1,102 @@
+//! Tests of environmental concentration support detection
+
+use sen0177::{
+    env::{EnvDetector, MIRROR_THRESHOLD},
+    Concentrations, Reading,
+};
+
+fn reading(cf1: Concentrations, atmospheric: Concentrations) -> Reading {
+    Reading::new(cf1, atmospheric, [600, 200, 40, 5, 1, 0])
+}
+
+#[test]
+fn genuine_blocks_are_supported() {
+    let mut detector = EnvDetector::new(3);
+    let clean = reading(Concentrations::new(4, 8, 10), Concentrations::new(4, 8, 10));
+    detector.observe(&clean);
+    assert_eq!(detector.supported(), None);
+    assert_eq!(detector.env_pm2_5(&clean), Some(8));
+
+    let smoky = reading(
+        Concentrations::new(60, 90, 110),
+        Concentrations::new(40, 60, 73),
+    );
+    detector.observe(&smoky);
+    assert_eq!(detector.supported(), Some(true));
+    assert_eq!(detector.atmospheric(&smoky), smoky.atmospheric());
+}
+
+#[test]
+fn zeroed_blocks_are_unsupported() {
+    let mut detector = EnvDetector::new(3);
+    let zeroed = reading(Concentrations::new(4, 8, 10), Concentrations::new(0, 0, 0));
+    assert_eq!(detector.env_pm1(&zeroed), None);
+    for _ in 0..2 {
+        detector.observe(&zeroed);
+    }
+    assert_eq!(detector.supported(), None);
+    assert_eq!(detector.suspect_frames(), 2);
+    detector.observe(&zeroed);
+    assert_eq!(detector.supported(), Some(false));
+
+    // Once unsupported, even plausible-looking values aren't returned
+    let clean = reading(Concentrations::new(4, 8, 10), Concentrations::new(4, 8, 10));
+    assert_eq!(detector.env_pm10(&clean), None);
+
+    detector.reset();
+    assert_eq!(detector.supported(), None);
+    assert_eq!(detector.env_pm10(&clean), Some(10));
+}
+
+#[test]
+fn mirrored_blocks_only_count_at_high_concentrations() {
+    let mut detector = EnvDetector::new(2);
+    let low = Concentrations::new(10, MIRROR_THRESHOLD, 50);
+    for _ in 0..5 {
+        detector.observe(&reading(low, low));
+    }
+    assert_eq!(detector.supported(), None);
+
+    let high = Concentrations::new(40, MIRROR_THRESHOLD + 20, 80);
+    assert_eq!(detector.atmospheric(&reading(high, high)), None);
+    detector.observe(&reading(high, high));
+    detector.observe(&reading(high, high));
+    assert_eq!(detector.supported(), Some(false));
+}
+
+#[test]
+fn environmental_above_standard_is_suspect() {
+    let mut detector = EnvDetector::new(1);
+    let swapped = reading(Concentrations::new(4, 8, 10), Concentrations::new(4, 9, 10));
+    detector.observe(&swapped);
+    assert_eq!(detector.supported(), Some(false));
+}
+
+#[test]
+fn clean_air_is_not_evidence() {
+    let mut detector = EnvDetector::new(1);
+    let zero = Concentrations::new(0, 0, 0);
+    detector.observe(&reading(zero, zero));
+    assert_eq!(detector.supported(), None);
+    assert_eq!(detector.env_pm2_5(&reading(zero, zero)), Some(0));
+}
+
+#[test]
+fn apply_marks_untrusted_readings() {
+    let mut detector = EnvDetector::new(2);
+    let smoky = reading(
+        Concentrations::new(60, 90, 110),
+        Concentrations::new(40, 60, 73),
+    );
+    assert_eq!(detector.apply(smoky), smoky);
+
+    let zeroed = reading(Concentrations::new(4, 8, 10), Concentrations::new(0, 0, 0));
+    let marked = detector.apply(zeroed);
+    assert_eq!(marked.env_pm2_5(), None);
+    assert_eq!(marked.cf1(), zeroed.cf1());
+
+    // Marked readings count against the sensor
+    detector.observe(&marked);
+    detector.observe(&marked);
+    assert_eq!(detector.supported(), Some(false));
+}
@@ -0,0 +1,76 @@
+//! Tests of the driver's `embedded-hal`-style error traits
+
+use embedded_hal::i2c;
+use embedded_hal_nb::serial;
+use sen0177::{
+    mock::MockSerial, serial::Sen0177Builder, AirQualitySensor, Error, ErrorKind, ErrorType,
+    Implausibility, Reading, SensorError,
+};
+
+/// A board-support style driver that only knows its sensor through the
+/// error traits
+struct Board<S> {
+    sensor: S,
+}
+
+impl<S: ErrorType> ErrorType for Board<S> {
+    type Error = S::Error;
+}
+
+impl<S> Board<S> {
+    fn read<E>(&mut self) -> Result<Reading, <Self as ErrorType>::Error>
+    where
+        S: AirQualitySensor<E> + ErrorType<Error = SensorError<E>>,
+    {
+        self.sensor.read()
+    }
+}
+
+fn kind_of<S: ErrorType>(_: &S, result: Result<Reading, S::Error>) -> Option<ErrorKind> {
+    result.err().map(|error| error.kind())
+}
+
+#[test]
+fn sensor_errors_report_their_kind() {
+    let cases: [(SensorError<()>, ErrorKind); 7] = [
+        (SensorError::BadMagic, ErrorKind::InvalidFrame),
+        (SensorError::ChecksumMismatch, ErrorKind::InvalidFrame),
+        (
+            SensorError::ImplausibleData(Implausibility::ConcentrationOrder),
+            ErrorKind::Implausible,
+        ),
+        (SensorError::Timeout, ErrorKind::Timeout),
+        (SensorError::StaleData, ErrorKind::Stale),
+        (SensorError::NoData { since: 0 }, ErrorKind::NoData),
+        (SensorError::ReadError(()), ErrorKind::Bus),
+    ];
+    for (error, kind) in cases {
+        assert_eq!(Error::kind(&error), kind);
+    }
+}
+
+#[test]
+fn bus_errors_keep_their_hal_kind() {
+    let error = SensorError::ReadError(serial::ErrorKind::Overrun);
+    assert_eq!(serial::Error::kind(&error), serial::ErrorKind::Overrun);
+    let error = SensorError::<serial::ErrorKind>::LikelyBaudMismatch;
+    assert_eq!(serial::Error::kind(&error), serial::ErrorKind::FrameFormat);
+
+    let error = SensorError::ReadError(i2c::ErrorKind::ArbitrationLoss);
+    assert_eq!(i2c::Error::kind(&error), i2c::ErrorKind::ArbitrationLoss);
+    let error = SensorError::<i2c::ErrorKind>::ChecksumMismatch;
+    assert_eq!(i2c::Error::kind(&error), i2c::ErrorKind::Other);
+}
+
+#[test]
+fn drivers_compose_through_the_error_type() {
+    let mut serial = MockSerial::new();
+    serial.feed_error(serial::ErrorKind::Noise);
+    let sensor = Sen0177Builder::new().timeout_polls(10).build(&mut serial);
+    let mut board = Board { sensor };
+
+    let result = board.read();
+    assert_eq!(kind_of(&board, result), Some(ErrorKind::Bus));
+    let result = board.read();
+    assert_eq!(kind_of(&board, result), Some(ErrorKind::Timeout));
+}
@@ -0,0 +1,92 @@
+//! Tests of the C ABI, called from Rust
+
+use std::ffi::CStr;
+
+use sen0177::{
+    ffi::{
+        sen0177_close, sen0177_open, sen0177_parse, sen0177_read, sen0177_reading,
+        sen0177_strerror, SEN0177_ERR_CHECKSUM, SEN0177_ERR_FRAME_LENGTH,
+        SEN0177_ERR_INVALID_ARGUMENT, SEN0177_OK,
+    },
+    protocol::encode_frame,
+    Concentrations, Reading,
+};
+
+#[test]
+fn parses_frames() {
+    let reading = Reading::new(
+        Concentrations::new(5, 10, 20),
+        Concentrations::new(4, 9, 18),
+        [600, 200, 40, 5, 1, 0],
+    )
+    .with_device_status(0x91, 0x02);
+    let mut frame = encode_frame(&reading);
+    let mut out = sen0177_reading::default();
+
+    let status = unsafe { sen0177_parse(frame.as_ptr(), frame.len(), &mut out) };
+    assert_eq!(status, SEN0177_OK);
+    assert_eq!(
+        out,
+        sen0177_reading {
+            pm1: 5,
+            pm2_5: 10,
+            pm10: 20,
+            env_pm1: 4,
+            env_pm2_5: 9,
+            env_pm10: 18,
+            particle_counts: [600, 200, 40, 5, 1, 0],
+            firmware_version: 0x91,
+            device_error_code: 0x02,
+            has_env: 1,
+        }
+    );
+
+    frame[10] ^= 1;
+    let status = unsafe { sen0177_parse(frame.as_ptr(), frame.len(), &mut out) };
+    assert_eq!(status, SEN0177_ERR_CHECKSUM);
+    let status = unsafe { sen0177_parse(frame.as_ptr(), 31, &mut out) };
+    assert_eq!(status, SEN0177_ERR_FRAME_LENGTH);
+}
+
+#[test]
+fn zeroes_unsupported_environmental_values() {
+    let concentrations = Concentrations::new(5, 10, 20);
+    let reading = Reading::new(concentrations, concentrations, [0; 6]).without_atmospheric();
+    let out = sen0177_reading::from(&reading);
+    assert_eq!(out.has_env, 0);
+    assert_eq!((out.env_pm1, out.env_pm2_5, out.env_pm10), (0, 0, 0));
+    assert_eq!(out.pm2_5, 10);
+}
+
+#[test]
+fn rejects_null_pointers() {
+    let frame = [0u8; 32];
+    let mut out = sen0177_reading::default();
+
+    unsafe {
+        assert_eq!(
+            sen0177_parse(std::ptr::null(), 32, &mut out),
+            SEN0177_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            sen0177_parse(frame.as_ptr(), 32, std::ptr::null_mut()),
+            SEN0177_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            sen0177_read(std::ptr::null_mut(), &mut out),
+            SEN0177_ERR_INVALID_ARGUMENT
+        );
+        assert!(sen0177_open(std::ptr::null(), 0).is_null());
+        assert!(sen0177_open(c"/dev/nonexistent-sen0177".as_ptr(), 0).is_null());
+        sen0177_close(std::ptr::null_mut());
+    }
+}
+
+#[test]
+fn describes_status_codes() {
+    let describe = |status| unsafe { CStr::from_ptr(sen0177_strerror(status)) };
+
+    assert_eq!(describe(SEN0177_OK), c"Success");
+    assert_eq!(describe(SEN0177_ERR_CHECKSUM), c"Data read was corrupt");
+    assert_eq!(describe(42), c"Unknown error");
+}
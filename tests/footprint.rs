@@ -0,0 +1,52 @@
+//! Tracks the RAM footprint of the parser and driver, which must leave
+//! plenty of room on targets with 2KB of RAM, such as the ATmega328
+//!
+//! Sizes are measured on the host, where pointers and `usize` are larger
+//! than on 8- and 16-bit targets, so the real footprint is smaller still.
+
+use core::mem::size_of;
+use embedded_hal_nb::serial::ErrorKind;
+use sen0177::{
+    decoder::FrameDecoder,
+    protocol::{FrameProtocol, Plantower, PlantowerStandard},
+    serial::{Sen0177, SerialDriver},
+    Concentrations, Reading,
+};
+
+/// A UART handle that, like most HALs', takes no space of its own
+struct Uart;
+
+impl embedded_hal_nb::serial::ErrorType for Uart {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal_nb::serial::Read<u8> for Uart {
+    fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, Self::Error> {
+        Err(embedded_hal_nb::nb::Error::WouldBlock)
+    }
+}
+
+#[test]
+fn parsed_values() {
+    assert_eq!(size_of::<Concentrations>(), 6);
+    assert!(size_of::<Reading>() <= 26);
+}
+
+#[test]
+fn decoder_and_drivers() {
+    // The frame buffer, plus a few counters
+    assert!(size_of::<FrameDecoder<PlantowerStandard>>() <= 72);
+    assert_eq!(
+        size_of::<FrameDecoder<PlantowerStandard>>(),
+        size_of::<FrameDecoder<Plantower>>()
+    );
+    assert!(size_of::<SerialDriver<PlantowerStandard, Uart>>() <= 80);
+    assert!(size_of::<Sen0177<Uart, ErrorKind>>() <= 88);
+}
+
+#[test]
+fn driver_state_and_output_fit_in_128_bytes() {
+    let total = size_of::<SerialDriver<PlantowerStandard, Uart>>()
+        + size_of::<(<PlantowerStandard as FrameProtocol>::Frame, Concentrations)>();
+    assert!(total <= 128, "{} bytes", total);
+}
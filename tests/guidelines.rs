@@ -0,0 +1,102 @@
+//! Tests of checking mean concentrations against guideline limits
+
+use sen0177::{
+    guidelines::{Averages, AveragingPeriod, Exceedance, GuidelineSet, Limit, Pollutant},
+    history::History,
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn who_2021_limits() {
+    let who = GuidelineSet::who_2021();
+    assert_eq!(who.name(), "WHO 2021");
+    assert_eq!(
+        who.limits(),
+        [
+            Limit {
+                pollutant: Pollutant::Pm2_5,
+                period: AveragingPeriod::Daily,
+                value: 150,
+            },
+            Limit {
+                pollutant: Pollutant::Pm2_5,
+                period: AveragingPeriod::Annual,
+                value: 50,
+            },
+            Limit {
+                pollutant: Pollutant::Pm10,
+                period: AveragingPeriod::Daily,
+                value: 450,
+            },
+            Limit {
+                pollutant: Pollutant::Pm10,
+                period: AveragingPeriod::Annual,
+                value: 150,
+            },
+        ]
+    );
+}
+
+#[test]
+fn reaching_a_limit_is_not_an_exceedance() {
+    let who = GuidelineSet::who_2021();
+    let at_limits = Averages {
+        pm2_5: 150,
+        pm10: 450,
+    };
+    assert_eq!(who.check(AveragingPeriod::Daily, at_limits).count(), 0);
+
+    let above = Averages {
+        pm2_5: 151,
+        pm10: 450,
+    };
+    let exceedances: Vec<_> = who.check(AveragingPeriod::Daily, above).collect();
+    assert_eq!(
+        exceedances,
+        [Exceedance {
+            limit: who.limits()[0],
+            measured: 151,
+        }]
+    );
+    assert_eq!(exceedances[0].excess(), 1);
+}
+
+#[test]
+fn only_limits_for_the_period_apply() {
+    let who = GuidelineSet::who_2021();
+    let averages = Averages {
+        pm2_5: 100,
+        pm10: 200,
+    };
+    assert_eq!(who.check(AveragingPeriod::Daily, averages).count(), 0);
+
+    let exceedances: Vec<_> = who.check(AveragingPeriod::Annual, averages).collect();
+    assert_eq!(exceedances.len(), 2);
+    assert_eq!(exceedances[0].limit.pollutant, Pollutant::Pm2_5);
+    assert_eq!(exceedances[0].excess(), 50);
+    assert_eq!(exceedances[1].limit.pollutant, Pollutant::Pm10);
+    assert_eq!(exceedances[1].excess(), 50);
+}
+
+#[test]
+fn averages_come_from_the_history_window() {
+    let mut history = History::<8>::new();
+    assert_eq!(Averages::from_history(&history, 0), None);
+
+    history.push(0, reading(100, 200));
+    history.push(10, reading(10, 20));
+    history.push(20, reading(11, 21));
+    assert_eq!(
+        Averages::from_history(&history, 10),
+        Some(Averages {
+            pm2_5: 105,
+            pm10: 205,
+        })
+    );
+    assert_eq!(Averages::from_history(&history, 30), None);
+}
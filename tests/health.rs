@@ -0,0 +1,80 @@
+//! Tests of the drivers' health checks
+
+mod common;
+
+use common::{clock, reading, sensor};
+use sen0177::{
+    health::{counts_consistent, MAX_FRAME_INTERVAL_MS},
+    mock::{Faults, MockSerial},
+    protocol::encode_frame,
+    Concentrations, Reading, SensorError,
+};
+
+/// A UART on which each frame fed arrives after a pause, so that the
+/// health check's flush of stale data finds nothing to discard
+fn serial() -> MockSerial {
+    let faults = Faults {
+        delay_polls: 1,
+        ..Faults::none()
+    };
+    MockSerial::with_faults(faults, 1)
+}
+
+#[test]
+fn healthy_stream() {
+    let mut serial = serial();
+    for pm2_5 in [10, 11, 12] {
+        serial.feed(&encode_frame(&reading(pm2_5)));
+    }
+    let report = sensor(&mut serial).health_check(&mut clock(1000), 3);
+
+    assert!(report.is_healthy());
+    assert_eq!(report.frames, 3);
+    assert_eq!(report.min_interval_ms, Some(1000));
+    assert_eq!(report.max_interval_ms, Some(1000));
+    assert_eq!(report.cadence_ok(), Some(true));
+    assert_eq!(report.last_reading, Some(reading(12)));
+}
+
+#[test]
+fn slow_frames_fail_cadence() {
+    let mut serial = serial();
+    for pm2_5 in [10, 11] {
+        serial.feed(&encode_frame(&reading(pm2_5)));
+    }
+    let report = sensor(&mut serial).health_check(&mut clock(MAX_FRAME_INTERVAL_MS + 1), 2);
+
+    assert_eq!(report.cadence_ok(), Some(false));
+    assert!(!report.is_healthy());
+}
+
+#[test]
+fn stops_at_timeout() {
+    let mut serial = serial();
+    serial.feed(&encode_frame(&reading(10)));
+    let report = sensor(&mut serial).health_check(&mut clock(1000), 3);
+
+    assert_eq!(report.frames, 1);
+    assert!(matches!(report.error, Some(SensorError::Timeout)));
+    assert_eq!(report.cadence_ok(), None);
+    assert!(!report.is_healthy());
+}
+
+#[test]
+fn flags_missing_particle_counts() {
+    let concentrations = Concentrations::new(5, 10, 20);
+    let uncounted = Reading::new(concentrations, concentrations, [0; 6]);
+    assert!(!counts_consistent(&uncounted));
+    assert!(counts_consistent(&Reading::default()));
+    assert!(counts_consistent(&reading(10)));
+
+    let mut serial = serial();
+    serial
+        .feed(&encode_frame(&reading(10)))
+        .feed(&encode_frame(&uncounted));
+    let report = sensor(&mut serial).health_check(&mut clock(1000), 2);
+
+    assert_eq!(report.frames, 2);
+    assert_eq!(report.inconsistent_frames, 1);
+    assert!(!report.is_healthy());
+}
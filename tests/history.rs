@@ -0,0 +1,50 @@
+//! Tests of the reading history's windowed statistics
+
+use sen0177::{history::History, Concentrations, Reading};
+
+fn reading(pm2_5: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm2_5);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn percentile_uses_nearest_rank() {
+    let mut history = History::<10>::new();
+    for (timestamp, pm2_5) in [40, 10, 30, 20, 50].into_iter().enumerate() {
+        history.push(timestamp as u64, reading(pm2_5));
+    }
+    assert_eq!(history.percentile(0, Reading::pm2_5, 0), Some(10));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 20), Some(10));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 21), Some(20));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 50), Some(30));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 100), Some(50));
+    // Beyond 100 is clamped
+    assert_eq!(history.percentile(0, Reading::pm2_5, 255), Some(50));
+
+    // Only the window counts
+    assert_eq!(history.percentile(3, Reading::pm2_5, 0), Some(20));
+}
+
+#[test]
+fn percentile_of_nothing_is_none() {
+    let history = History::<10>::new();
+    assert_eq!(history.percentile(0, Reading::pm2_5, 50), None);
+
+    let mut history = History::<10>::new();
+    history.push(5, reading(10));
+    assert_eq!(history.percentile(6, Reading::pm2_5, 50), None);
+}
+
+#[test]
+fn percentile_of_a_wrapped_buffer() {
+    let mut history = History::<4>::new();
+    // The first three readings are evicted
+    for (timestamp, pm2_5) in [90, 80, 70, 1, 4, 2, 3].into_iter().enumerate() {
+        history.push(timestamp as u64, reading(pm2_5));
+    }
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.percentile(0, Reading::pm2_5, 100), Some(4));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 50), Some(2));
+    assert_eq!(history.percentile(0, Reading::pm2_5, 0), Some(1));
+    assert_eq!(history.percentile(5, Reading::pm2_5, 0), Some(2));
+}
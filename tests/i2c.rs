@@ -0,0 +1,136 @@
+//! End-to-end tests of the I2C driver, against an in-memory sensor
+
+mod common;
+
+use common::reading;
+use core::convert::Infallible;
+use embedded_hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+use sen0177::{
+    i2c::{InvalidAddress, Sen0177, DEFAULT_ADDRESS},
+    protocol::encode_frame,
+    AirQualitySensor, Reading, SensorError,
+};
+
+/// Serves `frames` in turn, moving on to the next one after every
+/// `transfers_per_frame` transfers and repeating the last one forever
+struct FakeSensor {
+    frames: Vec<[u8; 32]>,
+    transfers_per_frame: usize,
+    max_read: usize,
+    transfers: usize,
+}
+
+impl FakeSensor {
+    fn new(readings: &[Reading]) -> Self {
+        Self {
+            frames: readings.iter().map(encode_frame).collect(),
+            transfers_per_frame: 1,
+            max_read: 32,
+            transfers: 0,
+        }
+    }
+}
+
+impl ErrorType for FakeSensor {
+    type Error = Infallible;
+}
+
+impl I2c<SevenBitAddress> for FakeSensor {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        assert_eq!(address, DEFAULT_ADDRESS);
+        let index = (self.transfers / self.transfers_per_frame).min(self.frames.len() - 1);
+        let frame = &self.frames[index];
+        let mut offset = 0;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => offset = bytes[0] as usize,
+                Operation::Read(buf) => {
+                    assert!(buf.len() <= self.max_read, "read too long for bus");
+                    buf.copy_from_slice(&frame[offset..offset + buf.len()]);
+                }
+            }
+        }
+        self.transfers += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn repeats_frames_by_default() {
+    let mut sensor = Sen0177::new_default_addr(FakeSensor::new(&[reading(12)]));
+
+    for _ in 0..10 {
+        assert_eq!(sensor.read().unwrap(), reading(12));
+    }
+}
+
+#[test]
+fn reports_stale_data() {
+    let mut sensor = Sen0177::new(FakeSensor::new(&[reading(12)]), DEFAULT_ADDRESS).stale_after(2);
+
+    for _ in 0..3 {
+        assert_eq!(sensor.read().unwrap(), reading(12));
+    }
+    assert!(matches!(sensor.read(), Err(SensorError::StaleData)));
+    assert!(matches!(sensor.read(), Err(SensorError::StaleData)));
+}
+
+#[test]
+fn new_frames_are_not_stale() {
+    let mut fake = FakeSensor::new(&[reading(12), reading(13), reading(14)]);
+    fake.transfers_per_frame = 2;
+    let mut sensor = Sen0177::new(fake, DEFAULT_ADDRESS).stale_after(1);
+
+    for pm2_5 in 12..15 {
+        assert_eq!(sensor.read().unwrap(), reading(pm2_5));
+        assert_eq!(sensor.read().unwrap(), reading(pm2_5));
+    }
+    assert!(matches!(sensor.read(), Err(SensorError::StaleData)));
+}
+
+#[test]
+fn health_check_catches_stale_data() {
+    let mut sensor = Sen0177::new(FakeSensor::new(&[reading(12)]), DEFAULT_ADDRESS).stale_after(1);
+    let report = sensor.health_check(3);
+
+    assert_eq!(report.frames, 2);
+    assert!(matches!(report.error, Some(SensorError::StaleData)));
+    assert_eq!(report.cadence_ok(), None);
+    assert!(!report.is_healthy());
+
+    let mut sensor = Sen0177::new_default_addr(FakeSensor::new(&[reading(12)]));
+    assert!(sensor.health_check(3).is_healthy());
+}
+
+#[test]
+fn reads_in_chunks() {
+    let mut fake = FakeSensor::new(&[reading(12)]);
+    fake.max_read = 8;
+    let mut sensor = Sen0177::new(fake, DEFAULT_ADDRESS).chunk_len(8);
+
+    assert_eq!(sensor.read().unwrap(), reading(12));
+    assert_eq!(sensor.read().unwrap(), reading(12));
+}
+
+#[test]
+fn rereads_frame_torn_between_chunks() {
+    let fake = FakeSensor::new(&[reading(12), reading(13)]);
+    let mut sensor = Sen0177::new(fake, DEFAULT_ADDRESS).chunk_len(16);
+
+    assert_eq!(sensor.read().unwrap(), reading(13));
+}
+
+#[test]
+fn rejects_reserved_addresses() {
+    for address in [0x00, 0x07, 0x78, 0x80, 0xff] {
+        assert!(matches!(
+            Sen0177::try_new(FakeSensor::new(&[reading(12)]), address),
+            Err(InvalidAddress(rejected)) if rejected == address
+        ));
+    }
+    assert!(Sen0177::try_new(FakeSensor::new(&[reading(12)]), DEFAULT_ADDRESS).is_ok());
+}
@@ -0,0 +1,97 @@
+//! Tests of the serial drivers over `std::io` streams
+
+mod common;
+
+use std::{
+    error::Error,
+    io::{self, Cursor, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use common::reading;
+use sen0177::{
+    io::{IoError, IoSerial},
+    protocol::{encode_command, encode_frame, Command},
+    serial::{Sen0177, Sen0177Builder},
+    AirQualitySensor, SensorError,
+};
+
+#[test]
+fn reads_frames_then_reports_end_of_stream() {
+    let mut recorded = vec![0x00, 0x55];
+    recorded.extend_from_slice(&encode_frame(&reading(12)));
+    recorded.extend_from_slice(&encode_frame(&reading(13)));
+    let mut sensor = Sen0177::new(IoSerial::new(Cursor::new(recorded)));
+
+    assert_eq!(sensor.read().unwrap(), reading(12));
+    assert_eq!(sensor.read().unwrap(), reading(13));
+    match sensor.read() {
+        Err(SensorError::ReadError(error)) => {
+            assert_eq!(error.0.kind(), std::io::ErrorKind::UnexpectedEof)
+        }
+        other => panic!("expected end of stream, got {:?}", other),
+    }
+}
+
+#[test]
+fn sends_commands_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let bridge = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut command = [0u8; 7];
+        stream.read_exact(&mut command).unwrap();
+        assert_eq!(command, encode_command(Command::PassiveMode));
+        stream.read_exact(&mut command).unwrap();
+        assert_eq!(command, encode_command(Command::PassiveRead));
+        stream.write_all(&encode_frame(&reading(30))).unwrap();
+        // Hold the connection open until the client is done
+        stream.read_exact(&mut command).ok();
+    });
+
+    let stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    let sensor = Sen0177Builder::new()
+        .timeout_polls(500)
+        .build(IoSerial::new(stream));
+    let mut sensor = sensor.into_passive().unwrap();
+    assert_eq!(sensor.read().unwrap(), reading(30));
+
+    drop(sensor);
+    bridge.join().unwrap();
+}
+
+#[test]
+fn read_timeouts_count_as_polls() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _peer = listener.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(1)))
+        .unwrap();
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(3)
+        .build(IoSerial::new(stream));
+
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+}
+
+#[test]
+fn read_errors_chain_the_io_error() {
+    let error: SensorError<IoError> = SensorError::ReadError(IoError(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "port timed out",
+    )));
+    assert!(error.to_string().starts_with("Read error: "));
+    assert!(error.source().is_none());
+
+    let error = error.chained();
+    assert_eq!(error.to_string(), "Read error");
+    let source = error.source().expect("no source");
+    assert_eq!(source.to_string(), "port timed out");
+    assert!(SensorError::<IoError>::Timeout.chained().source().is_none());
+}
@@ -0,0 +1,87 @@
+//! Tests of draining an interrupt-fed byte queue through a decoder
+
+mod common;
+
+use common::reading;
+use heapless::spsc::Queue;
+use sen0177::{decoder::FrameDecoder, isr::ByteQueue, protocol::encode_frame, SensorError};
+
+#[test]
+fn resumes_frames_across_drains() {
+    let mut queue: ByteQueue<64> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let mut decoder: FrameDecoder = FrameDecoder::new();
+    let frame = encode_frame(&reading(12));
+
+    for &byte in &frame[..20] {
+        producer.enqueue(byte).unwrap();
+    }
+    assert!(decoder.drain(&mut consumer).next().is_none());
+
+    for &byte in &frame[20..] {
+        producer.enqueue(byte).unwrap();
+    }
+    let results = decoder.drain(&mut consumer).collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().1, reading(12));
+}
+
+#[test]
+fn yields_every_queued_frame() {
+    let mut queue: ByteQueue<128> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let mut decoder: FrameDecoder = FrameDecoder::new();
+
+    for byte in [0x13, 0x37]
+        .into_iter()
+        .chain(encode_frame(&reading(20)))
+        .chain(encode_frame(&reading(21)))
+    {
+        producer.enqueue(byte).unwrap();
+    }
+
+    let readings = decoder
+        .drain(&mut consumer)
+        .map(|result| result.unwrap().1)
+        .collect::<Vec<_>>();
+    assert_eq!(readings, [reading(20), reading(21)]);
+}
+
+#[test]
+fn recovers_from_overrun() {
+    let mut queue: ByteQueue<64> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let mut decoder: FrameDecoder = FrameDecoder::new();
+
+    // The queue holds 63 bytes, so the end of the second frame is dropped
+    let mut dropped = 0;
+    for byte in encode_frame(&reading(30))
+        .into_iter()
+        .chain(encode_frame(&reading(31)))
+    {
+        if producer.enqueue(byte).is_err() {
+            dropped += 1;
+        }
+    }
+    assert_eq!(dropped, 1);
+    let results = decoder.drain(&mut consumer).collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().1, reading(30));
+
+    // The truncated frame absorbs the start of the next one and fails
+    for byte in encode_frame(&reading(32)) {
+        producer.enqueue(byte).unwrap();
+    }
+    assert!(matches!(
+        decoder.drain(&mut consumer).next(),
+        Some(Err(SensorError::ChecksumMismatch))
+    ));
+    for byte in encode_frame(&reading(33)) {
+        producer.enqueue(byte).unwrap();
+    }
+    let readings = decoder
+        .drain(&mut consumer)
+        .map(|result| result.unwrap().1)
+        .collect::<Vec<_>>();
+    assert_eq!(readings, [reading(33)]);
+}
@@ -0,0 +1,142 @@
+//! Tests of the flash logger, against an in-memory NOR flash
+
+use core::convert::Infallible;
+use embedded_storage::nor_flash::{ErrorType, NorFlash, ReadNorFlash};
+use sen0177::{
+    logger::{Logger, LoggerError, RECORD_LEN},
+    time::Timestamped,
+    Concentrations, Reading,
+};
+
+const SECTOR_LEN: usize = 256;
+
+/// Flash that, like the real thing, can only clear bits when written
+struct FakeFlash {
+    data: Vec<u8>,
+    erases: Vec<u32>,
+}
+
+impl FakeFlash {
+    fn new(sectors: usize) -> Self {
+        Self {
+            data: vec![0xff; sectors * SECTOR_LEN],
+            erases: Vec::new(),
+        }
+    }
+}
+
+impl ErrorType for FakeFlash {
+    type Error = Infallible;
+}
+
+impl ReadNorFlash for FakeFlash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl NorFlash for FakeFlash {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = SECTOR_LEN;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.erases.push(from);
+        self.data[from as usize..to as usize].fill(0xff);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        for (stored, byte) in self.data[offset as usize..].iter_mut().zip(bytes) {
+            *stored &= byte;
+        }
+        Ok(())
+    }
+}
+
+fn record(timestamp: u64) -> Timestamped<Reading> {
+    let pm = timestamp as u16;
+    let concentrations = Concentrations::new(pm, pm + 1, pm + 2);
+    Timestamped::new(
+        timestamp,
+        Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0])
+            .with_device_status(0x91, 0),
+    )
+}
+
+fn timestamps(logger: &mut Logger<FakeFlash>) -> Vec<u64> {
+    logger
+        .records()
+        .map(|record| record.unwrap().timestamp)
+        .collect()
+}
+
+#[test]
+fn rejects_unusable_regions() {
+    assert!(matches!(
+        Logger::open(FakeFlash::new(4), 0..SECTOR_LEN as u32),
+        Err(LoggerError::BadRegion)
+    ));
+    assert!(matches!(
+        Logger::open(FakeFlash::new(4), 16..3 * SECTOR_LEN as u32),
+        Err(LoggerError::BadRegion)
+    ));
+}
+
+#[test]
+fn round_trips_records() {
+    let mut logger = Logger::open(FakeFlash::new(4), 0..4 * SECTOR_LEN as u32).unwrap();
+    assert_eq!(logger.capacity(), (4 * (SECTOR_LEN / RECORD_LEN)) as u32);
+    for timestamp in 0..7 {
+        logger.append(&record(timestamp)).unwrap();
+    }
+
+    let records = logger.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records, (0..7).map(record).collect::<Vec<_>>());
+}
+
+#[test]
+fn wraps_around_and_resumes_after_reopening() {
+    let mut logger = Logger::open(FakeFlash::new(4), 0..4 * SECTOR_LEN as u32).unwrap();
+    let capacity = logger.capacity() as u64;
+    for timestamp in 0..capacity + 3 {
+        logger.append(&record(timestamp)).unwrap();
+    }
+    // The first sector was erased to make room, dropping its 5 records
+    assert_eq!(
+        timestamps(&mut logger),
+        (5..capacity + 3).collect::<Vec<_>>()
+    );
+
+    let mut logger = Logger::open(logger.release(), 0..4 * SECTOR_LEN as u32).unwrap();
+    logger.append(&record(capacity + 3)).unwrap();
+    assert_eq!(
+        timestamps(&mut logger),
+        (5..capacity + 4).collect::<Vec<_>>()
+    );
+
+    let flash = logger.release();
+    assert_eq!(flash.erases, [0, 256, 512, 768, 0]);
+}
+
+#[test]
+fn skips_interrupted_record() {
+    let mut logger = Logger::open(FakeFlash::new(2), 0..2 * SECTOR_LEN as u32).unwrap();
+    for timestamp in 0..3 {
+        logger.append(&record(timestamp)).unwrap();
+    }
+    let mut flash = logger.release();
+    // A write of the fourth record that lost power partway through
+    flash.data[3 * RECORD_LEN..3 * RECORD_LEN + 10].fill(0x00);
+
+    let mut logger = Logger::open(flash, 0..2 * SECTOR_LEN as u32).unwrap();
+    logger.append(&record(3)).unwrap();
+    assert_eq!(timestamps(&mut logger), [0, 1, 2, 3]);
+}
@@ -0,0 +1,54 @@
+//! Tests of the Matter cluster value mapping
+
+use sen0177::{
+    aqi,
+    matter::{self, AirQuality, LevelValue},
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    Reading::new(
+        Concentrations::new(pm2_5 / 2, pm2_5, pm10),
+        Concentrations::new(pm2_5 / 2, pm2_5, pm10),
+        [0; 6],
+    )
+}
+
+#[test]
+fn maps_aqi_categories_to_air_quality() {
+    assert_eq!(AirQuality::from(aqi::pm2_5(50)), AirQuality::Good);
+    assert_eq!(AirQuality::from(aqi::pm2_5(200)), AirQuality::Fair);
+    assert_eq!(AirQuality::from(aqi::pm2_5(400)), AirQuality::Moderate);
+    assert_eq!(AirQuality::from(aqi::pm2_5(1000)), AirQuality::Poor);
+    assert_eq!(AirQuality::from(aqi::pm2_5(2000)), AirQuality::VeryPoor);
+    assert_eq!(
+        AirQuality::from(aqi::pm2_5(3000)),
+        AirQuality::ExtremelyPoor
+    );
+    assert_eq!(AirQuality::ExtremelyPoor as u8, 6);
+}
+
+#[test]
+fn uses_the_worse_pollutant() {
+    assert_eq!(matter::air_quality(&reading(5, 10)), AirQuality::Good);
+    assert_eq!(matter::air_quality(&reading(5, 300)), AirQuality::Poor);
+    assert_eq!(matter::air_quality(&reading(60, 10)), AirQuality::Poor);
+}
+
+#[test]
+fn reports_level_values() {
+    assert_eq!(
+        matter::level_values(&reading(40, 100)),
+        [LevelValue::Unknown, LevelValue::High, LevelValue::Medium]
+    );
+    assert_eq!(LevelValue::Critical as u8, 4);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn reports_measured_values() {
+    assert_eq!(
+        matter::measured_values(&reading(40, 200)),
+        [20.0, 40.0, 200.0]
+    );
+}
@@ -0,0 +1,100 @@
+//! Tests of the Modbus register map
+
+use sen0177::{
+    modbus::{
+        Exception, RegisterMap, REGISTER_COUNT, STATUS_HAS_READING, STATUS_LAST_READ_FAILED,
+        STATUS_NO_ENV, STATUS_REGISTER,
+    },
+    Concentrations, Reading, SensorError,
+};
+
+fn reading() -> Reading {
+    Reading::new(
+        Concentrations::new(5, 12, 20),
+        Concentrations::new(4, 11, 19),
+        [900, 300, 60, 8, 2, 1],
+    )
+    .with_device_status(0x91, 0)
+}
+
+#[test]
+fn lays_out_reading_and_counters() {
+    let mut map = RegisterMap::new();
+    map.update::<()>(&Ok(reading()));
+    map.update::<()>(&Err(SensorError::ChecksumMismatch));
+    map.update::<()>(&Err(SensorError::Timeout));
+    map.update::<()>(&Err(SensorError::ReadError(())));
+
+    assert_eq!(
+        map.registers(),
+        [
+            5,
+            12,
+            20,
+            4,
+            11,
+            19,
+            900,
+            300,
+            60,
+            8,
+            2,
+            1,
+            0x91,
+            0,
+            STATUS_HAS_READING | STATUS_LAST_READ_FAILED,
+            0,
+            1,
+            0,
+            1,
+            0,
+            1,
+            0,
+            1
+        ]
+    );
+
+    map.update::<()>(&Ok(reading()));
+    assert_eq!(
+        map.registers()[STATUS_REGISTER as usize],
+        STATUS_HAS_READING
+    );
+    assert_eq!(map.registers()[16], 2);
+}
+
+#[test]
+fn flags_unsupported_environmental_values() {
+    let mut map = RegisterMap::new();
+    map.update::<()>(&Ok(reading().without_atmospheric()));
+    assert_eq!(map.registers()[..6], [5, 12, 20, 0, 0, 0]);
+    assert_eq!(
+        map.registers()[STATUS_REGISTER as usize],
+        STATUS_HAS_READING | STATUS_NO_ENV
+    );
+
+    map.update::<()>(&Ok(reading()));
+    assert_eq!(map.registers()[3..6], [4, 11, 19]);
+    assert_eq!(
+        map.registers()[STATUS_REGISTER as usize],
+        STATUS_HAS_READING
+    );
+}
+
+#[test]
+fn serves_read_requests() {
+    let mut map = RegisterMap::new();
+    map.update::<()>(&Ok(reading()));
+
+    let mut buf = [0u8; 250];
+    assert_eq!(map.read(1, 2, &mut buf), Ok(4));
+    assert_eq!(buf[..4], [0x00, 0x0c, 0x00, 0x14]);
+    assert_eq!(map.read(0, REGISTER_COUNT, &mut buf), Ok(46));
+
+    assert_eq!(
+        map.read(REGISTER_COUNT - 1, 2, &mut buf),
+        Err(Exception::IllegalDataAddress)
+    );
+    assert_eq!(map.read(0, 0, &mut buf), Err(Exception::IllegalDataValue));
+    assert_eq!(map.read(0, 126, &mut buf), Err(Exception::IllegalDataValue));
+    assert_eq!(Exception::IllegalDataAddress.code(), 2);
+}
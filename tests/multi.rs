@@ -0,0 +1,71 @@
+//! Tests of polling and combining several sensors
+
+use sen0177::{
+    multi::{Combine, MultiSensor},
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+/// A sensor that always reads the given PM2.5 concentration, or times out
+struct FakeSensor(Option<u16>);
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        let pm2_5 = self.0.ok_or(SensorError::Timeout)?;
+        let concentrations = Concentrations::new(pm2_5 / 2, pm2_5, pm2_5 * 2);
+        Ok(Reading::new(concentrations, concentrations, [pm2_5; 6]))
+    }
+}
+
+#[test]
+fn polls_round_robin() {
+    let mut multi = MultiSensor::new([FakeSensor(Some(10)), FakeSensor(None)]);
+    assert_eq!(multi.poll_next().0, 0);
+    assert!(matches!(multi.poll_next(), (1, Err(SensorError::Timeout))));
+    assert_eq!(multi.poll_next().0, 0);
+
+    let health = multi.health();
+    assert_eq!((health[0].reads, health[0].failures), (2, 0));
+    assert_eq!((health[1].reads, health[1].consecutive_failures), (0, 1));
+    assert!(health[0].is_healthy() && !health[1].is_healthy());
+}
+
+#[test]
+fn combines_with_mean_or_median() {
+    let sensors = || {
+        [
+            FakeSensor(Some(10)),
+            FakeSensor(Some(12)),
+            FakeSensor(Some(17)),
+        ]
+    };
+
+    let combined = MultiSensor::new(sensors()).poll_all().unwrap();
+    assert_eq!(combined.reading.pm2_5(), 12);
+    assert_eq!(combined.reading.pm10(), 24);
+    assert_eq!(combined.sensors_used(), 3);
+
+    let mut multi = MultiSensor::new(sensors()).combine(Combine::Mean);
+    assert_eq!(multi.read().unwrap().pm2_5(), 13);
+}
+
+#[test]
+fn excludes_outlier_sensors() {
+    let mut multi = MultiSensor::new([
+        FakeSensor(Some(20)),
+        FakeSensor(Some(90)),
+        FakeSensor(Some(22)),
+        FakeSensor(None),
+    ])
+    .combine(Combine::Mean);
+
+    let combined = multi.poll_all().unwrap();
+    assert_eq!(combined.included, [true, false, true, false]);
+    assert_eq!(combined.reading.pm2_5(), 21);
+    assert_eq!(multi.health()[1].outliers, 1);
+}
+
+#[test]
+fn fails_when_no_sensor_is_healthy() {
+    let mut multi = MultiSensor::new([FakeSensor(None), FakeSensor(None)]);
+    assert!(matches!(multi.read(), Err(SensorError::Timeout)));
+}
@@ -0,0 +1,59 @@
+//! Tests of data quality flags
+
+use sen0177::{
+    quality::{Qualified, QualityFlags},
+    Concentrations, Reading,
+};
+
+fn reading(pm1: u16, pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(pm1, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn assess_flags_plausibility_and_caller_status() {
+    let good = Qualified::assess(reading(5, 10, 15), true, true);
+    assert_eq!(good.flags, QualityFlags::VALID);
+
+    let implausible = Qualified::assess(reading(20, 10, 15), true, true);
+    assert!(!implausible.flags.contains(QualityFlags::PLAUSIBLE));
+    assert!(!implausible.flags.contains(QualityFlags::VALID));
+
+    let cold = Qualified::assess(reading(5, 10, 15), false, false);
+    assert_eq!(cold.flags, QualityFlags::PLAUSIBLE);
+}
+
+#[test]
+fn flags_travel_through_map_and_aggregate() {
+    let corrected = Qualified::assess(reading(5, 10, 15), true, true)
+        .map(|reading| u32::from(reading.pm2_5()) * 9)
+        .with(QualityFlags::HUMIDITY_CORRECTED);
+    assert_eq!(corrected.value, 90);
+    assert!(corrected
+        .flags
+        .contains(QualityFlags::VALID | QualityFlags::HUMIDITY_CORRECTED));
+
+    let interpolated = QualityFlags::VALID | QualityFlags::INTERPOLATED;
+    let unverified = QualityFlags::PLAUSIBLE | QualityFlags::WARMED_UP;
+    let aggregate =
+        QualityFlags::aggregate_all([QualityFlags::VALID, interpolated, unverified]).unwrap();
+    assert_eq!(aggregate, unverified | QualityFlags::INTERPOLATED);
+    assert_eq!(QualityFlags::aggregate_all([]), None);
+}
+
+#[test]
+fn bits_round_trip_and_reserved_bits_are_cleared() {
+    let flags = QualityFlags::VALID | QualityFlags::INTERPOLATED;
+    assert_eq!(QualityFlags::from_bits(flags.bits()), flags);
+    assert_eq!(QualityFlags::from_bits(0xe0), QualityFlags::NONE);
+    assert!(QualityFlags::from_bits(0xe0).is_empty());
+
+    let mut flags = QualityFlags::VALID;
+    flags.remove(QualityFlags::WARMED_UP);
+    assert_eq!(
+        flags,
+        QualityFlags::CHECKSUM_VERIFIED | QualityFlags::PLAUSIBLE
+    );
+    flags.set(QualityFlags::WARMED_UP, true);
+    assert_eq!(flags & QualityFlags::WARMED_UP, QualityFlags::WARMED_UP);
+}
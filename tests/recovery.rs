@@ -0,0 +1,139 @@
+//! Tests of the recovery escalation ladder
+
+mod common;
+
+use std::cell::Cell;
+
+use common::{reading, sensor};
+use embedded_hal_nb::serial::ErrorKind;
+use sen0177::{
+    mock::MockSerial,
+    protocol::{encode_command, Command},
+    recovery::{EscalationPolicy, HardReset, Recovering, RecoveryEvent, RecoveryStep},
+    SensorError,
+};
+
+fn escalated(step: RecoveryStep, failures: u32) -> RecoveryEvent {
+    RecoveryEvent::Escalated { step, failures }
+}
+
+/// Counts resets, failing if told to
+struct FakeReset<'a> {
+    resets: &'a Cell<u32>,
+    fail: bool,
+}
+
+impl HardReset for FakeReset<'_> {
+    fn hard_reset(&mut self) -> bool {
+        self.resets.set(self.resets.get() + 1);
+        !self.fail
+    }
+}
+
+#[test]
+fn policy_ladder() {
+    let policy = EscalationPolicy::new().retries(2).flushes(1).reinits(2);
+    let steps: Vec<_> = (1..=8)
+        .map(|failures| policy.step(failures, true))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            RecoveryStep::Retry,
+            RecoveryStep::Retry,
+            RecoveryStep::Flush,
+            RecoveryStep::Reinit,
+            RecoveryStep::Reinit,
+            RecoveryStep::HardReset,
+            RecoveryStep::GiveUp,
+            RecoveryStep::GiveUp,
+        ]
+    );
+    assert_eq!(policy.step(6, false), RecoveryStep::GiveUp);
+    assert_eq!(
+        EscalationPolicy::new().retries(0).step(1, true),
+        RecoveryStep::Flush
+    );
+}
+
+#[test]
+fn retries_then_recovers() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed_error(ErrorKind::Noise)
+        .feed_reading(&reading(12));
+    let mut events = Vec::new();
+    {
+        let sensor = sensor(&mut serial);
+        let mut sensor =
+            Recovering::new(sensor, EscalationPolicy::new()).on_event(|event| events.push(event));
+
+        assert_eq!(sensor.read().unwrap(), reading(12));
+        assert_eq!(sensor.failures(), 0);
+    }
+    assert_eq!(
+        events,
+        [
+            escalated(RecoveryStep::Retry, 1),
+            RecoveryEvent::Recovered { failures: 1 },
+        ]
+    );
+}
+
+#[test]
+fn climbs_the_ladder_then_gives_up() {
+    let mut serial = MockSerial::new();
+    let mut events = Vec::new();
+    {
+        let sensor = sensor(&mut serial);
+        let mut sensor =
+            Recovering::new(sensor, EscalationPolicy::new()).on_event(|event| events.push(event));
+
+        assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+        assert_eq!(sensor.failures(), 0);
+    }
+    // Without a hardware reset, that step is skipped
+    assert_eq!(
+        events,
+        [
+            escalated(RecoveryStep::Retry, 1),
+            escalated(RecoveryStep::Retry, 2),
+            escalated(RecoveryStep::Flush, 3),
+            escalated(RecoveryStep::Reinit, 4),
+            escalated(RecoveryStep::GiveUp, 5),
+        ]
+    );
+    let mut commands = encode_command(Command::Wakeup).to_vec();
+    commands.extend_from_slice(&encode_command(Command::ActiveMode));
+    assert_eq!(serial.written(), commands);
+}
+
+#[test]
+fn hard_resets_when_configured() {
+    let policy = EscalationPolicy::new().retries(0).flushes(0).reinits(0);
+    for fail in [false, true] {
+        let mut serial = MockSerial::new();
+        let resets = Cell::new(0);
+        let mut events = Vec::new();
+        {
+            let sensor = sensor(&mut serial);
+            let mut sensor = Recovering::new(sensor, policy)
+                .hard_reset(FakeReset {
+                    resets: &resets,
+                    fail,
+                })
+                .on_event(|event| events.push(event));
+
+            assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+        }
+        assert_eq!(resets.get(), 1);
+        // Whether or not the reset worked, the next failure gives up
+        assert_eq!(
+            events,
+            [
+                escalated(RecoveryStep::HardReset, 1),
+                escalated(RecoveryStep::GiveUp, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::reading;
+use sen0177::{
+    csv::{CsvError, Row, HEADER},
+    replay::{ReplayError, ReplaySensor},
+    time::Timestamped,
+    AirQualitySensor, SensorError,
+};
+use std::{
+    io::Cursor,
+    time::{Duration, Instant},
+};
+
+fn log(records: &[(u64, u16)]) -> String {
+    let mut log = HEADER.to_string();
+    for &(timestamp, pm2_5) in records {
+        log.push_str(Row::new(&Timestamped::new(timestamp, reading(pm2_5))).as_str());
+    }
+    log
+}
+
+#[test]
+fn replays_csv_log() {
+    let mut sensor = ReplaySensor::new(Cursor::new(log(&[(100, 12), (160, 35), (220, 8)])));
+    let record = sensor.read_timestamped().unwrap();
+    assert_eq!(record, Timestamped::new(100, reading(12)));
+    assert_eq!(sensor.read().unwrap(), reading(35));
+    assert_eq!(sensor.read().unwrap(), reading(8));
+    assert_eq!(sensor.line(), 4);
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ReadError(ReplayError::End))
+    ));
+    assert_eq!(sensor.info().name, "Replay");
+}
+
+#[test]
+fn skips_blank_lines_and_reports_bad_rows() {
+    let mut text = log(&[(1, 10)]);
+    text.push_str("\n2,not,a,row\n");
+    text.push_str(Row::new(&Timestamped::new(3, reading(30))).as_str());
+    let mut sensor = ReplaySensor::new(Cursor::new(text));
+    assert_eq!(sensor.read().unwrap(), reading(10));
+    match sensor.read() {
+        Err(SensorError::ReadError(error @ ReplayError::Csv { .. })) => {
+            assert_eq!(error.to_string(), "Invalid CSV row on line 4");
+            let source = std::error::Error::source(&error).expect("no source");
+            assert_eq!(
+                source.to_string(),
+                CsvError::WrongColumnCount(4).to_string()
+            );
+        }
+        other => panic!("expected a CSV error, got {:?}", other),
+    }
+    // Replay continues after a bad line
+    assert_eq!(sensor.read().unwrap(), reading(30));
+}
+
+#[test]
+fn paces_readings_by_timestamp() {
+    // 100ms between readings, replayed at 4x
+    let mut sensor = ReplaySensor::new(Cursor::new(log(&[(0, 1), (100, 2), (200, 3)])))
+        .paced(Duration::from_millis(1))
+        .speedup(4);
+    let start = Instant::now();
+    for _ in 0..3 {
+        sensor.read().unwrap();
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(50), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(150), "{:?}", elapsed);
+}
+
+#[test]
+fn unpaced_replay_does_not_wait() {
+    let mut sensor = ReplaySensor::new(Cursor::new(log(&[(0, 1), (1_000_000, 2)])));
+    let start = Instant::now();
+    sensor.read().unwrap();
+    sensor.read().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn replays_json_lines() {
+    let mut text = String::new();
+    for record in [
+        Timestamped::new(5, reading(12)),
+        Timestamped::new(6, reading(14)),
+    ] {
+        text.push_str(&serde_json::to_string(&record).unwrap());
+        text.push('\n');
+    }
+    // Formats may be mixed within a log
+    text.push_str(Row::new(&Timestamped::new(7, reading(16))).as_str());
+    let mut sensor = ReplaySensor::new(Cursor::new(text));
+    assert_eq!(
+        sensor.read_timestamped().unwrap(),
+        Timestamped::new(5, reading(12))
+    );
+    assert_eq!(sensor.read().unwrap(), reading(14));
+    assert_eq!(sensor.read().unwrap(), reading(16));
+}
@@ -0,0 +1,94 @@
+//! Tests of rolling long-window averages
+
+use sen0177::{
+    guidelines::{AveragingPeriod, GuidelineSet},
+    rolling::{AnnualAverage, DailyAverage, RollingAverage, SECONDS_PER_DAY, SECONDS_PER_HOUR},
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16, pm10: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm10);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn averages_hourly_means_equally() {
+    let mut daily = DailyAverage::daily(60);
+    // One hour with many readings at 10, another with a single one at 40
+    for minute in 0..60 {
+        daily.push(minute * 60, reading(10, 20));
+    }
+    daily.push(SECONDS_PER_HOUR, reading(40, 50));
+
+    let averages = daily.averages().unwrap();
+    assert_eq!(averages.pm2_5, 250);
+    assert_eq!(averages.pm10, 350);
+    assert_eq!(daily.count(), 61);
+    assert_eq!(daily.bins_with_data(), 2);
+}
+
+#[test]
+fn tracks_completeness() {
+    let mut daily = DailyAverage::daily(60);
+    assert_eq!(daily.completeness(), 0);
+    assert_eq!(daily.averages(), None);
+
+    // 18 full hours of readings every minute is 75%
+    for minute in 0..18 * 60 {
+        daily.push(minute * 60, reading(10, 10));
+    }
+    assert_eq!(daily.completeness(), 75);
+
+    // Extra readings in one hour don't make up for missing hours
+    for second in 0..3600 {
+        daily.push(17 * SECONDS_PER_HOUR + second, reading(10, 10));
+    }
+    assert_eq!(daily.completeness(), 75);
+}
+
+#[test]
+fn window_rolls_forward_and_forgets_old_bins() {
+    let mut rolling = RollingAverage::<3>::new(10, 1);
+    rolling.push(0, reading(100, 100));
+    rolling.push(10, reading(10, 10));
+    rolling.push(20, reading(10, 10));
+    assert_eq!(rolling.averages().unwrap().pm2_5, 400);
+
+    // The first bin falls out of the window
+    rolling.push(30, reading(10, 10));
+    assert_eq!(rolling.averages().unwrap().pm2_5, 100);
+    assert_eq!(rolling.completeness(), 100);
+
+    // Readings older than the window are ignored
+    rolling.push(5, reading(1000, 1000));
+    assert_eq!(rolling.averages().unwrap().pm2_5, 100);
+
+    // A long gap empties the window
+    rolling.push(1000, reading(20, 20));
+    assert_eq!(rolling.bins_with_data(), 1);
+    assert_eq!(rolling.completeness(), 33);
+    assert_eq!(rolling.averages().unwrap().pm2_5, 200);
+
+    rolling.clear();
+    assert_eq!(rolling.count(), 0);
+}
+
+#[test]
+fn annual_average_checks_against_guidelines() {
+    let mut annual = AnnualAverage::annual(3600);
+    for hour in 0..365 * 24 {
+        annual.push(hour * SECONDS_PER_HOUR, reading(12, 20));
+    }
+    assert_eq!(annual.completeness(), 100);
+    assert_eq!(annual.bins_with_data(), 365);
+
+    // A year later, only the last day's bin has been replaced
+    annual.push(365 * SECONDS_PER_DAY, reading(12, 20));
+    assert_eq!(annual.bins_with_data(), 365);
+
+    let exceedances: Vec<_> = GuidelineSet::us_epa()
+        .check(AveragingPeriod::Annual, annual.averages().unwrap())
+        .collect();
+    assert_eq!(exceedances.len(), 1);
+    assert_eq!(exceedances[0].measured, 120);
+}
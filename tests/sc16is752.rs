@@ -0,0 +1,151 @@
+//! Tests of the serial driver through an SC16IS752 bridge, against an
+//! in-memory register model
+
+mod common;
+
+use core::convert::Infallible;
+use embedded_hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+use std::collections::VecDeque;
+
+use common::{reading, sensor};
+use sen0177::{
+    protocol::{encode_command, encode_frame, Command},
+    sc16is752::{BridgeError, Channel, Sc16is752, XTAL_14_7456_MHZ},
+    AirQualitySensor, SensorError,
+};
+
+const ADDRESS: u8 = 0x48;
+
+/// One channel of a bridge, whose receive FIFO is refilled from `incoming`
+/// as it is drained
+#[derive(Default)]
+struct FakeBridge {
+    incoming: VecDeque<u8>,
+    /// (channel, register, value) for each register write
+    writes: Vec<(u8, u8, u8)>,
+    overrun: bool,
+    largest_burst: usize,
+}
+
+impl ErrorType for FakeBridge {
+    type Error = Infallible;
+}
+
+impl I2c<SevenBitAddress> for FakeBridge {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        assert_eq!(address, ADDRESS);
+        let mut reg = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let (channel, register) = ((bytes[0] >> 1) & 0x3, bytes[0] >> 3);
+                    if let Some(&value) = bytes.get(1) {
+                        self.writes.push((channel, register, value));
+                    }
+                    reg = Some(register);
+                }
+                Operation::Read(buf) => match reg.expect("read without a register") {
+                    // RHR
+                    0x00 => {
+                        self.largest_burst = self.largest_burst.max(buf.len());
+                        for byte in buf.iter_mut() {
+                            *byte = self.incoming.pop_front().expect("read past FIFO");
+                        }
+                    }
+                    // LSR
+                    0x05 => {
+                        buf[0] = 0x60
+                            | if std::mem::take(&mut self.overrun) {
+                                0x02
+                            } else {
+                                0
+                            }
+                    }
+                    // TXLVL
+                    0x08 => buf[0] = 64,
+                    // RXLVL
+                    0x09 => buf[0] = self.incoming.len().min(64) as u8,
+                    other => panic!("unexpected read of register {:#04x}", other),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn configures_channel_for_9600_baud() {
+    let bridge =
+        Sc16is752::new_i2c(FakeBridge::default(), ADDRESS, Channel::B, XTAL_14_7456_MHZ).unwrap();
+    let fake = bridge.release();
+    // Divisor latch on, divisor 96, 8N1, FIFOs enabled and reset
+    assert_eq!(
+        fake.writes,
+        [
+            (1, 3, 0x80),
+            (1, 0, 96),
+            (1, 1, 0),
+            (1, 3, 0x03),
+            (1, 2, 0x07)
+        ]
+    );
+}
+
+#[test]
+fn reads_frames_through_fifo() {
+    let mut fake = FakeBridge::default();
+    for pm2_5 in 10..13 {
+        fake.incoming.extend(encode_frame(&reading(pm2_5)));
+    }
+    let bridge = Sc16is752::new_i2c(fake, ADDRESS, Channel::A, XTAL_14_7456_MHZ).unwrap();
+    let mut sensor = sensor(bridge);
+
+    for pm2_5 in 10..13 {
+        assert_eq!(sensor.read().unwrap(), reading(pm2_5));
+    }
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+    assert_eq!(sensor.release().release().largest_burst, 64);
+}
+
+#[test]
+fn reports_overrun_and_recovers() {
+    let mut fake = FakeBridge {
+        overrun: true,
+        ..FakeBridge::default()
+    };
+    fake.incoming.extend(encode_frame(&reading(20)));
+    let bridge = Sc16is752::new_i2c(fake, ADDRESS, Channel::A, XTAL_14_7456_MHZ).unwrap();
+    let mut sensor = sensor(bridge);
+
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ReadError(BridgeError::Overrun))
+    ));
+    assert_eq!(sensor.read().unwrap(), reading(20));
+}
+
+#[test]
+fn sends_commands_through_thr() {
+    let mut fake = FakeBridge::default();
+    fake.incoming.extend(encode_frame(&reading(30)));
+    let bridge = Sc16is752::new_i2c(fake, ADDRESS, Channel::A, XTAL_14_7456_MHZ).unwrap();
+    let sensor = sensor(bridge);
+    let mut sensor = sensor.into_passive().unwrap();
+    assert_eq!(sensor.read().unwrap(), reading(30));
+
+    let fake = sensor.release().release();
+    let sent = fake
+        .writes
+        .iter()
+        .filter(|&&(_, register, _)| register == 0x00)
+        .skip(1) // the low byte of the divisor
+        .map(|&(_, _, value)| value)
+        .collect::<Vec<_>>();
+    let mut expected = encode_command(Command::PassiveMode).to_vec();
+    expected.extend(encode_command(Command::PassiveRead));
+    assert_eq!(sent, expected);
+}
@@ -0,0 +1,41 @@
+//! Tests of the JSON Schema export
+
+use sen0177::schema::{reading_json_schema, timestamped_reading_json_schema, SCHEMA_VERSION};
+
+#[test]
+fn describes_every_reading_field() {
+    let schema = reading_json_schema();
+
+    assert!(schema.contains(&format!(
+        "\"$id\": \"urn:sen0177:reading:v{}\"",
+        SCHEMA_VERSION
+    )));
+    for field in [
+        "pm1",
+        "pm2_5",
+        "pm10",
+        "env_pm1",
+        "env_pm2_5",
+        "env_pm10",
+        "particles_0_3",
+        "particles_0_5",
+        "particles_1",
+        "particles_2_5",
+        "particles_5",
+        "particles_10",
+        "firmware_version",
+        "device_error_code",
+    ] {
+        assert!(schema.contains(&format!("\"{}\": {{", field)), "{}", field);
+    }
+    assert_eq!(schema.matches("\"unit\": \"µg/m³\"").count(), 6);
+}
+
+#[test]
+fn nests_reading_in_timestamped_schema() {
+    let schema = timestamped_reading_json_schema();
+
+    assert!(schema.contains("\"timestamp\": {"));
+    assert!(schema.contains("\"value\": {"));
+    assert_eq!(schema.matches('{').count(), schema.matches('}').count());
+}
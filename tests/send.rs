@@ -0,0 +1,44 @@
+//! Compile-time checks that the types shared between interrupt handlers and
+//! tasks (as RTIC's resources are) can be sent between contexts
+
+use embedded_hal_nb::serial::ErrorKind;
+use sen0177::{
+    decoder::FrameDecoder,
+    history::History,
+    protocol::{Plantower, Pms5003T},
+    serial::{Passive, Sen0177, SerialDriver, Sleep},
+    Reading, SensorError,
+};
+
+fn assert_send<T: Send>() {}
+
+/// A `Send` UART, standing in for a HAL's
+struct Uart;
+
+impl embedded_hal_nb::serial::ErrorType for Uart {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal_nb::serial::Read<u8> for Uart {
+    fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, ErrorKind> {
+        Err(embedded_hal_nb::nb::Error::WouldBlock)
+    }
+}
+
+struct Delay;
+
+impl embedded_hal::delay::DelayNs for Delay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[test]
+fn shared_types_are_send() {
+    assert_send::<Reading>();
+    assert_send::<SensorError<ErrorKind>>();
+    assert_send::<History<8>>();
+    assert_send::<FrameDecoder<Plantower>>();
+    assert_send::<FrameDecoder<Pms5003T>>();
+    assert_send::<SerialDriver<Plantower, Uart>>();
+    assert_send::<Sen0177<Uart, ErrorKind>>();
+    assert_send::<Sen0177<Uart, ErrorKind, Passive, Sleep<Delay>>>();
+}
@@ -0,0 +1,45 @@
+//! Tests of SenML encoding
+
+use sen0177::{
+    senml::{Pack, CONCENTRATION_UNIT, COUNT_UNIT},
+    time::Timestamped,
+    Concentrations, Reading,
+};
+
+#[test]
+fn lists_every_value_with_units() {
+    let reading = Reading::new(
+        Concentrations::new(5, 12, 20),
+        Concentrations::new(4, 10, 18),
+        [600, 200, 40, 5, 1, 0],
+    );
+    let pack = Pack::timestamped(
+        "urn:dev:mac:0024befffe804ff1:",
+        &Timestamped::new(1_700_000_000, reading),
+    );
+    let records: Vec<_> = pack.records().collect();
+
+    assert_eq!(records[1], ("pm2_5", CONCENTRATION_UNIT, 12));
+    assert_eq!(records[4], ("env_pm2_5", CONCENTRATION_UNIT, 10));
+    assert_eq!(records[6], ("particles_0_3", COUNT_UNIT, 600));
+    assert_eq!(
+        records
+            .iter()
+            .map(|(_, _, value)| *value)
+            .collect::<Vec<_>>(),
+        [5, 12, 20, 4, 10, 18, 600, 200, 40, 5, 1, 0]
+    );
+}
+
+#[test]
+fn leaves_out_unsupported_environmental_values() {
+    let concentrations = Concentrations::new(5, 12, 20);
+    let reading =
+        Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0]).without_atmospheric();
+    let pack = Pack::new("urn:dev:mac:0024befffe804ff1:", &reading);
+    let records: Vec<_> = pack.records().collect();
+
+    assert_eq!(records.len(), 9);
+    assert_eq!(records[3], ("particles_0_3", COUNT_UNIT, 600));
+    assert!(records.iter().all(|(name, ..)| !name.starts_with("env_")));
+}
@@ -0,0 +1,444 @@
+//! End-to-end tests of the serial driver, fed from an in-memory UART
+
+mod common;
+
+use common::{clock, reading, sensor};
+use embedded_hal_nb::serial::ErrorKind;
+use sen0177::{
+    mock::{Faults, MockSerial},
+    protocol::{encode_command, encode_frame, Command},
+    serial::{Sen0177, Sen0177Builder},
+    watchdog::Watchdog,
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+#[test]
+fn clean_stream() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&encode_frame(&reading(12)))
+        .feed(&encode_frame(&reading(13)));
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(12));
+    assert_eq!(sensor.read().unwrap(), reading(13));
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+}
+
+#[test]
+fn resyncs_after_leading_garbage() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&[0x00, 0xff, 0x42, 0x00, 0x42, 0x42])
+        .feed(&encode_frame(&reading(20)));
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(20));
+}
+
+#[test]
+fn resyncs_when_joining_mid_frame() {
+    let frame = encode_frame(&reading(30));
+    let mut serial = MockSerial::new();
+    serial.feed(&frame[17..]).feed(&frame);
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(30));
+}
+
+#[test]
+fn resyncs_across_interleaved_garbage() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&encode_frame(&reading(1)))
+        .feed(b"garbage")
+        .feed(&encode_frame(&reading(2)))
+        .feed(&[0x42, 0x4c, 0x4d])
+        .feed(&encode_frame(&reading(3)));
+    let mut sensor = sensor(&mut serial);
+
+    for pm2_5 in 1..=3 {
+        assert_eq!(sensor.read().unwrap(), reading(pm2_5));
+    }
+}
+
+#[test]
+fn rejects_corrupted_frame_then_recovers() {
+    let mut corrupted = encode_frame(&reading(40));
+    corrupted[13] ^= 0x10;
+    let mut serial = MockSerial::new();
+    serial.feed(&corrupted).feed(&encode_frame(&reading(41)));
+    let mut sensor = sensor(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::ChecksumMismatch)));
+    assert_eq!(sensor.read().unwrap(), reading(41));
+}
+
+#[test]
+fn lenient_checksum_accepts_corrupted_frame() {
+    let mut corrupted = encode_frame(&reading(40));
+    corrupted[30] ^= 0xff;
+    let mut serial = MockSerial::new();
+    serial.feed(&corrupted);
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .strict_checksum(false)
+        .build(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(40));
+}
+
+#[test]
+fn lenient_read_flags_unverified_readings() {
+    let mut corrupted = encode_frame(&reading(40));
+    corrupted[30] ^= 0xff;
+    let mut serial = MockSerial::new();
+    serial.feed(&corrupted).feed(&encode_frame(&reading(41)));
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .strict_checksum(false)
+        .build(&mut serial);
+
+    let first = sensor.read_lenient().unwrap();
+    assert_eq!(first.reading, reading(40));
+    assert!(!first.verified);
+    let second = sensor.read_lenient().unwrap();
+    assert_eq!(second.reading, reading(41));
+    assert!(second.verified);
+}
+
+#[test]
+fn truncated_frame_is_rejected() {
+    let frame = encode_frame(&reading(50));
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&frame[..10])
+        .feed(&frame)
+        .feed(&encode_frame(&reading(51)));
+    let mut sensor = sensor(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::ChecksumMismatch)));
+    assert_eq!(sensor.read().unwrap(), reading(51));
+}
+
+#[test]
+fn skips_command_acknowledgement() {
+    let ack = [0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74];
+    let mut serial = MockSerial::new();
+    serial.feed(&ack).feed(&encode_frame(&reading(60)));
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(60));
+}
+
+#[test]
+fn gives_up_without_magic() {
+    let mut serial = MockSerial::new();
+    serial.feed(&[0x55; 200]);
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .resync_budget(64)
+        .build(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::BadMagic)));
+}
+
+#[test]
+fn resync_budget_in_milliseconds() {
+    let mut serial = MockSerial::new();
+    serial.feed(&[0x55; 200]);
+    // 100ms at 960 bytes per second
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .resync_time_ms(100)
+        .build(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::BadMagic)));
+    assert_eq!(serial.pending(), 200 - 96);
+}
+
+#[test]
+fn synchronizes_past_full_os_buffer() {
+    // A pseudo-random buffer of garbage, including false header bytes
+    let mut garbage = [0u8; 4000];
+    let mut state = 1u32;
+    for byte in &mut garbage {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        *byte = (state >> 16) as u8;
+    }
+    let mut serial = MockSerial::new();
+    serial.feed(&garbage).feed(&encode_frame(&reading(65)));
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(65));
+}
+
+#[test]
+fn hints_at_baud_mismatch() {
+    let mut serial = MockSerial::new();
+    for _ in 0..100 {
+        serial.feed(&[0x00, 0xff]);
+    }
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .resync_budget(64)
+        .build(&mut serial);
+
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::LikelyBaudMismatch)
+    ));
+}
+
+#[test]
+fn gives_up_after_sync_attempts() {
+    let mut serial = MockSerial::new();
+    for _ in 0..3 {
+        serial.feed(&[0x42, 0x00]);
+    }
+    serial.feed(&encode_frame(&reading(70)));
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .sync_attempts(3)
+        .build(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::BadMagic)));
+    assert_eq!(sensor.read().unwrap(), reading(70));
+}
+
+#[test]
+fn zero_limits_still_allow_one_attempt() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&encode_frame(&reading(70)))
+        .feed(&[0x55])
+        .feed(&encode_frame(&reading(75)));
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .sync_attempts(0)
+        .resync_budget(0)
+        .build(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(70));
+    assert!(matches!(sensor.read(), Err(SensorError::BadMagic)));
+    assert_eq!(sensor.read().unwrap(), reading(75));
+}
+
+#[test]
+fn times_out_mid_frame() {
+    let frame = encode_frame(&reading(80));
+    let mut serial = MockSerial::new();
+    serial.feed(&frame[..20]);
+    let mut sensor = sensor(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+}
+
+#[test]
+fn surfaces_bus_errors() {
+    let mut serial = MockSerial::new();
+    serial
+        .feed(&[0x42])
+        .feed_error(ErrorKind::Overrun)
+        .feed(&encode_frame(&reading(90)));
+    let mut sensor = sensor(&mut serial);
+
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ReadError(ErrorKind::Overrun))
+    ));
+    assert_eq!(sensor.read().unwrap(), reading(90));
+}
+
+#[test]
+fn passive_mode_requests_each_reading() {
+    let mut serial = MockSerial::new();
+    serial.feed(&encode_frame(&reading(100)));
+    {
+        let mut sensor = sensor(&mut serial).into_passive().unwrap();
+        assert_eq!(sensor.read().unwrap(), reading(100));
+    }
+
+    let mut expected = encode_command(Command::PassiveMode).to_vec();
+    expected.extend_from_slice(&encode_command(Command::PassiveRead));
+    assert_eq!(serial.written(), expected);
+}
+
+#[test]
+fn validation_rejects_implausible_reading() {
+    let concentrations = Concentrations::new(50, 10, 5);
+    let implausible = Reading::new(concentrations, concentrations, [0; 6]);
+    let mut serial = MockSerial::new();
+    serial.feed(&encode_frame(&implausible));
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .validate(true)
+        .build(&mut serial);
+
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::ImplausibleData(_))
+    ));
+}
+
+#[test]
+fn tolerates_delayed_data() {
+    let faults = Faults {
+        delay_polls: 5,
+        ..Faults::none()
+    };
+    let mut serial = MockSerial::with_faults(faults, 1);
+    serial.feed_reading(&reading(110));
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.read().unwrap(), reading(110));
+}
+
+#[test]
+fn times_out_on_long_delay() {
+    let faults = Faults {
+        delay_polls: 15,
+        ..Faults::none()
+    };
+    let mut serial = MockSerial::with_faults(faults, 1);
+    serial.feed_reading(&reading(120));
+    let mut sensor = sensor(&mut serial);
+
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+    assert_eq!(sensor.read().unwrap(), reading(120));
+}
+
+#[test]
+fn idles_between_polls() {
+    let faults = Faults {
+        delay_polls: 5,
+        ..Faults::none()
+    };
+    let mut serial = MockSerial::with_faults(faults, 1);
+    serial.feed_reading(&reading(115));
+    let mut idles = 0;
+    {
+        let mut sensor = sensor(&mut serial).with_idle(|| idles += 1);
+        assert_eq!(sensor.read().unwrap(), reading(115));
+        assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+    }
+
+    // Five polls while the data is delayed, then ten before timing out
+    assert_eq!(idles, 5 + 10);
+}
+
+#[test]
+fn survives_line_noise() {
+    let faults = Faults {
+        bit_flip_ppm: 500,
+        drop_ppm: 1_000,
+        truncate_ppm: 20_000,
+        delay_polls: 0,
+    };
+    let mut serial = MockSerial::with_faults(faults, 0x5eed);
+    for pm2_5 in 1..=500 {
+        serial.feed_reading(&reading(pm2_5));
+    }
+    let mut sensor = sensor(&mut serial);
+
+    let mut ok = 0;
+    let mut errors = 0;
+    loop {
+        match sensor.read() {
+            Ok(got) => {
+                assert_eq!(got, reading(got.pm2_5()));
+                ok += 1;
+            }
+            Err(SensorError::Timeout) => break,
+            Err(SensorError::BadMagic) | Err(SensorError::ChecksumMismatch) => errors += 1,
+            Err(err) => panic!("unexpected error: {:?}", err),
+        }
+    }
+    assert!(ok > 400, "only {} of 500 frames read", ok);
+    assert!(errors > 0);
+}
+
+#[test]
+fn flush_stale_discards_buffered_frames() {
+    let mut serial = MockSerial::new();
+    for pm2_5 in 1..=5 {
+        serial.feed_reading(&reading(pm2_5));
+    }
+    serial.feed(&encode_frame(&reading(6))[..7]);
+    let mut sensor = sensor(&mut serial);
+
+    assert_eq!(sensor.flush_stale().unwrap(), 5 * 32 + 7);
+    assert!(matches!(sensor.read(), Err(SensorError::Timeout)));
+}
+
+#[test]
+fn watchdog_reports_silent_sensor() {
+    let mut serial = MockSerial::new();
+    let mut fired = Vec::new();
+    {
+        let watchdog = Watchdog::new(clock(100), 1000).on_no_data(|since| fired.push(since));
+        let mut sensor = Sen0177::new(&mut serial).with_idle(watchdog);
+
+        assert!(matches!(
+            sensor.read(),
+            Err(SensorError::NoData { since: 100 })
+        ));
+        assert!(matches!(
+            sensor.read(),
+            Err(SensorError::NoData { since: 1200 })
+        ));
+    }
+    assert_eq!(fired, [100, 1200]);
+}
+
+#[test]
+fn watchdog_restarts_on_valid_frames() {
+    let faults = Faults {
+        delay_polls: 5,
+        ..Faults::none()
+    };
+    let mut serial = MockSerial::with_faults(faults, 1);
+    for pm2_5 in 1..=3 {
+        serial.feed_reading(&reading(pm2_5));
+    }
+    let mut sensor = Sen0177::new(&mut serial).with_idle(Watchdog::new(clock(100), 1000));
+
+    for pm2_5 in 1..=3 {
+        assert_eq!(sensor.read().unwrap(), reading(pm2_5));
+    }
+    assert!(matches!(
+        sensor.read(),
+        Err(SensorError::NoData { since: 1800 })
+    ));
+}
+
+#[test]
+fn detects_mirrored_environmental_concentrations() {
+    let concentrations = Concentrations::new(40, 80, 160);
+    let mirrored = Reading::new(concentrations, concentrations, [600, 200, 40, 5, 1, 0]);
+    let mut serial = MockSerial::new();
+    for _ in 0..3 {
+        serial.feed_reading(&mirrored);
+    }
+    let clean = Concentrations::new(10, 20, 40);
+    serial.feed_reading(&Reading::new(clean, clean, [600, 200, 40, 5, 1, 0]));
+    let mut sensor = Sen0177Builder::new()
+        .timeout_polls(10)
+        .env_detect_frames(3)
+        .build(&mut serial);
+
+    for _ in 0..2 {
+        // Suspect readings are marked even while undecided
+        assert_eq!(sensor.read().unwrap(), mirrored.without_atmospheric());
+    }
+    assert_eq!(sensor.env_supported(), None);
+    let reading = sensor.read().unwrap();
+    assert_eq!(sensor.env_supported(), Some(false));
+    assert_eq!(reading.env_pm2_5(), None);
+    assert_eq!(reading.pm2_5(), 80);
+
+    // Once decided, even plausible-looking readings are marked
+    let reading = sensor.read().unwrap();
+    assert_eq!(reading.atmospheric(), None);
+    assert_eq!(reading.cf1(), clean);
+}
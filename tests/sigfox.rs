@@ -0,0 +1,49 @@
+//! Tests of the 12-byte uplink payload
+
+use sen0177::{
+    aqi,
+    sigfox::{Payload, PayloadError, PAYLOAD_LEN},
+    Concentrations, Reading,
+};
+
+fn reading() -> Reading {
+    Reading::new(
+        Concentrations::new(5, 300, 420),
+        Concentrations::new(4, 10, 18),
+        [600, 200, 40, 5, 1, 0],
+    )
+    .with_device_status(0x91, 0x02)
+}
+
+#[test]
+fn encodes_documented_layout() {
+    let payload = Payload::new(&reading())
+        .with_aqi(aqi::pm2_5(3000))
+        .with_battery(87);
+
+    assert_eq!(
+        payload.encode(),
+        [0x01, 0x00, 0x05, 0x01, 0x2c, 0x01, 0xa4, 0x01, 0xc1, 87, 0x02, 0x00]
+    );
+}
+
+#[test]
+fn round_trips_with_and_without_optional_values() {
+    let bare = Payload::new(&reading());
+    assert_eq!(Payload::decode(&bare.encode()), Ok(bare));
+    assert_eq!(bare.encode()[7..10], [0xff, 0xff, 0xff]);
+
+    let full = bare.with_aqi(aqi::pm2_5(123)).with_battery(0);
+    assert_eq!(Payload::decode(&full.encode()), Ok(full));
+}
+
+#[test]
+fn rejects_foreign_payloads() {
+    let mut buf = Payload::new(&reading()).encode();
+    assert_eq!(
+        Payload::decode(&buf[..PAYLOAD_LEN - 1]),
+        Err(PayloadError::WrongLength(11))
+    );
+    buf[0] = 2;
+    assert_eq!(Payload::decode(&buf), Err(PayloadError::UnknownVersion(2)));
+}
@@ -0,0 +1,205 @@
+use sen0177::{
+    aqi::{AqiCalculator, AqiCategory},
+    protocol::parse_frame,
+    sim::{reading_for, Event, Noise, Pattern, Scenario, SimulatedSensor, DAY_MS, HOUR_MS},
+    AirQualitySensor, Reading, SensorError,
+};
+
+fn read<P: sen0177::sim::Profile>(
+    sensor: &mut SimulatedSensor<P>,
+) -> Result<Reading, SensorError<()>> {
+    sensor.read()
+}
+
+#[test]
+fn reading_for_is_plausible() {
+    for pm2_5 in [0, 1, 12, 35, 150, 999, u16::MAX] {
+        let reading = reading_for(pm2_5);
+        assert_eq!(reading.pm2_5(), pm2_5);
+        assert!(reading.pm1() <= reading.pm2_5());
+        assert!(reading.pm2_5() <= reading.pm10());
+        assert!(reading.env_pm2_5().unwrap() <= reading.pm2_5());
+        assert!(sen0177::health::counts_consistent(&reading));
+    }
+    assert_eq!(reading_for(20).env_pm2_5(), Some(20));
+    assert_eq!(reading_for(90).env_pm2_5(), Some(70));
+}
+
+#[test]
+fn ramp_interpolates_then_holds() {
+    let ramp = Pattern::Ramp {
+        from: 10,
+        to: 110,
+        duration_ms: 10_000,
+    };
+    assert_eq!(ramp.pm2_5_at(0), 10);
+    assert_eq!(ramp.pm2_5_at(5_000), 60);
+    assert_eq!(ramp.pm2_5_at(10_000), 110);
+    assert_eq!(ramp.pm2_5_at(60_000), 110);
+
+    let falling = Pattern::Ramp {
+        from: 100,
+        to: 0,
+        duration_ms: 4,
+    };
+    assert_eq!(falling.pm2_5_at(1), 75);
+}
+
+#[test]
+fn spike_decays_to_baseline() {
+    let spike = Pattern::Spike {
+        baseline: 8,
+        peak: 208,
+        start_ms: 1_000,
+        decay_ms: 2_000,
+    };
+    assert_eq!(spike.pm2_5_at(999), 8);
+    assert_eq!(spike.pm2_5_at(1_000), 208);
+    assert_eq!(spike.pm2_5_at(2_000), 108);
+    assert_eq!(spike.pm2_5_at(3_000), 8);
+}
+
+#[test]
+fn square_alternates() {
+    let square = Pattern::Square {
+        low: 5,
+        high: 50,
+        period_ms: 4_000,
+    };
+    let values: Vec<_> = (0..8)
+        .map(|second| square.pm2_5_at(second * 1_000))
+        .collect();
+    assert_eq!(values, [5, 5, 50, 50, 5, 5, 50, 50]);
+}
+
+#[test]
+fn sensor_advances_simulated_time() {
+    let mut sensor = SimulatedSensor::new(Pattern::Ramp {
+        from: 0,
+        to: 100,
+        duration_ms: 10_000,
+    })
+    .interval_ms(2_500);
+    let values: Vec<_> = (0..5).map(|_| read(&mut sensor).unwrap().pm2_5()).collect();
+    assert_eq!(values, [0, 25, 50, 75, 100]);
+    assert_eq!(sensor.elapsed_ms(), 12_500);
+    assert_eq!(AirQualitySensor::<()>::info(&sensor).name, "Simulated");
+}
+
+#[test]
+fn sensor_injects_failures() {
+    let mut sensor = SimulatedSensor::new(Pattern::Constant(12))
+        .fail_every(3)
+        .limit(5);
+    assert!(read(&mut sensor).is_ok());
+    assert!(read(&mut sensor).is_ok());
+    assert!(matches!(read(&mut sensor), Err(SensorError::Timeout)));
+    assert!(read(&mut sensor).is_ok());
+    assert!(read(&mut sensor).is_ok());
+    assert!(matches!(
+        read(&mut sensor),
+        Err(SensorError::NoData { since: 4_000 })
+    ));
+}
+
+#[test]
+fn closures_are_profiles() {
+    let mut sensor =
+        SimulatedSensor::new(|elapsed_ms: u64| reading_for((elapsed_ms / HOUR_MS) as u16))
+            .interval_ms(60_000);
+    let mut calculator = AqiCalculator::new(60);
+    for minute in 0..3 * 60 {
+        calculator.push(minute * 60, read(&mut sensor).unwrap());
+    }
+    let aqi = calculator.nowcast().unwrap();
+    assert_eq!(aqi.category(), AqiCategory::Good);
+    assert_eq!(sensor.release()(7 * HOUR_MS).pm2_5(), 7);
+}
+
+#[test]
+fn scenario_follows_daily_cycle() {
+    let scenario = Scenario::new(20).diurnal(10, 21);
+    assert_eq!(scenario.pm2_5_at(21 * HOUR_MS), 30);
+    assert_eq!(scenario.pm2_5_at(9 * HOUR_MS), 10);
+    assert_eq!(scenario.pm2_5_at(3 * HOUR_MS), 20);
+    assert_eq!(scenario.pm2_5_at(DAY_MS + 21 * HOUR_MS), 30);
+    for hour in 0..24 {
+        let pm2_5 = scenario.pm2_5_at(hour * HOUR_MS);
+        assert!((10..=30).contains(&pm2_5), "{} at {}:00", pm2_5, hour);
+    }
+
+    let shifted = Scenario::new(20).diurnal(10, 21).starting_at_hour(21);
+    assert_eq!(shifted.pm2_5_at(0), 30);
+
+    // The background doesn't go negative
+    let clean = Scenario::new(2).diurnal(10, 0);
+    assert_eq!(clean.pm2_5_at(12 * HOUR_MS), 0);
+}
+
+#[test]
+fn scenario_adds_events() {
+    let events = [
+        Event::cooking(HOUR_MS, 100),
+        Event::wildfire(DAY_MS, 4 * DAY_MS, 200),
+    ];
+    let scenario = Scenario::new(5).events(&events);
+    assert_eq!(scenario.pm2_5_at(0), 5);
+    assert_eq!(scenario.pm2_5_at(HOUR_MS + 5 * 60 * 1000), 55);
+    // Peak after the rise, then half of it a half-life later
+    assert_eq!(scenario.pm2_5_at(HOUR_MS + 10 * 60 * 1000), 105);
+    assert_eq!(scenario.pm2_5_at(HOUR_MS + 30 * 60 * 1000), 55);
+    assert_eq!(scenario.pm2_5_at(12 * HOUR_MS), 5);
+    // The wildfire builds up over a day, holds for two, and clears
+    assert_eq!(scenario.pm2_5_at(DAY_MS + 12 * HOUR_MS), 105);
+    assert_eq!(scenario.pm2_5_at(3 * DAY_MS), 205);
+    assert_eq!(scenario.pm2_5_at(4 * DAY_MS + 12 * HOUR_MS), 105);
+    assert_eq!(scenario.pm2_5_at(5 * DAY_MS), 5);
+}
+
+#[test]
+fn scenario_noise_is_seeded() {
+    let noisy = |seed| {
+        let mut scenario = Scenario::new(100).noise(Noise::TYPICAL).seed(seed);
+        scenario
+            .samples(60_000)
+            .take(1000)
+            .map(|(_, reading)| reading.pm2_5())
+            .collect::<Vec<_>>()
+    };
+    let values = noisy(7);
+    assert_eq!(values, noisy(7));
+    assert_ne!(values, noisy(8));
+
+    let mean = values.iter().map(|&v| f64::from(v)).sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|&v| (f64::from(v) - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+    assert!((mean - 100.0).abs() < 2.0, "mean {}", mean);
+    // 10% of 100µg/m³, plus 1µg/m³
+    assert!(
+        (variance.sqrt() - 10.05).abs() < 1.5,
+        "sd {}",
+        variance.sqrt()
+    );
+}
+
+#[test]
+fn scenario_produces_frames_and_drives_sensor() {
+    let events = [Event::smoke(0, 50)];
+    let mut scenario = Scenario::new(10).events(&events);
+    let frame = scenario.frame_at(60_000);
+    assert_eq!(parse_frame(&frame).unwrap().pm2_5(), 60);
+
+    let times: Vec<_> = scenario
+        .samples(30_000)
+        .take(3)
+        .map(|(time, _)| time)
+        .collect();
+    assert_eq!(times, [0, 30_000, 60_000]);
+
+    let mut sensor = SimulatedSensor::new(scenario).interval_ms(60_000);
+    assert_eq!(read(&mut sensor).unwrap().pm2_5(), 10);
+    assert_eq!(read(&mut sensor).unwrap().pm2_5(), 60);
+}
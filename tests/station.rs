@@ -0,0 +1,56 @@
+//! Tests of combining particulate and temperature/humidity sensors
+
+use sen0177::{
+    capability::{TempHumidity, TempHumiditySensor},
+    humidity,
+    station::{EnvironmentalStation, StationError},
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+struct FakeSensor(u16);
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        let concentrations = Concentrations::new(self.0, self.0, self.0);
+        Ok(Reading::new(concentrations, concentrations, [0; 6]))
+    }
+}
+
+/// A temperature/humidity sensor with its own error type
+struct FakeBme280(Option<TempHumidity>);
+
+#[derive(Debug, PartialEq)]
+struct I2cError;
+
+impl TempHumiditySensor<I2cError> for FakeBme280 {
+    fn read_temp_humidity(&mut self) -> Result<TempHumidity, SensorError<I2cError>> {
+        self.0.ok_or(SensorError::ReadError(I2cError))
+    }
+}
+
+#[test]
+fn applies_epa_correction() {
+    assert_eq!(humidity::epa_pm2_5(20, 500), 119);
+    assert_eq!(humidity::epa_pm2_5(100, 300), 556);
+    assert_eq!(humidity::epa_pm2_5(0, 1000), 0);
+}
+
+#[test]
+fn combines_both_sensors() {
+    let temp_humidity = TempHumidity::new(215, 500);
+    let mut station = EnvironmentalStation::new(FakeSensor(20), FakeBme280(Some(temp_humidity)));
+
+    let env = station.read().unwrap();
+    assert_eq!(env.reading.pm2_5(), 20);
+    assert_eq!(env.temp_humidity, temp_humidity);
+    assert_eq!(env.corrected_pm2_5, 119);
+}
+
+#[test]
+fn reports_which_sensor_failed() {
+    let mut station = EnvironmentalStation::new(FakeSensor(20), FakeBme280(None));
+    assert!(matches!(
+        station.read(),
+        Err(StationError::TempHumidity(SensorError::ReadError(I2cError)))
+    ));
+}
@@ -0,0 +1,45 @@
+//! Tests of timestamping readings with a pluggable clock
+
+use sen0177::{
+    history::History,
+    time::{Stamped, Timestamped},
+    AirQualitySensor, Concentrations, Reading, SensorError,
+};
+
+struct FakeSensor(u16);
+
+impl AirQualitySensor<()> for FakeSensor {
+    fn read(&mut self) -> Result<Reading, SensorError<()>> {
+        self.0 += 1;
+        let concentrations = Concentrations::new(self.0, self.0, self.0);
+        Ok(Reading::new(concentrations, concentrations, [0; 6]))
+    }
+}
+
+#[test]
+fn stamps_readings_from_closure_clock() {
+    let mut ticks = 100u64;
+    let mut sensor = Stamped::new(FakeSensor(0), || {
+        ticks += 10;
+        ticks
+    });
+    let mut history = History::<4>::new();
+
+    for _ in 0..3 {
+        history.push_timestamped(sensor.read_timestamped().unwrap());
+    }
+
+    let stamped = history
+        .iter()
+        .map(|(timestamp, reading)| Timestamped::new(timestamp, reading.pm2_5()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stamped,
+        [
+            Timestamped::new(110, 1),
+            Timestamped::new(120, 2),
+            Timestamped::new(130, 3)
+        ]
+    );
+    assert_eq!(history.mean(120, Reading::pm2_5), Some(25));
+}
@@ -0,0 +1,103 @@
+//! Tests of trend classification over a history
+
+use sen0177::{
+    history::History,
+    trend::{Trend, TrendAnalyzer},
+    Concentrations, Reading,
+};
+
+fn reading(pm2_5: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5, pm2_5, pm2_5);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+fn history(values: &[u16]) -> History<64> {
+    let mut history = History::new();
+    for (second, &value) in values.iter().enumerate() {
+        history.push(second as u64 * 10, reading(value));
+    }
+    history
+}
+
+#[test]
+fn classifies_direction_against_threshold() {
+    let analyzer = TrendAnalyzer::new(60, 50);
+    // 1µg/m³ every 10s is 6µg/m³ over the window
+    let rising = history(&[10, 11, 12, 13, 14, 15, 16]);
+    assert_eq!(analyzer.change(&rising, Reading::pm2_5), Some(60));
+    assert_eq!(analyzer.trend(&rising, Reading::pm2_5), Some(Trend::Rising));
+
+    let falling = history(&[16, 15, 14, 13, 12, 11, 10]);
+    assert_eq!(
+        analyzer.trend(&falling, Reading::pm2_5),
+        Some(Trend::Falling)
+    );
+
+    // 4µg/m³ over the window is below the threshold
+    let slow = history(&[10, 10, 11, 12, 12, 13, 14]);
+    assert_eq!(analyzer.trend(&slow, Reading::pm2_5), Some(Trend::Stable));
+}
+
+#[test]
+fn ignores_readings_outside_the_window_and_single_spikes() {
+    let analyzer = TrendAnalyzer::new(60, 50);
+    // A steep climb long ago, then flat with one noisy reading
+    let values = [0, 20, 40, 60, 80, 30, 30, 30, 38, 30, 30, 30];
+    let history = history(&values);
+    assert_eq!(
+        analyzer.trend(&history, Reading::pm2_5),
+        Some(Trend::Stable)
+    );
+
+    let trends = analyzer.reading_trends(&history).unwrap();
+    assert_eq!(trends.pm10, Trend::Stable);
+    // Two minutes of readings are too few for an hourly AQI
+    assert_eq!(trends.aqi, None);
+}
+
+#[test]
+fn aqi_trend_follows_the_hourly_aqi() {
+    // A reading a minute for two hours, climbing in the second hour
+    let mut history = History::<120>::new();
+    for minute in 0..120 {
+        history.push(minute * 60, reading(10 + minute.saturating_sub(60) as u16));
+    }
+    let analyzer = TrendAnalyzer::new(600, 50);
+    assert_eq!(analyzer.aqi_trend(&history), Some(Trend::Rising));
+    assert_eq!(
+        analyzer.reading_trends(&history).unwrap().aqi,
+        Some(Trend::Rising)
+    );
+
+    // Once the hour is flat, so is its AQI, even if the last reading spikes
+    let mut flat = History::<120>::new();
+    for minute in 0..120 {
+        flat.push(minute * 60, reading(10));
+    }
+    flat.push(120 * 60, reading(200));
+    assert_eq!(analyzer.aqi_trend(&flat), Some(Trend::Stable));
+}
+
+#[test]
+fn needs_two_distinct_timestamps() {
+    let analyzer = TrendAnalyzer::new(60, 50);
+    assert_eq!(analyzer.trend(&History::<4>::new(), Reading::pm2_5), None);
+    assert_eq!(analyzer.trend(&history(&[10]), Reading::pm2_5), None);
+
+    let mut same_time = History::<4>::new();
+    same_time.push(5, reading(10));
+    same_time.push(5, reading(20));
+    assert_eq!(analyzer.aqi_trend(&same_time), None);
+}
+
+#[test]
+fn backwards_timestamps_have_no_trend() {
+    let analyzer = TrendAnalyzer::new(60, 50);
+    let mut history = History::<8>::new();
+    history.push(100, reading(10));
+    history.push(50, reading(20));
+    history.push(110, reading(30));
+    assert_eq!(analyzer.change(&history, Reading::pm2_5), None);
+    assert_eq!(analyzer.trend(&history, Reading::pm2_5), None);
+    assert_eq!(analyzer.aqi_trend(&history), None);
+}
@@ -0,0 +1,121 @@
+//! Tests of the `embedded-graphics` reading widget, drawn to an in-memory
+//! framebuffer
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    pixelcolor::{Rgb888, RgbColor},
+    prelude::*,
+    primitives::Rectangle,
+};
+use sen0177::{
+    aqi, display::color_for, history::History, widget::ReadingWidget, Concentrations, Reading,
+};
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+
+struct Framebuffer {
+    pixels: [[Option<Rgb888>; WIDTH]; HEIGHT],
+}
+
+impl Framebuffer {
+    fn new() -> Self {
+        Self {
+            pixels: [[None; WIDTH]; HEIGHT],
+        }
+    }
+
+    fn drawn(&self, area: Rectangle) -> impl Iterator<Item = (Point, Rgb888)> + '_ {
+        self.pixels.iter().enumerate().flat_map(move |(y, row)| {
+            row.iter().enumerate().filter_map(move |(x, pixel)| {
+                let point = Point::new(x as i32, y as i32);
+                let inside = point.x >= area.top_left.x
+                    && point.y >= area.top_left.y
+                    && point.x < area.top_left.x + area.size.width as i32
+                    && point.y < area.top_left.y + area.size.height as i32;
+                pixel.filter(|_| inside).map(|color| (point, color))
+            })
+        })
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let row = self
+                .pixels
+                .get_mut(point.y as usize)
+                .expect("drawn below the display");
+            *row.get_mut(point.x as usize)
+                .expect("drawn beyond the display") = Some(color);
+        }
+        Ok(())
+    }
+}
+
+fn reading(pm2_5: u16) -> Reading {
+    let concentrations = Concentrations::new(pm2_5 / 2, pm2_5, pm2_5);
+    Reading::new(concentrations, concentrations, [0; 6])
+}
+
+#[test]
+fn swatch_shows_aqi_color() {
+    let mut display = Framebuffer::new();
+    let bounds = Rectangle::new(Point::new(2, 2), Size::new(60, 20));
+    ReadingWidget::new(reading(40), bounds, Rgb888::WHITE)
+        .with_aqi(aqi::pm2_5(400))
+        .draw(&mut display)
+        .unwrap();
+
+    let aqi = color_for(aqi::pm2_5(400));
+    assert_eq!(display.pixels[2][2], Some(Rgb888::new(aqi.r, aqi.g, aqi.b)));
+    assert_eq!(display.pixels[1][1], None);
+}
+
+#[test]
+fn swatch_needs_an_aqi() {
+    let mut display = Framebuffer::new();
+    let bounds = Rectangle::new(Point::new(2, 2), Size::new(60, 20));
+    ReadingWidget::new(reading(40), bounds, Rgb888::WHITE)
+        .draw(&mut display)
+        .unwrap();
+
+    assert_eq!(display.pixels[2][2], None);
+}
+
+#[test]
+fn sparkline_stays_within_bounds() {
+    let mut history = History::<100>::new();
+    for (timestamp, pm2_5) in (0..100).zip([3, 80, 15, 0, 42].iter().cycle()) {
+        history.push(timestamp, reading(*pm2_5));
+    }
+    let mut display = Framebuffer::new();
+    let bounds = Rectangle::new(Point::new(4, 4), Size::new(40, 40));
+    ReadingWidget::new(reading(42), bounds, Rgb888::WHITE)
+        .with_history(&history)
+        .draw(&mut display)
+        .unwrap();
+
+    let whole = Rectangle::new(Point::zero(), display.size());
+    assert!(display
+        .drawn(whole)
+        .all(|(point, _)| point.x >= 4 && point.y >= 4 && point.x < 44 && point.y < 44));
+    // The newest reading sits in the rightmost column, and the highest one
+    // reaches the top of the sparkline area
+    let sparkline = Rectangle::new(Point::new(4, 28), Size::new(40, 16));
+    assert!(display.drawn(sparkline).any(|(point, _)| point.x == 43));
+    assert!(display.drawn(sparkline).any(|(point, _)| point.y == 28));
+    assert!(display.drawn(sparkline).any(|(point, _)| point.y == 43));
+}